@@ -1,6 +1,9 @@
 use pyo3::prelude::*;
+use pyo3::types::PyModule;
 use std::ffi::CString;
 
+use crate::Module;
+
 /// A wrapper for the Python ast.dump function. This is a convenience function for dumping the AST
 /// to the terminal.
 pub fn dump(o: &Bound<'_, PyAny>, indent: Option<u8>) -> PyResult<String> {
@@ -17,3 +20,51 @@ pub fn dump(o: &Bound<'_, PyAny>, indent: Option<u8>) -> PyResult<String> {
         t.call1(args)?.extract()
     })
 }
+
+/// Serialize a parsed module to JSON, to cache it or to hand it to other tools. The JSON
+/// is written by Python's json module, from the serde representation of the module.
+pub fn dump_json(module: &Module) -> PyResult<String> {
+    Python::with_gil(|py| -> PyResult<String> {
+        let value = pythonize::pythonize(py, module)?;
+        let json = PyModule::import(py, "json")?;
+        json.call_method1("dumps", (value,))?.extract()
+    })
+}
+
+/// Read a module back from the JSON [`dump_json`] wrote.
+pub fn load_json(json: &str) -> PyResult<Module> {
+    Python::with_gil(|py| -> PyResult<Module> {
+        let value = PyModule::import(py, "json")?.call_method1("loads", (json,))?;
+        Ok(pythonize::depythonize(&value)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let module = crate::parse(
+            "def scale(values, factor=2.5, *rest, key=None, **options):
+    \"\"\"Scale the values.\"\"\"
+    total = 0
+    for value in values:
+        total += value * factor
+    return [total, 'done', b'raw', -1, None]
+",
+            "scale.py",
+        )
+        .unwrap();
+
+        let json = dump_json(&module).unwrap();
+        assert!(json.contains("\"scale\""), "{}", json);
+        assert_eq!(load_json(&json).unwrap(), module);
+    }
+
+    #[test]
+    fn test_load_json_rejects_other_json() {
+        assert!(load_json("[1, 2]").is_err());
+        assert!(load_json("{").is_err());
+    }
+}
@@ -26,6 +26,17 @@ pub trait Node {
         None
     }
 
+    /// The node's span in `filename`, for pointing errors at it.
+    fn source_location(&self, filename: impl Into<String>) -> crate::SourceLocation {
+        crate::SourceLocation::with_span(
+            filename,
+            self.lineno(),
+            self.col_offset(),
+            self.end_lineno(),
+            self.end_col_offset(),
+        )
+    }
+
     /// Generate an error message for the current code, adding line and column number.
     fn error_message(&self, mod_name: impl AsRef<str>, message: impl AsRef<str>) -> String {
         format!(
@@ -0,0 +1,195 @@
+use proc_macro2::TokenStream;
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    annotation_to_rust_type, impl_node_with_positions, is_bytearray_annotation, is_final_annotation, is_str_annotation, CodeGen, CodeGenContext, ExprType, Node, PythonOptions, PyAttributeExtractor,
+    SymbolTableNode, SymbolTableScopes,
+};
+
+/// Annotated assignment statement (e.g., `x: int = 5`, or just `x: int`)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AnnAssign {
+    /// The target being declared (left side)
+    pub target: ExprType,
+    /// The type annotation
+    pub annotation: Box<ExprType>,
+    /// The value being assigned, if there is one
+    pub value: Option<ExprType>,
+    /// Position information
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
+}
+
+impl<'a> FromPyObject<'a> for AnnAssign {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let target = ob.extract_attr_with_context("target", "annotated assignment target")?;
+        let annotation = ob.extract_attr_with_context("annotation", "annotated assignment annotation")?;
+        let value = ob.extract_attr_with_context("value", "annotated assignment value")?;
+        let value = if value.is_none() { None } else { Some(value.extract()?) };
+
+        Ok(AnnAssign {
+            target: target.extract()?,
+            annotation: Box::new(annotation.extract()?),
+            value,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
+        })
+    }
+}
+
+impl_node_with_positions!(AnnAssign { lineno, col_offset, end_lineno, end_col_offset });
+
+impl CodeGen for AnnAssign {
+    type Context = CodeGenContext;
+    type Options = PythonOptions;
+    type SymbolTable = SymbolTableScopes;
+
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = symbols;
+        if let ExprType::Name(name) = &self.target
+            && !symbols.is_outer_binding(&name.id)
+        {
+            symbols.insert(
+                name.id.clone(),
+                SymbolTableNode::VariableDef {
+                    name: name.id.clone(),
+                    type_annotation: Some(*self.annotation.clone()),
+                    is_mutable: false,
+                },
+            );
+        }
+        match self.value {
+            Some(value) => value.find_symbols(symbols),
+            None => symbols,
+        }
+    }
+
+    fn to_rust(
+        self,
+        ctx: Self::Context,
+        options: Self::Options,
+        symbols: Self::SymbolTable,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let declares = matches!(&self.target, ExprType::Name(name)
+            if matches!(symbols.lookup(&name.id), Some(SymbolTableNode::VariableDef { .. })));
+        // A Final variable is never assigned again.
+        let is_final = is_final_annotation(&self.annotation, &symbols);
+        let mutable = !is_final && matches!(&self.target, ExprType::Name(name)
+            if matches!(symbols.lookup(&name.id), Some(SymbolTableNode::VariableDef { is_mutable: true, .. })));
+        // A bare `Final` leaves the type to be inferred.
+        let rust_type = if is_final && !matches!(self.annotation.as_ref(), ExprType::Subscript(_)) {
+            None
+        } else {
+            Some(annotation_to_rust_type(&self.annotation, ctx.clone(), options.clone(), symbols.clone())?)
+        };
+        let rust_type = rust_type.map(|rust_type| quote!(: #rust_type));
+        let target = self.target.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let value = match self.value {
+            // A string constant has the type of `str` annotations.
+            Some(ExprType::Constant(constant))
+                if is_str_annotation(&self.annotation, &symbols) && constant.string_value().is_some() =>
+            {
+                constant.string_to_rust(&options)
+            }
+            // A bytearray owns its bytes.
+            Some(ExprType::Bytes(bytes)) if is_bytearray_annotation(&self.annotation, &symbols) => Some(bytes.to_vec_rust()),
+            value => value.map(|value| value.to_rust(ctx, options, symbols)).transpose()?,
+        };
+
+        match (declares, mutable, value) {
+            (true, true, Some(value)) => Ok(quote!(let mut #target #rust_type = #value;)),
+            (true, false, Some(value)) => Ok(quote!(let #target #rust_type = #value;)),
+            (true, true, None) => Ok(quote!(let mut #target #rust_type;)),
+            (true, false, None) => Ok(quote!(let #target #rust_type;)),
+            // Annotating an attribute or a global only assigns to it.
+            (false, _, Some(value)) => Ok(quote!(#target = #value;)),
+            (false, _, None) => Ok(quote!()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_parse_test;
+    use crate::test_utils::{module_to_rust, test_options};
+
+    create_parse_test!(test_ann_assign, "x: int = 5", "test.py");
+    create_parse_test!(test_ann_assign_without_value, "x: int", "test.py");
+
+    #[test]
+    fn test_annotated_variable_is_registered() {
+        let module = crate::parse("x: int = 5", "test.py").unwrap();
+        let symbols = module.find_symbols(SymbolTableScopes::new());
+        match symbols.lookup("x") {
+            Some(SymbolTableNode::VariableDef { name, type_annotation: Some(ExprType::Name(annotation)), is_mutable }) => {
+                assert_eq!(name, "x");
+                assert_eq!(annotation.id, "int");
+                assert!(!is_mutable);
+            }
+            other => panic!("expected a variable definition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_annotation_decides_method_dispatch() {
+        let code = module_to_rust(
+            "def f(v):
+    items: list = make()
+    items.append(v)
+    name: str = make()
+    return name.upper()
+",
+            test_options(),
+        );
+        assert!(code.contains("let mut items : Vec < PyObject > = make () ; ; items . push (v)"), "{}", code);
+        assert!(code.contains("let name : String = make ()"), "{}", code);
+        assert!(code.contains("name . to_uppercase ()"), "{}", code);
+    }
+
+    #[test]
+    fn test_final_variable_is_immutable() {
+        let code = module_to_rust(
+            "def f():
+    limit: Final[int] = compute()
+    scale: Final = 2
+    return limit * scale
+",
+            test_options(),
+        );
+        assert!(code.contains("let limit : i64 = compute () ;"), "{}", code);
+        assert!(code.contains("let scale = 2 ;"), "{}", code);
+    }
+
+    #[test]
+    fn test_assigning_annotated_variable_reassigns_it() {
+        let code = module_to_rust(
+            "def f():
+    total: int = 0
+    total = 2
+    return total
+",
+            test_options(),
+        );
+        assert!(code.contains("let mut total : i64 = 0 ; ; total = 2 ;"), "{}", code);
+    }
+
+    #[test]
+    fn test_optional_annotation_is_option() {
+        let code = module_to_rust(
+            "def f():
+    x: Optional[str] = None
+    y: Optional[Optional[int]] = None
+",
+            test_options(),
+        );
+        assert!(code.contains("let x : Option < String > = None ;"), "{}", code);
+        assert!(code.contains("let y : Option < i64 > = None ;"), "{}", code);
+    }
+}
@@ -1,11 +1,12 @@
 //! The module defines Python-syntax arguments and maps them into Rust-syntax versions.
 use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
-use quote::quote;
+use quote::{quote, ToTokens};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableScopes,
+    convert_ident, annotation_to_rust_type, is_str_annotation, CodeGen, CodeGenContext, ExprType, IdentCase, Node,
+    PythonOptions, SymbolTableScopes,
 };
 
 /// A complete argument representation that can hold any Python expression.
@@ -60,6 +61,30 @@ pub struct Arguments {
     pub defaults: Vec<Box<ExprType>>,
 }
 
+impl Arguments {
+    /// The parameters without the first one if it's `self`, the instance a method is
+    /// called on, and whether it was.
+    pub fn without_receiver(&self) -> (Arguments, bool) {
+        let mut arguments = self.clone();
+        let positional = if arguments.posonlyargs.is_empty() { &mut arguments.args } else { &mut arguments.posonlyargs };
+        if positional.first().is_some_and(|parameter| parameter.arg == "self") {
+            positional.remove(0);
+            (arguments, true)
+        } else {
+            (arguments, false)
+        }
+    }
+
+    /// All parameters in declaration order, including `*args` and `**kwargs`.
+    pub fn parameters(&self) -> impl Iterator<Item = &Parameter> {
+        self.posonlyargs
+            .iter()
+            .chain(self.args.iter())
+            .chain(self.vararg.iter())
+            .chain(self.kwonlyargs.iter())
+            .chain(self.kwarg.iter())
+    }
+}
 
 /// Function call arguments supporting all Python call patterns.
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
@@ -140,6 +165,26 @@ impl<'a> FromPyObject<'a> for Parameter {
     }
 }
 
+/// The type of a parameter without an annotation whose default is a constant, which it's
+/// taken to have the type of.
+fn default_type(default: &ExprType, options: &PythonOptions) -> Option<TokenStream> {
+    match default {
+        ExprType::Constant(constant) => constant.rust_type(options),
+        _ => None,
+    }
+}
+
+/// The binding a parameter's name is generated as, declared `mut` if the function body
+/// changes it in place.
+fn param_binding(name: &str, options: &PythonOptions, symbols: &SymbolTableScopes) -> TokenStream {
+    let ident = convert_ident(name, IdentCase::Value, options, symbols);
+    if symbols.is_mutated(name) {
+        quote!(mut #ident)
+    } else {
+        quote!(#ident)
+    }
+}
+
 impl CodeGen for Parameter {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -151,13 +196,11 @@ impl CodeGen for Parameter {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> std::result::Result<TokenStream, Box<dyn std::error::Error>> {
-        use quote::format_ident;
-        
-        let param_name = format_ident!("{}", self.arg);
+        let param_name = param_binding(&self.arg, &options, &symbols);
         
         // Generate type annotation if present
         if let Some(annotation) = self.annotation {
-            let rust_type = annotation.to_rust(ctx, options, symbols)?;
+            let rust_type = annotation_to_rust_type(&annotation, ctx, options, symbols)?;
             Ok(quote!(#param_name: #rust_type))
         } else {
             // Default to generic type for untyped parameters
@@ -240,11 +283,14 @@ impl CodeGen for Arguments {
                 // This argument has a default value
                 let default_idx = i - defaults_offset;
                 let default_value = &self.defaults[default_idx];
-                let _default_rust = default_value.as_ref().clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-                let param_name = quote::format_ident!("{}", arg.arg);
+                // The body binds the parameter again with its default filled in, which is
+                // the binding that's changed if anything is.
+                let param_name = convert_ident(&arg.arg, IdentCase::Value, &options, &symbols);
                 
                 if let Some(annotation) = &arg.annotation {
-                    let rust_type = annotation.as_ref().clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                    let rust_type = annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone())?.optional();
+                    params.push(quote!(#param_name: #rust_type));
+                } else if let Some(rust_type) = default_type(default_value, &options) {
                     params.push(quote!(#param_name: Option<#rust_type>));
                 } else {
                     params.push(quote!(#param_name: Option<impl Into<PyObject>>));
@@ -257,36 +303,42 @@ impl CodeGen for Arguments {
         
         // Process *args
         if let Some(vararg) = self.vararg {
-            let vararg_name = quote::format_ident!("{}", vararg.arg);
+            let vararg_name = param_binding(&vararg.arg, &options, &symbols);
             params.push(quote!(#vararg_name: impl IntoIterator<Item = impl Into<PyObject>>));
         }
         
         // Process keyword-only arguments
         for (i, arg) in self.kwonlyargs.into_iter().enumerate() {
-            let param_name = quote::format_ident!("{}", arg.arg);
-            
             // Check if this keyword-only arg has a default
-            let has_default = i < self.kw_defaults.len() && self.kw_defaults[i].is_some();
+            let default_value = self.kw_defaults.get(i).and_then(Option::as_deref);
+            let param_name = if default_value.is_some() {
+                convert_ident(&arg.arg, IdentCase::Value, &options, &symbols).to_token_stream()
+            } else {
+                param_binding(&arg.arg, &options, &symbols)
+            };
             
             if let Some(annotation) = &arg.annotation {
-                let rust_type = annotation.as_ref().clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-                if has_default {
-                    params.push(quote!(#param_name: Option<#rust_type>));
+                let rust_type = annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone())?;
+                if default_value.is_some() {
+                    let rust_type = rust_type.optional();
+                    params.push(quote!(#param_name: #rust_type));
                 } else {
                     params.push(quote!(#param_name: #rust_type));
                 }
             } else {
-                if has_default {
-                    params.push(quote!(#param_name: Option<impl Into<PyObject>>));
-                } else {
-                    params.push(quote!(#param_name: impl Into<PyObject>));
+                match default_value {
+                    Some(default_value) => match default_type(default_value, &options) {
+                        Some(rust_type) => params.push(quote!(#param_name: Option<#rust_type>)),
+                        None => params.push(quote!(#param_name: Option<impl Into<PyObject>>)),
+                    },
+                    None => params.push(quote!(#param_name: impl Into<PyObject>)),
                 }
             }
         }
         
         // Process **kwargs
         if let Some(kwarg) = self.kwarg {
-            let kwarg_name = quote::format_ident!("{}", kwarg.arg);
+            let kwarg_name = param_binding(&kwarg.arg, &options, &symbols);
             params.push(quote!(#kwarg_name: impl IntoIterator<Item = (impl AsRef<str>, impl Into<PyObject>)>));
         }
         
@@ -295,6 +347,81 @@ impl CodeGen for Arguments {
 }
 
 
+impl Arguments {
+    /// The parameters that have defaults, with their defaults.
+    pub fn defaulted(&self) -> Vec<(&Parameter, &ExprType)> {
+        let offset = self.args.len().saturating_sub(self.defaults.len());
+        let positional = self.args.iter().skip(offset).zip(self.defaults.iter().map(Box::as_ref));
+        let keyword = self
+            .kwonlyargs
+            .iter()
+            .zip(self.kw_defaults.iter())
+            .filter_map(|(parameter, default)| Some((parameter, default.as_deref()?)));
+        positional.chain(keyword).collect()
+    }
+
+    /// The statements the body of the function starts with, which give each parameter
+    /// with a default its default when the function is called with `None` for it, like
+    /// `let b = b.unwrap_or_else(|| 2);`.
+    pub fn default_bindings(
+        &self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> std::result::Result<TokenStream, Box<dyn std::error::Error>> {
+        let mut bindings = TokenStream::new();
+        for (parameter, default) in self.defaulted() {
+            let name = convert_ident(&parameter.arg, IdentCase::Value, &options, &symbols);
+            let binding = param_binding(&parameter.arg, &options, &symbols);
+            let is_str = parameter.annotation.as_ref().is_some_and(|annotation| is_str_annotation(annotation, &symbols));
+            let string_default = match default {
+                ExprType::Constant(constant) if is_str => constant.string_to_rust(&options),
+                _ => None,
+            };
+            let default = match string_default {
+                Some(default) => default,
+                None => default.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?,
+            };
+            bindings.extend(quote!(let #binding = #name.unwrap_or_else(|| #default);));
+        }
+        Ok(bindings)
+    }
+
+    /// The parameters as those of a closure, which can't have defaults or take any number
+    /// of arguments. A parameter without an annotation has the type the closure is called
+    /// with.
+    pub fn closure_parameters(
+        &self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> std::result::Result<TokenStream, Box<dyn std::error::Error>> {
+        if self.vararg.is_some()
+            || self.kwarg.is_some()
+            || !self.defaults.is_empty()
+            || self.kw_defaults.iter().any(Option::is_some)
+        {
+            return Err(crate::CodeGenError::unsupported(
+                "a nested function with default or variadic parameters that captures variables",
+                None,
+            )
+            .into());
+        }
+        let mut params = Vec::new();
+        for parameter in self.parameters() {
+            let param_name = param_binding(&parameter.arg, &options, &symbols);
+            match &parameter.annotation {
+                Some(annotation) => {
+                    let rust_type = annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone())?;
+                    params.push(quote!(#param_name: #rust_type));
+                }
+                None => params.push(param_name),
+            }
+        }
+        Ok(quote!(#(#params),*))
+    }
+}
+
 // Implementation for CallArguments
 impl<'a> FromPyObject<'a> for CallArguments {
     fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
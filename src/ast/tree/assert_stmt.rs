@@ -0,0 +1,232 @@
+use proc_macro2::TokenStream;
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+
+use tracing::warn;
+
+use crate::{
+    AssertMode, CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableScopes,
+    UNKNOWN_FILE,
+};
+
+/// Assert statement (assert test [, msg])
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Assert {
+    /// The condition that has to hold
+    pub test: ExprType,
+    /// The message to fail with (optional)
+    pub msg: Option<ExprType>,
+    /// Position information
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
+}
+
+impl<'a> FromPyObject<'a> for Assert {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let test: ExprType = ob.getattr("test")?.extract()?;
+
+        // Extract msg (optional)
+        let msg: Option<ExprType> = if let Ok(msg_attr) = ob.getattr("msg") {
+            if msg_attr.is_none() {
+                None
+            } else {
+                Some(msg_attr.extract()?)
+            }
+        } else {
+            None
+        };
+
+        Ok(Assert {
+            test,
+            msg,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
+        })
+    }
+}
+
+impl Node for Assert {
+    fn lineno(&self) -> Option<usize> { self.lineno }
+    fn col_offset(&self) -> Option<usize> { self.col_offset }
+    fn end_lineno(&self) -> Option<usize> { self.end_lineno }
+    fn end_col_offset(&self) -> Option<usize> { self.end_col_offset }
+}
+
+impl CodeGen for Assert {
+    type Context = CodeGenContext;
+    type Options = PythonOptions;
+    type SymbolTable = SymbolTableScopes;
+
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = self.test.find_symbols(symbols);
+        // Under AssertMode::Result, a failed assertion is an error of the module's type.
+        symbols.record_exception("AssertionError");
+        if let Some(msg) = self.msg {
+            msg.find_symbols(symbols)
+        } else {
+            symbols
+        }
+    }
+
+    fn to_rust(
+        self,
+        ctx: Self::Context,
+        options: Self::Options,
+        symbols: Self::SymbolTable,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // `assert (x, "message")` asserts a non-empty tuple, which always holds.
+        if let ExprType::Tuple(tuple) = &self.test
+            && !tuple.elts.is_empty()
+        {
+            warn!("Assertion on a tuple at line {:?} is always true", self.lineno);
+            options.diagnostics.warn(
+                "assertion on a tuple is always true, the message probably belongs after a comma outside it",
+                Some(self.source_location(UNKNOWN_FILE)),
+            );
+            return Ok(TokenStream::new());
+        }
+
+        if options.assert_mode == AssertMode::Result && options.state.returns_result {
+            return self.result_to_rust(ctx, options, symbols);
+        }
+
+        // The message becomes the trailing format arguments of the assertion macro.
+        let message = match self.msg {
+            Some(ExprType::Constant(c)) if c.string_value().is_some() => {
+                let text = c.string_value().unwrap_or_default().replace('{', "{{").replace('}', "}}");
+                quote!(, #text)
+            }
+            Some(msg) => {
+                let msg = msg.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                quote!(, "{}", #msg)
+            }
+            None => TokenStream::new(),
+        };
+
+        let debug = options.assert_mode == AssertMode::DebugAssert;
+
+        // A single equality gets assert_eq!, which reports both sides when it fails.
+        if let ExprType::Compare(compare) = &self.test
+            && let Some((left, right)) = compare.as_equality()
+        {
+            let left = left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            let right = right.clone().to_rust(ctx, options, symbols)?;
+            return Ok(if debug {
+                quote!(debug_assert_eq!(#left, #right #message))
+            } else {
+                quote!(assert_eq!(#left, #right #message))
+            });
+        }
+
+        let test = self.test.to_rust(ctx, options, symbols)?;
+        Ok(if debug {
+            quote!(debug_assert!(#test #message))
+        } else {
+            quote!(assert!(#test #message))
+        })
+    }
+}
+
+impl Assert {
+    /// A failed assertion returns an AssertionError with the message, like raising one.
+    fn result_to_rust(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let message = match self.msg {
+            Some(ExprType::Constant(c)) if c.string_value().is_some() => {
+                let text = c.string_value().unwrap_or_default();
+                quote!(#text.to_string())
+            }
+            // An f-string already formats to a String.
+            Some(msg @ ExprType::JoinedStr(_)) => msg.to_rust(ctx.clone(), options.clone(), symbols.clone())?,
+            Some(msg) => {
+                let msg = msg.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                quote!(format!("{}", #msg))
+            }
+            None => quote!(String::new()),
+        };
+        let test = self.test.to_rust(ctx, options, symbols)?;
+        Ok(quote! {
+            if !(#test) {
+                return Err(ModuleError::AssertionError { message: #message, source: None }.into());
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AssertMode, ErrorStrategy, PythonOptions};
+    use crate::test_utils::statement_to_rust;
+
+    #[test]
+    fn test_plain_assert() {
+        assert_eq!(statement_to_rust("assert ready", PythonOptions::default()), "assert ! (ready)");
+    }
+
+    #[test]
+    fn test_assert_with_message() {
+        assert_eq!(
+            statement_to_rust("assert ready, \"not ready\"", PythonOptions::default()),
+            "assert ! (ready , \"not ready\")"
+        );
+        assert_eq!(
+            statement_to_rust("assert ready, reason", PythonOptions::default()),
+            "assert ! (ready , \"{}\" , reason)"
+        );
+    }
+
+    #[test]
+    fn test_equality_assert() {
+        assert_eq!(statement_to_rust("assert x == y", PythonOptions::default()), "assert_eq ! (x , y)");
+        assert_eq!(
+            statement_to_rust("assert x == 1, \"x should be {1}\"", PythonOptions::default()),
+            "assert_eq ! (x , 1 , \"x should be {{1}}\")"
+        );
+    }
+
+    #[test]
+    fn test_debug_assert_mode() {
+        let mut options = PythonOptions::default();
+        options.assert_mode = AssertMode::DebugAssert;
+        assert_eq!(statement_to_rust("assert ready", options.clone()), "debug_assert ! (ready)");
+        assert_eq!(
+            statement_to_rust("assert x == y, \"differ\"", options),
+            "debug_assert_eq ! (x , y , \"differ\")"
+        );
+    }
+
+    #[test]
+    fn test_result_assert_mode() {
+        let mut options = PythonOptions::default();
+        options.assert_mode = AssertMode::Result;
+        options.error_strategy = ErrorStrategy::Result;
+        let code = statement_to_rust("def f(x):\n    assert x > 0, f\"bad {x}\"\n", options.clone());
+        assert!(code.contains("-> Result < () , ModuleError >"), "{}", code);
+        assert!(
+            code.contains("if ! ((x) > (0)) { return Err (ModuleError :: AssertionError { message : format ! (\"bad {}\" , x) , source : None } . into ()) ; }"),
+            "{}",
+            code
+        );
+
+        // Outside a function that returns a Result, assert! is the only way to fail.
+        assert_eq!(statement_to_rust("assert ready", options), "assert ! (ready)");
+    }
+
+    #[test]
+    fn test_assert_on_tuple_warns() {
+        let options = PythonOptions::default();
+        assert_eq!(statement_to_rust("assert (ready, \"not ready\")", options.clone()), "");
+        let diagnostics = options.diagnostics.to_vec();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("always true"), "{}", diagnostics[0]);
+    }
+}
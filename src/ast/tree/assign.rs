@@ -2,10 +2,11 @@ use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
 use quote::quote;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableNode,
-    SymbolTableScopes,
+    impl_node_with_positions, CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableNode,
+    SymbolTableScopes, TypeVar, ClassDef,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -13,6 +14,10 @@ pub struct Assign {
     pub targets: Vec<ExprType>,
     pub value: ExprType,
     pub type_comment: Option<String>,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
 }
 
 impl<'a> FromPyObject<'a> for Assign {
@@ -40,10 +45,16 @@ impl<'a> FromPyObject<'a> for Assign {
             targets: targets,
             value: value,
             type_comment: None,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
         })
     }
 }
 
+impl_node_with_positions!(Assign { lineno, col_offset, end_lineno, end_col_offset });
+
 impl<'a> CodeGen for Assign {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -52,16 +63,38 @@ impl<'a> CodeGen for Assign {
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
         let mut symbols = symbols;
         let mut position = 0;
+        let record = ClassDef::from_record_call(&self);
         for target in self.targets {
-            // Only add symbols for Name assignments, not for Attribute assignments
+            // Only add symbols for Name assignments, not for Attribute assignments.
+            // Names declared global/nonlocal keep pointing at the outer binding.
+            // Assigning to an item changes the container in place.
+            if let ExprType::Subscript(subscript) = &target
+                && let ExprType::Name(name) = subscript.value.as_ref()
+            {
+                symbols.mark_mutated(&name.id);
+            }
             if let ExprType::Name(name) = target {
-                symbols.insert(
-                    name.id,
-                    SymbolTableNode::Assign {
+                if symbols.is_outer_binding(&name.id) {
+                    position += 1;
+                    continue;
+                }
+                // Assigning to an annotated variable again keeps its declaration.
+                if matches!(symbols.lookup_current(&name.id), Some(SymbolTableNode::VariableDef { .. })) {
+                    symbols.mark_mutated(&name.id);
+                    position += 1;
+                    continue;
+                }
+                // `T = TypeVar("T")` declares a type variable for annotations, and
+                // `Point = NamedTuple("Point", ...)` a class.
+                let node = match (TypeVar::from_call(&self.value), record.clone()) {
+                    (Some(type_var), _) => SymbolTableNode::TypeVar(type_var),
+                    (None, Some(class)) => SymbolTableNode::ClassDef { class, mutating_methods: HashSet::new() },
+                    (None, None) => SymbolTableNode::Assign {
                         position: position,
                         value: self.value.clone(),
                     },
-                );
+                };
+                symbols.insert(name.id, node);
             }
             // Could also handle other target types here if needed
             position += 1;
@@ -75,11 +108,56 @@ impl<'a> CodeGen for Assign {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // Type variables only exist in annotations, where they're generic parameters.
+        if TypeVar::from_call(&self.value).is_some() {
+            return Ok(TokenStream::new());
+        }
+        if let Some(class) = ClassDef::from_record_call(&self) {
+            return class.to_rust(ctx, options, symbols);
+        }
+        // A variable of the module that functions share is set through its cell.
+        if let [ExprType::Name(name)] = self.targets.as_slice()
+            && symbols.is_module_variable(&name.id)
+        {
+            let cell = name.ident_to_rust(&options, &symbols);
+            let value = self.value.to_rust(ctx, options, symbols)?;
+            return Ok(quote!(#cell.set(#value);));
+        }
+
         let mut target_streams = Vec::new();
+        let rebinds_outer = self.targets.len() == 1
+            && matches!(&self.targets[0], ExprType::Name(name) if symbols.is_outer_binding(&name.id)
+                || matches!(symbols.lookup(&name.id), Some(SymbolTableNode::VariableDef { .. })));
+        // Assigning to an attribute or an item changes it rather than declaring anything,
+        // and the attributes a constructor assigns are variables it has already declared.
+        let assigns_field = matches!(self.targets.as_slice(), [ExprType::Attribute(_) | ExprType::Subscript(_)]);
+        // So are the variables the assignment changes, which are declared already.
+        let reassigns = self.lineno.zip(self.col_offset).is_some_and(|position| symbols.is_reassignment(position));
+        let mutated = self.targets.len() == 1
+            && matches!(&self.targets[0], ExprType::Name(name) if symbols.is_mutated(&name.id));
         
         // Convert each target to Rust code
         for target in self.targets {
-            let target_code = target.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            let target_code = match &target {
+                // Unpacking into variables declares each of them, and the ones changed
+                // later are mutable.
+                ExprType::Tuple(tuple)
+                    if !reassigns
+                        && tuple.elts.iter().all(|elt| matches!(elt, ExprType::Name(_)))
+                        && tuple.elts.iter().any(|elt| matches!(elt, ExprType::Name(name) if symbols.is_mutated(&name.id))) =>
+                {
+                    let mut elements = Vec::new();
+                    for elt in &tuple.elts {
+                        let element = elt.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                        match elt {
+                            ExprType::Name(name) if symbols.is_mutated(&name.id) => elements.push(quote!(mut #element)),
+                            _ => elements.push(element),
+                        }
+                    }
+                    quote!((#(#elements,)*))
+                }
+                target => target.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?,
+            };
             target_streams.push(target_code);
         }
         
@@ -88,9 +166,15 @@ impl<'a> CodeGen for Assign {
         // For single target assignment
         if target_streams.len() == 1 {
             let target = &target_streams[0];
-            // Check if this is a new variable declaration or reassignment
-            // For now, we'll use `let` for new declarations
-            Ok(quote!(let #target = #value;))
+            // Names declared global/nonlocal or with an annotation are reassigned in
+            // place, anything else is a new declaration.
+            if rebinds_outer || assigns_field || reassigns {
+                Ok(quote!(#target = #value;))
+            } else if mutated {
+                Ok(quote!(let mut #target = #value;))
+            } else {
+                Ok(quote!(let #target = #value;))
+            }
         } else {
             // For multiple assignment targets like: a, b = 1, 2
             // Use tuple destructuring in Rust
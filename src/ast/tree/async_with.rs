@@ -1,11 +1,10 @@
 use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
-use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Node, PythonOptions, Statement, SymbolTableScopes,
-    extract_list,
+    CodeGen, CodeGenContext, CodeGenError, ExprType, Node, PythonOptions, Statement,
+    SymbolTableScopes, UNKNOWN_FILE, extract_list,
 };
 
 /// Async with statement (async with context as var: ...)
@@ -100,30 +99,45 @@ impl CodeGen for AsyncWith {
 
     fn to_rust(
         self,
-        ctx: Self::Context,
-        options: Self::Options,
-        symbols: Self::SymbolTable,
+        _ctx: Self::Context,
+        _options: Self::Options,
+        _symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        // Generate body
-        let body_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.body.into_iter()
-            .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
-            .collect();
-        let body_tokens = body_tokens?;
-
-        // For now, generate a simplified async block
-        // In practice, this would need proper async context management
-        Ok(quote! {
-            {
-                // Async with block - simplified translation
-                // Python's async with doesn't map directly to Rust patterns
-                #(#body_tokens)*
-            }
-        })
+        // Async context managers need __aenter__ and __aexit__ awaited, which nothing maps
+        // to yet, and a block that silently skipped them would be wrong.
+        Err(CodeGenError::unsupported(
+            "async with",
+            Some(self.source_location(UNKNOWN_FILE)),
+        )
+        .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // Tests would go here - currently commented out as they need full AST infrastructure
-    // create_parse_test!(test_simple_async_with, "async with context:\n    pass", "test.py");
-}
\ No newline at end of file
+    use crate::{CodeGen, CodeGenContext, PythonOptions, Severity, SymbolTableScopes};
+
+    #[test]
+    fn test_async_with_is_unsupported() {
+        let module = crate::parse("async def f():\n    async with lock:\n        pass\n", "test.py").unwrap();
+        // The statement becomes a todo!() and the rest of the module is still generated.
+        let options = PythonOptions::default();
+        let code = module
+            .to_rust(
+                CodeGenContext::Module("test".to_string()),
+                options.clone(),
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+        assert!(code.contains("pub async fn f () { todo ! (\"UNSUPPORTED: async with\") ; }"), "{}", code);
+
+        let diagnostics = options.diagnostics.to_vec();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].message, "async with is not supported");
+        let location = diagnostics[0].location.as_ref();
+        assert_eq!(location.and_then(|l| l.line), Some(2));
+        assert_eq!(location.map(|l| l.filename.as_str()), Some("test.py"));
+    }
+}
@@ -2,16 +2,20 @@ use proc_macro2::TokenStream;
 use pyo3::{Bound, PyAny, FromPyObject, PyResult, prelude::PyAnyMethods, types::PyTypeMethods};
 use quote::{format_ident, quote};
 
-use crate::{dump, CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableScopes};
+use crate::{convert_ident, dump, impl_node_with_positions, sanitize_ident, CodeGen, IdentCase, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableNode, SymbolTableScopes};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 //#[pyo3(transparent)]
 pub struct Attribute {
-    value: Box<ExprType>,
-    attr: String,
-    ctx: String,
+    pub value: Box<ExprType>,
+    pub attr: String,
+    pub ctx: String,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
 }
 
 impl<'a> FromPyObject<'a> for Attribute {
@@ -34,10 +38,16 @@ impl<'a> FromPyObject<'a> for Attribute {
             value: Box::new(value.extract().expect("Attribute.value")),
             attr: attr.extract().expect("Attribute.attr"),
             ctx: ctx.to_string(),
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
         })
     }
 }
 
+impl_node_with_positions!(Attribute { lineno, col_offset, end_lineno, end_col_offset });
+
 impl<'a> CodeGen for Attribute {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -49,13 +59,36 @@ impl<'a> CodeGen for Attribute {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // The members of Enums are variants, whose name and value are methods.
+        if let Some(member) = enum_member(&self, &options, &symbols) {
+            return Ok(member);
+        }
+        if let ExprType::Attribute(value) = self.value.as_ref()
+            && matches!(self.attr.as_str(), "name" | "value")
+            && let Some(member) = enum_member(value, &options, &symbols)
+        {
+            let attr = format_ident!("{}", self.attr);
+            return Ok(quote!(#member.#attr()));
+        }
+
+        // The attributes a constructor assigns are its local variables.
+        if matches!(self.value.as_ref(), ExprType::Name(name) if name.id == "self")
+            && options.state.constructor_fields.contains(&self.attr)
+        {
+            let attr = sanitize_ident(&self.attr);
+            return Ok(quote!(#attr));
+        }
+
+        // Names bound by import statements are modules, like np after `import numpy as np`.
+        let imported = matches!(self.value.as_ref(), ExprType::Name(name)
+            if matches!(symbols.lookup(&name.id), Some(SymbolTableNode::Import(_) | SymbolTableNode::Alias(_))));
         let value_tokens = self.value.to_rust(ctx, options, symbols)?;
         let value_str = value_tokens.to_string();
-        let attr = format_ident!("{}", self.attr);
+        let attr = sanitize_ident(&self.attr);
         
         // Determine if this is a module access or a field/method access
         // Module names are typically lowercase and match Python stdlib modules
-        let is_module_access = matches!(value_str.as_str(), 
+        let is_module_access = imported || matches!(value_str.as_str(), 
             "sys" | "os" | "subprocess" | "json" | "urllib" | "xml" | "asyncio" |
             "os :: path" | "os::path" // for nested modules
         );
@@ -80,3 +113,18 @@ impl<'a> CodeGen for Attribute {
         }
     }
 }
+
+/// The path of the variant an attribute like `Color.RED` is, if it's a member of an Enum.
+fn enum_member(attribute: &Attribute, options: &PythonOptions, symbols: &SymbolTableScopes) -> Option<TokenStream> {
+    let ExprType::Name(name) = attribute.value.as_ref() else {
+        return None;
+    };
+    let Some(SymbolTableNode::ClassDef { class, .. }) = symbols.lookup(&name.id) else {
+        return None;
+    };
+    let members = class.enum_members(options, symbols).ok()??;
+    let member = members.iter().find(|member| member.name == attribute.attr)?;
+    let class = convert_ident(&class.name, IdentCase::Type, options, symbols);
+    let variant = &member.variant;
+    Some(quote!(#class::#variant))
+}
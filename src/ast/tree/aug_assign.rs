@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableScopes,
-    BinOps, FromPythonString, PyAttributeExtractor,
+    BinOps, FromPythonString, PyAttributeExtractor, matmul_to_rust,
 };
 
 /// Augmented assignment statement (e.g., x += 1, y -= 2, etc.)
@@ -64,7 +64,12 @@ impl CodeGen for AugAssign {
     type SymbolTable = SymbolTableScopes;
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
-        // Process the value for symbols, but don't add new symbols for augmented assignment
+        // Process the value for symbols, but don't add new symbols for augmented assignment.
+        // The target is changed in place, though.
+        let mut symbols = symbols;
+        if let ExprType::Name(name) = &self.target {
+            symbols.mark_mutated(&name.id);
+        }
         self.value.find_symbols(symbols)
     }
 
@@ -74,35 +79,54 @@ impl CodeGen for AugAssign {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let target = self.target.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-        let value = self.value.to_rust(ctx, options, symbols)?;
-        
-        // Generate the appropriate augmented assignment operator
-        match self.op {
-            BinOps::Add => Ok(quote!(#target += #value)),
-            BinOps::Sub => Ok(quote!(#target -= #value)),
-            BinOps::Mult => Ok(quote!(#target *= #value)),
-            BinOps::Div => Ok(quote!(#target /= #value)),
-            BinOps::FloorDiv => Ok(quote!(#target /= #value)), // Rust doesn't have floor div assign
-            BinOps::Mod => Ok(quote!(#target %= #value)),
-            BinOps::BitAnd => Ok(quote!(#target &= #value)),
-            BinOps::BitOr => Ok(quote!(#target |= #value)),
-            BinOps::BitXor => Ok(quote!(#target ^= #value)),
-            BinOps::LShift => Ok(quote!(#target <<= #value)),
-            BinOps::RShift => Ok(quote!(#target >>= #value)),
-            BinOps::Pow => {
-                // Rust doesn't have **= operator, so we need to expand it
-                Ok(quote!(#target = (#target).pow(#value)))
-            },
-            BinOps::MatMult => {
-                // Matrix multiplication assignment - not directly supported in Rust
-                // Would need specific matrix library support
-                Err(format!("Matrix multiplication assignment not supported in Rust").into())
-            },
-            BinOps::Unknown => {
-                Err(format!("Unknown augmented assignment operator").into())
-            },
+        // A variable of the module that functions share is changed through its cell.
+        if let ExprType::Name(name) = &self.target
+            && symbols.is_module_variable(&name.id)
+        {
+            let cell = name.ident_to_rust(&options, &symbols);
+            let value = self.value.to_rust(ctx, options.clone(), symbols)?;
+            let update = operator_to_rust(self.op, &quote!(__value), &value, &options)?;
+            return Ok(quote!({ let mut __value = #cell.get(); #update; #cell.set(__value); }));
         }
+
+        let target = self.target.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let value = self.value.to_rust(ctx, options.clone(), symbols)?;
+        operator_to_rust(self.op, &target, &value, &options)
+    }
+}
+
+/// Generate the augmented assignment of `value` to `target` with the operator `op`.
+fn operator_to_rust(
+    op: BinOps,
+    target: &TokenStream,
+    value: &TokenStream,
+    options: &PythonOptions,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    // Generate the appropriate augmented assignment operator
+    match op {
+        BinOps::Add => Ok(quote!(#target += #value)),
+        BinOps::Sub => Ok(quote!(#target -= #value)),
+        BinOps::Mult => Ok(quote!(#target *= #value)),
+        BinOps::Div => Ok(quote!(#target /= #value)),
+        // Python's floored semantics need the helpers BinOp uses.
+        BinOps::FloorDiv => Ok(quote!(#target = python_floor_div(#target, #value))),
+        BinOps::Mod => Ok(quote!(#target = python_mod(#target, #value))),
+        BinOps::BitAnd => Ok(quote!(#target &= #value)),
+        BinOps::BitOr => Ok(quote!(#target |= #value)),
+        BinOps::BitXor => Ok(quote!(#target ^= #value)),
+        BinOps::LShift => Ok(quote!(#target <<= #value)),
+        BinOps::RShift => Ok(quote!(#target >>= #value)),
+        BinOps::Pow => {
+            // Rust doesn't have **= operator, so we need to expand it
+            Ok(quote!(#target = (#target).pow(#value)))
+        },
+        BinOps::MatMult => {
+            let product = matmul_to_rust(target, value, options);
+            Ok(quote!(#target = #product))
+        },
+        BinOps::Unknown => {
+            Err(format!("Unknown augmented assignment operator").into())
+        },
     }
 }
 
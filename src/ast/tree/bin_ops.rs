@@ -4,8 +4,9 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    dump, CodeGen, CodeGenContext, Error, ExprType, Node, PythonOptions, SymbolTableScopes,
-    PythonOperator, BinaryOperation, FromPythonString, PyAttributeExtractor,
+    dump, impl_node_with_positions, CodeGen, CodeGenContext, CodeGenError, Constant, Error, ExprType, Node, PowType, PythonOptions,
+    SymbolTableScopes, PythonOperator, BinaryOperation, FromPythonString, PyAttributeExtractor,
+    is_str_expr, receiver_to_rust, truthiness, Truthiness, UNKNOWN_FILE, UnaryOp, Ops,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -71,6 +72,10 @@ impl PythonOperator for BinOps {
                 op: self.clone(), 
                 left: Box::new(ExprType::Name(crate::Name { id: "unknown".to_string() })),
                 right: Box::new(ExprType::Name(crate::Name { id: "unknown".to_string() })),
+                lineno: None,
+                col_offset: None,
+                end_lineno: None,
+                end_col_offset: None,
             }).into()),
         }
     }
@@ -93,6 +98,16 @@ impl PythonOperator for BinOps {
     }
 }
 
+impl BinOps {
+    /// True for the operators that act on the bits of integer operands.
+    pub fn is_bitwise(&self) -> bool {
+        matches!(
+            self,
+            BinOps::BitAnd | BinOps::BitOr | BinOps::BitXor | BinOps::LShift | BinOps::RShift
+        )
+    }
+}
+
 impl<'a> FromPyObject<'a> for BinOps {
     fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
         let err_msg = format!("Unimplemented binary op {}", dump(ob, None)?);
@@ -104,11 +119,17 @@ impl<'a> FromPyObject<'a> for BinOps {
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct BinOp {
-    op: BinOps,
-    left: Box<ExprType>,
-    right: Box<ExprType>,
+    pub op: BinOps,
+    pub left: Box<ExprType>,
+    pub right: Box<ExprType>,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
 }
 
+impl_node_with_positions!(BinOp { lineno, col_offset, end_lineno, end_col_offset });
+
 impl BinaryOperation for BinOp {
     type OperatorType = BinOps;
     
@@ -149,6 +170,10 @@ impl<'a> FromPyObject<'a> for BinOp {
             op,
             left: Box::new(left),
             right: Box::new(right),
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
         })
     }
 }
@@ -166,18 +191,59 @@ impl CodeGen for BinOp {
     ) -> std::result::Result<TokenStream, Box<dyn std::error::Error>> {
         // Special handling for Pow operator which needs different syntax
         if matches!(self.op, BinOps::Pow) {
+            return self.pow_to_rust(ctx, options, symbols);
+        }
+        
+        // Python floors where Rust truncates, which only differs for negative operands, so
+        // anything not known to be non-negative goes through a helper. Floats are floored
+        // in place.
+        if matches!(self.op, BinOps::FloorDiv | BinOps::Mod)
+            && !(is_non_negative_int(&self.left) && is_non_negative_int(&self.right))
+        {
+            let float_type = if is_float_operand(&self.left, &options, &symbols) || is_float_operand(&self.right, &options, &symbols) {
+                Some(options.float_type_tokens()?)
+            } else {
+                None
+            };
             let left = self.left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
             let right = self.right.clone().to_rust(ctx, options, symbols)?;
-            return Ok(quote!((#left).pow(#right)));
+            return Ok(match self.op {
+                BinOps::FloorDiv => floor_div_to_rust(&left, &right, float_type.as_ref()),
+                _ => mod_to_rust(&left, &right, float_type.as_ref()),
+            });
         }
-        
-        // For Div, we need to cast to f64
+
+        // Multiplying a str or a list by a count repeats it.
+        if matches!(self.op, BinOps::Mult)
+            && let Some(tokens) = self.repeat_to_rust(&ctx, &options, &symbols)?
+        {
+            return Ok(tokens);
+        }
+
+        if matches!(self.op, BinOps::MatMult) {
+            let left = self.left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            let right = self.right.clone().to_rust(ctx, options.clone(), symbols)?;
+            return Ok(matmul_to_rust(&left, &right, &options));
+        }
+
+        // True division of ints is a float, so both operands are cast to the float_type.
         if matches!(self.op, BinOps::Div) {
+            let float_type = options.float_type_tokens()?;
             let left = self.left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
             let right = self.right.clone().to_rust(ctx, options, symbols)?;
-            return Ok(quote!((#left) as f64 / (#right) as f64));
+            return Ok(quote!((#left) as #float_type / (#right) as #float_type));
         }
         
+        // Bitwise operators map one to one. Python integers are signed, so `>>` is an
+        // arithmetic shift, which is also what Rust does for the signed integer types
+        // we generate.
+        if self.op.is_bitwise() {
+            let left = self.left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            let right = self.right.clone().to_rust(ctx, options, symbols)?;
+            let op = self.op.to_rust_op()?;
+            return Ok(quote!((#left) #op (#right)));
+        }
+
         // Special handling for list addition (concatenation)
         if matches!(self.op, BinOps::Add) {
             let left = self.left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
@@ -198,15 +264,226 @@ impl CodeGen for BinOp {
             }
         }
         
+        // Arithmetic on two integer literals has nothing else to decide its type, which
+        // Rust would infer as i32 and overflow on, so the left one has the int_type's suffix.
+        if matches!(self.op, BinOps::Add | BinOps::Sub | BinOps::Mult)
+            && let (ExprType::Constant(left), ExprType::Constant(right)) = (self.left.as_ref(), self.right.as_ref())
+            && let (Some(left), Some(right)) = (left.int_to_rust(&options, true)?, right.int_to_rust(&options, false)?)
+        {
+            let op = self.op.to_rust_op()?;
+            return Ok(quote!((#left) #op (#right)));
+        }
+
         // Use the generic binary operation implementation for everything else
         self.generate_rust_code(ctx, options, symbols)
     }
 }
 
+/// Matrix multiplication has no Rust operator, so it's either `*` on the types of
+/// [`PythonOptions::matmul_crate`] or a call to a user supplied `matmul` function.
+pub fn matmul_to_rust(left: &TokenStream, right: &TokenStream, options: &PythonOptions) -> TokenStream {
+    match options.matmul_crate {
+        Some(_) => quote!((#left) * (#right)),
+        None => quote!(matmul(&(#left), &(#right))),
+    }
+}
+
+impl BinOp {
+    /// `s * n` and `n * s` become `s.repeat(n)` when `s` is known to be a str, and
+    /// `[x] * n` becomes `vec![x; n]`. Returns None for any other multiplication.
+    fn repeat_to_rust(
+        &self,
+        ctx: &CodeGenContext,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> std::result::Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+        let (sequence, count) = if is_sequence(&self.left, symbols) {
+            (self.left.as_ref(), self.right.as_ref())
+        } else if is_sequence(&self.right, symbols) {
+            (self.right.as_ref(), self.left.as_ref())
+        } else {
+            return Ok(None);
+        };
+
+        // Counts are usize in Rust, which integer literals already infer to.
+        let count = match count {
+            ExprType::Constant(c) if c.int_value().is_some() => {
+                count.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?
+            }
+            _ => {
+                let count = receiver_to_rust(count, ctx, options, symbols)?;
+                quote!(#count as usize)
+            }
+        };
+
+        match sequence {
+            ExprType::List(elements) if elements.len() == 1 => {
+                let element = elements[0].clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                Ok(Some(quote!(vec![#element; #count])))
+            }
+            _ => {
+                let sequence = receiver_to_rust(sequence, ctx, options, symbols)?;
+                Ok(Some(quote!(#sequence.repeat(#count))))
+            }
+        }
+    }
+
+    /// Rust has no power operator, so `x ** n` becomes a method call. Which one depends on
+    /// whether the operands are floats, which their annotations and constants tell, and
+    /// otherwise [`PythonOptions::default_pow_type`] does. An int to a negative power is a
+    /// float in Python, so a negative constant exponent of an int is unsupported unless the
+    /// base is a constant too, and an exponent that isn't constant is checked when it runs.
+    fn pow_to_rust(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> std::result::Result<TokenStream, Box<dyn std::error::Error>> {
+        let base = as_constant(&self.left);
+        let exponent = as_constant(&self.right);
+
+        if let (Some(base), Some(exponent)) = (base, exponent)
+            && let Some(folded) = fold_pow(base, exponent)
+        {
+            return Ok(folded);
+        }
+        if let (Some(base), Some(exponent)) = (base, negative_int(&self.right))
+            && let Some(folded) = fold_negative_pow(base, exponent)
+        {
+            return Ok(folded);
+        }
+
+        let left = self.left.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        if let Some(exponent) = exponent {
+            // Squaring a name is cheaper as a multiplication, and evaluating it twice is harmless.
+            if exponent.int_value() == Some(2) && matches!(self.left.as_ref(), ExprType::Name(_)) {
+                return Ok(quote!((#left) * (#left)));
+            }
+            if exponent.is_float() && exponent.float_value() == Some(0.5) {
+                return Ok(quote!(((#left) as f64).sqrt()));
+            }
+        }
+
+        let location = self.source_location(UNKNOWN_FILE);
+        let negative_exponent = negative_int(&self.right);
+        let int_exponent = exponent.is_some_and(|e| e.int_value().is_some()) || negative_exponent.is_some();
+        let known_ints = is_int_operand(&self.left, &options, &symbols) && is_int_operand(&self.right, &options, &symbols);
+        let is_float = is_float_operand(&self.left, &options, &symbols)
+            || is_float_operand(&self.right, &options, &symbols)
+            || (!known_ints && options.default_pow_type == PowType::Float);
+        let right = self.right.clone().to_rust(ctx, options.clone(), symbols)?;
+        let float_type = options.float_type_tokens()?;
+
+        Ok(match (is_float, int_exponent) {
+            (true, true) => quote!(((#left) as #float_type).powi(#right)),
+            (true, false) => quote!(((#left) as #float_type).powf((#right) as #float_type)),
+            (false, true) if negative_exponent.is_some() => {
+                return Err(CodeGenError::unsupported(
+                    "raising an int to a negative power (a float in Python)",
+                    Some(location),
+                )
+                .into());
+            }
+            (false, true) => quote!((#left).pow(#right)),
+            (false, false) => quote!((#left).pow(u32::try_from(#right).expect("negative exponent"))),
+        })
+    }
+}
+
+/// Whether `expr` is a str or a list literal, which `*` repeats.
+fn is_sequence(expr: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    matches!(expr, ExprType::List(_)) || is_str_expr(expr, symbols)
+}
+
+/// Generates Python's `//`, which floors the quotient, for ints with the
+/// `python_floor_div` helper, and otherwise for floats of `float_type`.
+pub fn floor_div_to_rust(left: &TokenStream, right: &TokenStream, float_type: Option<&TokenStream>) -> TokenStream {
+    match float_type {
+        Some(float_type) => quote!(((#left) as #float_type / (#right) as #float_type).floor()),
+        None => quote!(python_floor_div(#left, #right)),
+    }
+}
+
+/// Generates Python's `%`, which takes the sign of the divisor, for ints with the
+/// `python_mod` helper, and otherwise for floats of `float_type`.
+pub fn mod_to_rust(left: &TokenStream, right: &TokenStream, float_type: Option<&TokenStream>) -> TokenStream {
+    match float_type {
+        Some(float_type) => quote!({
+            let (a, b) = ((#left) as #float_type, (#right) as #float_type);
+            a - b * (a / b).floor()
+        }),
+        None => quote!(python_mod(#left, #right)),
+    }
+}
+
+/// Whether `expr` is known to be a float, from its annotation or what it's assigned.
+pub fn is_float_operand(expr: &ExprType, options: &PythonOptions, symbols: &SymbolTableScopes) -> bool {
+    match expr {
+        ExprType::UnaryOp(UnaryOp { op: Ops::USub | Ops::UAdd, operand }) => is_float_operand(operand, options, symbols),
+        _ => truthiness(expr, options, symbols) == Truthiness::Float,
+    }
+}
+
+/// Whether `expr` is known to be an int, from its annotation or what it's assigned.
+fn is_int_operand(expr: &ExprType, options: &PythonOptions, symbols: &SymbolTableScopes) -> bool {
+    negative_int(expr).is_some() || truthiness(expr, options, symbols) == Truthiness::Int
+}
+
+/// The value of a negated int constant, like `-2`.
+fn negative_int(expr: &ExprType) -> Option<i64> {
+    match expr {
+        ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand }) => as_constant(operand)
+            .and_then(Constant::int_value)
+            .filter(|i| *i > 0)
+            .map(|i| -i),
+        _ => None,
+    }
+}
+
+fn is_non_negative_int(expr: &ExprType) -> bool {
+    as_constant(expr).and_then(Constant::int_value).is_some_and(|i| i >= 0)
+}
+
+fn as_constant(expr: &ExprType) -> Option<&Constant> {
+    match expr {
+        ExprType::Constant(c) => Some(c),
+        _ => None,
+    }
+}
+
+/// Computes `base ** exponent` for two numeric constants, unless the result can't be
+/// represented as a literal.
+fn fold_pow(base: &Constant, exponent: &Constant) -> Option<TokenStream> {
+    if let (Some(base), Some(exponent)) = (base.int_value(), exponent.int_value()) {
+        let value = base.checked_pow(u32::try_from(exponent).ok()?)?;
+        let value = proc_macro2::Literal::i64_unsuffixed(value);
+        return Some(quote!(#value));
+    }
+
+    let value = base.float_value()?.powf(exponent.float_value()?);
+    if !value.is_finite() {
+        return None;
+    }
+    let value = proc_macro2::Literal::f64_unsuffixed(value);
+    Some(quote!(#value))
+}
+
+/// Computes `base ** exponent` for a numeric constant to a negative int, which is a float
+/// in Python even when the base is an int, so `2 ** -1` is `0.5`.
+fn fold_negative_pow(base: &Constant, exponent: i64) -> Option<TokenStream> {
+    let value = base.float_value()?.powi(i32::try_from(exponent).ok()?);
+    if !value.is_finite() {
+        return None;
+    }
+    let value = proc_macro2::Literal::f64_unsuffixed(value);
+    Some(quote!(#value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::create_parse_test;
+    use crate::test_utils::{expr_to_rust, module_to_rust, test_options};
 
     create_parse_test!(test_add, "1 + 2", "test_case.py");
     create_parse_test!(test_subtract, "1 - 2", "test_case.py");
@@ -214,7 +491,182 @@ mod tests {
     create_parse_test!(test_divide, "8 / 2", "test_case.py");
     create_parse_test!(test_power, "2 ** 3", "test_case.py");
     create_parse_test!(test_modulo, "10 % 3", "test_case.py");
+
+    #[test]
+    fn test_repetition() {
+        assert_eq!(expr_to_rust("\"abc\" * 3", PythonOptions::default()), "\"abc\" . repeat (3)");
+        assert_eq!(expr_to_rust("3 * \"abc\"", PythonOptions::default()), "\"abc\" . repeat (3)");
+        assert_eq!(expr_to_rust("\"-\" * width", PythonOptions::default()), "\"-\" . repeat (width as usize)");
+        assert_eq!(expr_to_rust("[0] * n", PythonOptions::default()), "vec ! [0 ; n as usize]");
+        assert!(expr_to_rust("[1, 2] * 2", PythonOptions::default()).ends_with("] . repeat (2)"));
+        assert_eq!(expr_to_rust("a * 3", PythonOptions::default()), "(a) * (3)");
+    }
+
+    #[test]
+    fn test_repetition_of_str_variables() {
+        let code = module_to_rust("def line(s: str, n: int):\n    return s * n\n", test_options());
+        assert!(code.contains("{ s . repeat (n as usize) }"), "{}", code);
+    }
+
+    #[test]
+    fn test_matmul() {
+        assert_eq!(expr_to_rust("a @ b", PythonOptions::default()), "matmul (& (a) , & (b))");
+
+        let mut options = PythonOptions::default();
+        options.matmul_crate = Some("nalgebra".to_string());
+        assert_eq!(expr_to_rust("a @ b", options.clone()), "(a) * (b)");
+
+        options.with_std_python = false;
+        let code = module_to_rust("def f(a, b):\n    return a @ b\n", options);
+        assert!(code.starts_with("use nalgebra :: * ;"), "{}", code);
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(expr_to_rust("a & b", PythonOptions::default()), "(a) & (b)");
+        assert_eq!(expr_to_rust("a | b", PythonOptions::default()), "(a) | (b)");
+        assert_eq!(expr_to_rust("a ^ b", PythonOptions::default()), "(a) ^ (b)");
+        assert_eq!(expr_to_rust("a << 2", PythonOptions::default()), "(a) << (2)");
+        assert_eq!(expr_to_rust("a >> 2", PythonOptions::default()), "(a) >> (2)");
+        assert_eq!(expr_to_rust("~a", PythonOptions::default()), "! a");
+    }
+
+    #[test]
+    fn test_bitwise_precedence_is_preserved() {
+        assert_eq!(expr_to_rust("a & b | c", PythonOptions::default()), "((a) & (b)) | (c)");
+        assert_eq!(expr_to_rust("a & (b | c)", PythonOptions::default()), "(a) & ((b) | (c))");
+    }
     
+    #[test]
+    fn test_pow() {
+        assert_eq!(expr_to_rust("x ** n", PythonOptions::default()), "(x) . pow (u32 :: try_from (n) . expect (\"negative exponent\"))");
+        assert_eq!(expr_to_rust("x ** 3", PythonOptions::default()), "(x) . pow (3)");
+        assert_eq!(expr_to_rust("x ** 1.5", PythonOptions::default()), "((x) as f64) . powf ((1.5) as f64)");
+        assert_eq!(expr_to_rust("2.5 ** n", PythonOptions::default()), "((2.5) as f64) . powf ((n) as f64)");
+    }
+
+    #[test]
+    fn test_pow_default_type() {
+        let mut options = PythonOptions::default();
+        options.default_pow_type = PowType::Float;
+        assert_eq!(expr_to_rust("x ** n", options.clone()), "((x) as f64) . powf ((n) as f64)");
+        assert_eq!(expr_to_rust("x ** 3", options), "((x) as f64) . powi (3)");
+    }
+
+    #[test]
+    fn test_pow_special_cases() {
+        assert_eq!(expr_to_rust("x ** 2", PythonOptions::default()), "(x) * (x)");
+        assert_eq!(expr_to_rust("f(x) ** 2", PythonOptions::default()), "(f (x)) . pow (2)");
+        assert_eq!(expr_to_rust("x ** 0.5", PythonOptions::default()), "((x) as f64) . sqrt ()");
+    }
+
+    #[test]
+    fn test_pow_of_constants_is_precomputed() {
+        assert_eq!(expr_to_rust("2 ** 10", PythonOptions::default()), "1024");
+        assert_eq!(expr_to_rust("4 ** 0.5", PythonOptions::default()), "2.0");
+        assert_eq!(expr_to_rust("1.5 ** 2", PythonOptions::default()), "2.25");
+        // An int to a negative power is a float.
+        assert_eq!(expr_to_rust("2 ** -1", PythonOptions::default()), "0.5");
+        assert_eq!(expr_to_rust("2.0 ** -2", PythonOptions::default()), "0.25");
+        // Too big for an i64, so it's left to run.
+        assert_eq!(expr_to_rust("10 ** 30", PythonOptions::default()), "(10) . pow (30)");
+    }
+
+    #[test]
+    fn test_floor_div_and_mod() {
+        assert_eq!(expr_to_rust("a // b", PythonOptions::default()), "python_floor_div (a , b)");
+        assert_eq!(expr_to_rust("-7 // 2", PythonOptions::default()), "python_floor_div (- 7 , 2)");
+        assert_eq!(expr_to_rust("a % b", PythonOptions::default()), "python_mod (a , b)");
+        // Non-negative operands agree with Rust's operators.
+        assert_eq!(expr_to_rust("7 // 2", PythonOptions::default()), "(7) / (2)");
+        assert_eq!(expr_to_rust("7 % 2", PythonOptions::default()), "(7) % (2)");
+    }
+
+    #[test]
+    fn test_floor_div_helper_is_generated() {
+        let code = module_to_rust("def f(a, b):\n    return a // b", test_options());
+        assert!(code.contains("fn python_floor_div (a : i64 , b : i64) -> i64"), "{}", code);
+        assert!(!code.contains("fn python_mod"), "{}", code);
+    }
+
+    #[test]
+    fn test_negative_operands_floor() {
+        // Python floors toward negative infinity and takes the sign of the divisor, where
+        // Rust truncates and takes the sign of the dividend.
+        assert_eq!(expr_to_rust("7 // -2", PythonOptions::default()), "python_floor_div (7 , - 2)");
+        assert_eq!(expr_to_rust("-7 % 2", PythonOptions::default()), "python_mod (- 7 , 2)");
+        assert_eq!(expr_to_rust("7 % -2", PythonOptions::default()), "python_mod (7 , - 2)");
+        assert_eq!(expr_to_rust("-7 / 2", PythonOptions::default()), "(- 7) as f64 / (2) as f64");
+    }
+
+    #[test]
+    fn test_int_type_decides_the_helpers() {
+        let generate = |int_type: &str, float_type: &str| {
+            let mut options = test_options();
+            options.int_type = int_type.to_string();
+            options.float_type = float_type.to_string();
+            module_to_rust("def f(a: int, b: int):\n    return a // b + a % b + 7 + a / b", options)
+        };
+
+        let code = generate("i32", "f32");
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub fn f (a : i32 , b : i32)"), "{}", code);
+        assert!(code.contains("(a) as f32 / (b) as f32"), "{}", code);
+        assert!(code.contains("fn python_floor_div (a : i32 , b : i32) -> i32"), "{}", code);
+        assert!(code.contains("fn python_mod (a : i32 , b : i32) -> i32 { ((a % b) + b) % b }"), "{}", code);
+
+        let code = generate("bigint", "f64");
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub fn f (a : num_bigint :: BigInt , b : num_bigint :: BigInt)"), "{}", code);
+        assert!(code.contains("num_bigint :: BigInt :: from (7i64)"), "{}", code);
+        assert!(code.contains("let zero = num_bigint :: BigInt :: default () ; let d = & a / & b ;"), "{}", code);
+        assert!(code.contains("((& a % & b) + & b) % & b"), "{}", code);
+    }
+
+    #[test]
+    fn test_negative_float_operands_floor() {
+        let code = module_to_rust("def f(x: float, y: float):\n    return x // y, x % y, -7.5 // 2\n", test_options());
+        assert!(code.contains("((x) as f64 / (y) as f64) . floor ()"), "{}", code);
+        assert!(code.contains("{ let (a , b) = ((x) as f64 , (y) as f64) ; a - b * (a / b) . floor () }"), "{}", code);
+        assert!(code.contains("((- 7.5) as f64 / (2) as f64) . floor ()"), "{}", code);
+        assert!(!code.contains("python_floor_div"), "{}", code);
+    }
+
+    #[test]
+    fn test_divmod() {
+        let code = module_to_rust("def f(a: int, b: int, x: float):\n    return divmod(a, -b), divmod(x, 2)\n", test_options());
+        assert!(code.contains("{ let (a , b) = (a , - b) ; (python_floor_div (a , b) , python_mod (a , b)) }"), "{}", code);
+        assert!(code.contains("{ let (a , b) = (x , 2) ; (((a) as f64 / (b) as f64) . floor () ,"), "{}", code);
+        assert!(code.contains("fn python_floor_div") && code.contains("fn python_mod"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_pow_operand_types() {
+        let code = module_to_rust("def f(x: float, n: int, m: int):\n    return x ** n, n ** m, 2.0 ** -1\n", test_options());
+        assert!(code.contains("((x) as f64) . powf ((n) as f64)"), "{}", code);
+        assert!(code.contains("(n) . pow (u32 :: try_from (m) . expect (\"negative exponent\"))"), "{}", code);
+        assert!(code.contains("0.5"), "{}", code);
+
+        // An int to a negative power is a float in Python.
+        let code = module_to_rust("def f(n: int):\n    return n ** -2\n", test_options());
+        assert!(code.contains("FAILED: raising an int to a negative power (a float in Python) is not supported"), "{}", code);
+    }
+
+    #[test]
+    fn test_int_literal_that_does_not_fit_is_an_error() {
+        let module = crate::parse("x = 1\ny = 3000000000\n", "big.py").unwrap();
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.int_type = "i32".to_string();
+        let error = module
+            .to_rust(CodeGenContext::Module("big".to_string()), options, SymbolTableScopes::new())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("expected i32, found the integer 3000000000"), "{}", error);
+        assert!(error.contains("big.py:2"), "{}", error);
+    }
+
     #[test]
     fn test_operator_precedence() {
         let add_op = BinOps::Add;
@@ -4,7 +4,7 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    dump, CodeGen, CodeGenContext, Error, ExprType, Node, PythonOptions, SymbolTableScopes,
+    dump, condition_to_rust, CodeGen, CodeGenContext, Error, ExprType, Node, PythonOptions, SymbolTableScopes,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -40,9 +40,9 @@ impl<'a> FromPyObject<'a> for BoolOps {
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct BoolOp {
-    op: BoolOps,
-    left: Box<ExprType>,
-    right: Box<ExprType>,
+    pub op: BoolOps,
+    pub left: Box<ExprType>,
+    pub right: Box<ExprType>,
 }
 
 impl<'a> FromPyObject<'a> for BoolOp {
@@ -110,14 +110,9 @@ impl<'a> CodeGen for BoolOp {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let left = self
-            .left
-            .clone()
-            .to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-        let right = self
-            .right
-            .clone()
-            .to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        // The operands are tested like conditions are.
+        let left = condition_to_rust(*self.left.clone(), ctx.clone(), options.clone(), symbols.clone())?;
+        let right = condition_to_rust(*self.right.clone(), ctx.clone(), options.clone(), symbols.clone())?;
             
         // Python's boolean operators are different from Rust's - they return operands, not booleans
         // For now, we'll use a simplified approach that works for common cases
@@ -0,0 +1,68 @@
+use proc_macro2::TokenStream;
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+
+use crate::{CodeGen, CodeGenContext, PythonOptions, SymbolTableScopes};
+
+/// A bytes constant, like `b"\x00\xff"`. It keeps the bytes themselves, which don't have
+/// to be valid UTF-8, so they serialize as a list of numbers rather than as text.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[repr(transparent)]
+pub struct Bytes(pub Vec<u8>);
+
+impl<'a> FromPyObject<'a> for Bytes {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let value = ob.getattr("value")?;
+        let value: &[u8] = value.extract()?;
+        Ok(Bytes(value.to_vec()))
+    }
+}
+
+impl Bytes {
+    /// The bytes as an owned `Vec<u8>`, for `bytearray`s.
+    pub fn to_vec_rust(&self) -> TokenStream {
+        let literal = proc_macro2::Literal::byte_string(&self.0);
+        quote!(#literal.to_vec())
+    }
+}
+
+impl CodeGen for Bytes {
+    type Context = CodeGenContext;
+    type Options = PythonOptions;
+    type SymbolTable = SymbolTableScopes;
+
+    fn to_rust(
+        self,
+        _ctx: Self::Context,
+        _options: Self::Options,
+        _symbols: Self::SymbolTable,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // A byte string literal escapes every byte that isn't printable ASCII.
+        let literal = proc_macro2::Literal::byte_string(&self.0);
+        Ok(quote!(#literal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::expr_to_rust;
+
+    #[test]
+    fn test_bytes_are_kept_byte_for_byte() {
+        let module = crate::parse("b'\\x00\\x01\\xff\"'", "test.py").unwrap();
+        match &module.raw.body[0].statement {
+            crate::StatementType::Expr(e) => assert_eq!(e.value, crate::ExprType::Bytes(Bytes(vec![0, 1, 0xff, b'"']))),
+            s => panic!("expected an expression statement, got {:?}", s),
+        }
+        assert_eq!(expr_to_rust("b'\\x00\\x01\\xff\"'", PythonOptions::default()), "b\"\\0\\x01\\xFF\\\"\"");
+    }
+
+    #[test]
+    fn test_bytes_serialize_byte_safely() {
+        let bytes = crate::ExprType::Bytes(Bytes(vec![0x80, 0xff]));
+        let pickled = serde_pickle::to_vec(&bytes, Default::default()).unwrap();
+        assert_eq!(serde_pickle::from_slice::<crate::ExprType>(&pickled, Default::default()).unwrap(), bytes);
+    }
+}
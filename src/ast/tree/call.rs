@@ -3,13 +3,17 @@ use pyo3::{Bound, FromPyObject, PyAny, PyResult};
 use quote::quote;
 use serde::{Deserialize, Serialize};
 
-use crate::{CodeGen, CodeGenContext, ExprType, Keyword, PythonOptions, SymbolTableScopes, extract_required_attr};
+use crate::{impl_node_with_positions, is_str_annotation, Arguments, Parameter, CodeGen, CodeGenContext, CodeGenError, SymbolTableNode, ExprType, Keyword, PythonOptions, SymbolTableScopes, UnknownKeywords, UNKNOWN_FILE, extract_required_attr, lookup_builtin, method_call_to_rust, instance_class, receiver_class, MUTATING_METHODS, Node, StatementType};
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Call {
     pub func: Box<ExprType>,
     pub args: Vec<ExprType>,
     pub keywords: Vec<Keyword>,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
 }
 
 impl<'a> FromPyObject<'a> for Call {
@@ -22,6 +26,219 @@ impl<'a> FromPyObject<'a> for Call {
             func: Box::new(func),
             args,
             keywords,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
+        })
+    }
+}
+
+impl_node_with_positions!(Call { lineno, col_offset, end_lineno, end_col_offset });
+
+impl Call {
+    /// The parameters of the function, class or method of the module the call calls, by
+    /// which its arguments are matched up.
+    fn callee_parameters(&self, symbols: &SymbolTableScopes) -> Option<(String, Arguments)> {
+        // A method of an instance whose class is known takes the arguments after `self`.
+        if let ExprType::Attribute(attribute) = self.func.as_ref() {
+            let class = receiver_class(&attribute.value, symbols)?;
+            return class.body.iter().find_map(|statement| match &statement.statement {
+                StatementType::FunctionDef(method) if method.name == attribute.attr && method.args.without_receiver().1 => {
+                    Some((method.name.clone(), method.args.without_receiver().0))
+                }
+                _ => None,
+            });
+        }
+        let ExprType::Name(func_name) = self.func.as_ref() else {
+            return None;
+        };
+        match symbols.lookup(&func_name.id) {
+            Some(SymbolTableNode::FunctionDef(function)) => Some((func_name.id.clone(), function.args.clone())),
+            Some(SymbolTableNode::ClassDef { class, .. }) => match class.init() {
+                Some(init) => Some((func_name.id.clone(), init.args.without_receiver().0)),
+                None => class.field_parameters(symbols).map(|parameters| (func_name.id.clone(), parameters)),
+            },
+            _ => None,
+        }
+    }
+
+    /// The arguments of the call in the order of the parameters of `function`, which
+    /// Rust passes them in. Keyword arguments go where their parameter is, and an
+    /// argument for a parameter with a default is an `Option`, `None` if it isn't given.
+    ///
+    /// The positional arguments left over after the parameters are the `*args`, and the
+    /// keyword arguments without a parameter of their name are the `**kwargs`, each of
+    /// which the call can unpack into: `f(*items)` passes `items` as the `*args`, and
+    /// `f(**options)` passes `options` as the `**kwargs`. Unpacking into the other
+    /// parameters fails, since which of them the values are for isn't known.
+    fn bind_arguments(
+        &self,
+        function: &str,
+        parameters: &Arguments,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<Vec<TokenStream>, Box<dyn std::error::Error>> {
+        let location = self.source_location(UNKNOWN_FILE);
+        let invalid = |message: String| -> Box<dyn std::error::Error> {
+            CodeGenError::invalid_call(function, message, Some(location.clone())).into()
+        };
+
+        // (parameter, has a default, can be passed by keyword), in the order of the signature.
+        let defaults_offset = parameters.args.len().saturating_sub(parameters.defaults.len());
+        let slots: Vec<(&Parameter, bool, bool)> = parameters
+            .posonlyargs
+            .iter()
+            .map(|p| (p, false, false))
+            .chain(parameters.args.iter().enumerate().map(|(i, p)| (p, i >= defaults_offset, true)))
+            .chain(
+                parameters
+                    .kwonlyargs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (p, parameters.kw_defaults.get(i).is_some_and(Option::is_some), true)),
+            )
+            .collect();
+        let positional = parameters.posonlyargs.len() + parameters.args.len();
+        let unpacked = self.args.iter().enumerate().find_map(|(i, arg)| match arg {
+            ExprType::Starred(starred) if i < positional || parameters.vararg.is_none() => Some(starred),
+            _ => None,
+        });
+        if let Some(starred) = unpacked {
+            let value = starred.value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            return Err(invalid(format!("it can't unpack *{} into the parameters it takes by position", value)));
+        }
+        let extra = self.args.get(positional..).unwrap_or_default();
+        if !extra.is_empty() && parameters.vararg.is_none() {
+            return Err(invalid(format!(
+                "it takes {} positional arguments but {} were given",
+                positional,
+                self.args.len()
+            )));
+        }
+        let mut extra_keywords = Vec::new();
+        for keyword in &self.keywords {
+            let Some(name) = keyword.arg.as_deref() else {
+                if parameters.kwarg.is_none() {
+                    return Err(invalid("it takes no **kwargs to unpack keyword arguments into".to_string()));
+                }
+                extra_keywords.push(keyword);
+                continue;
+            };
+            match slots.iter().position(|(slot, _, _)| slot.arg == name) {
+                Some(i) if i < self.args.len().min(positional) => {
+                    return Err(invalid(format!("it got multiple values for the argument '{}'", name)));
+                }
+                Some(i) if slots[i].2 => (),
+                _ if parameters.kwarg.is_some() => extra_keywords.push(keyword),
+                _ => return Err(invalid(format!("it has no parameter '{}' to pass by keyword", name))),
+            }
+        }
+
+        let mut arguments = Vec::new();
+        for (i, (parameter, has_default, _)) in slots.into_iter().enumerate() {
+            // The `*args` come right after the positional parameters.
+            if i == positional && parameters.vararg.is_some() {
+                arguments.push(self.unpacked_to_rust(extra, ctx.clone(), options.clone(), symbols.clone())?);
+            }
+            let value = self.args.get(i).filter(|_| i < positional).or_else(|| {
+                self.keywords
+                    .iter()
+                    .find(|keyword| keyword.arg.as_ref() == Some(&parameter.arg))
+                    .map(|keyword| &keyword.value)
+            });
+            let value = match value {
+                // A string constant has the type of `str` parameters.
+                Some(ExprType::Constant(constant))
+                    if parameter.annotation.as_ref().is_some_and(|annotation| is_str_annotation(annotation, &symbols))
+                        && constant.string_value().is_some() =>
+                {
+                    constant.string_to_rust(&options)
+                }
+                Some(value) => Some(value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?),
+                None => None,
+            };
+            let argument = match (value, has_default) {
+                (Some(value), false) => value,
+                (Some(value), true) => quote!(Some(#value)),
+                (None, true) => quote!(None),
+                (None, false) => return Err(invalid(format!("the argument '{}' is missing", parameter.arg))),
+            };
+            arguments.push(argument);
+        }
+        if arguments.len() == positional && parameters.vararg.is_some() {
+            arguments.push(self.unpacked_to_rust(extra, ctx.clone(), options.clone(), symbols.clone())?);
+        }
+        if parameters.kwarg.is_some() {
+            arguments.push(Self::keywords_to_rust(&extra_keywords, ctx, options, symbols)?);
+        }
+        Ok(arguments)
+    }
+
+    /// The positional arguments a call passes as the callee's `*args`, as an iterator. The
+    /// ones it unpacks are passed as they are, and the others are collected into Vecs.
+    fn unpacked_to_rust(
+        &self,
+        arguments: &[ExprType],
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let mut parts = Vec::new();
+        let mut items = Vec::new();
+        for argument in arguments {
+            match argument {
+                ExprType::Starred(starred) => {
+                    if !items.is_empty() {
+                        parts.push(quote!(vec![#(#items),*]));
+                        items.clear();
+                    }
+                    parts.push(starred.value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?);
+                }
+                argument => items.push(argument.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?),
+            }
+        }
+        if !items.is_empty() {
+            parts.push(quote!(vec![#(#items),*]));
+        }
+        Ok(match parts.split_first() {
+            None => quote!(std::iter::empty::<PyObject>()),
+            Some((first, [])) => quote!(#first),
+            Some((first, rest)) => quote!((#first).into_iter()#(.chain(#rest))*),
+        })
+    }
+
+    /// The keyword arguments a call passes as the callee's `**kwargs`, as an iterator of
+    /// names and values, like the unpacked arguments.
+    fn keywords_to_rust(
+        keywords: &[&Keyword],
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let mut parts = Vec::new();
+        let mut items = Vec::new();
+        for keyword in keywords {
+            let value = keyword.value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            match &keyword.arg {
+                Some(name) => items.push(quote!((#name, #value))),
+                None => {
+                    if !items.is_empty() {
+                        parts.push(quote!(vec![#(#items),*]));
+                        items.clear();
+                    }
+                    parts.push(value);
+                }
+            }
+        }
+        if !items.is_empty() {
+            parts.push(quote!(vec![#(#items),*]));
+        }
+        Ok(match parts.split_first() {
+            None => quote!(std::iter::empty::<(&str, PyObject)>()),
+            Some((first, [])) => quote!(#first),
+            Some((first, rest)) => quote!((#first).into_iter()#(.chain(#rest))*),
         })
     }
 }
@@ -31,28 +248,112 @@ impl<'a> CodeGen for Call {
     type Options = PythonOptions;
     type SymbolTable = SymbolTableScopes;
 
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = symbols;
+        // Calling a method like append() on a variable changes it in place.
+        if let ExprType::Attribute(attribute) = self.func.as_ref()
+            && let ExprType::Name(receiver) = attribute.value.as_ref()
+            && MUTATING_METHODS.contains(&attribute.attr.as_str())
+        {
+            symbols.mark_mutated(&receiver.id);
+        }
+        // So does calling a method of its class that takes `&mut self`.
+        if let ExprType::Attribute(attribute) = self.func.as_ref()
+            && let ExprType::Name(receiver) = attribute.value.as_ref()
+            && let Some(ExprType::Name(class)) = match symbols.lookup(&receiver.id) {
+                Some(SymbolTableNode::Assign { value, .. }) => instance_class(value, &symbols),
+                _ => symbols.type_annotation(&receiver.id).cloned(),
+            }
+            && matches!(symbols.lookup(&class.id), Some(SymbolTableNode::ClassDef { mutating_methods, .. })
+                if mutating_methods.contains(&attribute.attr))
+        {
+            symbols.mark_mutated(&receiver.id);
+        }
+        let symbols = self.func.find_symbols(symbols);
+        self.args.into_iter().fold(symbols, |acc, arg| arg.find_symbols(acc))
+    }
+
     fn to_rust(
         self,
         ctx: Self::Context,
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let name = self.func.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-        
-        let mut all_args = Vec::new();
-        
-        // Add positional arguments
-        for arg in self.args {
-            let rust_arg = arg.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-            all_args.push(rust_arg);
+        // Builtins like print() get dedicated code generation unless the user shadowed them.
+        if let ExprType::Name(func_name) = self.func.as_ref()
+            && let Some(builtin) = lookup_builtin(&func_name.id, &symbols)
+            && let Some(tokens) = (builtin.generate)(&self, ctx.clone(), options.clone(), symbols.clone())?
+        {
+            return Ok(tokens);
         }
-        
-        // Add keyword arguments
-        for keyword in self.keywords {
-            let rust_kw = keyword.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-            all_args.push(rust_kw);
+
+        // So do methods of values with a known type, like str.
+        if let Some(tokens) = method_call_to_rust(&self, ctx.clone(), options.clone(), symbols.clone())? {
+            return Ok(tokens);
         }
-        
+
+        // Calling a function of the module that returns a Result propagates its error where
+        // the caller can, and panics like an uncaught exception where it can't.
+        let raises = matches!(self.func.as_ref(), ExprType::Name(func_name)
+            if matches!(symbols.lookup(&func_name.id), Some(SymbolTableNode::FunctionDef(f)) if f.returns_result(&symbols, &options)));
+        let propagate = match (raises, options.state.returns_result) {
+            (false, _) => quote!(),
+            (true, true) => quote!(?),
+            (true, false) => quote!(.unwrap()),
+        };
+
+        // Instantiating a class with an `__init__`, or a dataclass, calls the constructor
+        // generated for it, and a NamedTuple or TypedDict is made by its struct's.
+        let constructor = match self.func.as_ref() {
+            ExprType::Name(func_name) => match symbols.lookup(&func_name.id) {
+                Some(SymbolTableNode::ClassDef { class, .. }) if class.init().is_some() || class.dataclass(&symbols).is_some() => {
+                    Some(quote!(Data::new))
+                }
+                Some(SymbolTableNode::ClassDef { class, .. }) if class.record_kind().is_some() => Some(quote!(new)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let bound = match self.callee_parameters(&symbols) {
+            Some((function, parameters)) => {
+                Some(self.bind_arguments(&function, &parameters, ctx.clone(), options.clone(), symbols.clone())?)
+            }
+            None => None,
+        };
+        let location = self.source_location(UNKNOWN_FILE);
+        let name = self.func.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let name = match constructor {
+            Some(constructor) => quote!(#name::#constructor),
+            None => name,
+        };
+
+        let all_args = match bound {
+            Some(arguments) => arguments,
+            None => {
+                if options.unknown_keywords == UnknownKeywords::Error && !self.keywords.is_empty() {
+                    return Err(CodeGenError::unsupported(
+                        "keyword arguments to a callee whose parameters aren't known",
+                        Some(location),
+                    )
+                    .into());
+                }
+                let mut all_args = Vec::new();
+
+                // Add positional arguments
+                for arg in self.args {
+                    let rust_arg = arg.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                    all_args.push(rust_arg);
+                }
+
+                // Add keyword arguments
+                for keyword in self.keywords {
+                    let rust_kw = keyword.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                    all_args.push(rust_kw);
+                }
+                all_args
+            }
+        };
+
         // Check if we're in an async context and if the function being called is async
         let call_expr = quote!(#name(#(#all_args),*));
         
@@ -102,21 +403,21 @@ impl<'a> CodeGen for Call {
         };
         
         match ctx {
-            CodeGenContext::Async(_) => {
+            _ if ctx.is_async() => {
                 // In async context, we assume Python async functions need .await
                 // We'll check if the function name suggests it's async
                 if name_str.contains("async") || 
                    name_str.starts_with("a") || // Common async function naming
                    // TODO: Better async function detection based on symbol table
                    false {
-                    Ok(quote!(#final_call.await))
+                    Ok(quote!(#final_call.await #propagate))
                 } else {
                     // For now, just return the regular call
                     // In a full implementation, we'd track which functions are async
-                    Ok(final_call)
+                    Ok(quote!(#final_call #propagate))
                 }
             },
-            _ => Ok(final_call)
+            _ => Ok(quote!(#final_call #propagate))
         }
     }
 }
@@ -124,6 +425,7 @@ impl<'a> CodeGen for Call {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{assert_compiles, module_to_rust, test_options, try_module_to_rust};
 
     #[test]
     fn test_lookup_of_function() {
@@ -136,12 +438,126 @@ foo(b=9)",
             "test.py",
         )
         .unwrap();
-        let _code = result
+        // foo() has no parameter b.
+        let error = result
             .to_rust(
                 CodeGenContext::Module("test".to_string()),
                 options,
                 SymbolTableScopes::new(),
             )
-            .unwrap();
+            .unwrap_err();
+        assert!(
+            matches!(error.downcast_ref::<CodeGenError>(), Some(CodeGenError::InvalidCall { function, .. }) if function == "foo"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_keyword_arguments_are_passed_in_parameter_order() {
+        let code = try_module_to_rust(
+            "def area(width: int, height: int, scale: int = 1, *, unit: str = 'cm') -> int:
+    return width * height * scale
+
+def main():
+    area(height=2, width=3)
+    area(4, scale=2, height=5)
+    area(1, 2, 3, unit='mm')
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("area (3 , 2 , None , None)"), "{}", code);
+        assert!(code.contains("area (4 , 5 , Some (2) , None)"), "{}", code);
+        assert!(code.contains("area (1 , 2 , Some (3) , Some (String :: from (\"mm\")))"), "{}", code);
+    }
+
+    #[test]
+    fn test_defaults_fill_parameters_passed_none() {
+        let code = module_to_rust(
+            "def k(a, b=2):
+    return a + b
+
+def label(name: str, suffix: str = '!', *, times: int = 1) -> str:
+    return name + suffix * times
+
+def main():
+    k(1)
+    k(1, 5)
+    label('hi', times=2)
+",
+            test_options(),
+        );
+        assert!(code.contains("pub fn k (a : impl Into < PyObject > , b : Option < i64 >) { let b = b . unwrap_or_else (|| 2) ;"), "{}", code);
+        assert!(code.contains("let suffix = suffix . unwrap_or_else (|| String :: from (\"!\")) ; let times = times . unwrap_or_else (|| 1) ;"), "{}", code);
+
+        let code = module_to_rust(
+            "def k(a: int, b: int = 2) -> int:
+    return a + b
+
+def main():
+    print(k(1), k(1, b=5))
+",
+            test_options(),
+        );
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_invalid_keyword_arguments() {
+        let source = "def pair(left, right):
+    pass
+
+pair(1, left=2)
+";
+        let error = try_module_to_rust(source, test_options()).unwrap_err();
+        assert!(error.to_string().contains("multiple values for the argument 'left'"), "{}", error);
+        let error = try_module_to_rust("def pair(left, right):
+    pass
+
+pair(right=2)
+", test_options()).unwrap_err();
+        assert!(error.to_string().contains("the argument 'left' is missing"), "{}", error);
+
+        // Without the callee's parameters, the values are passed as they're written,
+        // unless that's an error.
+        let code = try_module_to_rust("make(size=2, color=1)
+", test_options()).unwrap();
+        assert!(code.contains("make (2 , 1)"), "{}", code);
+        let mut options = test_options();
+        options.unknown_keywords = UnknownKeywords::Error;
+        let code = module_to_rust("def f():
+    make(size=2)
+", options);
+        assert!(code.contains("UNSUPPORTED: keyword arguments to a callee whose parameters aren't known"), "{}", code);
+    }
+
+    #[test]
+    fn test_unpacking_into_varargs_and_kwargs() {
+        let code = try_module_to_rust(
+            "def log(level: int, *messages, sep: str = ' ', **extra):
+    pass
+
+def main(items, options):
+    log(1)
+    log(1, 'a', 'b', sep=',', user='ann')
+    log(2, *items)
+    log(3, 'x', *items, **options)
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("log (1 , std :: iter :: empty :: < PyObject > () , None , std :: iter :: empty :: < (& str , PyObject) > ())"), "{}", code);
+        assert!(code.contains("log (1 , vec ! [\"a\" , \"b\"] , Some (String :: from (\",\")) , vec ! [(\"user\" , \"ann\")])"), "{}", code);
+        assert!(code.contains("log (2 , items , None , std :: iter :: empty :: < (& str , PyObject) > ())"), "{}", code);
+        assert!(code.contains("log (3 , (vec ! [\"x\"]) . into_iter () . chain (items) , None , options)"), "{}", code);
+
+        // Which fixed parameters unpacked values are for can't be known.
+        let error = try_module_to_rust("def pair(left, right):\n    pass\n\npair(*items)\n", test_options()).unwrap_err();
+        assert!(error.to_string().contains("it can't unpack *items into the parameters it takes by position"), "{}", error);
+        let error = try_module_to_rust("def pair(left, right):\n    pass\n\npair(**options)\n", test_options()).unwrap_err();
+        assert!(error.to_string().contains("it takes no **kwargs to unpack keyword arguments into"), "{}", error);
     }
 }
@@ -18,32 +18,122 @@
 //! 4. Static data will be declared with lazy_static inside the module.
 //! 5. Additional classes will be nested inside the module, and therefore they appear as modules inside a module.
 //! 6. Each class also contains a trait, named Cls, which is used in inheritance.
-//! 7. Each method of the class in Python will be translated to have a prototype in Cls. If it is possible to implement the method as a default method,
-//! it will be, otherwise (if the method refers to attributes of the class), a prototype will be added to Cls, and the implementation will be done inside
-//! an impl Cls for Data block.
+//! 7. Each method of the class in Python will be translated to a method of Data, in an impl Data block with its constructor, so that it can use
+//! the fields and be called without Cls in scope.
 //! 8. Cls will implement Clone, Default.
+//!
+//! Classes that are really something Rust has its own construct for are that instead: a `typing.Protocol` is a trait,
+//! an `enum.Enum` is an enum, whose members are its variants, and a `NamedTuple` or `TypedDict` is a plain struct with
+//! its fields. A `@dataclass` keeps the module, but its fields are its annotated attributes, and its Data derives the
+//! traits the decorator generates methods for.
 
-use proc_macro2::TokenStream;
+use proc_macro2::{TokenStream, TokenTree};
 use pyo3::FromPyObject;
-use quote::{format_ident, quote};
+use quote::quote;
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Name, PythonOptions, Statement, StatementType,
-    SymbolTableNode, SymbolTableScopes,
+    annotation_to_rust_type, Arguments, is_abc_name, convert_ident, generic_parameters, impl_node_with_positions, imported_name, is_str_annotation, sanitize_ident, Object,
+    is_typing_name, type_variables, variant_ident, walk_statement, AnnAssign, Assign, CodeGen, CodeGenContext,
+    CodeGenError, ExprType, FunctionDef, IdentCase, ImplTarget, Keyword, comparison_impls, is_comparison_method, is_iterator_method, iterator_impls, lookup_special_method, Name, Node, Parameter, PythonOptions, Statement, StatementType, SymbolTableNode,
+    SymbolTableScopes, Tuple, TypeVar, Visitor, UNKNOWN_FILE, Call, MUTATING_METHODS, walk_call, Attribute,
 };
 
+use std::collections::{HashMap, HashSet};
+
 use tracing::debug;
 
 use serde::{Deserialize, Serialize};
 
+/// The kinds of classes that only hold fields, which are plain structs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordKind {
+    /// A `typing.NamedTuple`, whose fields are also its items
+    NamedTuple,
+    /// A `typing.TypedDict`, whose fields are optional unless it's `total`
+    TypedDict { total: bool },
+}
+
+/// A field of a NamedTuple or TypedDict.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordField {
+    pub name: String,
+    pub annotation: ExprType,
+    /// The value a NamedTuple's field has if it isn't given one
+    pub default: Option<ExprType>,
+}
+
+impl RecordField {
+    /// The type of the field's values, and whether a record of `kind` requires it: the
+    /// fields of a TypedDict can be marked `Required` or `NotRequired`, and are required
+    /// unless it isn't total.
+    pub fn requirement(&self, kind: RecordKind) -> (&ExprType, bool) {
+        match &self.annotation {
+            ExprType::Subscript(subscript) if is_typing_name(&subscript.value, "Required") => (subscript.slice.as_ref(), true),
+            ExprType::Subscript(subscript) if is_typing_name(&subscript.value, "NotRequired") => (subscript.slice.as_ref(), false),
+            annotation => (annotation, !matches!(kind, RecordKind::TypedDict { total: false })),
+        }
+    }
+}
+
+/// What the `@dataclass` decorator of a class generates, from its arguments, like
+/// `@dataclass(frozen=True)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dataclass {
+    /// `__eq__`, which is `PartialEq`
+    pub eq: bool,
+    /// `__repr__`, which is `Debug`
+    pub repr: bool,
+    /// The ordering methods, which are `PartialOrd`
+    pub order: bool,
+    /// Whether the fields can't be assigned, so they're private
+    pub frozen: bool,
+}
+
+/// The fields of a class's struct, with their Rust types.
+type Fields = Vec<(proc_macro2::Ident, TokenStream)>;
+
+/// How a dataclass field that isn't given a value gets one.
+enum FieldDefault<'a> {
+    /// It has to be given one
+    Required,
+    /// It's the default's value
+    Value(&'a ExprType),
+    /// It's made by a `default_factory`
+    Factory,
+}
+
+/// A member of an `Enum` class, which is a variant of the Rust enum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumMember {
+    /// The member's name in Python
+    pub name: String,
+    /// The variant's name
+    pub variant: proc_macro2::Ident,
+    pub value: EnumValue,
+}
+
+/// The value of an `Enum` member.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EnumValue {
+    Int(i128),
+    Str(String),
+}
+
 #[derive(Clone, Debug, Default, FromPyObject, Serialize, Deserialize, PartialEq)]
 pub struct ClassDef {
     pub name: String,
-    pub bases: Vec<Name>,
-    pub keywords: Vec<String>,
+    pub bases: Vec<ExprType>,
+    pub keywords: Vec<Keyword>,
     pub body: Vec<Statement>,
+    pub decorator_list: Vec<ExprType>,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
 }
 
+impl_node_with_positions!(ClassDef { lineno, col_offset, end_lineno, end_col_offset });
+
 impl CodeGen for ClassDef {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -51,7 +141,8 @@ impl CodeGen for ClassDef {
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
         let mut symbols = symbols;
-        symbols.insert(self.name.clone(), SymbolTableNode::ClassDef(self.clone()));
+        let mutating_methods = self.mutating_methods(&symbols);
+        symbols.insert(self.name.clone(), SymbolTableNode::ClassDef { class: self.clone(), mutating_methods });
         symbols
     }
 
@@ -62,39 +153,161 @@ impl CodeGen for ClassDef {
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
         let mut streams = TokenStream::new();
-        let class_name = format_ident!("{}", self.name);
-
-        // The Python convention is that functions that begin with a single underscore,
-        // it's private. Otherwise, it's public. We formalize that by default.
-        let visibility = if self.name.starts_with("_") && !self.name.starts_with("__") {
-            format_ident!("")
-        } else if self.name.starts_with("__") && self.name.ends_with("__") {
-            format_ident!("pub(crate)")
-        } else {
-            format_ident!("pub")
-        };
+        let class_name = convert_ident(&self.name, IdentCase::Type, &options, &symbols);
+
+        let visibility = options.visibility_for(&self.name).tokens();
+
+        // A `Generic[T]` base only declares the class's type variables.
+        let type_vars = self.type_variables(&symbols);
+        let generics = generic_parameters(&type_vars, CodeGenContext::Class, options.clone(), symbols.clone())?;
+        let type_parameters: Vec<_> = type_vars.iter().map(|type_var| sanitize_ident(&type_var.name)).collect();
+        let arguments = if type_parameters.is_empty() { quote!() } else { quote!(<#(#type_parameters),*>) };
+        // The Protocols the class lists are traits it implements, not classes it inherits from.
+        let protocols: Vec<ClassDef> = self.protocol_bases(&symbols);
+        let bases: Vec<&Name> = self
+            .bases
+            .iter()
+            .filter_map(|base| match base {
+                ExprType::Name(name) if !protocols.iter().any(|protocol| protocol.name == name.id) => Some(name),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(kind) = self.record_kind() {
+            return self.record_to_rust(kind, visibility, options, symbols);
+        }
+
+        if let Some(members) = self.enum_members(&options, &symbols)? {
+            return self.enum_to_rust(&members, visibility, options, symbols);
+        }
+
+        if self.is_protocol() {
+            return self.protocol_to_rust(&protocols, generics, visibility, options, symbols);
+        }
 
         // bases will be empty if there are no base classes, which prevents any base traits
         // being added, and also prevents the : from being emitted.
-        let mut bases = TokenStream::new();
-        if self.bases.len() > 0 {
-            bases.extend(quote!(:));
-            let base_name = format_ident!("{}", self.bases[0].id);
-            bases.extend(quote!(#base_name::Cls));
-            for base in &self.bases[1..] {
-                bases.extend(quote!(+));
-                let base_name = format_ident!("{}", base.id);
-                bases.extend(quote!(#base_name));
+        let mut base_traits = TokenStream::new();
+        if let Some((first, rest)) = bases.split_first() {
+            base_traits.extend(quote!(:));
+            let base_name = sanitize_ident(&first.id);
+            base_traits.extend(quote!(#base_name::Cls));
+            for base in rest {
+                base_traits.extend(quote!(+));
+                let base_name = sanitize_ident(&base.id);
+                base_traits.extend(quote!(#base_name));
             }
         }
 
+        // The fields are the attributes `__init__` assigns, and it becomes the constructor,
+        // unless it's a dataclass's, which are its annotated attributes.
+        let dataclass = self.dataclass(&symbols);
+        let init = self.init();
+        let (fields, constructor) = match (init, dataclass) {
+            (Some(init), _) => {
+                let fields = self.fields(init, &options, &symbols)?;
+                let (_, marker_value) = phantom_marker(&type_parameters, &fields);
+                let constructor = self.constructor_to_rust(init, &fields, marker_value, &visibility, options.clone(), symbols.clone())?;
+                (fields, constructor)
+            }
+            (None, Some(_)) => self.dataclass_fields(&type_parameters, &visibility, &options, &symbols)?,
+            (None, None) => (Vec::new(), quote!()),
+        };
+        let (marker, _) = phantom_marker(&type_parameters, &fields);
+        let field_names: Vec<_> = fields.iter().map(|(name, _)| name).collect();
+        let field_types: Vec<_> = fields.iter().map(|(_, rust_type)| rust_type).collect();
+        // A frozen dataclass can't be assigned to, so its fields are private.
+        let field_visibility = if dataclass.is_some_and(|dataclass| dataclass.frozen) { quote!() } else { visibility.clone() };
+        let derives = self.derives(dataclass);
+        // A dataclass whose fields all have defaults is its own default.
+        let field_list = self.dataclass_field_list();
+        let defaults = field_list.iter().map(|_| quote!(None));
+        let default = match dataclass {
+            Some(_)
+                if init.is_none()
+                    && field_list
+                        .iter()
+                        .all(|field| !matches!(Self::field_default(field, &symbols), FieldDefault::Required)) =>
+            {
+                quote! {
+                    impl #generics Default for Data #arguments {
+                        fn default() -> Self {
+                            Self::new(#(#defaults),*)
+                        }
+                    }
+                }
+            }
+            _ => quote!(),
+        };
+
+        let mut method_options = options.clone();
+        method_options.state.type_parameters = type_vars.iter().map(|type_var| type_var.name.clone()).collect();
+        method_options.state.mutating_methods = match symbols.lookup(&self.name) {
+            Some(SymbolTableNode::ClassDef { class, mutating_methods }) if class.name == self.name => mutating_methods.clone(),
+            _ => self.mutating_methods(&symbols),
+        };
+        // The methods a Protocol declares are implemented in the Protocol's trait.
+        let mut implementations: Vec<TokenStream> = protocols.iter().map(|_| TokenStream::new()).collect();
+        // Special methods implement Rust traits, like std::ops::Add for `__add__`.
+        let target = ImplTarget {
+            class: &self,
+            generics: generics.clone(),
+            arguments: arguments.clone(),
+            visibility: visibility.clone(),
+        };
+        let mut special_impls = TokenStream::new();
         for s in self.body.clone() {
-            streams.extend(
-                s.clone()
-                    .to_rust(CodeGenContext::Class, options.clone(), symbols.clone())
-                    .expect(format!("Failed to parse statement {:?}", s).as_str()),
-            );
+            // The constructor is generated with the struct, and the docstring documents the
+            // class.
+            if init.is_some() && matches!(&s.statement, StatementType::FunctionDef(function) if function.name == "__init__")
+                || is_docstring(&s)
+            {
+                continue;
+            }
+            // A dataclass's annotated attributes are its fields.
+            if dataclass.is_some() && matches!(&s.statement, StatementType::AnnAssign(_)) {
+                continue;
+            }
+            if matches!(&s.statement, StatementType::FunctionDef(function)
+                if is_comparison_method(function) || is_iterator_method(function))
+            {
+                continue;
+            }
+            if let StatementType::FunctionDef(function) = &s.statement
+                && let Some(special) = lookup_special_method(function)
+            {
+                special_impls.extend((special.generate)(function, &target, method_options.clone(), symbols.clone())?);
+                continue;
+            }
+            let protocol = match &s.statement {
+                StatementType::FunctionDef(function) => protocols.iter().position(|protocol| protocol.declares(&function.name)),
+                _ => None,
+            };
+            let ctx = if protocol.is_some() { CodeGenContext::Trait } else { CodeGenContext::Class };
+            let method = self.with_typed_receiver(s.clone()).to_rust(ctx, method_options.clone(), symbols.clone())?;
+            match protocol {
+                Some(i) => implementations[i].extend(method),
+                None => streams.extend(method),
+            }
         }
+        // The methods are Data's own, next to the constructor, so that they can use its
+        // fields and be called without the trait in scope.
+        let methods = if constructor.is_empty() && streams.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                impl #generics Data #arguments {
+                    #constructor
+                    #streams
+                }
+            }
+        };
+        special_impls.extend(comparison_impls(&target, method_options.clone(), symbols.clone())?);
+        special_impls.extend(iterator_impls(&target, method_options.clone(), symbols.clone())?);
+        let protocol_names = protocols.iter().map(|protocol| sanitize_ident(&protocol.name));
+        let protocol_impls = quote! {
+            #(impl #generics super::#protocol_names for Data #arguments { #implementations })*
+        };
 
         let class = if let Some(docstring) = self.get_docstring() {
             // Convert docstring to Rust doc comments
@@ -104,7 +317,8 @@ impl CodeGen for ClassDef {
                     if line.trim().is_empty() {
                         quote! { #[doc = ""] }
                     } else {
-                        let doc_line = format!("{}", line);
+                        // rustdoc's /// comments are doc attributes with a leading space.
+                        let doc_line = format!(" {}", line);
                         quote! { #[doc = #doc_line] }
                     }
                 })
@@ -114,28 +328,34 @@ impl CodeGen for ClassDef {
                 #(#doc_lines)*
                 #visibility mod #class_name {
                     use super::*;
-                    #visibility trait Cls #bases {
-                        #streams
-                    }
-                    #[derive(Clone, Default)]
-                    #visibility struct Data {
-
+                    #visibility trait Cls #generics #base_traits {}
+                    #[derive(#(#derives),*)]
+                    #visibility struct Data #generics {
+                        #(#field_visibility #field_names: #field_types,)*
+                        #marker
                     }
-                    impl Cls for Data {}
+                    #methods
+                    #default
+                    impl #generics Cls #arguments for Data #arguments {}
+                    #protocol_impls
+                    #special_impls
                 }
             }
         } else {
             quote! {
                 #visibility mod #class_name {
                     use super::*;
-                    #visibility trait Cls #bases {
-                        #streams
-                    }
-                    #[derive(Clone, Default)]
-                    #visibility struct Data {
-
+                    #visibility trait Cls #generics #base_traits {}
+                    #[derive(#(#derives),*)]
+                    #visibility struct Data #generics {
+                        #(#field_visibility #field_names: #field_types,)*
+                        #marker
                     }
-                    impl Cls for Data {}
+                    #methods
+                    #default
+                    impl #generics Cls #arguments for Data #arguments {}
+                    #protocol_impls
+                    #special_impls
                 }
             }
         };
@@ -144,30 +364,1058 @@ impl CodeGen for ClassDef {
     }
 }
 
+/// The attributes of `self` a method's body assigns, like `self.total = 0`, with the
+/// annotations they're declared with, in the order they're first assigned.
+pub fn collect_self_assignments(body: &[Statement]) -> Vec<(String, Option<ExprType>)> {
+    SelfAssignments::of(body).fields
+}
+
+/// The annotation of an attribute of an instance whose class is known, like `self.items`
+/// in a method, which is the annotation of the class's field. Chains of attributes, like
+/// `self.child.name`, are followed through the classes of the fields.
+pub fn attribute_annotation(attribute: &Attribute, symbols: &SymbolTableScopes) -> Option<ExprType> {
+    let class = match attribute.value.as_ref() {
+        ExprType::Name(name) => symbols.type_annotation(&name.id).cloned(),
+        ExprType::Attribute(value) => attribute_annotation(value, symbols),
+        _ => None,
+    };
+    let Some(ExprType::Name(class)) = class else {
+        return None;
+    };
+    match symbols.lookup(&class.id) {
+        Some(SymbolTableNode::ClassDef { class, .. }) => class.field_annotation(&attribute.attr, symbols),
+        _ => None,
+    }
+}
+
+/// The class of the module `value` is an instance of, if it's known, like that of a
+/// variable assigned `Child("name")`, of an annotated parameter or of `self.child`.
+pub fn receiver_class<'a>(value: &ExprType, symbols: &'a SymbolTableScopes) -> Option<&'a ClassDef> {
+    let annotation = match value {
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::Assign { value, .. }) => instance_class(value, symbols),
+            _ => symbols.type_annotation(&name.id).cloned(),
+        },
+        ExprType::Attribute(attribute) => attribute_annotation(attribute, symbols),
+        _ => None,
+    };
+    let Some(ExprType::Name(class)) = annotation else {
+        return None;
+    };
+    match symbols.lookup(&class.id) {
+        Some(SymbolTableNode::ClassDef { class, .. }) => Some(class),
+        _ => None,
+    }
+}
+
+/// Collects the attributes of `self` that are assigned, and how often they are.
+#[derive(Default)]
+struct SelfAssignments {
+    fields: Vec<(String, Option<ExprType>)>,
+    counts: HashMap<String, usize>,
+    /// The value each attribute is first assigned
+    values: HashMap<String, ExprType>,
+}
+
+impl SelfAssignments {
+    fn of(body: &[Statement]) -> Self {
+        let mut assignments = SelfAssignments::default();
+        body.iter().for_each(|statement| assignments.visit_statement(statement));
+        assignments
+    }
+
+    fn assign(&mut self, target: &ExprType, annotation: Option<&ExprType>, value: Option<&ExprType>) {
+        let Some(attr) = self_attribute(target) else {
+            return;
+        };
+        *self.counts.entry(attr.to_string()).or_default() += 1;
+        if let Some(value) = value {
+            self.values.entry(attr.to_string()).or_insert_with(|| value.clone());
+        }
+        match self.fields.iter_mut().find(|(name, _)| name == attr) {
+            Some((_, declared)) => {
+                if declared.is_none() {
+                    *declared = annotation.cloned();
+                }
+            }
+            None => self.fields.push((attr.to_string(), annotation.cloned())),
+        }
+    }
+}
+
+impl Visitor for SelfAssignments {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match &stmt.statement {
+            StatementType::Assign(assign) => {
+                assign.targets.iter().for_each(|target| self.assign(target, None, Some(&assign.value)))
+            }
+            StatementType::AnnAssign(assign) => {
+                self.assign(&assign.target, Some(&assign.annotation), assign.value.as_ref())
+            }
+            StatementType::AugAssign(assign) => self.assign(&assign.target, None, None),
+            // Functions and classes defined in the method have a `self` of their own.
+            StatementType::FunctionDef(_) | StatementType::AsyncFunctionDef(_) | StatementType::ClassDef(_) => return,
+            _ => {}
+        }
+        walk_statement(stmt, self);
+    }
+}
+
+/// The field a generic struct uses the type parameters none of its fields do with, which
+/// Rust requires it to, and the value the constructor gives it.
+fn phantom_marker(type_parameters: &[proc_macro2::Ident], fields: &[(proc_macro2::Ident, TokenStream)]) -> (TokenStream, TokenStream) {
+    let unused: Vec<_> = type_parameters
+        .iter()
+        .filter(|parameter| !fields.iter().any(|(_, rust_type)| mentions(rust_type, parameter)))
+        .collect();
+    if unused.is_empty() {
+        return (quote!(), quote!());
+    }
+    (
+        quote!(_type_parameters: ::std::marker::PhantomData<(#(#unused,)*)>,),
+        quote!(_type_parameters: ::std::marker::PhantomData,),
+    )
+}
+
+/// Returns true if the tokens of a type mention the identifier, like `Vec<T>` does `T`.
+fn mentions(tokens: &TokenStream, name: &proc_macro2::Ident) -> bool {
+    tokens.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(ident) => ident == *name,
+        TokenTree::Group(group) => mentions(&group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Finds out if a method changes the instance it's called on: if it assigns to an
+/// attribute of `self`, calls a method like append() on one, or calls a method of the
+/// class that changes it.
+struct SelfMutations<'a> {
+    class: &'a ClassDef,
+    /// The methods of the class known to change the instance
+    mutating_methods: &'a HashSet<String>,
+    symbols: &'a SymbolTableScopes,
+    mutates: bool,
+}
+
+impl<'a> SelfMutations<'a> {
+    fn of(class: &'a ClassDef, function: &FunctionDef, mutating_methods: &'a HashSet<String>, symbols: &'a SymbolTableScopes) -> bool {
+        let mut mutations = SelfMutations { class, mutating_methods, symbols, mutates: false };
+        function.body.iter().for_each(|statement| mutations.visit_statement(statement));
+        mutations.mutates
+    }
+
+    fn assign(&mut self, target: &ExprType) {
+        match target {
+            ExprType::Tuple(tuple) => tuple.elts.iter().for_each(|target| self.assign(target)),
+            ExprType::Attribute(_) | ExprType::Subscript(_) => self.mutates |= is_in_self(target),
+            _ => {}
+        }
+    }
+}
+
+impl Visitor for SelfMutations<'_> {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match &stmt.statement {
+            StatementType::Assign(assign) => assign.targets.iter().for_each(|target| self.assign(target)),
+            StatementType::AnnAssign(assign) => self.assign(&assign.target),
+            StatementType::AugAssign(assign) => self.assign(&assign.target),
+            // Functions and classes defined in the method have a `self` of their own.
+            StatementType::FunctionDef(_) | StatementType::AsyncFunctionDef(_) | StatementType::ClassDef(_) => return,
+            _ => {}
+        }
+        walk_statement(stmt, self);
+    }
+
+    fn visit_call(&mut self, node: &Call) {
+        if let ExprType::Attribute(attribute) = node.func.as_ref() {
+            self.mutates |= match attribute.value.as_ref() {
+                ExprType::Name(name) if name.id == "self" => self.mutating_methods.contains(&attribute.attr),
+                value if !is_in_self(value) => false,
+                // So does calling a method that changes a field, which is one of a
+                // container's or of the field's class.
+                ExprType::Attribute(field) if matches!(field.value.as_ref(), ExprType::Name(name) if name.id == "self") => {
+                    MUTATING_METHODS.contains(&attribute.attr.as_str())
+                        || matches!(self.class.field_annotation(&field.attr, self.symbols), Some(ExprType::Name(class))
+                            if matches!(self.symbols.lookup(&class.id), Some(SymbolTableNode::ClassDef { mutating_methods, .. })
+                                if mutating_methods.contains(&attribute.attr)))
+                }
+                _ => MUTATING_METHODS.contains(&attribute.attr.as_str()),
+            };
+        }
+        walk_call(node, self);
+    }
+}
+
+/// The class `value` makes a new instance of, if it calls a class of the module, like
+/// `Child("name")`, as the annotation of the instance.
+pub fn instance_class(value: &ExprType, symbols: &SymbolTableScopes) -> Option<ExprType> {
+    match value {
+        ExprType::Call(call) => match call.func.as_ref() {
+            ExprType::Name(class) if matches!(symbols.lookup(&class.id), Some(SymbolTableNode::ClassDef { .. })) => {
+                Some(call.func.as_ref().clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns true if `expr` is an attribute or item of `self`, or one of theirs, like
+/// `self.child.items[0]`.
+fn is_in_self(expr: &ExprType) -> bool {
+    match expr {
+        ExprType::Attribute(attribute) => {
+            matches!(attribute.value.as_ref(), ExprType::Name(name) if name.id == "self") || is_in_self(&attribute.value)
+        }
+        ExprType::Subscript(subscript) => {
+            matches!(subscript.value.as_ref(), ExprType::Name(name) if name.id == "self") || is_in_self(&subscript.value)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the statement is a string on its own, like a docstring.
+pub(crate) fn is_docstring(statement: &Statement) -> bool {
+    matches!(&statement.statement, StatementType::Expr(expr)
+        if matches!(&expr.value, ExprType::Constant(constant) if constant.string_value().is_some()))
+}
+
+/// The name of the attribute if `target` is an attribute of `self`, like `self.total`.
+fn self_attribute(target: &ExprType) -> Option<&str> {
+    match target {
+        ExprType::Attribute(attribute) if matches!(attribute.value.as_ref(), ExprType::Name(name) if name.id == "self") => {
+            Some(&attribute.attr)
+        }
+        _ => None,
+    }
+}
+
 impl ClassDef {
-    fn get_docstring(&self) -> Option<String> {
-        if self.body.is_empty() {
-            return None;
+    /// The class's `__init__` method, if it has one that takes `self`.
+    pub fn init(&self) -> Option<&FunctionDef> {
+        self.body.iter().find_map(|s| match &s.statement {
+            StatementType::FunctionDef(function) if function.name == "__init__" && function.args.without_receiver().1 => {
+                Some(function)
+            }
+            _ => None,
+        })
+    }
+
+    /// The annotation of one of the class's fields. A field `__init__` assigns without
+    /// one has the annotation of the parameter it's assigned, or is an instance of the
+    /// class it's assigned a new one of.
+    pub fn field_annotation(&self, name: &str, symbols: &SymbolTableScopes) -> Option<ExprType> {
+        if let Some(field) = self.record_fields().into_iter().find(|field| field.name == name) {
+            return Some(field.annotation);
         }
-        
-        let expr = self.body[0].clone();
-        match expr.statement {
-            StatementType::Expr(e) => match e.value {
-                ExprType::Constant(c) => {
-                    let raw_string = c.to_string();
-                    // Clean up the docstring for Rust documentation
-                    Some(self.format_docstring(&raw_string))
+        let init = self.init()?;
+        let assignments = SelfAssignments::of(&init.body);
+        if let Some((_, Some(annotation))) = assignments.fields.iter().find(|(field, _)| field == name) {
+            return Some(annotation.clone());
+        }
+        match assignments.values.get(name)? {
+            ExprType::Name(value) => init.args.parameters().find(|parameter| parameter.arg == value.id)?.annotation.as_deref().cloned(),
+            value => instance_class(value, symbols),
+        }
+    }
+
+    /// A method of the class with `self` annotated as an instance of it, so that the
+    /// types of its attributes are known in the body.
+    fn with_typed_receiver(&self, statement: Statement) -> Statement {
+        let mut statement = statement;
+        if let StatementType::FunctionDef(function) = &mut statement.statement {
+            let positional = if function.args.posonlyargs.is_empty() { &mut function.args.args } else { &mut function.args.posonlyargs };
+            if let Some(receiver) = positional.first_mut().filter(|parameter| parameter.arg == "self" && parameter.annotation.is_none()) {
+                receiver.annotation = Some(Box::new(ExprType::Name(Name { id: self.name.clone() })));
+            }
+        }
+        statement
+    }
+
+    /// The methods of the class that change the instance, which take `&mut self`. A
+    /// method also changes it by calling another one that does, so the methods are looked
+    /// at again until no more are found. The base classes' methods that change it count
+    /// too.
+    pub fn mutating_methods(&self, symbols: &SymbolTableScopes) -> HashSet<String> {
+        let mut mutating: HashSet<String> = self
+            .bases
+            .iter()
+            .filter_map(|base| match base {
+                ExprType::Name(base) => match symbols.lookup(&base.id) {
+                    Some(SymbolTableNode::ClassDef { mutating_methods, .. }) => Some(mutating_methods.clone()),
+                    _ => None,
                 },
                 _ => None,
-            },
-            _ => None,
+            })
+            .flatten()
+            .collect();
+        let methods: Vec<&FunctionDef> = self
+            .body
+            .iter()
+            .filter_map(|s| match &s.statement {
+                StatementType::FunctionDef(function) if function.name != "__init__" && function.args.without_receiver().1 => {
+                    Some(function)
+                }
+                _ => None,
+            })
+            .collect();
+        loop {
+            let found: Vec<String> = methods
+                .iter()
+                .filter(|method| !mutating.contains(&method.name) && SelfMutations::of(self, method, &mutating, symbols))
+                .map(|method| method.name.clone())
+                .collect();
+            if found.is_empty() {
+                return mutating;
+            }
+            mutating.extend(found);
+        }
+    }
+
+    /// The arguments of the class's `@dataclass` decorator, if it has one. Only the
+    /// arguments that change the traits or the fields are used.
+    pub fn dataclass(&self, symbols: &SymbolTableScopes) -> Option<Dataclass> {
+        self.decorator_list.iter().find_map(|decorator| {
+            let (func, keywords) = match decorator {
+                ExprType::Call(call) => (call.func.as_ref(), call.keywords.as_slice()),
+                decorator => (decorator, [].as_slice()),
+            };
+            if imported_name(func, "dataclasses", symbols).as_deref() != Some("dataclass") {
+                return None;
+            }
+            let flag = |name: &str, default: bool| {
+                keywords
+                    .iter()
+                    .find(|keyword| keyword.arg.as_deref() == Some(name))
+                    .and_then(|keyword| match &keyword.value {
+                        ExprType::Constant(c) => c.bool_value(),
+                        _ => None,
+                    })
+                    .unwrap_or(default)
+            };
+            Some(Dataclass { eq: flag("eq", true), repr: flag("repr", true), order: flag("order", false), frozen: flag("frozen", false) })
+        })
+    }
+
+    /// The traits the struct of the class derives. A dataclass derives those of the methods
+    /// the decorator generates, unless the class defines them itself.
+    fn derives(&self, dataclass: Option<Dataclass>) -> Vec<TokenStream> {
+        let Some(dataclass) = dataclass else {
+            return vec![quote!(Clone), quote!(Default)];
+        };
+        let mut derives = vec![quote!(Clone)];
+        if dataclass.repr && !self.declares("__repr__") {
+            derives.push(quote!(Debug));
+        }
+        if dataclass.eq && !self.declares("__eq__") {
+            derives.push(quote!(PartialEq));
+            if dataclass.order && !["__lt__", "__le__", "__gt__", "__ge__"].iter().any(|method| self.declares(method)) {
+                derives.push(quote!(PartialOrd));
+            }
+        }
+        derives
+    }
+
+    /// The fields of a dataclass, which are its annotated attributes, other than the ones
+    /// that are a `ClassVar`.
+    fn dataclass_field_list(&self) -> Vec<RecordField> {
+        let mut fields = self.record_fields();
+        fields.retain(|field| match &field.annotation {
+            ExprType::Subscript(subscript) => !is_typing_name(&subscript.value, "ClassVar"),
+            annotation => !is_typing_name(annotation, "ClassVar"),
+        });
+        fields
+    }
+
+    /// The default of a dataclass field, which `field()` can give with `default` or
+    /// `default_factory`.
+    fn field_default<'a>(field: &'a RecordField, symbols: &SymbolTableScopes) -> FieldDefault<'a> {
+        match &field.default {
+            None => FieldDefault::Required,
+            Some(ExprType::Call(call)) if imported_name(&call.func, "dataclasses", symbols).as_deref() == Some("field") => call
+                .keywords
+                .iter()
+                .find_map(|keyword| match keyword.arg.as_deref() {
+                    Some("default") => Some(FieldDefault::Value(&keyword.value)),
+                    Some("default_factory") => Some(FieldDefault::Factory),
+                    _ => None,
+                })
+                .unwrap_or(FieldDefault::Required),
+            Some(default) => FieldDefault::Value(default),
+        }
+    }
+
+    /// A dataclass's fields, and its `new`, which takes the fields in order, the ones with
+    /// defaults as Options.
+    fn dataclass_fields(
+        &self,
+        type_parameters: &[proc_macro2::Ident],
+        visibility: &TokenStream,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> Result<(Fields, TokenStream), Box<dyn std::error::Error>> {
+        let ctx = CodeGenContext::Class;
+        let mut fields = Vec::new();
+        let mut parameters = Vec::new();
+        let mut initializers = Vec::new();
+        for field in self.dataclass_field_list() {
+            let name = sanitize_ident(&field.name);
+            let rust_type = annotation_to_rust_type(&field.annotation, ctx.clone(), options.clone(), symbols.clone())?;
+            let rust_type = quote!(#rust_type);
+            let initializer = match Self::field_default(&field, symbols) {
+                // The factory is taken to make the default of the field's type, like `list`.
+                FieldDefault::Factory => quote!(#name.unwrap_or_default()),
+                FieldDefault::Value(ExprType::Constant(constant))
+                    if is_str_annotation(&field.annotation, symbols) && constant.string_value().is_some() =>
+                {
+                    let default = constant.string_to_rust(options);
+                    quote!(#name.unwrap_or_else(|| #default))
+                }
+                FieldDefault::Value(default) => {
+                    let default = default.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                    quote!(#name.unwrap_or_else(|| #default))
+                }
+                FieldDefault::Required => {
+                    parameters.push(quote!(#name: #rust_type));
+                    initializers.push(quote!(#name));
+                    fields.push((name, rust_type));
+                    continue;
+                }
+            };
+            parameters.push(quote!(#name: Option<#rust_type>));
+            initializers.push(quote!(#name: #initializer));
+            fields.push((name, rust_type));
+        }
+        let (_, marker) = phantom_marker(type_parameters, &fields);
+        let constructor = quote! {
+            #visibility fn new(#(#parameters),*) -> Self {
+                Self { #(#initializers,)* #marker }
+            }
+        };
+        Ok((fields, constructor))
+    }
+
+    /// The fields of the class's struct, which are the attributes `__init__` assigns. A
+    /// field has the type it's annotated with, or that of the parameter or literal it's
+    /// assigned, and is a `PyObject` if that isn't known.
+    fn fields(
+        &self,
+        init: &FunctionDef,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> Result<Vec<(proc_macro2::Ident, TokenStream)>, Box<dyn std::error::Error>> {
+        let symbols = init.scope_symbols(symbols.clone());
+        let assignments = SelfAssignments::of(&init.body);
+        let mut fields = Vec::new();
+        for (name, annotation) in assignments.fields {
+            let value = assignments.values.get(&name);
+            let annotation = annotation.or_else(|| match value {
+                Some(ExprType::Name(value)) => symbols.type_annotation(&value.id).cloned(),
+                Some(value) => instance_class(value, &symbols),
+                None => None,
+            });
+            let rust_type = match (annotation, value) {
+                (Some(annotation), _) => {
+                    let rust_type = annotation_to_rust_type(&annotation, CodeGenContext::Class, options.clone(), symbols.clone())?;
+                    quote!(#rust_type)
+                }
+                (None, Some(ExprType::Constant(constant))) if constant.string_value().is_some() => options.string_type.tokens(),
+                (None, Some(ExprType::Constant(constant))) => constant.rust_type(options).unwrap_or_else(|| quote!(PyObject)),
+                (None, _) => quote!(PyObject),
+            };
+            fields.push((sanitize_ident(&name), rust_type));
+        }
+        Ok(fields)
+    }
+
+    /// `__init__` becomes `new`, which builds the instance out of the attributes it
+    /// assigns. They're local variables until then, declared where they're first assigned
+    /// if that's in the body itself, and with their default value before the body if not.
+    fn constructor_to_rust(
+        &self,
+        init: &FunctionDef,
+        fields: &[(proc_macro2::Ident, TokenStream)],
+        marker: TokenStream,
+        visibility: &TokenStream,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let assignments = SelfAssignments::of(&init.body);
+        let mut options = options;
+        options.state.constructor_fields = assignments.fields.iter().map(|(name, _)| name.clone()).collect();
+        let ctx = CodeGenContext::Function;
+        let symbols = init.scope_symbols(symbols);
+        let (arguments, _) = init.args.without_receiver();
+        let parameters = arguments.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+
+        let mut body = TokenStream::new();
+        let mut declared = Vec::new();
+        for statement in &init.body {
+            let first = match &statement.statement {
+                StatementType::Assign(assign) if assign.targets.len() == 1 => Some((&assign.targets[0], &assign.value)),
+                StatementType::AnnAssign(assign) => assign.value.as_ref().map(|value| (&assign.target, value)),
+                _ => None,
+            }
+            .and_then(|(target, value)| Some((self_attribute(target)?, value)))
+            .filter(|(attr, _)| !declared.contains(attr));
+            match first {
+                Some((attr, value)) => {
+                    declared.push(attr);
+                    let Some(((field, rust_type), _)) = fields.iter().zip(&options.state.constructor_fields).find(|(_, name)| *name == attr)
+                    else {
+                        continue;
+                    };
+                    let value = match value {
+                        // A string constant has the string type the field has.
+                        ExprType::Constant(constant) if constant.string_value().is_some() && *rust_type.to_string() == *options.string_type.tokens().to_string() => {
+                            constant.string_to_rust(&options)
+                        }
+                        value => Some(value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?),
+                    };
+                    if assignments.counts.get(attr).copied().unwrap_or_default() > 1 {
+                        body.extend(quote!(let mut #field: #rust_type = #value;));
+                    } else {
+                        body.extend(quote!(let #field: #rust_type = #value;));
+                    }
+                }
+                // The docstring isn't code.
+                None if is_docstring(statement) => {}
+                None => {
+                    let tokens = statement.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                    body.extend(quote!(#tokens;));
+                }
+            }
+        }
+        // Attributes only assigned in nested blocks start out with their default value.
+        let undeclared = fields
+            .iter()
+            .zip(&options.state.constructor_fields)
+            .filter(|(_, name)| !declared.contains(&name.as_str()))
+            .map(|((field, rust_type), _)| quote!(let mut #field: #rust_type = Default::default();));
+        let field_names = fields.iter().map(|(field, _)| field);
+
+        Ok(quote! {
+            #visibility fn new(#parameters) -> Self {
+                #(#undeclared)*
+                #body
+                Self { #(#field_names,)* #marker }
+            }
+        })
+    }
+
+    /// Returns true if the class is a `typing.Protocol`, a trait any class that has its
+    /// methods implements. So is an abstract base class, one deriving from `abc.ABC`, with
+    /// `ABCMeta` as its metaclass, or with abstract methods, whose trait the classes
+    /// deriving from it implement.
+    pub fn is_protocol(&self) -> bool {
+        self.bases.iter().any(|base| match base {
+            ExprType::Subscript(subscript) => is_typing_name(&subscript.value, "Protocol"),
+            base => is_typing_name(base, "Protocol") || is_abc_name(base, "ABC"),
+        }) || self
+            .keywords
+            .iter()
+            .any(|keyword| keyword.arg.as_deref() == Some("metaclass") && is_abc_name(&keyword.value, "ABCMeta"))
+            || self
+                .body
+                .iter()
+                .any(|s| matches!(&s.statement, StatementType::FunctionDef(function) if function.is_abstract()))
+    }
+
+    /// Returns true if the class has a method of this name.
+    fn declares(&self, method: &str) -> bool {
+        self.body
+            .iter()
+            .any(|s| matches!(&s.statement, StatementType::FunctionDef(function) if function.name == method))
+    }
+
+    /// The Protocols defined in the module that the class lists as its bases.
+    fn protocol_bases(&self, symbols: &SymbolTableScopes) -> Vec<ClassDef> {
+        self.bases
+            .iter()
+            .filter_map(|base| match base {
+                ExprType::Name(name) => match symbols.lookup(&name.id) {
+                    Some(SymbolTableNode::ClassDef { class, .. }) if class.is_protocol() => Some(class.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A Protocol is a trait, whose methods without a body are required, and whose other
+    /// methods are provided. The Protocols it extends are its supertraits.
+    fn protocol_to_rust(
+        &self,
+        protocols: &[ClassDef],
+        generics: TokenStream,
+        visibility: TokenStream,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let class_name = sanitize_ident(&self.name);
+        let supertraits = protocols.iter().map(|protocol| sanitize_ident(&protocol.name));
+        let supertraits = if protocols.is_empty() { quote!() } else { quote!(: #(#supertraits)+*) };
+
+        let mut method_options = options.clone();
+        method_options.state.in_protocol = true;
+        let mut methods = TokenStream::new();
+        for (i, s) in self.body.iter().enumerate() {
+            match &s.statement {
+                StatementType::FunctionDef(_) => {
+                    methods.extend(s.clone().to_rust(CodeGenContext::Trait, method_options.clone(), symbols.clone())?)
+                }
+                StatementType::Pass => {}
+                StatementType::Expr(_) if i == 0 && self.get_docstring().is_some() => {}
+                _ => {
+                    return Err(CodeGenError::unsupported(
+                        format!("a Protocol member that isn't a method, in {}", self.name),
+                        Some(s.source_location(UNKNOWN_FILE)),
+                    )
+                    .into())
+                }
+            }
+        }
+
+        let docs = self.get_docstring().into_iter().flat_map(|docstring| {
+            docstring
+                .lines()
+                .map(|line| if line.trim().is_empty() { String::new() } else { format!(" {}", line) })
+                .collect::<Vec<_>>()
+        });
+        Ok(quote! {
+            #(#[doc = #docs])*
+            #visibility trait #class_name #generics #supertraits {
+                #methods
+            }
+        })
+    }
+
+    /// The kind of record the class is, if it's a NamedTuple or TypedDict.
+    pub fn record_kind(&self) -> Option<RecordKind> {
+        let total = !self.keywords.iter().any(|keyword| {
+            keyword.arg.as_deref() == Some("total")
+                && matches!(&keyword.value, ExprType::Constant(c) if c.bool_value() == Some(false))
+        });
+        self.bases.iter().find_map(|base| {
+            if is_typing_name(base, "NamedTuple") {
+                Some(RecordKind::NamedTuple)
+            } else if is_typing_name(base, "TypedDict") {
+                Some(RecordKind::TypedDict { total })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The annotated fields of the class, in order.
+    pub fn record_fields(&self) -> Vec<RecordField> {
+        self.body
+            .iter()
+            .filter_map(|s| match &s.statement {
+                StatementType::AnnAssign(field) => match &field.target {
+                    ExprType::Name(name) => Some(RecordField {
+                        name: name.id.clone(),
+                        annotation: *field.annotation.clone(),
+                        default: field.value.clone(),
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The parameters of the `new` of a NamedTuple, TypedDict or dataclass without an
+    /// `__init__`, by which the arguments of a call making one are matched up with its
+    /// fields. A TypedDict takes them by keyword, and the ones it doesn't require have a
+    /// default, which is leaving them out.
+    pub fn field_parameters(&self, symbols: &SymbolTableScopes) -> Option<Arguments> {
+        if self.init().is_none() && self.dataclass(symbols).is_some() {
+            let mut arguments = Arguments::default();
+            for field in self.dataclass_field_list() {
+                if !matches!(Self::field_default(&field, symbols), FieldDefault::Required) {
+                    arguments.defaults.extend(field.default.clone().map(Box::new));
+                }
+                arguments.args.push(Parameter {
+                    arg: field.name,
+                    annotation: Some(Box::new(field.annotation)),
+                    ..Default::default()
+                });
+            }
+            return Some(arguments);
+        }
+        let kind = self.record_kind()?;
+        let mut arguments = Arguments::default();
+        for field in self.record_fields() {
+            let (annotation, required) = field.requirement(kind);
+            let parameter = Parameter {
+                arg: field.name.clone(),
+                annotation: Some(Box::new(annotation.clone())),
+                ..Default::default()
+            };
+            match kind {
+                RecordKind::NamedTuple => {
+                    arguments.args.push(parameter);
+                    arguments.defaults.extend(field.default.map(Box::new));
+                }
+                RecordKind::TypedDict { .. } => {
+                    arguments.kwonlyargs.push(parameter);
+                    arguments.kw_defaults.push((!required).then(Box::default));
+                }
+            }
+        }
+        Some(arguments)
+    }
+
+    /// The class an assignment defines with the functional form of NamedTuple or
+    /// TypedDict, like `Point = NamedTuple("Point", [("x", int), ("y", int)])` or
+    /// `Config = TypedDict("Config", {"host": str}, total=False)`, which is the class
+    /// declaring the same fields.
+    pub fn from_record_call(assign: &Assign) -> Option<ClassDef> {
+        let [ExprType::Name(name)] = assign.targets.as_slice() else {
+            return None;
+        };
+        let ExprType::Call(call) = &assign.value else {
+            return None;
+        };
+        if !is_typing_name(&call.func, "NamedTuple") && !is_typing_name(&call.func, "TypedDict") {
+            return None;
+        }
+        let fields: Vec<(String, ExprType)> = match call.args.as_slice() {
+            [ExprType::Constant(_), ExprType::List(fields)] => fields
+                .iter()
+                .map(|field| match field {
+                    ExprType::Tuple(Tuple { elts, .. }) => match elts.as_slice() {
+                        [ExprType::Constant(field), annotation] => Some((field.string_value()?, annotation.clone())),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+            [ExprType::Constant(_), ExprType::Dict(fields)] => fields
+                .keys
+                .iter()
+                .zip(&fields.values)
+                .map(|(field, annotation)| match field {
+                    Some(ExprType::Constant(field)) => Some((field.string_value()?, annotation.clone())),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+            _ => return None,
+        };
+
+        let statement = |statement| Statement {
+            lineno: assign.lineno,
+            col_offset: assign.col_offset,
+            end_lineno: assign.end_lineno,
+            end_col_offset: assign.end_col_offset,
+            statement,
+        };
+        let body = fields
+            .into_iter()
+            .map(|(field, annotation)| {
+                statement(StatementType::AnnAssign(AnnAssign {
+                    target: ExprType::Name(Name { id: field }),
+                    annotation: Box::new(annotation),
+                    value: None,
+                    lineno: assign.lineno,
+                    col_offset: assign.col_offset,
+                    end_lineno: assign.end_lineno,
+                    end_col_offset: assign.end_col_offset,
+                }))
+            })
+            .collect();
+        Some(ClassDef {
+            name: name.id.clone(),
+            bases: vec![(*call.func).clone()],
+            keywords: call.keywords.clone(),
+            body,
+            decorator_list: Vec::new(),
+            lineno: assign.lineno,
+            col_offset: assign.col_offset,
+            end_lineno: assign.end_lineno,
+            end_col_offset: assign.end_col_offset,
+        })
+    }
+
+    /// A NamedTuple or TypedDict is a struct with a public field for each of the class's,
+    /// made with `new`, which takes the fields in order. A field with a default is taken as
+    /// an Option, and the fields of a TypedDict that isn't total, or that are
+    /// `NotRequired`, are Options.
+    fn record_to_rust(
+        &self,
+        kind: RecordKind,
+        visibility: TokenStream,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let class_name = convert_ident(&self.name, IdentCase::Type, &options, &symbols);
+        let ctx = CodeGenContext::Class;
+
+        let mut fields = Vec::new();
+        let mut parameters = Vec::new();
+        let mut initializers = Vec::new();
+        let mut defaults = Vec::new();
+        for field in self.record_fields() {
+            let name = convert_ident(&field.name, IdentCase::Value, &options, &symbols);
+            let (annotation, required) = field.requirement(kind);
+            let mut rust_type = annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone())?;
+            if !required {
+                rust_type = rust_type.optional();
+            }
+            fields.push(quote!(pub #name: #rust_type));
+
+            let default = match &field.default {
+                Some(ExprType::Constant(constant)) if is_str_annotation(annotation, &symbols) && constant.string_value().is_some() => {
+                    let default = constant.string_to_rust(&options);
+                    quote!(#default)
+                }
+                Some(default) => default.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?,
+                None => {
+                    parameters.push(quote!(#name: #rust_type));
+                    initializers.push(quote!(#name));
+                    continue;
+                }
+            };
+            parameters.push(quote!(#name: Option<#rust_type>));
+            initializers.push(quote!(#name: #name.unwrap_or_else(|| #default)));
+            defaults.push(quote!(None));
+        }
+
+        // A NamedTuple whose fields all have defaults is also its own default.
+        let default = if kind == RecordKind::NamedTuple && defaults.len() == parameters.len() {
+            quote! {
+                impl Default for #class_name {
+                    fn default() -> Self {
+                        Self::new(#(#defaults),*)
+                    }
+                }
+            }
+        } else {
+            quote!()
+        };
+        let constructor = quote! {
+            impl #class_name {
+                pub fn new(#(#parameters),*) -> Self {
+                    Self { #(#initializers),* }
+                }
+            }
+            #default
+        };
+
+        let docs = self.get_docstring().into_iter().flat_map(|docstring| {
+            docstring
+                .lines()
+                .map(|line| if line.trim().is_empty() { String::new() } else { format!(" {}", line) })
+                .collect::<Vec<_>>()
+        });
+        Ok(quote! {
+            #(#[doc = #docs])*
+            #[derive(Clone, Debug, PartialEq)]
+            #visibility struct #class_name {
+                #(#fields),*
+            }
+            #constructor
+        })
+    }
+
+    /// The name of a NamedTuple's field at `index`, which it also is as a tuple.
+    pub fn named_tuple_field(&self, index: i64) -> Option<String> {
+        if self.record_kind() != Some(RecordKind::NamedTuple) {
+            return None;
+        }
+        let fields = self.record_fields();
+        let index = if index < 0 { fields.len().checked_sub(index.unsigned_abs() as usize)? } else { index as usize };
+        fields.get(index).map(|field| field.name.clone())
+    }
+
+    /// The kind of Enum the class is, like `IntEnum`, if it derives from one in the `enum`
+    /// module.
+    pub fn enum_kind(&self, symbols: &SymbolTableScopes) -> Option<String> {
+        self.bases
+            .iter()
+            .filter_map(|base| imported_name(base, "enum", symbols))
+            .find(|kind| matches!(kind.as_str(), "Enum" | "IntEnum" | "StrEnum"))
+    }
+
+    /// The members of an Enum class, with their values, or None if the class isn't an
+    /// Enum. Values made by `auto()` count up from 1, or are the member's name in
+    /// lowercase for a `StrEnum`.
+    pub fn enum_members(
+        &self,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> Result<Option<Vec<EnumMember>>, Box<dyn std::error::Error>> {
+        let Some(kind) = self.enum_kind(symbols) else {
+            return Ok(None);
+        };
+        let mut members: Vec<EnumMember> = Vec::new();
+        for s in &self.body {
+            let StatementType::Assign(assign) = &s.statement else {
+                continue;
+            };
+            let [ExprType::Name(name)] = assign.targets.as_slice() else {
+                continue;
+            };
+            let value = match &assign.value {
+                ExprType::Call(call) if imported_name(&call.func, "enum", symbols).as_deref() == Some("auto") => {
+                    if kind == "StrEnum" {
+                        EnumValue::Str(name.id.to_lowercase())
+                    } else {
+                        let previous = members.iter().rev().find_map(|member| match member.value {
+                            EnumValue::Int(value) => Some(value),
+                            EnumValue::Str(_) => None,
+                        });
+                        EnumValue::Int(previous.map_or(1, |previous| previous + 1))
+                    }
+                }
+                ExprType::Constant(constant) if constant.int_value().is_some() => {
+                    EnumValue::Int(constant.int_value().unwrap_or_default().into())
+                }
+                ExprType::Constant(constant) if constant.string_value().is_some() => {
+                    EnumValue::Str(constant.string_value().unwrap_or_default())
+                }
+                _ => {
+                    return Err(CodeGenError::unsupported(
+                        format!("the value of the Enum member {}.{}", self.name, name.id),
+                        Some(s.source_location(UNKNOWN_FILE)),
+                    )
+                    .into())
+                }
+            };
+            if let Some(first) = members.first()
+                && std::mem::discriminant(&first.value) != std::mem::discriminant(&value)
+            {
+                return Err(CodeGenError::unsupported(
+                    format!("an Enum with both int and str values, {}", self.name),
+                    Some(s.source_location(UNKNOWN_FILE)),
+                )
+                .into());
+            }
+            members.push(EnumMember {
+                name: name.id.clone(),
+                variant: variant_ident(&name.id, options),
+                value,
+            });
+        }
+        Ok(Some(members))
+    }
+
+    /// An Enum is a Rust enum, whose int values are its discriminants, and whose str values
+    /// are returned by its `value()` method. `ALL` has every variant, in order, like
+    /// iterating over the class does in Python. Its members print like `str()` makes them:
+    /// the value of an `IntEnum` or `StrEnum`, and the class and member name otherwise,
+    /// like `Color.RED`, unless the class has a `__str__` of its own.
+    fn enum_to_rust(
+        &self,
+        members: &[EnumMember],
+        visibility: TokenStream,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let class_name = convert_ident(&self.name, IdentCase::Type, &options, &symbols);
+        let variants: Vec<_> = members.iter().map(|member| &member.variant).collect();
+        let names: Vec<_> = members.iter().map(|member| &member.name).collect();
+
+        let (declarations, value) = match members.first().map(|member| &member.value) {
+            Some(EnumValue::Str(_)) => {
+                let values = members.iter().map(|member| match &member.value {
+                    EnumValue::Str(value) => value.clone(),
+                    EnumValue::Int(value) => value.to_string(),
+                });
+                (
+                    quote!(#(#variants),*),
+                    quote! {
+                        pub fn value(&self) -> &'static str {
+                            match self {
+                                #(#class_name::#variants => #values,)*
+                            }
+                        }
+                    },
+                )
+            }
+            _ => {
+                let int_type = options.int_type_tokens()?;
+                let discriminants = members.iter().map(|member| match &member.value {
+                    EnumValue::Int(value) => proc_macro2::Literal::i128_unsuffixed(*value),
+                    EnumValue::Str(_) => proc_macro2::Literal::i128_unsuffixed(0),
+                });
+                (
+                    quote!(#(#variants = #discriminants),*),
+                    quote! {
+                        pub fn value(&self) -> #int_type {
+                            *self as #int_type
+                        }
+                    },
+                )
+            }
+        };
+
+        let mut methods = TokenStream::new();
+        for s in &self.body {
+            if let StatementType::FunctionDef(_) = &s.statement {
+                methods.extend(s.clone().to_rust(CodeGenContext::Class, options.clone(), symbols.clone())?);
+            }
         }
+        let has_str = self.body.iter().any(|s| matches!(&s.statement, StatementType::FunctionDef(f) if f.name == "__str__"));
+        let python_name = &self.name;
+        let display = match self.enum_kind(&symbols).as_deref() {
+            _ if has_str => quote!(write!(f, "{}", self.__str__())),
+            Some("IntEnum" | "StrEnum") => quote!(write!(f, "{}", self.value())),
+            _ => quote!(write!(f, "{}.{}", #python_name, self.name())),
+        };
+
+        let docs = self.get_docstring().into_iter().flat_map(|docstring| {
+            docstring
+                .lines()
+                .map(|line| if line.trim().is_empty() { String::new() } else { format!(" {}", line) })
+                .collect::<Vec<_>>()
+        });
+        Ok(quote! {
+            #(#[doc = #docs])*
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            #visibility enum #class_name {
+                #declarations
+            }
+            impl #class_name {
+                pub const ALL: &'static [#class_name] = &[#(#class_name::#variants),*];
+
+                pub fn name(&self) -> &'static str {
+                    match self {
+                        #(#class_name::#variants => #names,)*
+                    }
+                }
+
+                #value
+                #methods
+            }
+            impl std::fmt::Display for #class_name {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    #display
+                }
+            }
+        })
+    }
+
+    /// The type variables of the class, which its `Generic[T, U]` or `Protocol[T]` base declares.
+    pub fn type_variables(&self, symbols: &SymbolTableScopes) -> Vec<TypeVar> {
+        let mut found = Vec::new();
+        for base in &self.bases {
+            if let ExprType::Subscript(subscript) = base
+                && (is_typing_name(&subscript.value, "Generic") || is_typing_name(&subscript.value, "Protocol"))
+            {
+                type_variables(&subscript.slice, symbols, &mut found);
+            }
+        }
+        found
+    }
+
+    fn get_docstring(&self) -> Option<String> {
+        // Clean up the docstring for Rust documentation
+        Self::get_docstring_from_body(self.body()).map(|text| self.format_docstring(&text))
     }
     
-    fn format_docstring(&self, raw: &str) -> String {
-        // Remove surrounding quotes
-        let content = raw.trim_matches('"');
-        
+    fn format_docstring(&self, content: &str) -> String {
         // Split into lines and clean up Python-style indentation
         let lines: Vec<&str> = content.lines().collect();
         if lines.is_empty() {
@@ -188,10 +1436,407 @@ impl ClassDef {
                 let cleaned = line.trim();
                 if !cleaned.is_empty() {
                     formatted.push(cleaned.to_string());
+                } else if formatted.last().is_some_and(|last| !last.is_empty()) {
+                    // Keep paragraph breaks, but not runs of blank lines.
+                    formatted.push(String::new());
                 }
             }
+            while formatted.last().is_some_and(|last| last.is_empty()) {
+                formatted.pop();
+            }
         }
         
         formatted.join("\n")
     }
 }
+
+impl Object for ClassDef {
+    fn body(&self) -> &[Statement] {
+        &self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_compiles, module_to_rust, test_options};
+
+    #[test]
+    fn test_marker_uses_only_the_type_parameters_fields_dont() {
+        let code = module_to_rust(
+            "from typing import Generic, TypeVar
+
+T = TypeVar('T')
+U = TypeVar('U')
+
+class Stack(Generic[T]):
+    def __init__(self):
+        self.items: list[T] = []
+
+class Tagged(Generic[T, U]):
+    def __init__(self, value: T, count: int):
+        self.value = value
+        self.count = count
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub struct Data < T > { pub items : Vec < T > , }"), "{}", code);
+        assert!(code.contains("Self { items , }"), "{}", code);
+        assert!(
+            code.contains("pub struct Data < T , U > { pub value : T , pub count : i64 , _type_parameters : :: std :: marker :: PhantomData < (U ,) > , }"),
+            "{}",
+            code
+        );
+        assert!(code.contains("Self { value , count , _type_parameters : :: std :: marker :: PhantomData , }"), "{}", code);
+    }
+
+    #[test]
+    fn test_private_class_has_no_visibility() {
+        let code = module_to_rust("class _Hidden:\n    pass\n", test_options());
+        assert!(code.starts_with("mod _Hidden { use super :: * ; trait Cls { }"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_generic_base_makes_the_class_generic() {
+        let code = module_to_rust(
+            "from typing import Generic, List, TypeVar
+
+K = TypeVar('K')
+V = TypeVar('V')
+
+class Pair(Generic[K, V]):
+    def key(self, keys: List[K]) -> K:
+        return keys[0]
+",
+            test_options(),
+        );
+        assert!(code.contains("pub trait Cls < K , V > {"), "{}", code);
+        assert!(code.contains("pub struct Data < K , V > { _type_parameters : :: std :: marker :: PhantomData < (K , V ,) > , }"), "{}", code);
+        assert!(code.contains("impl < K , V > Cls < K , V > for Data < K , V > { }"), "{}", code);
+        // The method uses the class's type variables, it doesn't declare its own.
+        assert!(code.contains("fn key (& self , keys : Vec < K >) -> K"), "{}", code);
+    }
+
+    #[test]
+    fn test_protocol_becomes_a_trait() {
+        let code = module_to_rust(
+            "from typing import Protocol
+
+class Drawable(Protocol):
+    \"\"\"Something that can be drawn.\"\"\"
+    def draw(self, scale: int) -> str:
+        ...
+
+    def outline(self) -> str:
+        return draw(1)
+
+class Circle(Drawable):
+    def draw(self, scale: int) -> str:
+        return \"circle\"
+
+    def area(self) -> int:
+        return 3
+
+def render(shape: Drawable) -> str:
+    return shape.draw(2)
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub trait Drawable { fn draw (& self , scale : i64) -> String ; fn outline (& self) -> String {"), "{}", code);
+        // The conforming class implements the Protocol's methods in the Protocol's trait,
+        // and its own methods in an impl of its Data.
+        assert!(code.contains("pub trait Cls { } # [derive (Clone , Default)] pub struct Data { } impl Data { pub fn area (& self) -> i64 { 3 } }"), "{}", code);
+        assert!(code.contains("impl super :: Drawable for Data { fn draw (& self , scale : i64) -> String {"), "{}", code);
+        assert!(code.contains("pub fn render (shape : impl Drawable) -> String"), "{}", code);
+    }
+
+    #[test]
+    fn test_methods_changing_the_instance_take_mut_self() {
+        let code = module_to_rust(
+            "class Child:
+    def __init__(self, name: str):
+        self.name = name
+
+    def rename(self, name: str):
+        self.name = name
+
+class Counter:
+    def __init__(self):
+        self.count = 0
+        self.items: list[int] = []
+        self.child = Child(\"c\")
+
+    def twice(self):
+        self.step()
+        self.step()
+
+    def step(self):
+        self.bump()
+
+    def bump(self):
+        self.count += 1
+
+    def get(self) -> int:
+        return self.count
+
+    def add(self, x: int):
+        self.items.append(x)
+
+    def label(self) -> str:
+        return self.child.name.upper()
+
+    def relabel(self):
+        self.child.rename(\"d\")
+
+def run() -> int:
+    counter = Counter()
+    counter.twice()
+    counter.add(2)
+    return counter.get()
+",
+            test_options(),
+        );
+        // Methods changing the instance through others that do take `&mut self` as well,
+        // whichever order they're defined in.
+        assert!(code.contains("fn twice (& mut self) { self . step () ; self . step () ; }"), "{}", code);
+        assert!(code.contains("fn step (& mut self)"), "{}", code);
+        assert!(code.contains("fn bump (& mut self) { self . count += 1 ; }"), "{}", code);
+        assert!(code.contains("fn get (& self) -> i64"), "{}", code);
+        assert!(code.contains("fn add (& mut self , x : i64) { self . items . push (x) ; }"), "{}", code);
+        assert!(code.contains("fn label (& self) -> String { self . child . name . to_uppercase () }"), "{}", code);
+        assert!(code.contains("fn relabel (& mut self)"), "{}", code);
+        assert!(code.contains("fn rename (& mut self , name : String) { self . name = name ; ; }"), "{}", code);
+        assert!(code.contains("let mut counter = Counter :: Data :: new ()"), "{}", code);
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_abstract_base_class_becomes_a_trait() {
+        let code = module_to_rust(
+            "from abc import ABC, abstractmethod
+
+class Shape(ABC):
+    @abstractmethod
+    def area(self) -> float:
+        raise NotImplementedError
+
+    def describe(self) -> str:
+        return \"shape\"
+
+class Square(Shape):
+    def __init__(self, side: float):
+        self.side = side
+
+    def area(self) -> float:
+        return self.side * self.side
+
+class Runner:
+    @abstractmethod
+    def run(self) -> int:
+        pass
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        // Abstract methods are required, the others have default implementations.
+        assert!(code.contains("pub trait Shape { fn area (& self) -> f64 ; fn describe (& self) -> String {"), "{}", code);
+        assert!(code.contains("impl super :: Shape for Data { fn area (& self) -> f64 {"), "{}", code);
+        assert!(code.contains("pub trait Runner { fn run (& self) -> i64 ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_enum_members_are_variants_with_discriminants() {
+        let code = module_to_rust(
+            "import enum
+
+class Status(enum.Enum):
+    OK = 200
+    NOT_FOUND = 404
+    ERROR = 500
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub enum Status { Ok = 200 , NotFound = 404 , Error = 500 }"), "{}", code);
+        assert!(!code.contains("pub mod Status"), "{}", code);
+    }
+
+    #[test]
+    fn test_enum_becomes_a_rust_enum() {
+        let code = module_to_rust(
+            "from enum import Enum, StrEnum, auto
+
+class Color(Enum):
+    RED = 1
+    DARK_GREEN = auto()
+
+class Mode(StrEnum):
+    READ = auto()
+    WRITE = 'w'
+
+def pick() -> int:
+    for c in Color:
+        print(c)
+    return Color.DARK_GREEN.value
+
+def default_mode() -> Mode:
+    return Mode.READ
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub enum Color { Red = 1 , DarkGreen = 2 }"), "{}", code);
+        assert!(code.contains("pub const ALL : & 'static [Color] = & [Color :: Red , Color :: DarkGreen] ;"), "{}", code);
+        assert!(code.contains("pub fn value (& self) -> i64 { * self as i64 }"), "{}", code);
+        assert!(code.contains("pub enum Mode { Read , Write }"), "{}", code);
+        assert!(code.contains("match self { Mode :: Read => \"read\" , Mode :: Write => \"w\" , }"), "{}", code);
+        // Members used in other functions are the variants.
+        assert!(code.contains("for c in Color :: ALL . iter () . copied ()"), "{}", code);
+        assert!(code.contains("Color :: DarkGreen . value ()"), "{}", code);
+        assert!(code.contains("pub fn default_mode () -> Mode { Mode :: Read }"), "{}", code);
+        // Members print as the class and member name, or as the value of a StrEnum.
+        assert!(code.contains("impl std :: fmt :: Display for Color { fn fmt (& self , f : & mut std :: fmt :: Formatter) -> std :: fmt :: Result { write ! (f , \"{}.{}\" , \"Color\" , self . name ()) } }"), "{}", code);
+        assert!(code.contains("impl std :: fmt :: Display for Mode { fn fmt (& self , f : & mut std :: fmt :: Formatter) -> std :: fmt :: Result { write ! (f , \"{}\" , self . value ()) } }"), "{}", code);
+    }
+
+    #[test]
+    fn test_named_tuples_and_typed_dicts_become_structs() {
+        let code = module_to_rust(
+            "from typing import NamedTuple, NotRequired, TypedDict
+
+class Point(NamedTuple):
+    x: int
+    y: int = 0
+
+Pair = NamedTuple('Pair', [('left', str), ('right', str)])
+
+class Config(TypedDict, total=False):
+    host: str
+    port: int
+
+Options = TypedDict('Options', {'verbose': bool, 'name': NotRequired[str]})
+
+def last(p: Point) -> int:
+    return p[0] + p[-1]
+
+def make():
+    p = Point(1, y=2)
+    pair = Pair('a', 'b')
+    options = Options(verbose=True)
+    print(p, pair, options, Point(3))
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("# [derive (Clone , Debug , PartialEq)] pub struct Point { pub x : i64 , pub y : i64 }"), "{}", code);
+        assert!(code.contains("pub fn new (x : i64 , y : Option < i64 >) -> Self { Self { x , y : y . unwrap_or_else (|| 0) } }"), "{}", code);
+        assert!(code.contains("pub struct Pair { pub left : String , pub right : String }"), "{}", code);
+        assert!(code.contains("pub struct Config { pub host : Option < String > , pub port : Option < i64 > }"), "{}", code);
+        assert!(code.contains("pub struct Options { pub verbose : bool , pub name : Option < String > }"), "{}", code);
+        // The items of a NamedTuple are its fields.
+        assert!(code.contains("(p . x) + (p . y)"), "{}", code);
+        assert!(!code.contains("__module_init__"), "{}", code);
+        // Calls make them with `new`, which takes the fields with defaults as Options.
+        assert!(code.contains("let p = Point :: new (1 , Some (2))"), "{}", code);
+        assert!(code.contains("Point :: new (3 , None)"), "{}", code);
+        assert!(code.contains("let pair = Pair :: new (String :: from (\"a\") , String :: from (\"b\"))"), "{}", code);
+        assert!(code.contains("pub fn new (verbose : bool , name : Option < String >) -> Self { Self { verbose , name } }"), "{}", code);
+        assert!(code.contains("let options = Options :: new (true , None)"), "{}", code);
+    }
+
+    #[test]
+    fn test_init_assignments_become_fields() {
+        let source = "class Account:
+    def __init__(self, owner: str, start: int):
+        \"\"\"Open the account.\"\"\"
+        self.owner = owner
+        self.history: list[int] = []
+        self.label = \"main\"
+        if start > 100:
+            self.vip = True
+        self.balance = start
+        self.balance += 1
+
+    def deposit(self, amount: int):
+        self.balance += amount
+
+def open_account() -> Account:
+    account = Account(\"ann\", 5)
+    account.deposit(10)
+    return account
+";
+        let module = crate::parse(source, "test.py").unwrap();
+        let StatementType::ClassDef(class) = &module.raw.body[0].statement else {
+            panic!("expected a class");
+        };
+        let fields: Vec<_> = collect_self_assignments(&class.init().unwrap().body).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(fields, ["owner", "history", "label", "vip", "balance"]);
+
+        let code = module_to_rust(source, test_options());
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub struct Data { pub owner : String , pub history : Vec < i64 > , pub label : String , pub vip : bool , pub balance : i64 , }"), "{}", code);
+        assert!(code.contains("impl Data { pub fn new (owner : String , start : i64) -> Self { let mut vip : bool = Default :: default () ;"), "{}", code);
+        assert!(code.contains("let label : String = String :: from (\"main\") ;"), "{}", code);
+        assert!(code.contains("if (start) > (100) { vip = true ; ; }"), "{}", code);
+        // The constructor isn't a method, and the docstring isn't code.
+        assert!(
+            code.contains("let mut balance : i64 = start ; balance += 1 ; Self { owner , history , label , vip , balance , } } pub fn deposit"),
+            "{}",
+            code
+        );
+        assert!(!code.contains("Open the account"), "{}", code);
+        assert!(code.contains("Account :: Data :: new (String :: from (\"ann\") , 5)"), "{}", code);
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_dataclass_fields_and_derives() {
+        let code = module_to_rust(
+            "from dataclasses import dataclass, field
+
+@dataclass(order=True)
+class Point:
+    x: int
+    y: int = 0
+    label: str = 'origin'
+    tags: list[str] = field(default_factory=list)
+
+@dataclass(frozen=True, eq=False)
+class Config:
+    name: str = 'app'
+    retries: int = field(default=3)
+
+def make():
+    p = Point(1, label='p')
+    config = Config(retries=5)
+    print(p.x, Config().name, config.retries)
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(
+            code.contains("# [derive (Clone , Debug , PartialEq , PartialOrd)] pub struct Data { pub x : i64 , pub y : i64 , pub label : String , pub tags : Vec < String > , }"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(
+                "pub fn new (x : i64 , y : Option < i64 > , label : Option < String > , tags : Option < Vec < String > >) -> Self { \
+                 Self { x , y : y . unwrap_or_else (|| 0) , label : label . unwrap_or_else (|| String :: from (\"origin\")) , tags : tags . unwrap_or_default () , } }"
+            ),
+            "{}",
+            code
+        );
+        // A frozen dataclass's fields are private, and with defaults for all of them it's
+        // its own default.
+        assert!(code.contains("# [derive (Clone , Debug)] pub struct Data { name : String , retries : i64 , }"), "{}", code);
+        assert!(code.contains("impl Default for Data { fn default () -> Self { Self :: new (None , None) } }"), "{}", code);
+        // Calls make them with `new`, passing the fields with defaults that they're given.
+        assert!(code.contains("let p = Point :: Data :: new (1 , None , Some (String :: from (\"p\")) , None)"), "{}", code);
+        assert!(code.contains("let config = Config :: Data :: new (None , Some (5))"), "{}", code);
+        assert!(code.contains("Config :: Data :: new (None , None) . name"), "{}", code);
+    }
+}
\ No newline at end of file
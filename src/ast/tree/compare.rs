@@ -4,7 +4,7 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    dump, CodeGen, CodeGenContext, Error, ExprType, Node, PythonOptions, SymbolTableScopes,
+    dump, truthiness, CodeGen, CodeGenContext, Constant, Error, ExprType, Node, PythonOptions, SymbolTableScopes, Truthiness,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -34,9 +34,9 @@ impl<'a> FromPyObject<'a> for Compares {
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Compare {
-    ops: Vec<Compares>,
-    left: Box<ExprType>,
-    comparators: Vec<ExprType>,
+    pub ops: Vec<Compares>,
+    pub left: Box<ExprType>,
+    pub comparators: Vec<ExprType>,
 }
 
 impl<'a> FromPyObject<'a> for Compare {
@@ -120,6 +120,16 @@ impl<'a> FromPyObject<'a> for Compare {
     }
 }
 
+impl Compare {
+    /// The operands of a single `==` comparison, like `x == y`.
+    pub fn as_equality(&self) -> Option<(&ExprType, &ExprType)> {
+        match (self.ops.as_slice(), self.comparators.as_slice()) {
+            ([Compares::Eq], [right]) => Some((&self.left, right)),
+            _ => None,
+        }
+    }
+}
+
 impl CodeGen for Compare {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -139,8 +149,11 @@ impl CodeGen for Compare {
         let ops = self.ops.clone();
         let comparators = self.comparators.clone();
 
+        // `x is None` checks which variant an Option is.
+        let is_option = truthiness(&self.left, &options, &symbols) == Truthiness::Option;
         let mut index = 0;
         for op in ops.iter() {
+            let compares_to_none = matches!(comparators.get(index), Some(ExprType::Constant(Constant(None)) | ExprType::NoneType(_)));
             let comparator = comparators
                 .get(index)
                 .expect("getting comparator")
@@ -153,6 +166,8 @@ impl CodeGen for Compare {
                 Compares::LtE => quote!((#left) <= (#comparator)),
                 Compares::Gt => quote!((#left) > (#comparator)),
                 Compares::GtE => quote!((#left) >= (#comparator)),
+                Compares::Is if is_option && compares_to_none => quote!((#left).is_none()),
+                Compares::IsNot if is_option && compares_to_none => quote!((#left).is_some()),
                 Compares::Is => quote!(&#left == &#comparator),
                 Compares::IsNot => quote!(&#left != &#comparator),
                 Compares::In => quote!((#comparator).get(#left) == Some(_)),
@@ -1,13 +1,12 @@
 use std::fmt::*;
 
-use encoding::{all::ISO_8859_6, DecoderTrap, Encoding};
 use litrs::Literal;
 use tracing::debug;
 use proc_macro2::*;
-use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods, types::PyInt};
 use quote::quote;
 
-use crate::{CodeGen, CodeGenContext, Node, PythonOptions, SymbolTableScopes};
+use crate::{CodeGen, CodeGenContext, CodeGenError, Node, PythonOptions, StringType, SymbolTableScopes};
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -34,7 +33,11 @@ impl<'de> Deserialize<'de> for Constant {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let l = Literal::parse(s).expect("[3] Parsing the literal");
+        // None is serialized as its Python name, because it isn't a Rust literal.
+        if s == "None" {
+            return Ok(Self(None));
+        }
+        let l = Literal::parse(s).map_err(serde::de::Error::custom)?;
         Ok(Self(Some(l)))
     }
 }
@@ -48,36 +51,154 @@ impl std::string::ToString for Constant {
     }
 }
 
-pub fn try_string(value: &Bound<PyAny>) -> PyResult<Option<Literal<String>>> {
-    let v: String = value.extract()?;
-    let l = Literal::parse(format!("\"{}\"", v)).expect("[4] Parsing the literal");
+impl Constant {
+    /// The constant written as `source` in Python, like `12` or `"text"`.
+    pub fn parse(source: &str) -> Option<Self> {
+        Literal::parse(source.to_string()).ok().map(|literal| Constant(Some(literal)))
+    }
 
-    Ok(Some(l))
+    /// The text of a string constant, without quotes or escapes, or None for other constants.
+    pub fn string_value(&self) -> Option<String> {
+        match &self.0 {
+            Some(Literal::String(s)) => Some(s.value().to_string()),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer constant, or None for other constants.
+    pub fn int_value(&self) -> Option<i64> {
+        match &self.0 {
+            Some(Literal::Integer(i)) => i.value::<i64>(),
+            _ => None,
+        }
+    }
+
+    /// The value of a bool constant, or None for other constants.
+    pub fn bool_value(&self) -> Option<bool> {
+        match &self.0 {
+            Some(Literal::Bool(b)) => Some(b.value()),
+            _ => None,
+        }
+    }
+
+    /// The value of a numeric constant as a float, or None for other constants.
+    pub fn float_value(&self) -> Option<f64> {
+        match &self.0 {
+            Some(Literal::Integer(i)) => i.value::<i64>().map(|i| i as f64),
+            Some(Literal::Float(f)) => f.number_part().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// True for float constants like `0.5`.
+    pub fn is_float(&self) -> bool {
+        matches!(self.0, Some(Literal::Float(_)))
+    }
+
+    /// The Rust type the constant's literal has, or None for constants like None. Numbers
+    /// have the `int_type` and `float_type` of the options.
+    pub fn rust_type(&self, options: &PythonOptions) -> Option<TokenStream> {
+        match &self.0 {
+            Some(Literal::String(_)) => Some(quote!(&'static str)),
+            Some(Literal::Bool(_)) => Some(quote!(bool)),
+            Some(Literal::Float(_)) => options.float_type.parse().ok(),
+            Some(Literal::Integer(_)) => options.int_type_tokens().ok(),
+            _ => None,
+        }
+    }
+
+    /// A string constant as a value of the `string_type` of the options, or None for other
+    /// constants.
+    pub fn string_to_rust(&self, options: &PythonOptions) -> Option<TokenStream> {
+        let Some(Literal::String(literal)) = &self.0 else {
+            return None;
+        };
+        if options.string_type == StringType::Cow {
+            options.required_imports.require("std::borrow::Cow");
+        }
+        let literal = proc_macro2::Literal::string(literal.value());
+        Some(options.string_type.literal(quote!(#literal)))
+    }
+
+    /// An integer constant as a literal of the `int_type` of the options, or None for other
+    /// constants. Rust infers `i32` for a literal when nothing else decides its type, so a
+    /// literal that doesn't fit one, or any literal if `suffixed` is set, has the type's
+    /// suffix. A type that isn't a primitive, like a bignum, is converted from an `i64`
+    /// literal, or parsed from the digits of a literal that doesn't fit one.
+    pub fn int_to_rust(
+        &self,
+        options: &PythonOptions,
+        suffixed: bool,
+    ) -> std::result::Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+        let Some(Literal::Integer(literal)) = &self.0 else {
+            return Ok(None);
+        };
+        let int_type = options.int_type_tokens()?;
+        let value = literal.value::<i128>();
+        let digits = literal.to_string();
+
+        let Some((min, max)) = int_range(options.int_rust_type()) else {
+            return Ok(Some(match literal.value::<i64>() {
+                Some(value) => {
+                    let value = proc_macro2::Literal::i64_suffixed(value);
+                    quote!(#int_type::from(#value))
+                }
+                None => quote!(#digits.parse::<#int_type>().unwrap()),
+            }));
+        };
+        let value = value.filter(|value| (min..=max).contains(value)).ok_or_else(|| CodeGenError::TypeMismatch {
+            expected: options.int_type.clone(),
+            found: format!("the integer {}, which doesn't fit in one", digits),
+            location: None,
+        })?;
+        let literal: TokenStream = if suffixed || i32::try_from(value).is_err() {
+            format!("{}{}", value, options.int_type).parse()
+        } else {
+            value.to_string().parse()
+        }
+        .map_err(|_| CodeGenError::malformed(format!("constant {} isn't a Rust literal", digits)))?;
+        Ok(Some(literal))
+    }
 }
 
-pub fn try_bytes(value: &Bound<PyAny>) -> PyResult<Option<Literal<String>>> {
-    let v: &[u8] = value.extract()?;
-    let l = Literal::parse(format!(
-        "b\"{}\"",
-        ISO_8859_6
-            .decode(v, DecoderTrap::Replace)
-            .expect("decoding byte string")
-    ))
-    .expect("[4] Parsing the literal");
+/// The range of a primitive integer type, or None if it isn't one. `u128` values beyond
+/// `i128::MAX` aren't supported.
+pub fn int_range(int_type: &str) -> Option<(i128, i128)> {
+    Some(match int_type {
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" | "isize" => (i64::MIN.into(), i64::MAX.into()),
+        "i128" => (i128::MIN, i128::MAX),
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" | "usize" => (0, u64::MAX.into()),
+        "u128" => (0, i128::MAX),
+        _ => return None,
+    })
+}
+
+pub fn try_string(value: &Bound<PyAny>) -> PyResult<Option<Literal<String>>> {
+    let v: String = value.extract()?;
+    // Debug formatting quotes the string and escapes it like a Rust string literal.
+    let l = Literal::parse(format!("{:?}", v)).expect("[4] Parsing the literal");
 
     Ok(Some(l))
 }
 
 pub fn try_int(value: &Bound<PyAny>) -> PyResult<Option<Literal<String>>> {
-    let v: isize = value.extract()?;
-    let l = Literal::parse(format!("{}", v)).expect("[4] Parsing the literal");
+    // Python integers have any number of digits, so they're kept as their digits.
+    let v = value.downcast::<PyInt>()?;
+    let l = Literal::parse(v.str()?.to_string()).expect("[4] Parsing the literal");
 
     Ok(Some(l))
 }
 
 pub fn try_float(value: &Bound<PyAny>) -> PyResult<Option<Literal<String>>> {
     let v: f64 = value.extract()?;
-    let l = Literal::parse(format!("{}", v)).expect("[4] Parsing the literal");
+    // Debug formatting keeps the `.0` of a whole number, so it's still a float literal.
+    let l = Literal::parse(format!("{:?}", v)).expect("[4] Parsing the literal");
 
     Ok(Some(l))
 }
@@ -116,15 +237,13 @@ impl<'a> FromPyObject<'a> for Constant {
 
         let l = if let Ok(l) = try_string(&value) {
             l
-        } else if let Ok(l) = try_bytes(&value) {
-            l
         // We have to evaluaet bool before int because if a bool is evaluated as it, it will be cooerced to an in.
         } else if let Ok(l) = try_bool(&value) {
             l
-        } else if let Ok(l) = try_float(&value) {
-            l
         } else if let Ok(l) = try_int(&value) {
             l
+        } else if let Ok(l) = try_float(&value) {
+            l
         } else if let Ok(l) = try_option(&value) {
             l
         } else {
@@ -143,15 +262,18 @@ impl CodeGen for Constant {
     fn to_rust(
         self,
         _ctx: Self::Context,
-        _options: Self::Options,
+        options: Self::Options,
         _symbols: Self::SymbolTable,
     ) -> std::result::Result<TokenStream, Box<dyn std::error::Error>> {
+        if let Some(int) = self.int_to_rust(&options, false)? {
+            return Ok(int);
+        }
         match self.0 {
             Some(c) => {
                 let v: TokenStream = c
                     .to_string()
                     .parse()
-                    .expect(format!("parsing Constant {}", c).as_str());
+                    .map_err(|_| CodeGenError::malformed(format!("constant {} isn't a Rust literal", c)))?;
                 Ok(quote!(#v))
             }
             None => Ok(quote!(None)),
@@ -164,6 +286,7 @@ mod tests {
     use test_log::test;
     //use super::*;
     use crate::{symbols::SymbolTableScopes, CodeGen};
+    use crate::test_utils::{test_options, try_module_to_rust};
     use tracing::debug;
 
     #[test]
@@ -264,4 +387,33 @@ mod tests {
 
         assert_eq!("use stdpython :: * ; None", ast.to_string());
     }
+
+    /// The code for a module of a single expression, with `int_type` as the int_type.
+    fn int_to_rust(source: &str, int_type: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let mut options = test_options();
+        options.int_type = int_type.to_string();
+        try_module_to_rust(source, options)
+    }
+
+    #[test]
+    fn test_int_literals_follow_int_type() {
+        assert_eq!(int_to_rust("7", "i64").unwrap(), "7");
+        assert_eq!(int_to_rust("7", "i32").unwrap(), "7");
+        // Without a suffix, Rust would take a literal that doesn't fit an i32 to be one.
+        assert_eq!(int_to_rust("3000000000", "i64").unwrap(), "3000000000i64");
+        assert_eq!(int_to_rust("3000000000", "u32").unwrap(), "3000000000u32");
+        assert!(int_to_rust("3000000000", "i32").unwrap_err().to_string().contains("expected i32"));
+        assert_eq!(int_to_rust("7.0", "i32").unwrap(), "7.0");
+        assert!(int_to_rust("100000 * 100000", "i64").unwrap().contains("(100000i64) * (100000)"));
+        assert!(int_to_rust("100000 * 100000", "i32").unwrap().contains("(100000i32) * (100000)"));
+    }
+
+    #[test]
+    fn test_int_literals_of_a_bignum_type() {
+        assert_eq!(int_to_rust("7", "BigInt").unwrap(), "BigInt :: from (7i64)");
+        assert_eq!(
+            int_to_rust("123456789012345678901234567890", "BigInt").unwrap(),
+            "\"123456789012345678901234567890\" . parse :: < BigInt > () . unwrap ()"
+        );
+    }
 }
@@ -47,26 +47,60 @@ impl CodeGen for Dict {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        if self.keys.is_empty() {
+            return Ok(quote!(std::collections::HashMap::new()));
+        }
+
+        // Consecutive entries are inserted together, and `**other` entries extend the map
+        // with a copy of the other one, in order, so later keys replace earlier ones.
+        let mut groups: Vec<TokenStream> = Vec::new();
         let mut pairs = Vec::new();
-        
         for (key, value) in self.keys.iter().zip(self.values.iter()) {
+            let value_tokens = element_to_rust(value, &ctx, &options, &symbols)?;
             match key {
-                Some(k) => {
-                    let key_tokens = k.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-                    let value_tokens = value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-                    pairs.push(quote! { (#key_tokens, #value_tokens) });
+                Some(key) => {
+                    let key_tokens = element_to_rust(key, &ctx, &options, &symbols)?;
+                    pairs.push(quote!((#key_tokens, #value_tokens)));
                 }
                 None => {
-                    // Handle dictionary unpacking (**dict)
-                    let value_tokens = value.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-                    pairs.push(quote! { ..#value_tokens });
+                    if !pairs.is_empty() {
+                        groups.push(quote!([#(#pairs),*]));
+                        pairs.clear();
+                    }
+                    groups.push(quote!((#value_tokens).clone()));
                 }
             }
         }
-        
-        Ok(quote! {
-            std::collections::HashMap::from([#(#pairs),*])
-        })
+        if !pairs.is_empty() {
+            groups.push(quote!([#(#pairs),*]));
+        }
+
+        if self.keys.iter().all(Option::is_some) {
+            let entries = &groups[0];
+            return Ok(quote!(std::collections::HashMap::from(#entries)));
+        }
+        Ok(quote!({
+            let mut dict = std::collections::HashMap::new();
+            #(dict.extend(#groups);)*
+            dict
+        }))
+    }
+}
+
+/// Generates an element of a dict, set or tuple literal. A string constant is a value of
+/// the `string_type`, like it is where a `str` is expected, since the element types of the
+/// containers are resolved the same way.
+pub fn element_to_rust(
+    element: &ExprType,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    match element {
+        ExprType::Constant(constant) if constant.string_value().is_some() => {
+            Ok(constant.string_to_rust(options).unwrap_or_default())
+        }
+        _ => element.clone().to_rust(ctx.clone(), options.clone(), symbols.clone()),
     }
 }
 
@@ -74,8 +108,55 @@ impl CodeGen for Dict {
 mod tests {
     use super::*;
     use crate::create_parse_test;
+    use crate::test_utils::{module_to_rust, test_options};
 
     create_parse_test!(test_empty_dict, "{}", "dict_test.py");
     create_parse_test!(test_simple_dict, "{'a': 1, 'b': 2}", "dict_test.py");
     create_parse_test!(test_dict_with_variables, "{x: y, z: w}", "dict_test.py");
+
+    #[test]
+    fn test_literals_of_annotated_returns() {
+        let code = module_to_rust(
+            "def counts() -> dict[str, int]:
+    return {'a': 1, 'b': 2}
+
+def primes() -> set[int]:
+    return {2, 3, 5}
+
+def pair(x: int) -> tuple[int, str]:
+    return (x, 'one')
+
+def empty() -> dict[str, int]:
+    return {}
+
+def nothing() -> set[str]:
+    return set()
+",
+            test_options(),
+        );
+        assert!(
+            code.contains("-> HashMap < String , i64 > { std :: collections :: HashMap :: from ([(String :: from (\"a\") , 1) , (String :: from (\"b\") , 2)]) }"),
+            "{}",
+            code
+        );
+        assert!(code.contains("-> HashSet < i64 > { std :: collections :: HashSet :: from ([2 , 3 , 5]) }"), "{}", code);
+        assert!(code.contains("-> (i64 , String) { (x , String :: from (\"one\")) }"), "{}", code);
+        assert!(code.contains("-> HashMap < String , i64 > { std :: collections :: HashMap :: new () }"), "{}", code);
+        assert!(code.contains("-> HashSet < String > { std :: collections :: HashSet :: new () }"), "{}", code);
+    }
+
+    #[test]
+    fn test_dict_spread_extends_in_order() {
+        let code = module_to_rust(
+            "def merged(base: dict[str, int]) -> dict[str, int]:
+    return {'a': 1, **base, 'b': 2}
+",
+            test_options(),
+        );
+        assert!(
+            code.contains("let mut dict = std :: collections :: HashMap :: new () ; dict . extend ([(String :: from (\"a\") , 1)]) ; dict . extend ((base) . clone ()) ; dict . extend ([(String :: from (\"b\") , 2)]) ; dict"),
+            "{}",
+            code
+        );
+    }
 }
\ No newline at end of file
@@ -1,11 +1,11 @@
 use proc_macro2::TokenStream;
-use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods, types::PyTypeMethods};
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods, types::{PyBytes, PyTypeMethods}};
 use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    dump, Attribute, Await, BinOp, BoolOp, Call, CodeGen, CodeGenContext, Compare, Constant, Error,
-    Name, NamedExpr, Node, PythonOptions, SymbolTableScopes, UnaryOp, Lambda, IfExp, Dict, Set, Tuple, Subscript, Starred, ListComp, DictComp, SetComp, GeneratorExp, Yield, YieldFrom, JoinedStr, FormattedValue,
+    annotation_to_rust_type, dump, Attribute, Await, BinOp, BoolOp, Bytes, Call, CodeGen, CodeGenContext, Compare, Constant,
+    unsupported_to_rust, variant_name, Name, NamedExpr, Node, PythonOptions, SymbolTableScopes, UnaryOp, Lambda, IfExp, Dict, Set, Tuple, Subscript, Starred, ListComp, DictComp, SetComp, GeneratorExp, Yield, YieldFrom, JoinedStr, FormattedValue,
 };
 
 /// Mostly this shouldn't be used, but it exists so that we don't have to manually implement FromPyObject on all of ExprType
@@ -49,6 +49,8 @@ pub enum ExprType {
     FormattedValue(FormattedValue),
     JoinedStr(JoinedStr),
     Constant(Constant),
+    /// A bytes constant, which keeps its bytes as they are
+    Bytes(Bytes),
 
     /// These can appear in a few places, such as the left side of an assignment.
     Attribute(Attribute),
@@ -59,6 +61,8 @@ pub enum ExprType {
     Tuple(Tuple),
     /*Slice(),*/
     NoneType(Constant),
+    /// `...`, the one constant that isn't a literal
+    Ellipsis,
 
     Unimplemented(String),
     #[default]
@@ -130,6 +134,8 @@ impl<'a> FromPyObject<'a> for ExprType {
                 );
                 Ok(Self::Compare(c))
             }
+            "Constant" if ob.getattr("value")?.is(ob.py().Ellipsis()) => Ok(Self::Ellipsis),
+            "Constant" if ob.getattr("value")?.is_instance_of::<PyBytes>() => Ok(Self::Bytes(ob.extract()?)),
             "Constant" => {
                 tracing::debug!("constant: {}", dump(ob, None)?);
                 let c = ob.extract().expect(
@@ -238,6 +244,16 @@ impl<'a> FromPyObject<'a> for ExprType {
                 );
                 Ok(Self::Lambda(l))
             }
+            "NamedExpr" => {
+                let n = ob.extract().expect(
+                    ob.error_message(
+                        "<unknown>",
+                        format!("extracting NamedExpr in expression {}", dump(ob, None)?),
+                    )
+                    .as_str(),
+                );
+                Ok(Self::NamedExpr(n))
+            }
             "IfExp" => {
                 let i = ob.extract().expect(
                     ob.error_message(
@@ -353,11 +369,65 @@ impl<'a> FromPyObject<'a> for ExprType {
     }
 }
 
+/// An expression's position is its node's, for the kinds of expression that record one.
+macro_rules! expr_type_position {
+    ($self:ident, $method:ident) => {
+        match $self {
+            ExprType::BinOp(e) => e.$method(),
+            ExprType::Lambda(e) => e.$method(),
+            ExprType::IfExp(e) => e.$method(),
+            ExprType::Dict(e) => e.$method(),
+            ExprType::Set(e) => e.$method(),
+            ExprType::ListComp(e) => e.$method(),
+            ExprType::DictComp(e) => e.$method(),
+            ExprType::SetComp(e) => e.$method(),
+            ExprType::GeneratorExp(e) => e.$method(),
+            ExprType::Yield(e) => e.$method(),
+            ExprType::YieldFrom(e) => e.$method(),
+            ExprType::Call(e) => e.$method(),
+            ExprType::FormattedValue(e) => e.$method(),
+            ExprType::JoinedStr(e) => e.$method(),
+            ExprType::Attribute(e) => e.$method(),
+            ExprType::Subscript(e) => e.$method(),
+            ExprType::Starred(e) => e.$method(),
+            ExprType::Tuple(e) => e.$method(),
+            _ => None,
+        }
+    };
+}
+
+impl Node for ExprType {
+    fn lineno(&self) -> Option<usize> {
+        expr_type_position!(self, lineno)
+    }
+
+    fn col_offset(&self) -> Option<usize> {
+        expr_type_position!(self, col_offset)
+    }
+
+    fn end_lineno(&self) -> Option<usize> {
+        expr_type_position!(self, end_lineno)
+    }
+
+    fn end_col_offset(&self) -> Option<usize> {
+        expr_type_position!(self, end_col_offset)
+    }
+}
+
 impl<'a> CodeGen for ExprType {
     type Context = CodeGenContext;
     type Options = PythonOptions;
     type SymbolTable = SymbolTableScopes;
 
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        match self {
+            ExprType::Call(call) => call.find_symbols(symbols),
+            ExprType::IfExp(i) => i.find_symbols(symbols),
+            ExprType::NamedExpr(n) => n.find_symbols(symbols),
+            _ => symbols,
+        }
+    }
+
     fn to_rust(
         self,
         ctx: Self::Context,
@@ -372,8 +442,10 @@ impl<'a> CodeGen for ExprType {
             ExprType::Call(call) => call.to_rust(ctx, options, symbols),
             ExprType::Compare(c) => c.to_rust(ctx, options, symbols),
             ExprType::Constant(c) => c.to_rust(ctx, options, symbols),
+            ExprType::Bytes(b) => b.to_rust(ctx, options, symbols),
             ExprType::Lambda(l) => l.to_rust(ctx, options, symbols),
             ExprType::IfExp(i) => i.to_rust(ctx, options, symbols),
+            ExprType::NamedExpr(n) => n.to_rust(ctx, options, symbols),
             ExprType::Dict(d) => d.to_rust(ctx, options, symbols),
             ExprType::Set(s) => s.to_rust(ctx, options, symbols),
             ExprType::ListComp(lc) => lc.to_rust(ctx, options, symbols),
@@ -392,10 +464,7 @@ impl<'a> CodeGen for ExprType {
                 let mut has_starred = false;
                 
                 for li in l {
-                    let code = li
-                        .clone()
-                        .to_rust(ctx.clone(), options.clone(), symbols.clone())
-                        .expect(format!("Extracting list item {:?}", li).as_str());
+                    let code = li.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
                     
                     // Check if this is a starred expression
                     if matches!(li, ExprType::Starred(_)) {
@@ -466,10 +535,7 @@ impl<'a> CodeGen for ExprType {
             ExprType::NoneType(c) => c.to_rust(ctx, options, symbols),
             ExprType::UnaryOp(operand) => operand.to_rust(ctx, options, symbols),
 
-            _ => {
-                let error = Error::ExprTypeNotYetImplemented(self);
-                Err(error.into())
-            }
+            _ => Ok(unsupported_to_rust(&variant_name(&self), None, &options)),
         }
     }
 }
@@ -579,6 +645,14 @@ impl<'a> FromPyObject<'a> for Expr {
                 r.value = ExprType::Call(et);
                 Ok(r)
             }
+            "Constant" if ob_value.getattr("value")?.is(ob.py().Ellipsis()) => {
+                r.value = ExprType::Ellipsis;
+                Ok(r)
+            }
+            "Constant" if ob_value.getattr("value")?.is_instance_of::<PyBytes>() => {
+                r.value = ExprType::Bytes(ob_value.extract()?);
+                Ok(r)
+            }
             "Constant" => {
                 let c = ob_value.extract().expect(
                     ob.error_message(
@@ -828,6 +902,10 @@ impl CodeGen for Expr {
     type Options = PythonOptions;
     type SymbolTable = SymbolTableScopes;
 
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        self.value.find_symbols(symbols)
+    }
+
     fn to_rust(
         self,
         ctx: Self::Context,
@@ -840,11 +918,13 @@ impl CodeGen for Expr {
         };
 
         match self.value.clone() {
+            ExprType::Attribute(a) => a.to_rust(ctx, options, symbols),
             ExprType::Await(a) => a.to_rust(ctx.clone(), options, symbols),
             ExprType::BinOp(binop) => binop.to_rust(ctx.clone(), options, symbols),
             ExprType::BoolOp(boolop) => boolop.to_rust(ctx.clone(), options, symbols),
             ExprType::Call(call) => call.to_rust(ctx.clone(), options, symbols),
             ExprType::Constant(constant) => constant.to_rust(ctx, options, symbols),
+            ExprType::Bytes(bytes) => bytes.to_rust(ctx, options, symbols),
             ExprType::Compare(compare) => compare.to_rust(ctx, options, symbols),
             ExprType::Lambda(l) => l.to_rust(ctx, options, symbols),
             ExprType::IfExp(i) => i.to_rust(ctx, options, symbols),
@@ -864,16 +944,47 @@ impl CodeGen for Expr {
             ExprType::YieldFrom(yf) => yf.to_rust(ctx, options, symbols),
             ExprType::JoinedStr(js) => js.to_rust(ctx, options, symbols),
             ExprType::FormattedValue(fv) => fv.to_rust(ctx, options, symbols),
-            // NoneType expressions generate no code.
-            ExprType::NoneType(_c) => Ok(quote!()),
+            // NoneType expressions generate no code, and neither does `...` as a
+            // statement, like the body of a stub.
+            ExprType::NoneType(_) | ExprType::Ellipsis => Ok(quote!()),
             _ => {
-                let error = Error::ExprTypeNotYetImplemented(self.value);
-                Err(error.into())
+                let location = self.source_location(crate::UNKNOWN_FILE);
+                Ok(unsupported_to_rust(&variant_name(&self.value), Some(location), &options))
             }
         }
     }
 }
 
+impl Expr {
+    /// The expression as the value a function returns, which is a value of the
+    /// `string_type` if it's a string constant returned from a function annotated to
+    /// return `str`. A function annotated to return an `Optional` returns the values
+    /// other than None as `Some`, unless they're optional already.
+    pub fn returned_to_rust(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let optional = match &self.value {
+            ExprType::Constant(Constant(None)) | ExprType::NoneType(_) => true,
+            ExprType::Name(name) => match symbols.type_annotation(&name.id) {
+                Some(annotation) => annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone())?.is_option(),
+                None => false,
+            },
+            _ => false,
+        };
+        let wraps = options.state.returns_option && !options.state.in_iterator_next && !optional;
+        let value = match &self.value {
+            ExprType::Constant(constant) if options.state.returns_string && constant.string_value().is_some() => {
+                constant.string_to_rust(&options).unwrap_or_default()
+            }
+            _ => self.to_rust(ctx, options, symbols)?,
+        };
+        Ok(if wraps { quote!(Some(#value)) } else { value })
+    }
+}
+
 impl Node for Expr {
     fn lineno(&self) -> Option<usize> {
         self.lineno
@@ -906,6 +1017,20 @@ mod tests {
             .clone()
             .to_rust(CodeGenContext::Module("test".to_string()), options, symbols)
             .unwrap();
-        assert_eq!(tokens.to_string(), "fn __module_init__ () { test () } fn main () { __module_init__ () ; }");
+        assert_eq!(tokens.to_string(), "fn __module_init__ () { test () ; } fn main () { __module_init__ () ; }");
+    }
+
+    #[test]
+    fn test_expression_has_position() {
+        let module = crate::parse("x = 1
+y = [f(x)
+     for x in range(3)]
+", "test.py").unwrap();
+        let crate::StatementType::Assign(assign) = &module.raw.body[1].statement else {
+            panic!("expected an assignment");
+        };
+        assert_eq!((assign.value.lineno(), assign.value.col_offset()), (Some(2), Some(4)));
+        assert_eq!(assign.value.end_lineno(), Some(3));
+        assert_eq!(ExprType::Unknown.lineno(), None);
     }
 }
@@ -4,8 +4,8 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Node, PythonOptions, SymbolTableScopes,
-    extract_list,
+    CodeGen, CodeGenContext, Error, ExprType, Node, PythonOptions, SourceLocation,
+    SymbolTableScopes, extract_list,
 };
 
 /// Joined string (f-string, e.g., f"Hello {name}")
@@ -120,21 +120,44 @@ impl CodeGen for JoinedStr {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        // Generate each part of the f-string
-        let part_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.values.into_iter()
-            .map(|val| val.to_rust(ctx.clone(), options.clone(), symbols.clone()))
-            .collect();
-        let part_tokens = part_tokens?;
-
-        // For now, generate a simple format! macro call
-        // This is a simplified translation - real f-strings are more complex
-        if part_tokens.is_empty() {
-            Ok(quote! { String::new() })
-        } else {
-            Ok(quote! {
-                format!("{}", #(#part_tokens)+*)
-            })
+        let (format_string, arguments) = self.format_arguments(ctx, options, symbols)?;
+        Ok(quote! {
+            format!(#format_string #(, #arguments)*)
+        })
+    }
+}
+
+impl JoinedStr {
+    /// The format string and arguments of the f-string, for `format!` or any of the other
+    /// formatting macros, like `write!`.
+    pub fn format_arguments(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<(String, Vec<TokenStream>), Box<dyn std::error::Error>> {
+        let mut format_string = String::new();
+        let mut arguments = Vec::new();
+
+        for value in self.values {
+            match value {
+                // Literal text; braces have to be escaped again for format!.
+                ExprType::Constant(c) => {
+                    let text = c.string_value().unwrap_or_else(|| c.to_string());
+                    format_string.push_str(&text.replace('{', "{{").replace('}', "}}"));
+                }
+                ExprType::FormattedValue(fv) => {
+                    format_string.push_str(&fv.placeholder()?);
+                    arguments.push((*fv.value).to_rust(ctx.clone(), options.clone(), symbols.clone())?);
+                }
+                other => {
+                    format_string.push_str("{}");
+                    arguments.push(other.to_rust(ctx.clone(), options.clone(), symbols.clone())?);
+                }
+            }
         }
+
+        Ok((format_string, arguments))
     }
 }
 
@@ -158,26 +181,232 @@ impl CodeGen for FormattedValue {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let value_tokens = (*self.value).to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-        
-        if let Some(format_spec) = self.format_spec {
-            let _spec_tokens = (*format_spec).to_rust(ctx, options, symbols)?;
-            // For now, generate a simple format with the format specifier
-            // TODO: Properly handle format specifications
-            Ok(quote! {
-                format!("{}", #value_tokens)
-            })
-        } else {
-            // Simple case - just format with default formatting
-            Ok(quote! {
-                format!("{}", #value_tokens)
-            })
+        let placeholder = self.placeholder()?;
+        let value_tokens = (*self.value).to_rust(ctx, options, symbols)?;
+
+        Ok(quote! {
+            format!(#placeholder, #value_tokens)
+        })
+    }
+}
+
+/// Python conversion flags, as stored in `FormattedValue::conversion`.
+const CONVERSION_STR: i32 = 's' as i32;
+const CONVERSION_REPR: i32 = 'r' as i32;
+const CONVERSION_ASCII: i32 = 'a' as i32;
+
+impl FormattedValue {
+    /// The Rust format! placeholder equivalent to this replacement field, e.g. `{:>8}`.
+    pub fn placeholder(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let debug = match self.conversion {
+            None | Some(CONVERSION_STR) => false,
+            Some(CONVERSION_REPR) | Some(CONVERSION_ASCII) => true,
+            Some(other) => return Err(self.unsupported(format!("f-string conversion {}", other))),
+        };
+
+        let spec = match &self.format_spec {
+            Some(spec) => translate_format_spec(&self.format_spec_text(spec)?)
+                .map_err(|message| self.unsupported(message))?,
+            None => String::new(),
+        };
+
+        if debug && !spec.is_empty() && spec.ends_with(|c: char| c.is_ascii_alphabetic()) {
+            return Err(self.unsupported("a typed format spec combined with !r".to_string()));
+        }
+
+        Ok(match (spec.is_empty(), debug) {
+            (true, false) => "{}".to_string(),
+            (_, true) => format!("{{:{}?}}", spec),
+            (false, false) => format!("{{:{}}}", spec),
+        })
+    }
+
+    /// The format spec is itself a JoinedStr; only constant specs can be translated.
+    fn format_spec_text(&self, spec: &ExprType) -> Result<String, Box<dyn std::error::Error>> {
+        let values = match spec {
+            ExprType::JoinedStr(js) => js.values.clone(),
+            other => vec![other.clone()],
+        };
+
+        let mut text = String::new();
+        for value in values {
+            match value {
+                ExprType::Constant(c) => {
+                    text.push_str(&c.string_value().unwrap_or_else(|| c.to_string()))
+                }
+                _ => {
+                    return Err(self.unsupported(
+                        "nested replacement fields in an f-string format spec".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(text)
+    }
+
+    fn unsupported(&self, feature: String) -> Box<dyn std::error::Error> {
+        Box::new(Error::unsupported_feature(
+            SourceLocation::with_span(
+                "<unknown>",
+                self.lineno,
+                self.col_offset,
+                self.end_lineno,
+                self.end_col_offset,
+            ),
+            feature,
+            "Simplify the format spec or format the value explicitly before interpolating it",
+        ))
+    }
+}
+
+/// Translates a Python format spec (`[[fill]align][sign][#][0][width][.precision][type]`)
+/// into the equivalent Rust format spec, or describes why there is none.
+pub fn translate_format_spec(spec: &str) -> std::result::Result<String, String> {
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+    let mut out = String::new();
+
+    // Fill and alignment
+    let is_align = |c: char| matches!(c, '<' | '>' | '^' | '=');
+    if chars.len() >= 2 && is_align(chars[1]) {
+        if chars[0] == '{' || chars[0] == '}' {
+            return Err(format!("format spec fill character '{}'", chars[0]));
         }
+        out.push(chars[0]);
+        out.push(chars[1]);
+        i = 2;
+    } else if !chars.is_empty() && is_align(chars[0]) {
+        out.push(chars[0]);
+        i = 1;
     }
+    if out.ends_with('=') {
+        return Err("format spec alignment '='".to_string());
+    }
+
+    // Sign
+    match chars.get(i) {
+        Some('+') => {
+            out.push('+');
+            i += 1;
+        }
+        Some('-') => i += 1,
+        Some(' ') => return Err("format spec sign ' '".to_string()),
+        _ => (),
+    }
+
+    if chars.get(i) == Some(&'z') {
+        return Err("format spec option 'z'".to_string());
+    }
+    if chars.get(i) == Some(&'#') {
+        out.push('#');
+        i += 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        out.push('0');
+        i += 1;
+    }
+
+    // Width
+    while let Some(c) = chars.get(i).filter(|c| c.is_ascii_digit()) {
+        out.push(*c);
+        i += 1;
+    }
+
+    if let Some(c @ (',' | '_')) = chars.get(i) {
+        return Err(format!("format spec grouping option '{}'", c));
+    }
+
+    // Precision
+    let mut has_precision = false;
+    if chars.get(i) == Some(&'.') {
+        out.push('.');
+        i += 1;
+        while let Some(c) = chars.get(i).filter(|c| c.is_ascii_digit()) {
+            out.push(*c);
+            i += 1;
+            has_precision = true;
+        }
+        if !has_precision {
+            return Err(format!("format spec '{}' has an empty precision", spec));
+        }
+    }
+
+    // Presentation type
+    match chars.get(i) {
+        None | Some('s') | Some('d') => (),
+        // Python defaults to six digits of precision for fixed point.
+        Some('f') | Some('F') => {
+            if !has_precision {
+                out.push_str(".6");
+            }
+        }
+        Some(c @ ('e' | 'E' | 'x' | 'X' | 'o' | 'b')) => out.push(*c),
+        Some(c) => return Err(format!("format spec presentation type '{}'", c)),
+    }
+    if i + 1 < chars.len() {
+        return Err(format!("format spec '{}'", spec));
+    }
+
+    Ok(out)
 }
 
 #[cfg(test)]
 mod tests {
-    // Tests would go here - currently commented out as they need full AST infrastructure
-    // create_parse_test!(test_simple_fstring, "f'Hello {name}'", "test.py");
-}
\ No newline at end of file
+    use super::*;
+    use crate::test_utils::try_expr_to_rust;
+
+    #[test]
+    fn test_simple_fstring() {
+        assert_eq!(
+            try_expr_to_rust("f'Hello {name}!'", PythonOptions::default()).unwrap(),
+            "format ! (\"Hello {}!\" , name)"
+        );
+    }
+
+    #[test]
+    fn test_fstring_conversions() {
+        assert_eq!(
+            try_expr_to_rust("f'{a!r} {b!s}'", PythonOptions::default()).unwrap(),
+            "format ! (\"{:?} {}\" , a , b)"
+        );
+    }
+
+    #[test]
+    fn test_fstring_format_specs() {
+        assert_eq!(
+            try_expr_to_rust("f'{x:.2f}|{n:>8}|{v:08.3f}|{h:#x}'", PythonOptions::default()).unwrap(),
+            "format ! (\"{:.2}|{:>8}|{:08.3}|{:#x}\" , x , n , v , h)"
+        );
+    }
+
+    #[test]
+    fn test_fstring_unsupported_format_spec() {
+        assert!(try_expr_to_rust("f'{n:,}'", PythonOptions::default()).is_err());
+        assert!(try_expr_to_rust("f'{n:{width}}'", PythonOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_fstring_brace_escapes() {
+        assert_eq!(
+            try_expr_to_rust("f'{{literal}} {x}'", PythonOptions::default()).unwrap(),
+            "format ! (\"{{literal}} {}\" , x)"
+        );
+    }
+
+    #[test]
+    fn test_nested_fstring() {
+        assert_eq!(
+            try_expr_to_rust("f\"outer {f'inner {a}'}\"", PythonOptions::default()).unwrap(),
+            "format ! (\"outer {}\" , format ! (\"inner {}\" , a))"
+        );
+    }
+
+    #[test]
+    fn test_translate_format_spec() {
+        assert_eq!(translate_format_spec("*^10").unwrap(), "*^10");
+        assert_eq!(translate_format_spec("+.3e").unwrap(), "+.3e");
+        assert_eq!(translate_format_spec("f").unwrap(), ".6");
+        assert!(translate_format_spec("=10").is_err());
+        assert!(translate_format_spec("%").is_err());
+    }
+}
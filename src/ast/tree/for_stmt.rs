@@ -4,7 +4,7 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
+    convert_ident, CodeGen, CodeGenContext, ExprType, IdentCase, PythonOptions, SymbolTableNode, SymbolTableScopes,
     Node, impl_node_with_positions, PyAttributeExtractor, extract_list
 };
 
@@ -66,23 +66,42 @@ impl CodeGen for For {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let target = self.target.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-        let iter = self.iter.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let target = self.target.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        // The body can change the loop variable.
+        let target = match &self.target {
+            ExprType::Name(name) if symbols.is_mutated(&name.id) => quote!(mut #target),
+            _ => target,
+        };
+        let iter = match &self.iter {
+            // Iterating over an Enum class goes through its members.
+            ExprType::Name(name) if matches!(symbols.lookup(&name.id),
+                Some(SymbolTableNode::ClassDef { class, .. }) if class.enum_kind(&symbols).is_some()) =>
+            {
+                let class = convert_ident(&name.id, IdentCase::Type, &options, &symbols);
+                quote!(#class::ALL.iter().copied())
+            }
+            iter => iter.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?,
+        };
         
+        // breaks in the body belong to this loop, not to one the loop is nested in.
+        let mut body_options = options.clone();
+        body_options.state.in_loop_with_else = !self.orelse.is_empty();
+        let body_ctx = ctx.enter_loop();
+        let label = body_ctx.loop_label_definition();
         let body_stmts: Result<Vec<_>, _> = self.body
             .into_iter()
-            .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
+            .map(|stmt| stmt.to_rust(body_ctx.clone(), body_options.clone(), symbols.clone()))
             .collect();
         let body_stmts = body_stmts?;
         
         if self.orelse.is_empty() {
             Ok(quote! {
-                for #target in #iter {
-                    #(#body_stmts)*
+                #label for #target in #iter {
+                    #(#body_stmts;)*
                 }
             })
         } else {
-            // Note: Rust doesn't have for-else, so we need to track completion
+            // Rust doesn't have for-else. The else clause runs unless a break set the flag.
             let else_stmts: Result<Vec<_>, _> = self.orelse
                 .into_iter()
                 .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
@@ -91,14 +110,12 @@ impl CodeGen for For {
             
             Ok(quote! {
                 {
-                    let mut completed = true;
-                    for #target in #iter {
-                        #(#body_stmts)*
-                        completed = false;
-                        break;
+                    let mut broke = false;
+                    #label for #target in #iter {
+                        #(#body_stmts;)*
                     }
-                    if completed {
-                        #(#else_stmts)*
+                    if !broke {
+                        #(#else_stmts;)*
                     }
                 }
             })
@@ -110,8 +127,72 @@ impl CodeGen for For {
 mod tests {
     use super::*;
     use crate::create_parse_test;
+    use crate::test_utils::statement_to_rust;
 
     create_parse_test!(test_simple_for, "for x in range(10):\n    print(x)", "for_test.py");
     create_parse_test!(test_for_else, "for x in range(10):\n    print(x)\nelse:\n    print('done')", "for_test.py");
     create_parse_test!(test_for_list, "for item in [1, 2, 3]:\n    print(item)", "for_test.py");
-}
\ No newline at end of file
+
+    #[test]
+    fn test_search_loop_runs_else_only_without_break() {
+        let code = statement_to_rust(
+            "for x in xs:\n    if pred(x):\n        break\nelse:\n    not_found()\n",
+            PythonOptions::default(),
+        );
+        assert_eq!(
+            code,
+            "{ let mut broke = false ; for x in xs { if pred (x) { broke = true ; break ; } ; } if ! broke { not_found () ; } }"
+        );
+    }
+
+    #[test]
+    fn test_break_of_nested_loop_leaves_flag_alone() {
+        let code = statement_to_rust(
+            "for x in xs:\n    for y in ys:\n        break\n    if x:\n        break\nelse:\n    pass\n",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("'py_loop_2 : for y in ys { break 'py_loop_2 ; }"), "{}", code);
+        assert!(code.contains("if x { broke = true ; break ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_loop_without_else_is_unchanged() {
+        let code = statement_to_rust("for x in xs:\n    if x:\n        break\n", PythonOptions::default());
+        assert_eq!(code, "for x in xs { if x { break ; } ; }");
+    }
+
+    #[test]
+    fn test_nested_loops_are_labeled() {
+        let code = statement_to_rust(
+            "for x in xs:\n    for y in ys:\n        if y:\n            break\n    if x:\n        continue\n    print(x)\n",
+            PythonOptions::default(),
+        );
+        assert_eq!(
+            code,
+            "for x in xs { 'py_loop_2 : for y in ys { if y { break 'py_loop_2 ; } ; } ; if x { continue ; } ; println ! (\"{}\" , x) ; }"
+        );
+        syn::parse_str::<syn::Block>(&format!("{{ {} }}", code)).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_range_loops_iterate_rust_ranges() {
+        assert_eq!(statement_to_rust("for i in range(10):\n    work(i)\n", PythonOptions::default()), "for i in 0 .. 10 { work (i) ; }");
+        assert_eq!(statement_to_rust("for i in range(a, b):\n    work(i)\n", PythonOptions::default()), "for i in a .. b { work (i) ; }");
+        assert_eq!(
+            statement_to_rust("for i in range(a, b, 3):\n    work(i)\n", PythonOptions::default()),
+            "for i in (a .. b) . step_by (3) { work (i) ; }"
+        );
+    }
+
+    #[test]
+    fn test_enumerate_and_zip_loops_unpack_tuples() {
+        assert_eq!(
+            statement_to_rust("for i, x in enumerate(items):\n    work(i, x)\n", PythonOptions::default()),
+            "for (i , x) in items . iter () . enumerate () { work (i , x) ; }"
+        );
+        assert_eq!(
+            statement_to_rust("for a, b in zip(xs, ys):\n    work(a, b)\n", PythonOptions::default()),
+            "for (a , b) in xs . iter () . zip (ys . iter ()) { work (a , b) ; }"
+        );
+    }
+}
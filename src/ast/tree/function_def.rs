@@ -1,13 +1,16 @@
+use std::collections::{HashMap, HashSet};
+
 use tracing::debug;
 use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, ToTokens};
 use serde::{Deserialize, Serialize};
 use crate::ast::tree::statement::PyStatementTrait;
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Object, ParameterList, PythonOptions, Statement,
-    StatementType, SymbolTableNode, SymbolTableScopes,
+    convert_ident, failed_to_rust, impl_node_with_positions, annotation_to_rust_type, is_str_annotation, is_typing_name, RustType, AssertMode, Node, UNKNOWN_FILE, CodeGen, CodeGenContext, ErrorStrategy, ExprType, IdentCase, Object, ParameterList, PythonOptions, Statement,
+    StatementType, SymbolTableNode, SymbolTableScopes, TypeVar, generic_parameters, type_variables, walk_call, Call,
+    Name, Visitor, CodeGenError, is_abc_name,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -16,6 +19,12 @@ pub struct FunctionDef {
     pub args: ParameterList,
     pub body: Vec<Statement>,
     pub decorator_list: Vec<ExprType>,
+    /// The return annotation, if there is one
+    pub returns: Option<Box<ExprType>>,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
 }
 
 impl<'a> FromPyObject<'a> for FunctionDef {
@@ -26,16 +35,26 @@ impl<'a> FromPyObject<'a> for FunctionDef {
         
         // Extract decorator_list as Vec<ExprType>
         let decorator_list: Vec<ExprType> = ob.getattr("decorator_list")?.extract().unwrap_or_default();
+
+        let returns = ob.getattr("returns")?;
+        let returns = if returns.is_none() { None } else { Some(Box::new(returns.extract()?)) };
         
         Ok(FunctionDef {
             name,
             args,
             body,
             decorator_list,
+            returns,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
         })
     }
 }
 
+impl_node_with_positions!(FunctionDef { lineno, col_offset, end_lineno, end_col_offset });
+
 impl PyStatementTrait for FunctionDef {
 }
 
@@ -50,6 +69,17 @@ impl CodeGen for FunctionDef {
             self.name.clone(),
             SymbolTableNode::FunctionDef(self.clone()),
         );
+        // The parameters and locals live in a child scope that is discarded again, so they
+        // don't leak into the enclosing scope.
+        let mut symbols = self.scope_symbols(symbols);
+        let scope = symbols.pop_scope();
+        // A nonlocal variable is declared to be assigned, which changes the enclosing
+        // function's variable.
+        for (name, node) in scope.iter().flat_map(|scope| scope.symbols.iter()) {
+            if matches!(node, SymbolTableNode::Nonlocal) {
+                symbols.mark_mutated(name);
+            }
+        }
         symbols
     }
 
@@ -60,17 +90,13 @@ impl CodeGen for FunctionDef {
         symbols: SymbolTableScopes,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
         let mut streams = TokenStream::new();
-        let fn_name = format_ident!("{}", self.name);
-
-        // The Python convention is that functions that begin with a single underscore,
-        // it's private. Otherwise, it's public. We formalize that by default.
-        let visibility = if self.name.starts_with("_") && !self.name.starts_with("__") {
-            quote!()  // private, no visibility modifier
-        } else if self.name.starts_with("__") && self.name.ends_with("__") {
-            quote!(pub(crate))  // dunder methods are crate-visible
-        } else {
-            quote!(pub)  // regular methods are public
-        };
+        let fn_name = convert_ident(&self.name, IdentCase::Value, &options, &symbols);
+
+        // A method takes the instance it's called on as its receiver. The items of a trait
+        // have no visibility.
+        let (arguments, has_receiver) = self.args.without_receiver();
+        let is_method = matches!(ctx, CodeGenContext::Class | CodeGenContext::Trait) && has_receiver;
+        let visibility = if matches!(ctx, CodeGenContext::Trait) { quote!() } else { options.visibility_for(&self.name).tokens() };
 
         let is_async = match ctx.clone() {
             CodeGenContext::Async(_) => {
@@ -78,22 +104,109 @@ impl CodeGen for FunctionDef {
             }
             _ => quote!(),
         };
+        let ctx = ctx.outside_loops();
 
-        let parameters = self
-            .args
-            .clone()
-            .to_rust(ctx.clone(), options.clone(), symbols.clone())
-            .expect(format!("parsing arguments {:?}", self.args).as_str());
-
-        for s in self.body.iter() {
-            streams.extend(
-                s.clone()
-                    .to_rust(ctx.clone(), options.clone(), symbols.clone())
-                    .expect(format!("parsing statement {:?}", s).as_str()),
-            );
-            streams.extend(quote!(;));
+        // Resolve names declared in the body, like global and nonlocal declarations,
+        // in a scope of their own.
+        let symbols = self.scope_symbols(symbols);
+        let closure = !is_method && symbols.is_nested_function() && self.captures(&symbols);
+        if closure && NameUses::of(&self.body).names.contains(&self.name) {
+            return Err(CodeGenError::unsupported(
+                format!("the nested function {}, which captures variables and calls itself", self.name),
+                Some(self.source_location(UNKNOWN_FILE)),
+            )
+            .into());
         }
 
+        let parameters = if is_method {
+            let parameters = arguments.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            let receiver = if options.state.mutating_methods.contains(&self.name) { quote!(&mut self) } else { quote!(&self) };
+            if parameters.is_empty() { receiver } else { quote!(#receiver, #parameters) }
+        } else {
+            self.args.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?
+        };
+        let generics = generic_parameters(&self.type_variables(&symbols, &options), ctx.clone(), options.clone(), symbols.clone())?;
+
+        // Under the Result error strategy, functions that raise return a Result with the
+        // module's error type instead of panicking.
+        let mut options = options;
+        let escapes = options.state.escaping_closures.contains(&self.name);
+        options.state.returns_result = self.returns_result(&symbols, &options);
+
+        // The functions nested in this one that it doesn't just call are closures that
+        // outlive it.
+        let uses = NameUses::of(&self.body);
+        options.state.escaping_closures = self
+            .body
+            .iter()
+            .filter_map(|s| match &s.statement {
+                StatementType::FunctionDef(nested) if uses.values.contains(&nested.name) => Some(nested.name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        // Generators collect what they yield into a Vec, and return it as an iterator once
+        // the body has run.
+        options.state.in_generator = self.is_generator();
+        options.state.returns_string = !options.state.in_generator && self.returns.as_ref().is_some_and(|returns| is_str_annotation(returns, &symbols));
+        options.state.yields_string = options.state.in_generator && self.item_annotation().is_some_and(|item| is_str_annotation(item, &symbols));
+        // Other functions return what their annotation says, if they have one.
+        let annotated = match &self.returns {
+            Some(returns) if !options.state.in_generator => {
+                Some(annotation_to_rust_type(returns, ctx.clone(), options.clone(), symbols.clone())?)
+            }
+            _ => None,
+        };
+        options.state.returns_option = annotated.as_ref().is_some_and(RustType::is_option);
+        let (value_type, value) = if options.state.in_generator {
+            let item_type = self.item_type(&ctx, &options, &symbols)?;
+            streams.extend(quote!(let mut __yielded: Vec<#item_type> = Vec::new();));
+            (quote!(impl Iterator<Item = #item_type>), quote!(__yielded.into_iter()))
+        } else if let Some(annotated) = &annotated {
+            (quote!(#annotated), quote!(()))
+        } else {
+            (quote!(()), quote!(()))
+        };
+
+        let return_type = if options.state.returns_result {
+            quote!(-> Result<#value_type, ModuleError>)
+        } else if options.state.in_generator || annotated.as_ref().is_some_and(|annotated| !matches!(annotated, RustType::Unit)) {
+            quote!(-> #value_type)
+        } else {
+            quote!()
+        };
+
+        let defaults = if is_method { &arguments } else { &self.args };
+        streams.extend(defaults.default_bindings(ctx.clone(), options.clone(), symbols.clone())?);
+        let (statements, tail) = self.body_to_rust(&ctx, &options, &symbols);
+        streams.extend(statements);
+        match tail {
+            Some(tail) if options.state.returns_result => streams.extend(quote!(Ok(#tail))),
+            Some(tail) => streams.extend(tail),
+            None if options.state.returns_result => streams.extend(quote!(Ok(#value))),
+            None if options.state.in_generator => streams.extend(value),
+            None => {}
+        }
+
+        // A function nested in another that uses the other's variables is a closure, since
+        // a Rust fn can't capture them.
+        if closure {
+            let parameters = self.args.closure_parameters(ctx.clone(), options.clone(), symbols.clone())?;
+            let uses = NameUses::of(&self.body);
+            let mutable = uses.names.iter().any(|name| symbols.is_captured(name) && symbols.is_mutated(name));
+            let mutable = if mutable { quote!(mut) } else { quote!() };
+            let capture = if escapes { quote!(move) } else { quote!() };
+            return Ok(quote!(let #mutable #fn_name = #capture |#parameters| #return_type { #streams };));
+        }
+
+        // A Protocol's method without a body is one that its trait requires, and so is an
+        // abstract method.
+        let body = if options.state.in_protocol && is_method && (self.is_stub() || self.is_abstract()) {
+            quote!(;)
+        } else {
+            quote!({ #streams })
+        };
+
         let function = if let Some(docstring) = self.get_docstring() {
             // Convert docstring to Rust doc comments
             let doc_lines: Vec<_> = docstring
@@ -102,7 +215,8 @@ impl CodeGen for FunctionDef {
                     if line.trim().is_empty() {
                         quote! { #[doc = ""] }
                     } else {
-                        let doc_line = format!("{}", line);
+                        // rustdoc's /// comments are doc attributes with a leading space.
+                        let doc_line = format!(" {}", line);
                         quote! { #[doc = #doc_line] }
                     }
                 })
@@ -110,15 +224,11 @@ impl CodeGen for FunctionDef {
             
             quote! {
                 #(#doc_lines)*
-                #visibility #is_async fn #fn_name(#parameters) {
-                    #streams
-                }
+                #visibility #is_async fn #fn_name #generics (#parameters) #return_type #body
             }
         } else {
             quote! {
-                #visibility #is_async fn #fn_name(#parameters) {
-                    #streams
-                }
+                #visibility #is_async fn #fn_name #generics (#parameters) #return_type #body
             }
         };
 
@@ -127,31 +237,580 @@ impl CodeGen for FunctionDef {
     }
 }
 
+/// The names a body refers to, including in the functions nested in it.
+#[derive(Default)]
+struct NameUses {
+    /// Every name the body refers to
+    names: HashSet<String>,
+    /// The names it uses other than by calling them
+    values: HashSet<String>,
+}
+
+impl NameUses {
+    fn of(body: &[Statement]) -> Self {
+        let mut uses = NameUses::default();
+        body.iter().for_each(|statement| uses.visit_statement(statement));
+        uses
+    }
+}
+
+impl Visitor for NameUses {
+    fn visit_call(&mut self, node: &Call) {
+        match node.func.as_ref() {
+            ExprType::Name(name) => {
+                self.names.insert(name.id.clone());
+                node.args.iter().for_each(|arg| self.visit_expr(arg));
+                node.keywords.iter().for_each(|keyword| self.visit_keyword(keyword));
+            }
+            _ => walk_call(node, self),
+        }
+    }
+
+    fn visit_name(&mut self, node: &Name) {
+        self.names.insert(node.id.clone());
+        self.values.insert(node.id.clone());
+    }
+}
+
+/// The assignments of a function's body that change a variable declared before them,
+/// rather than declaring a new one, like a total declared before a loop and added to in
+/// it. Python's variables belong to the function, but the Rust the body becomes has
+/// blocks, so a name first assigned in a block and used after it is declared before the
+/// block, and a name assigned a value of another type is declared again.
+#[derive(Default)]
+struct Reassignments {
+    /// The names declared in each block that's open, innermost last, with the kind of
+    /// value they were assigned, if it's known
+    blocks: Vec<HashMap<String, Option<ValueKind>>>,
+    /// The names declared ahead of a block that haven't been assigned on every path yet,
+    /// with the number of loops that were open where they were declared
+    unassigned: HashMap<String, usize>,
+    /// The number of loops that are open
+    loops: usize,
+    /// The positions of the assignments that change a variable
+    positions: Vec<(usize, usize)>,
+    /// The variables they change
+    changed: HashSet<String>,
+    /// The names declared ahead of the block statements at the positions, and whether
+    /// the statement assigns them on every path
+    hoisted: Vec<((usize, usize), String, bool)>,
+}
+
+/// The kinds of value a variable can be assigned that are told apart without inferring
+/// types, to notice a variable assigned a value of another type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ValueKind {
+    Bool,
+    Int,
+    Float,
+    Str,
+    List,
+    Dict,
+    Set,
+    Tuple,
+}
+
+impl ValueKind {
+    fn of(value: &ExprType) -> Option<Self> {
+        match value {
+            ExprType::Constant(c) if c.bool_value().is_some() => Some(ValueKind::Bool),
+            ExprType::Constant(c) if c.int_value().is_some() => Some(ValueKind::Int),
+            ExprType::Constant(c) if c.is_float() => Some(ValueKind::Float),
+            ExprType::Constant(c) if c.string_value().is_some() => Some(ValueKind::Str),
+            ExprType::JoinedStr(_) => Some(ValueKind::Str),
+            ExprType::List(_) | ExprType::ListComp(_) => Some(ValueKind::List),
+            ExprType::Dict(_) | ExprType::DictComp(_) => Some(ValueKind::Dict),
+            ExprType::Set(_) | ExprType::SetComp(_) => Some(ValueKind::Set),
+            ExprType::Tuple(_) => Some(ValueKind::Tuple),
+            _ => None,
+        }
+    }
+}
+
+/// The names a statement uses, read or assigned.
+#[derive(Default)]
+struct UsedNames(HashSet<String>);
+
+impl Visitor for UsedNames {
+    fn visit_name(&mut self, node: &Name) {
+        self.0.insert(node.id.clone());
+    }
+}
+
+impl Reassignments {
+    fn of(function: &FunctionDef) -> Self {
+        let mut reassignments = Reassignments::default();
+        let parameters = function.args.parameters().map(|parameter| parameter.arg.clone());
+        reassignments.block(&function.body, parameters);
+        reassignments
+    }
+
+    /// The names an assignment to `target` binds, if it only binds names.
+    fn names(target: &ExprType) -> Vec<String> {
+        match target {
+            ExprType::Name(name) => vec![name.id.clone()],
+            ExprType::Tuple(tuple) => {
+                let names: Vec<_> = tuple.elts.iter().flat_map(Self::names).collect();
+                if names.len() == tuple.elts.len() { names } else { Vec::new() }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The names assigned in the blocks nested in a statement.
+    fn assigned_in_blocks(statement: &Statement, names: &mut Vec<String>) {
+        for body in statement.statement.nested_bodies() {
+            for statement in body {
+                if let StatementType::Assign(assign) = &statement.statement {
+                    names.extend(assign.targets.iter().flat_map(Self::names));
+                }
+                Self::assigned_in_blocks(statement, names);
+            }
+        }
+    }
+
+    /// Whether running `body` assigns `name` on every path that carries on after it.
+    fn assigns(body: &[Statement], name: &str) -> bool {
+        body.iter().any(|statement| match &statement.statement {
+            StatementType::Assign(assign) => assign.targets.iter().any(|target| Self::names(target).iter().any(|n| n == name)),
+            StatementType::If(i) => Self::assigns(&i.body, name) && Self::assigns(&i.orelse, name),
+            StatementType::With(w) => Self::assigns(&w.body, name),
+            StatementType::AsyncWith(w) => Self::assigns(&w.body, name),
+            StatementType::Return(_) | StatementType::Raise(_) | StatementType::Break | StatementType::Continue => true,
+            _ => false,
+        })
+    }
+
+    fn declared(&self, name: &str) -> Option<Option<ValueKind>> {
+        self.blocks.iter().rev().find_map(|block| block.get(name).copied())
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.declared(name).is_some()
+    }
+
+    /// Visit a block, which starts out declaring `declared`, like a loop its target.
+    fn block(&mut self, body: &[Statement], declared: impl IntoIterator<Item = String>) {
+        self.blocks.push(declared.into_iter().map(|name| (name, None)).collect());
+        for (index, statement) in body.iter().enumerate() {
+            self.hoist(statement, &body[index + 1..]);
+            self.visit_statement(statement);
+        }
+        self.blocks.pop();
+    }
+
+    /// Visit blocks only one of which runs, like the branches of an if, after which the
+    /// names declared ahead of them are assigned if every branch assigns them.
+    fn alternatives<'a>(&mut self, bodies: impl IntoIterator<Item = (&'a [Statement], Option<String>)>) {
+        let before = self.unassigned.clone();
+        let mut after = HashMap::new();
+        for (body, declared) in bodies {
+            self.unassigned = before.clone();
+            self.block(body, declared);
+            after.extend(std::mem::take(&mut self.unassigned));
+        }
+        self.unassigned = after;
+    }
+
+    /// Visit the body of a loop, which can run more than once.
+    fn looped(&mut self, body: &[Statement], declared: Vec<String>) {
+        self.loops += 1;
+        self.block(body, declared);
+        self.loops -= 1;
+    }
+
+    /// Declare the names `statement`'s blocks assign that aren't declared yet but are used
+    /// after it, in `rest`, ahead of the statement.
+    fn hoist(&mut self, statement: &Statement, rest: &[Statement]) {
+        let Some(position) = statement.lineno.zip(statement.col_offset) else {
+            return;
+        };
+        let mut assigned = Vec::new();
+        Self::assigned_in_blocks(statement, &mut assigned);
+        if assigned.is_empty() {
+            return;
+        }
+        let mut used = UsedNames::default();
+        rest.iter().for_each(|statement| used.visit_statement(statement));
+        // The clauses of a try statement run in closures of their own, so a name one of
+        // them assigns and another uses is declared before the statement as well.
+        if let StatementType::Try(_) = &statement.statement {
+            for clause in statement.statement.nested_bodies() {
+                let mut assigned_in_clause = Vec::new();
+                let mut used_in_clause = UsedNames::default();
+                for statement in clause {
+                    if let StatementType::Assign(assign) = &statement.statement {
+                        assigned_in_clause.extend(assign.targets.iter().flat_map(Self::names));
+                    }
+                    Self::assigned_in_blocks(statement, &mut assigned_in_clause);
+                    used_in_clause.visit_statement(statement);
+                }
+                used.0.extend(used_in_clause.0.into_iter().filter(|name| !assigned_in_clause.contains(name)));
+            }
+        }
+        for name in assigned {
+            if self.is_declared(&name) || !used.0.contains(&name) {
+                continue;
+            }
+            let definite = Self::assigns(std::slice::from_ref(statement), &name);
+            // A name that might not be assigned starts out with a default value, which
+            // the block changes.
+            if !definite {
+                self.changed.insert(name.clone());
+            }
+            self.hoisted.push((position, name.clone(), definite));
+            self.unassigned.insert(name.clone(), self.loops);
+            self.declare(vec![name], None);
+        }
+    }
+
+    fn declare(&mut self, names: Vec<String>, kind: Option<ValueKind>) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.extend(names.into_iter().map(|name| (name, kind)));
+        }
+    }
+
+    /// Record an assignment to names that are all declared. The first assignment to a
+    /// name declared ahead of a block, outside of a loop, only initializes it.
+    fn reassign(&mut self, names: &[String], position: (usize, usize)) {
+        self.positions.push(position);
+        for name in names {
+            match self.unassigned.remove(name) {
+                Some(loops) if loops == self.loops => {}
+                _ => {
+                    self.changed.insert(name.clone());
+                }
+            }
+        }
+    }
+}
+
+impl Visitor for Reassignments {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match &stmt.statement {
+            StatementType::Assign(assign) => {
+                let names: Vec<_> = assign.targets.iter().map(Self::names).collect();
+                let kind = ValueKind::of(&assign.value);
+                match (names.as_slice(), assign.lineno.zip(assign.col_offset)) {
+                    // A value of another kind is a new variable, which shadows the one
+                    // declared before.
+                    ([names], _) if names.len() == 1 && kind.is_some() && matches!(self.declared(&names[0]), Some(Some(declared)) if Some(declared) != kind) => {
+                        self.declare(names.clone(), kind);
+                    }
+                    ([names], Some(position)) if !names.is_empty() && names.iter().all(|name| self.is_declared(name)) => {
+                        let names = names.clone();
+                        self.reassign(&names, position);
+                    }
+                    ([names], _) if names.len() == 1 => self.declare(names.clone(), kind),
+                    _ => names.into_iter().for_each(|names| self.declare(names, None)),
+                }
+            }
+            StatementType::AnnAssign(assign) => self.declare(Self::names(&assign.target), None),
+            StatementType::For(f) => {
+                self.looped(&f.body, Self::names(&f.target));
+                self.block(&f.orelse, []);
+            }
+            StatementType::AsyncFor(f) => {
+                self.looped(&f.body, Self::names(&f.target));
+                self.block(&f.orelse, []);
+            }
+            StatementType::If(i) => self.alternatives([(i.body.as_slice(), None), (i.orelse.as_slice(), None)]),
+            StatementType::While(w) => {
+                self.looped(&w.body, Vec::new());
+                self.block(&w.orelse, []);
+            }
+            StatementType::Try(t) => {
+                self.block(&t.body, []);
+                for handler in &t.handlers {
+                    self.block(&handler.body, handler.name.clone());
+                }
+                self.block(&t.orelse, []);
+                self.block(&t.finalbody, []);
+            }
+            StatementType::With(w) => {
+                let declared = w.items.iter().flat_map(|item| item.optional_vars.iter().flat_map(Self::names));
+                self.block(&w.body, declared.collect::<Vec<_>>());
+            }
+            StatementType::AsyncWith(w) => {
+                let declared = w.items.iter().flat_map(|item| item.optional_vars.iter().flat_map(Self::names));
+                self.block(&w.body, declared.collect::<Vec<_>>());
+            }
+            StatementType::Match(m) => {
+                self.alternatives(m.cases.iter().map(|case| (case.body.as_slice(), None)));
+            }
+            // Nested functions and classes have variables of their own.
+            _ => {}
+        }
+    }
+}
+
 impl FunctionDef {
-    fn get_docstring(&self) -> Option<String> {
-        if self.body.is_empty() {
+    /// Whether the function uses variables of the functions it's nested in, given the
+    /// symbols of its own scope.
+    fn captures(&self, symbols: &SymbolTableScopes) -> bool {
+        NameUses::of(&self.body).names.iter().any(|name| symbols.is_captured(name))
+    }
+
+    /// The statements of the body, in the scope of the function, and the value of a return
+    /// at the end of it, which becomes the function's tail expression.
+    pub fn body_to_rust(
+        &self,
+        ctx: &CodeGenContext,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> (TokenStream, Option<TokenStream>) {
+        // A statement that fails becomes a todo!() and is reported in the diagnostics, so
+        // the rest of the function is still generated.
+        let statement_to_rust = |s: &Statement, tokens: Result<TokenStream, Box<dyn std::error::Error>>| {
+            tokens.unwrap_or_else(|e| failed_to_rust(&*e, s.source_location(UNKNOWN_FILE), options))
+        };
+
+        let (body, tail) = match self.body.split_last() {
+            Some((last, rest)) if !options.state.in_generator => match &last.statement {
+                StatementType::Return(Some(e)) => {
+                    let e = e.clone().returned_to_rust(ctx.clone(), options.clone(), symbols.clone());
+                    (rest, Some(statement_to_rust(last, e)))
+                }
+                _ => (self.body.as_slice(), None),
+            },
+            _ => (self.body.as_slice(), None),
+        };
+
+        let mut statements = TokenStream::new();
+        for s in body.iter() {
+            let tokens = s.clone().to_rust(ctx.clone(), options.clone(), symbols.clone());
+            statements.extend(statement_to_rust(s, tokens));
+            statements.extend(quote!(;));
+        }
+        (statements, tail)
+    }
+
+    /// Returns true if the body is only a docstring, `...` or `pass`, like the methods of a
+    /// Protocol that only declare a signature.
+    pub fn is_stub(&self) -> bool {
+        self.body.iter().all(|s| match &s.statement {
+            StatementType::Pass => true,
+            StatementType::Expr(e) => matches!(&e.value, ExprType::Ellipsis)
+                || matches!(&e.value, ExprType::Constant(c) if c.string_value().is_some()),
+            _ => false,
+        })
+    }
+
+    /// Returns true if the function is decorated with `abc.abstractmethod`.
+    pub fn is_abstract(&self) -> bool {
+        self.decorator_list.iter().any(|decorator| is_abc_name(decorator, "abstractmethod"))
+    }
+
+    /// The type variables the function's annotations refer to, which are its generic
+    /// parameters, except for those of the class it's a method of.
+    pub fn type_variables(&self, symbols: &SymbolTableScopes, options: &PythonOptions) -> Vec<TypeVar> {
+        let mut found = Vec::new();
+        let annotations = self.args.parameters().filter_map(|parameter| parameter.annotation.as_deref());
+        for annotation in annotations.chain(self.returns.as_deref()) {
+            type_variables(annotation, symbols, &mut found);
+        }
+        found.retain(|type_var| !options.state.type_parameters.contains(&type_var.name));
+        found
+    }
+
+    /// Returns true if the function's own body contains a raise statement, or an assert
+    /// statement when failed assertions return errors.
+    pub fn raises(&self, options: &PythonOptions) -> bool {
+        fn block_raises(block: &[Statement], asserts_raise: bool) -> bool {
+            block.iter().any(|s| {
+                matches!(s.statement, StatementType::Raise(_))
+                    || (asserts_raise && matches!(s.statement, StatementType::Assert(_)))
+                    || s.statement.nested_bodies().into_iter().any(|body| block_raises(body, asserts_raise))
+            })
+        }
+        block_raises(&self.body, options.assert_mode == AssertMode::Result)
+    }
+
+    /// Returns true if calling the function can raise, because it raises itself or calls a
+    /// function of the module that can. Only calls that are a statement's whole value count.
+    pub fn can_raise(&self, symbols: &SymbolTableScopes, options: &PythonOptions) -> bool {
+        fn check(
+            function: &FunctionDef,
+            symbols: &SymbolTableScopes,
+            options: &PythonOptions,
+            visited: &mut Vec<String>,
+        ) -> bool {
+            if function.raises(options) {
+                return true;
+            }
+            visited.push(function.name.clone());
+            function.called_names().into_iter().any(|name| {
+                !visited.iter().any(|v| v == name)
+                    && matches!(symbols.lookup(name), Some(SymbolTableNode::FunctionDef(f)) if check(f, symbols, options, visited))
+            })
+        }
+        check(self, symbols, options, &mut Vec::new())
+    }
+
+    /// Returns true if the function returns a Result, which it does if it can raise and
+    /// raising returns an Err: under the Result error strategy, or when a try statement
+    /// calls it, so that the try can catch what it raises.
+    pub fn returns_result(&self, symbols: &SymbolTableScopes, options: &PythonOptions) -> bool {
+        (options.error_strategy == ErrorStrategy::Result || symbols.is_called_in_try(&self.name)) && self.can_raise(symbols, options)
+    }
+
+    /// The names of the functions called as the value of a statement in the body, like
+    /// `f(x)` or `y = f(x)`.
+    fn called_names(&self) -> Vec<&str> {
+        Self::calls_in(&self.body)
+    }
+
+    /// The names of the functions called as the value of a statement in a block, like
+    /// `f(x)` or `y = f(x)`, including in the blocks nested in it.
+    pub fn calls_in(block: &[Statement]) -> Vec<&str> {
+        fn block_calls<'a>(block: &'a [Statement], found: &mut Vec<&'a str>) {
+            for s in block {
+                let value = match &s.statement {
+                    StatementType::Expr(e) => Some(&e.value),
+                    StatementType::Assign(a) => Some(&a.value),
+                    StatementType::AugAssign(a) => Some(&a.value),
+                    StatementType::AnnAssign(a) => a.value.as_ref(),
+                    StatementType::Return(Some(e)) => Some(&e.value),
+                    _ => None,
+                };
+                let call = match (&s.statement, value) {
+                    (StatementType::Call(call), _) | (_, Some(ExprType::Call(call))) => Some(call),
+                    _ => None,
+                };
+                if let Some(call) = call
+                    && let ExprType::Name(name) = call.func.as_ref()
+                {
+                    found.push(&name.id);
+                }
+                for body in s.statement.nested_bodies() {
+                    block_calls(body, found);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        block_calls(block, &mut found);
+        found
+    }
+
+    /// The yield and yield from expressions in the function's own body.
+    pub fn yields(&self) -> Vec<&ExprType> {
+        fn block_yields<'a>(block: &'a [Statement], found: &mut Vec<&'a ExprType>) {
+            for s in block {
+                let value = match &s.statement {
+                    StatementType::Expr(e) => Some(&e.value),
+                    StatementType::Assign(a) => Some(&a.value),
+                    _ => None,
+                };
+                if let Some(value @ (ExprType::Yield(_) | ExprType::YieldFrom(_))) = value {
+                    found.push(value);
+                }
+                for body in s.statement.nested_bodies() {
+                    block_yields(body, found);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        block_yields(&self.body, &mut found);
+        found
+    }
+
+    /// Returns true if the function is a generator, because its body yields.
+    pub fn is_generator(&self) -> bool {
+        !self.yields().is_empty()
+    }
+
+    /// The annotation of the values a generator yields, if it's annotated to return an
+    /// `Iterator[T]`, `Iterable[T]` or `Generator[T, ...]`.
+    fn item_annotation(&self) -> Option<&ExprType> {
+        let Some(ExprType::Subscript(subscript)) = self.returns.as_deref() else {
+            return None;
+        };
+        if !["Iterator", "Iterable", "Generator"].iter().any(|name| is_typing_name(&subscript.value, name)) {
             return None;
         }
-        
-        let expr = self.body[0].clone();
-        match expr.statement {
-            StatementType::Expr(e) => match e.value {
-                ExprType::Constant(c) => {
-                    let raw_string = c.to_string();
-                    // Clean up the docstring for Rust documentation
-                    Some(self.format_docstring(&raw_string))
+        match subscript.slice.as_ref() {
+            ExprType::Tuple(tuple) => tuple.elts.first(),
+            item => Some(item),
+        }
+    }
+
+    /// The type of the values a generator yields, which an `Iterator[T]`, `Iterable[T]` or
+    /// `Generator[T, ...]` return annotation gives. Without one, it's only known when every
+    /// yield is of a constant, or of a variable with an annotation, of the same type.
+    fn item_type(
+        &self,
+        ctx: &CodeGenContext,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let resolve = |annotation: &ExprType| annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone());
+        if let Some(item) = self.item_annotation() {
+            let item = resolve(item)?;
+            return Ok(quote!(#item));
+        }
+
+        let mut types = Vec::new();
+        for value in self.yields() {
+            let item_type = match value {
+                ExprType::Yield(y) => match y.value.as_deref() {
+                    Some(ExprType::Constant(c)) => c.rust_type(options).map(|t| t.to_string()),
+                    Some(ExprType::Name(name)) => match symbols.type_annotation(&name.id) {
+                        Some(annotation) => Some(resolve(annotation)?.to_token_stream().to_string()),
+                        None => None,
+                    },
+                    None => Some("()".to_string()),
+                    _ => None,
                 },
                 _ => None,
-            },
-            _ => None,
+            };
+            types.push(item_type);
         }
+        Ok(match types.first() {
+            Some(Some(first)) if types.iter().all(|t| t.as_ref() == Some(first)) => {
+                first.parse().unwrap_or_else(|_| quote!(PyObject))
+            }
+            _ => quote!(PyObject),
+        })
+    }
+
+    /// Pushes a new scope holding the parameters and the names bound in the body.
+    pub fn scope_symbols(&self, symbols: SymbolTableScopes) -> SymbolTableScopes {
+        let mut symbols = symbols;
+        symbols.push_function_scope(&self.name);
+        for parameter in self.args.parameters() {
+            symbols.insert(
+                parameter.arg.clone(),
+                SymbolTableNode::Parameter(parameter.clone()),
+            );
+        }
+        // The receiver of a method stays `self`.
+        if self.args.without_receiver().1 {
+            symbols.rename("self", format_ident!("self"));
+        }
+        let mut symbols = self.body.iter().fold(symbols, |acc, s| s.clone().find_symbols(acc));
+        // A variable that's assigned again is changed, rather than shadowed.
+        let reassignments = Reassignments::of(self);
+        for name in &reassignments.changed {
+            symbols.mark_mutated(name);
+        }
+        for position in reassignments.positions {
+            symbols.mark_reassignment(position);
+        }
+        for (position, name, definite) in reassignments.hoisted {
+            symbols.mark_hoisted(position, name, definite);
+        }
+        symbols
+    }
+
+    fn get_docstring(&self) -> Option<String> {
+        // Clean up the docstring for Rust documentation
+        Self::get_docstring_from_body(self.body()).map(|text| self.format_docstring(&text))
     }
     
-    fn format_docstring(&self, raw: &str) -> String {
-        // Remove surrounding quotes
-        let content = raw.trim_matches('"');
-        
-        // Split into lines and clean up Python-style indentation
+    fn format_docstring(&self, content: &str) -> String {
+        // The summary can start on the line after the opening quotes.
+        let content = content.trim_start_matches(|c: char| c != '\n' && c.is_whitespace());
+        let content = content.strip_prefix('\n').unwrap_or(content);
         let lines: Vec<&str> = content.lines().collect();
         if lines.is_empty() {
             return String::new();
@@ -165,10 +824,21 @@ impl FunctionDef {
             if !lines[0].trim().is_empty() && !lines[1].trim().is_empty() {
                 formatted.push(String::new());
             }
+
+            // The other lines are indented like the function's body, which isn't part of
+            // the text, but indentation relative to it is.
+            let indent = lines
+                .iter()
+                .skip(1)
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.len() - line.trim_start().len())
+                .min()
+                .unwrap_or(0);
             
             // Process remaining lines, cleaning up indentation
             for line in lines.iter().skip(1) {
                 let cleaned = line.trim();
+                let dedented = line.get(indent..).unwrap_or(cleaned).trim_end();
                 if cleaned.starts_with("Args:") {
                     formatted.push(String::new());
                     formatted.push("# Arguments".to_string());
@@ -183,10 +853,17 @@ impl FunctionDef {
                     formatted.push(format!("```rust"));
                     formatted.push(format!("// {}", cleaned));
                 } else if !cleaned.is_empty() {
-                    formatted.push(cleaned.to_string());
+                    formatted.push(dedented.to_string());
+                } else if formatted.last().is_some_and(|last| !last.is_empty()) {
+                    // Keep paragraph breaks, but not runs of blank lines.
+                    formatted.push(String::new());
                 }
             }
             
+            while formatted.last().is_some_and(|last| last.is_empty()) {
+                formatted.pop();
+            }
+
             // Close any open code blocks
             if content.contains(">>>") {
                 formatted.push("```".to_string());
@@ -197,4 +874,630 @@ impl FunctionDef {
     }
 }
 
-impl Object for FunctionDef {}
+impl Object for FunctionDef {
+    fn body(&self) -> &[Statement] {
+        &self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StringType, Visibility, VisibilityPolicy};
+    use crate::test_utils::{assert_compiles, module_to_rust, statement_to_rust, test_options, try_module_to_rust};
+
+    const NESTED: &str = "def outer(a):
+    x = 1
+    def inner(b):
+        y = 2
+";
+
+    #[test]
+    fn test_nested_function_locals_stay_out_of_module_scope() {
+        let module = crate::parse(NESTED, "test.py").unwrap();
+        let symbols = module.find_symbols(SymbolTableScopes::new());
+
+        assert!(symbols.lookup("outer").is_some());
+        for name in ["a", "x", "inner", "b", "y"] {
+            assert!(symbols.lookup(name).is_none(), "{} leaked into module scope", name);
+        }
+    }
+
+    #[test]
+    fn test_function_scope_holds_parameters_and_locals() {
+        let module = crate::parse(NESTED, "test.py").unwrap();
+        let outer = match &module.raw.body[0].statement {
+            StatementType::FunctionDef(f) => f.clone(),
+            s => panic!("expected a function, got {:?}", s),
+        };
+        let symbols = outer.scope_symbols(SymbolTableScopes::new());
+
+        assert!(matches!(symbols.lookup("a"), Some(SymbolTableNode::Parameter(_))));
+        assert!(matches!(symbols.lookup("x"), Some(SymbolTableNode::Assign { .. })));
+        assert!(matches!(symbols.lookup("inner"), Some(SymbolTableNode::FunctionDef(_))));
+        assert!(symbols.lookup("b").is_none());
+        assert!(symbols.lookup("y").is_none());
+    }
+
+    #[test]
+    fn test_default_visibility_follows_underscore_convention() {
+        let options = PythonOptions::default();
+        assert!(statement_to_rust("def area():\n    pass", options.clone()).starts_with("pub fn area"));
+        assert!(statement_to_rust("def _helper():\n    pass", options.clone()).starts_with("fn _helper"));
+        assert!(statement_to_rust("def __init__():\n    pass", options).starts_with("pub (crate) fn __init__"));
+    }
+
+    #[test]
+    fn test_default_visibility_option() {
+        let mut options = PythonOptions::default();
+        options.default_visibility = Visibility::Crate;
+        assert!(statement_to_rust("def area():\n    pass", options.clone()).starts_with("pub (crate) fn area"));
+        assert!(statement_to_rust("def _helper():\n    pass", options).starts_with("fn _helper"));
+    }
+
+    #[test]
+    fn test_force_pub() {
+        let options = PythonOptions::with_force_pub();
+        assert_eq!(options.visibility_policy, VisibilityPolicy::Public);
+        assert!(statement_to_rust("def _helper():\n    pass", options.clone()).starts_with("pub fn _helper"));
+        assert!(statement_to_rust("def __init__():\n    pass", options).starts_with("pub fn __init__"));
+    }
+
+    #[test]
+    fn test_visibility_policy() {
+        let code = statement_to_rust("def _helper():\n    pass", PythonOptions::default());
+        assert!(code.starts_with("fn _helper () {"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+
+        let mut options = PythonOptions::default();
+        options.visibility_policy = VisibilityPolicy::Public;
+        assert!(statement_to_rust("def _helper():\n    pass", options.clone()).starts_with("pub fn _helper"));
+        options.visibility_policy = VisibilityPolicy::Private;
+        assert!(statement_to_rust("def area():\n    pass", options.clone()).starts_with("fn area"));
+        assert!(statement_to_rust("def __init__():\n    pass", options).starts_with("fn __init__"));
+    }
+
+    #[test]
+    fn test_rust_case_identifiers() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.rust_case_identifiers = true;
+        let module = crate::parse(
+            "def computeArea(shapeWidth, height):
+    return shapeWidth * height
+
+computeArea(2, 3)
+",
+            "test.py",
+        )
+        .unwrap();
+        let code = module
+            .to_rust(
+                CodeGenContext::Module("test".to_string()),
+                options,
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+
+        assert!(code.contains("pub fn compute_area (shape_width"), "{}", code);
+        assert!(code.contains("(shape_width) * (height)"), "{}", code);
+        assert!(code.contains("compute_area (2 , 3)"), "{}", code);
+        assert!(!code.contains("computeArea"), "{}", code);
+    }
+
+    #[test]
+    fn test_rust_case_identifiers_avoid_collisions() {
+        let mut options = PythonOptions::default();
+        options.rust_case_identifiers = true;
+        let module = crate::parse(
+            "def getValue():
+    pass
+
+def get_value():
+    pass
+",
+            "test.py",
+        )
+        .unwrap();
+        let code = module
+            .to_rust(
+                CodeGenContext::Module("test".to_string()),
+                options,
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+
+        assert!(code.contains("fn getValue"), "{}", code);
+        assert!(code.contains("fn get_value"), "{}", code);
+    }
+
+    #[test]
+    fn test_function_def_has_position() {
+        let module = crate::parse("x = 1\n\ndef f(a):\n    return a\n", "test.py").unwrap();
+        match &module.raw.body[1].statement {
+            StatementType::FunctionDef(function) => {
+                assert_eq!(function.lineno(), Some(3));
+                assert_eq!(function.col_offset(), Some(0));
+                assert_eq!(function.end_lineno(), Some(4));
+            }
+            other => panic!("Expected a function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_failing_statement_becomes_todo() {
+        let mut module = crate::parse("def f(x):\n    x += 1\n    print(x)\n", "test.py").unwrap();
+        let StatementType::FunctionDef(function) = &mut module.raw.body[0].statement else {
+            panic!("Expected a function");
+        };
+        let StatementType::AugAssign(aug_assign) = &mut function.body[0].statement else {
+            panic!("Expected an augmented assignment");
+        };
+        aug_assign.op = crate::BinOps::Unknown;
+
+        let options = PythonOptions::default();
+        let code = module.raw.body[0]
+            .clone()
+            .to_rust(CodeGenContext::Module("test".to_string()), options.clone(), SymbolTableScopes::new())
+            .unwrap()
+            .to_string();
+        // The statements after the one that failed are still generated.
+        assert!(code.contains("todo ! (\"FAILED: Unknown augmented assignment operator\") ; println !"), "{}", code);
+
+        let diagnostics = options.diagnostics.to_vec();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::Severity::Error);
+        assert_eq!(diagnostics[0].location.as_ref().and_then(|l| l.line), Some(2));
+    }
+
+    #[test]
+    fn test_return_statements() {
+        let code = statement_to_rust(
+            "def clamp(x):
+    if x < 0:
+        return 0
+    if x > 9:
+        return
+    print(x)
+",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("{ return 0 ; }"), "{}", code);
+        assert!(code.contains("{ return ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_tail_return_is_an_expression() {
+        let code = statement_to_rust("def double(x):\n    y = x * 2\n    return y\n", PythonOptions::default());
+        assert!(code.ends_with("let y = (x) * (2) ; ; y }"), "{}", code);
+        assert!(!code.contains("return"), "{}", code);
+    }
+
+    #[test]
+    fn test_nested_function_sees_enclosing_scopes() {
+        let module = crate::parse(NESTED, "test.py").unwrap();
+        let outer = match &module.raw.body[0].statement {
+            StatementType::FunctionDef(f) => f.clone(),
+            s => panic!("expected a function, got {:?}", s),
+        };
+        let inner = match &outer.body[1].statement {
+            StatementType::FunctionDef(f) => f.clone(),
+            s => panic!("expected a function, got {:?}", s),
+        };
+        let symbols = module.clone().find_symbols(SymbolTableScopes::new());
+        let symbols = outer.scope_symbols(symbols);
+        let mut symbols = inner.scope_symbols(symbols);
+
+        assert!(matches!(symbols.lookup("b"), Some(SymbolTableNode::Parameter(_))));
+        assert!(matches!(symbols.lookup("x"), Some(SymbolTableNode::Assign { .. })));
+        assert!(matches!(symbols.lookup("outer"), Some(SymbolTableNode::FunctionDef(_))));
+
+        symbols.pop_scope();
+        assert!(symbols.lookup("b").is_none());
+        assert!(symbols.lookup("a").is_some());
+    }
+
+    #[test]
+    fn test_nested_function_capturing_becomes_closure() {
+        let code = statement_to_rust(
+            "def outer(n: int) -> int:
+    total = 0
+    def add(x: int) -> int:
+        return x + n
+    def bump():
+        nonlocal total
+        total += 1
+    def square(x: int) -> int:
+        return x * x
+    bump()
+    return add(square(total))
+",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("let mut total = 0 ;"), "{}", code);
+        assert!(code.contains("let add = | x : i64 | -> i64 { (x) + (n) } ;"), "{}", code);
+        assert!(code.contains("let mut bump = | | { ; total += 1 ; } ;"), "{}", code);
+        // A function that captures nothing can stay a function.
+        assert!(code.contains("fn square (x : i64) -> i64"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+
+        let code = statement_to_rust(
+            "def make_adder(n: int):
+    def add(x: int) -> int:
+        return x + n
+    return add
+",
+            PythonOptions::default(),
+        );
+        // A closure that's returned takes what it captures along.
+        assert!(code.contains("let add = move | x : i64 | -> i64 { (x) + (n) } ;"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_docstring_is_only_the_text() {
+        let module = crate::parse("def f():\n  \"hello\"", "test.py").unwrap();
+        let function = match &module.raw.body[0].statement {
+            StatementType::FunctionDef(f) => f.clone(),
+            s => panic!("expected a function, got {:?}", s),
+        };
+        assert_eq!(function.get_docstring().as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn test_docstring_is_dedented() {
+        let module = crate::parse(
+            "def f():\n    \"\"\"\n    Summary.\n\n    Details:\n        indented\n    \"\"\"\n    pass\n",
+            "test.py",
+        )
+        .unwrap();
+        let mut function = match &module.raw.body[0].statement {
+            StatementType::FunctionDef(f) => f.clone(),
+            s => panic!("expected a function, got {:?}", s),
+        };
+        assert_eq!(function.get_docstring().as_deref(), Some("Summary.\n\nDetails:\n    indented"));
+
+        function.body.clear();
+        assert_eq!(function.get_docstring(), None);
+    }
+
+    #[test]
+    fn test_docstring_lines_become_doc_comments() {
+        let code = statement_to_rust(
+            "def total(items):
+    \"\"\"Add up the \"items\".
+
+    Empty lists add up to zero.
+    \"\"\"
+    return sum(items)
+",
+            PythonOptions::default(),
+        );
+        assert!(
+            code.starts_with(
+                "# [doc = \" Add up the \\\"items\\\".\"] # [doc = \"\"] # [doc = \" Empty lists add up to zero.\"] pub fn total"
+            ),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_optional_returns_are_some() {
+        let code = statement_to_rust(
+            "def find(xs: List[int], x: int) -> Optional[int]:
+    for i in xs:
+        if i == x:
+            return i
+    return None
+",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("-> Option < i64 >"), "{}", code);
+        assert!(code.contains("return Some (i) ;"), "{}", code);
+        assert!(code.ends_with("; None }"), "{}", code);
+
+        // A value that's optional already is returned as it is.
+        let code = statement_to_rust(
+            "def pick(a: int, b: Optional[int]) -> int | None:\n    if a > 0:\n        return a\n    return b\n",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("return Some (a) ;"), "{}", code);
+        assert!(code.ends_with("; b }"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_generator_returns_an_iterator() {
+        let code = statement_to_rust(
+            "def pair():
+    yield 1
+    yield 2
+",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("fn pair () -> impl Iterator < Item = i64 >"), "{}", code);
+        assert!(code.contains("let mut __yielded : Vec < i64 > = Vec :: new () ;"), "{}", code);
+        assert!(code.contains("__yielded . push (1) ;"), "{}", code);
+        assert!(code.contains("__yielded . push (2) ;"), "{}", code);
+        assert!(code.ends_with("__yielded . into_iter () }"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_generator_item_type_from_annotations() {
+        let code = statement_to_rust(
+            "def count(n: int) -> Iterator[int]:
+    for i in range(n):
+        yield i
+",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("fn count (n : i64) -> impl Iterator < Item = i64 >"), "{}", code);
+        assert!(code.contains("let mut __yielded : Vec < i64 > = Vec :: new () ;"), "{}", code);
+
+        // The string constants a generator of str yields are Strings.
+        let code = statement_to_rust("def words() -> Generator[str, None, None]:\n    yield \"a\"\n", PythonOptions::default());
+        assert!(code.contains("-> impl Iterator < Item = String >"), "{}", code);
+        assert!(code.contains("__yielded . push (String :: from (\"a\"))"), "{}", code);
+
+        // Without one, the item type is that of the annotated variables it yields.
+        let code = statement_to_rust("def both(a: float, b: float):\n    yield a\n    yield b\n", PythonOptions::default());
+        assert!(code.contains("-> impl Iterator < Item = f64 >"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_generator_return_and_yield_from() {
+        let code = statement_to_rust(
+            "def chain(xs, stop):
+    for x in xs:
+        if x == stop:
+            return
+        yield x
+    yield from xs
+",
+            PythonOptions::default(),
+        );
+        assert!(code.contains("-> impl Iterator < Item = PyObject >"), "{}", code);
+        assert!(code.contains("return __yielded . into_iter ()"), "{}", code);
+        assert!(code.contains("__yielded . extend (xs)"), "{}", code);
+    }
+
+    #[test]
+    fn test_optional_annotations_become_options() {
+        let code = statement_to_rust(
+            "def find(items: list, key: Optional[Key] = None, limit: Count | None = None) -> Optional[Item]:
+    return None
+",
+            PythonOptions::default(),
+        );
+        assert!(
+            code.starts_with("pub fn find (items : Vec < PyObject > , key : Option < Key > , limit : Option < Count >) -> Option < Item >"),
+            "{}",
+            code
+        );
+
+        let code = statement_to_rust("def log(message: Optional[Text]) -> None:\n    print(message)\n", PythonOptions::default());
+        assert!(code.starts_with("pub fn log (message : Option < Text >) {"), "{}", code);
+    }
+
+    #[test]
+    fn test_annotated_signature() {
+        let code = statement_to_rust(
+            "def apply(f: Callable[[int], str], counts: dict[str, int], pair: tuple[float, bool]) -> list[str]:\n    ...\n",
+            PythonOptions::default(),
+        );
+        assert!(
+            code.starts_with(
+                "pub fn apply (f : impl Fn (i64) -> String , counts : HashMap < String , i64 > , pair : (f64 , bool)) -> Vec < String > {"
+            ),
+            "{}",
+            code
+        );
+        assert!(!code.contains("todo !"), "{}", code);
+    }
+
+    #[test]
+    fn test_typing_containers_in_signature() {
+        let code = try_module_to_rust(
+            "from typing import Dict, List, Set, Tuple
+
+def index(words: List[str], seen: Set[str]) -> Dict[str, int]:
+    ...
+
+def split(pair: Tuple[int, str], rest: Tuple[int, ...]) -> List[Tuple[int, str]]:
+    ...
+",
+            test_options(),
+        )
+        .unwrap();
+        assert!(code.contains("use std :: collections :: HashMap ;"), "{}", code);
+        assert!(code.contains("use std :: collections :: HashSet ;"), "{}", code);
+        assert!(code.contains("pub fn index (words : Vec < String > , seen : HashSet < String >) -> HashMap < String , i64 > {"), "{}", code);
+        assert!(code.contains("pub fn split (pair : (i64 , String) , rest : Vec < i64 >) -> Vec < (i64 , String) > {"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_type_map_comes_before_builtin_types() {
+        let mut options = PythonOptions::default();
+        options.map_type("Decimal", "rust_decimal::Decimal").map_type("int", "num_bigint::BigInt");
+        let code = statement_to_rust("def scale(x: Decimal, n: int) -> Decimal:\n    return x\n", options);
+        assert!(
+            code.starts_with("pub fn scale (x : rust_decimal :: Decimal , n : num_bigint :: BigInt) -> rust_decimal :: Decimal"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_string_type_decides_str_annotations() {
+        let source = "def greet(name: str) -> str:
+    greeting: str = 'hello'
+    return 'hi'
+";
+        let code = statement_to_rust(source, PythonOptions::default());
+        assert!(code.starts_with("pub fn greet (name : String) -> String {"), "{}", code);
+        assert!(code.contains("let greeting : String = String :: from (\"hello\")"), "{}", code);
+        assert!(code.ends_with("String :: from (\"hi\") }"), "{}", code);
+
+        let mut options = PythonOptions::default();
+        options.string_type = StringType::Str;
+        let code = statement_to_rust(source, options);
+        assert!(code.starts_with("pub fn greet (name : & str) -> & str {"), "{}", code);
+        assert!(code.contains("let greeting : & str = \"hello\""), "{}", code);
+        assert!(code.ends_with("\"hi\" }"), "{}", code);
+    }
+
+    #[test]
+    fn test_type_variables_become_generic_parameters() {
+        let code = try_module_to_rust(
+            "from typing import List, TypeVar
+
+T = TypeVar('T')
+N = TypeVar('N', bound=int)
+
+def first(items: List[T]) -> T:
+    return items[0]
+
+def clamp(value: N, low: N, high: 'T') -> N:
+    return value
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub fn first < T > (items : Vec < T >) -> T"), "{}", code);
+        assert!(code.contains("pub fn clamp < N : Into < i64 > , T > (value : N , low : N , high : T) -> N"), "{}", code);
+        assert!(!code.contains("TypeVar"), "{}", code);
+    }
+
+    #[test]
+    fn test_type_variables_are_recorded_and_propagated() {
+        let source = "from typing import Tuple, TypeVar
+
+T = TypeVar('T')
+U = TypeVar('U')
+
+def identity(x: T) -> T:
+    return x
+
+def swap(pair: Tuple[T, U]) -> Tuple[U, T]:
+    return (pair[1], pair[0])
+";
+        let symbols = crate::parse(source, "test.py").unwrap().find_symbols(SymbolTableScopes::new());
+        assert!(matches!(symbols.lookup("T"), Some(SymbolTableNode::TypeVar(type_var)) if type_var.name == "T"));
+
+        let code = try_module_to_rust(source, test_options()).unwrap();
+        assert!(code.contains("pub fn identity < T > (x : T) -> T { x }"), "{}", code);
+        assert!(code.contains("pub fn swap < T , U > (pair : (T , U)) -> (U , T)"), "{}", code);
+    }
+
+    #[test]
+    fn test_constrained_type_variable_is_unsupported() {
+        let code = try_module_to_rust(
+            "from typing import TypeVar
+
+S = TypeVar('S', int, str)
+
+def echo(value: S) -> S:
+    return value
+",
+            test_options(),
+        )
+        .unwrap();
+        assert!(code.contains("compile_error ! (\"UNSUPPORTED: the constrained type variable S\") ;"), "{}", code);
+    }
+
+    #[test]
+    fn test_keywords_are_sanitized_consistently() {
+        let code = statement_to_rust("def match(type: int, self: int) -> int:\n    return type + self\n", PythonOptions::default());
+        assert!(code.starts_with("pub fn r#match (r#type : i64 , self_ : i64) -> i64 {"), "{}", code);
+        assert!(code.contains("(r#type) + (self_)"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_only_changed_variables_are_mutable() {
+        let code = statement_to_rust(
+            "def f(items: list[int], start: int, step: int) -> int:
+    total = 0
+    scale = 2
+    for x in items:
+        x += scale
+        total = total + x
+    for y in items:
+        print(y)
+    start = start + total
+    low, high = 0, step
+    low, high = high, low
+    if total:
+        flag = 1
+    flag = 2
+    return start + low + flag
+",
+            PythonOptions::default(),
+        );
+        assert!(code.starts_with("pub fn f (items : Vec < i64 > , mut start : i64 , step : i64) -> i64 {"), "{}", code);
+        assert!(code.contains("let mut total = 0 ;"), "{}", code);
+        assert!(code.contains("let scale = 2 ;"), "{}", code);
+        // The loop changes the variables declared before it, rather than shadowing them.
+        assert!(code.contains("for mut x in items { x += scale ; total = (total) + (x) ;"), "{}", code);
+        assert!(code.contains("for y in items {"), "{}", code);
+        assert!(code.contains("start = (start) + (total) ;"), "{}", code);
+        assert!(code.contains("let (mut low , mut high ,) = (0 , step) ; ; (low , high) = (high , low) ;"), "{}", code);
+        // A variable first assigned in a block and used after it is declared before it.
+        assert!(
+            code.contains("let mut flag = Default :: default () ; if total { flag = 1 ; ; } ; flag = 2 ;"),
+            "{}",
+            code
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_variables_assigned_in_blocks_are_declared_before_them() {
+        let code = module_to_rust(
+            "def pick(c: bool) -> int:
+    if c:
+        e = 1
+    else:
+        e = 2
+    return e
+def last(items: list[int]) -> int:
+    for x in items:
+        if x > 2:
+            best = x
+    return best
+def nested(a: bool, b: bool) -> int:
+    if a:
+        if b:
+            v = 1
+        else:
+            v = 2
+        v = v + 1
+    else:
+        return 0
+    return v
+def retype() -> int:
+    b = 2
+    print(b)
+    b = \"s\"
+    print(b)
+    return 0
+",
+            test_options(),
+        );
+        assert!(code.contains("let e ; if c { e = 1 ; ; } else { e = 2 ; ; } ; e"), "{}", code);
+        // The loop might not assign it, so it has a value to start with.
+        assert!(code.contains("let mut best = Default :: default () ; for x in items {"), "{}", code);
+        assert!(code.contains("let mut v ; if a {"), "{}", code);
+        // Assigning a value of another type declares the variable again.
+        assert!(code.contains("let b = 2 ;"), "{}", code);
+        assert!(code.contains("let b = \"s\" ;"), "{}", code);
+        assert_compiles(&code);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    walk_statement, CodeGen, CodeGenContext, Node, PythonOptions, Statement, StatementType, SymbolTableNode,
+    SymbolTableScopes, Visitor,
+};
+
+/// Global statement (global x, y)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Global {
+    /// The names declared as referring to the module scope
+    pub names: Vec<String>,
+    /// Position information
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
+}
+
+impl<'a> FromPyObject<'a> for Global {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let names: Vec<String> = ob.getattr("names")?.extract()?;
+
+        Ok(Global {
+            names,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
+        })
+    }
+}
+
+impl Global {
+    /// The names declared `global` anywhere in a module's body, like in its functions, the
+    /// functions nested in them and the methods of its classes.
+    pub fn declared_in(body: &[Statement]) -> HashSet<String> {
+        let mut globals = GlobalNames::default();
+        body.iter().for_each(|statement| globals.visit_statement(statement));
+        globals.0
+    }
+}
+
+#[derive(Default)]
+struct GlobalNames(HashSet<String>);
+
+impl Visitor for GlobalNames {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        if let StatementType::Global(global) = &stmt.statement {
+            self.0.extend(global.names.iter().cloned());
+        }
+        walk_statement(stmt, self);
+    }
+}
+
+impl Node for Global {
+    fn lineno(&self) -> Option<usize> { self.lineno }
+    fn col_offset(&self) -> Option<usize> { self.col_offset }
+    fn end_lineno(&self) -> Option<usize> { self.end_lineno }
+    fn end_col_offset(&self) -> Option<usize> { self.end_col_offset }
+}
+
+impl CodeGen for Global {
+    type Context = CodeGenContext;
+    type Options = PythonOptions;
+    type SymbolTable = SymbolTableScopes;
+
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = symbols;
+        for name in self.names {
+            symbols.insert(name, SymbolTableNode::Global);
+        }
+        symbols
+    }
+
+    fn to_rust(
+        self,
+        _ctx: Self::Context,
+        _options: Self::Options,
+        _symbols: Self::SymbolTable,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // The declaration only affects name resolution, which is handled through
+        // the symbol table, so there is nothing to emit.
+        Ok(quote!())
+    }
+}
+
+/// Nonlocal statement (nonlocal x, y)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Nonlocal {
+    /// The names declared as referring to an enclosing function scope
+    pub names: Vec<String>,
+    /// Position information
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
+}
+
+impl<'a> FromPyObject<'a> for Nonlocal {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let names: Vec<String> = ob.getattr("names")?.extract()?;
+
+        Ok(Nonlocal {
+            names,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
+        })
+    }
+}
+
+impl Node for Nonlocal {
+    fn lineno(&self) -> Option<usize> { self.lineno }
+    fn col_offset(&self) -> Option<usize> { self.col_offset }
+    fn end_lineno(&self) -> Option<usize> { self.end_lineno }
+    fn end_col_offset(&self) -> Option<usize> { self.end_col_offset }
+}
+
+impl CodeGen for Nonlocal {
+    type Context = CodeGenContext;
+    type Options = PythonOptions;
+    type SymbolTable = SymbolTableScopes;
+
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = symbols;
+        for name in self.names {
+            symbols.insert(name, SymbolTableNode::Nonlocal);
+        }
+        symbols
+    }
+
+    fn to_rust(
+        self,
+        _ctx: Self::Context,
+        _options: Self::Options,
+        _symbols: Self::SymbolTable,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        Ok(quote!())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_compiles, module_to_rust, test_options};
+    use crate::StatementType;
+
+    #[test]
+    fn test_global_names() {
+        let module = crate::parse("global a, b", "test.py").unwrap();
+        match &module.raw.body[0].statement {
+            StatementType::Global(g) => assert_eq!(g.names, vec!["a", "b"]),
+            s => panic!("expected global statement, got {:?}", s),
+        }
+    }
+
+    #[test]
+    fn test_global_reuses_outer_binding() {
+        let module = crate::parse(
+            "counter = 0
+def increment():
+    global counter
+    counter += 1
+    counter = counter * 2
+",
+            "test.py",
+        )
+        .unwrap();
+        let function = module.raw.body[1].clone();
+        let code = function
+            .to_rust(
+                CodeGenContext::Module("test".to_string()),
+                PythonOptions::default(),
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+
+        assert!(code.contains("counter += 1"));
+        assert!(code.contains("counter = (counter) * (2)"));
+        assert!(!code.contains("let counter"));
+    }
+
+    #[test]
+    fn test_globals_are_shared_with_functions() {
+        let code = module_to_rust(
+            "counter = 0
+def bump() -> int:
+    global counter
+    counter += 1
+    return counter
+def reset():
+    global counter
+    counter = 0
+def run():
+    bump()
+    print(counter)
+counter = 5
+",
+            test_options(),
+        );
+
+        assert!(code.contains("static counter : std :: cell :: Cell < i64 > = std :: cell :: Cell :: new (0)"));
+        assert!(code.contains("counter . set (5)"));
+        assert!(!code.contains("let counter"));
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_nonlocal_reuses_outer_binding() {
+        let module = crate::parse(
+            "def outer():
+    total = 0
+    def add():
+        nonlocal total
+        total = total + 1
+",
+            "test.py",
+        )
+        .unwrap();
+        let code = module
+            .to_rust(
+                CodeGenContext::Module("test".to_string()),
+                PythonOptions::default(),
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+
+        assert!(code.contains("let mut total = 0"), "{}", code);
+        assert!(code.contains("total = (total) + (1)"));
+        assert!(!code.contains("let total = (total)"));
+    }
+}
@@ -4,7 +4,7 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
+    condition_to_rust, CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
     Node, impl_node_with_positions, PyAttributeExtractor
 };
 
@@ -48,13 +48,19 @@ impl CodeGen for IfExp {
     type Options = PythonOptions;
     type SymbolTable = SymbolTableScopes;
 
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let symbols = self.test.find_symbols(symbols);
+        let symbols = self.body.find_symbols(symbols);
+        self.orelse.find_symbols(symbols)
+    }
+
     fn to_rust(
         self,
         ctx: Self::Context,
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let test = self.test.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let test = condition_to_rust(*self.test, ctx.clone(), options.clone(), symbols.clone())?;
         let body = self.body.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
         let orelse = self.orelse.to_rust(ctx, options, symbols)?;
         
@@ -71,4 +77,19 @@ mod tests {
 
     create_parse_test!(test_if_expression, "x if condition else y", "if_exp_test.py");
     create_parse_test!(test_nested_if_expression, "a if b else c if d else e", "if_exp_test.py");
+
+    #[test]
+    fn test_if_expression_binding() {
+        let module = crate::parse("x = a if c else b", "if_exp_test.py").unwrap();
+        let code = module.raw.body[0]
+            .clone()
+            .to_rust(
+                CodeGenContext::Module("if_exp_test".to_string()),
+                PythonOptions::default(),
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+        assert_eq!(code, "let x = if c { a } else { b } ;");
+    }
 }
\ No newline at end of file
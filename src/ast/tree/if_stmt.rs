@@ -4,8 +4,8 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
-    Node, impl_node_with_positions, PyAttributeExtractor, extract_list
+    condition_to_rust, CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
+    Node, impl_node_with_positions, PyAttributeExtractor, extract_list, hoist
 };
 
 use super::Statement;
@@ -49,7 +49,9 @@ impl CodeGen for If {
     type SymbolTable = SymbolTableScopes;
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
-        let symbols = self.test.find_symbols(symbols);
+        let (test, named) = hoist(self.test);
+        let symbols = named.into_iter().fold(symbols, |acc, n| n.find_symbols(acc));
+        let symbols = test.find_symbols(symbols);
         let symbols = self.body.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc));
         self.orelse.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc))
     }
@@ -60,8 +62,14 @@ impl CodeGen for If {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        // Regular if statement handling
-        let test = self.test.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        // Assignment expressions in the condition are bound before it, where the rest of
+        // the function can see them, like in Python.
+        let (test, named) = hoist(self.test);
+        let bindings = named
+            .into_iter()
+            .map(|n| n.binding(ctx.clone(), options.clone(), symbols.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let test = condition_to_rust(test, ctx.clone(), options.clone(), symbols.clone())?;
         
         let body_stmts: Result<Vec<_>, _> = self.body
             .into_iter()
@@ -71,8 +79,9 @@ impl CodeGen for If {
         
         if self.orelse.is_empty() {
             Ok(quote! {
+                #(#bindings)*
                 if #test {
-                    #(#body_stmts)*
+                    #(#body_stmts;)*
                 }
             })
         } else {
@@ -83,10 +92,11 @@ impl CodeGen for If {
             let else_stmts = else_stmts?;
             
             Ok(quote! {
+                #(#bindings)*
                 if #test {
-                    #(#body_stmts)*
+                    #(#body_stmts;)*
                 } else {
-                    #(#else_stmts)*
+                    #(#else_stmts;)*
                 }
             })
         }
@@ -100,5 +110,6 @@ mod tests {
 
     create_parse_test!(test_simple_if, "if x > 5:\n    print('big')", "if_test.py");
     create_parse_test!(test_if_else, "if x > 5:\n    print('big')\nelse:\n    print('small')", "if_test.py");
+    create_parse_test!(test_if_walrus, "if (n := len(a)) > 10:\n    print(n)", "if_test.py");
     create_parse_test!(test_if_elif, "if x > 10:\n    print('huge')\nelif x > 5:\n    print('big')\nelse:\n    print('small')", "if_test.py");
 }
\ No newline at end of file
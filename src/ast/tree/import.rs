@@ -1,10 +1,10 @@
 use tracing::debug;
 use proc_macro2::TokenStream;
 use pyo3::FromPyObject;
-use quote::{format_ident, quote};
+use quote::quote;
 use serde::{Deserialize, Serialize};
 
-use crate::{CodeGen, CodeGenContext, PythonOptions, SymbolTableNode, SymbolTableScopes};
+use crate::{sanitize_ident, CodeGen, CodeGenContext, CodeGenError, ImportMapping, PythonOptions, SymbolTableNode, SymbolTableScopes};
 
 #[derive(Clone, Debug, FromPyObject, Serialize, Deserialize, PartialEq)]
 pub struct Alias {
@@ -20,7 +20,8 @@ pub struct Import {
 /// An Import (or FromImport) statement causes 2 things to occur:
 /// 1. Declares the imported object within the existing scope.
 /// 2. Causes the referenced module to be compiled into the program (only once).
-
+///
+/// What a module is in Rust comes from [`PythonOptions::import_map`].
 impl CodeGen for Import {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -29,9 +30,10 @@ impl CodeGen for Import {
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
         let mut symbols = symbols;
         for alias in self.names.iter() {
-            symbols.insert(alias.name.clone(), SymbolTableNode::Import(self.clone()));
-            if let Some(a) = alias.asname.clone() {
-                symbols.insert(a, SymbolTableNode::Alias(alias.name.clone()))
+            // `import numpy as np` only binds np.
+            match alias.asname.clone() {
+                Some(a) => symbols.insert(a, SymbolTableNode::Alias(alias.name.clone())),
+                None => symbols.insert(alias.name.clone(), SymbolTableNode::Import(self.clone())),
             }
         }
         symbols
@@ -43,62 +45,100 @@ impl CodeGen for Import {
         options: Self::Options,
         _symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let visibility = reexport(&options);
         let mut tokens = TokenStream::new();
         for alias in self.names.iter() {
-            // Check if this is a Python standard library module that needs special handling
-            let rust_import = match alias.name.as_str() {
-                // Python stdlib modules that don't have direct Rust equivalents
-                "os" | "sys" | "subprocess" | "json" | "urllib" | "xml" | "asyncio" => {
-                    // These will be provided by the stdpython runtime
-                    // Generate a comment instead of a use statement
-                    quote! {
-                        // Python module '{}' will be provided by stdpython runtime
-                    }
-                }
-                "os.path" => {
-                    quote! {
-                        // Python os.path module will be provided by stdpython runtime
-                    }
+            let (path, shim) = module_path(&alias.name, &options)?;
+            let rust_import = match &alias.asname {
+                // The runtime provides the modules it translates.
+                None if shim => quote!(),
+                None if alias.name.contains('.') => quote!(#visibility use #path;),
+                None => {
+                    let name = sanitize_ident(&alias.name);
+                    quote!(#visibility use #path as #name;)
                 }
-                _ => {
-                    // Handle other imports normally
-                    let names = if alias.name.contains('.') {
-                        let parts: Vec<&str> = alias.name.split('.').collect();
-                        let idents: Vec<_> = parts.iter().map(|part| format_ident!("{}", part)).collect();
-                        quote!(#(#idents)::*)
-                    } else {
-                        let single_name = format_ident!("{}", alias.name);
-                        quote!(#single_name)
-                    };
-                    
-                    match &alias.asname {
-                        None => {
-                            quote! {use #names;}
-                        }
-                        Some(n) => {
-                            let name = format_ident!("{}", n);
-                            quote! {use #names as #name;}
-                        }
-                    }
+                Some(n) => {
+                    let name = sanitize_ident(n);
+                    quote!(#visibility use #path as #name;)
                 }
             };
-            
             tokens.extend(rust_import);
         }
         debug!("context: {:?}", ctx);
-        debug!("options: {:?}", options);
         debug!("tokens: {}", tokens);
         Ok(tokens)
     }
 }
 
+/// The Rust path of an imported Python module, and whether the code generator translates
+/// the module itself. A submodule is under the path of the closest enclosing module that
+/// is mapped.
+fn module_path(module: &str, options: &PythonOptions) -> Result<(TokenStream, bool), Box<dyn std::error::Error>> {
+    let parts: Vec<&str> = module.split('.').collect();
+    for mapped in (1..=parts.len()).rev() {
+        let Some(mapping) = options.import_map.get(&parts[..mapped].join(".")) else {
+            continue;
+        };
+        let base = match mapping {
+            ImportMapping::Shim => {
+                let idents = parts[..mapped].iter().map(|part| sanitize_ident(part));
+                quote!(#(#idents)::*)
+            }
+            ImportMapping::Path(path) => path.parse::<TokenStream>().map_err(|_| {
+                CodeGenError::malformed(format!("the import map's path for {} isn't a Rust path: {}", module, path))
+            })?,
+            ImportMapping::Crate(name) => {
+                let name = sanitize_ident(name);
+                quote!(::#name)
+            }
+        };
+        let rest = parts[mapped..].iter().map(|part| sanitize_ident(part));
+        return Ok((quote!(#base #(::#rest)*), *mapping == ImportMapping::Shim));
+    }
+    Err(CodeGenError::UnmappedImport {
+        module: module.to_string(),
+        location: None,
+    }
+    .into())
+}
+
+/// The visibility of the `use` items generated for imports.
+fn reexport(options: &PythonOptions) -> TokenStream {
+    if options.reexport_imports { quote!(pub) } else { quote!() }
+}
+
 #[derive(Clone, Debug, FromPyObject, Serialize, Deserialize, PartialEq)]
 pub struct ImportFrom {
-    pub module: String,
+    /// The module imported from, which is None for `from . import name`.
+    pub module: Option<String>,
     pub names: Vec<Alias>,
+    /// The number of dots of a relative import, 0 for an absolute one.
     pub level: usize,
 }
 
+impl ImportFrom {
+    /// The module a relative import imports from, as the names of the packages from the
+    /// crate root, for an import in a module of `package`.
+    pub fn relative_module(&self, package: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let up = self.level.saturating_sub(1);
+        if up > package.len() {
+            return Err(CodeGenError::unsupported(
+                format!("relative import beyond the top-level package: from {} import", self.display_module()),
+                None,
+            )
+            .into());
+        }
+        let mut module = package[..package.len() - up].to_vec();
+        module.extend(self.module.iter().flat_map(|m| m.split('.')).map(String::from));
+        Ok(module)
+    }
+
+    /// The module as it's written in Python, with the dots of a relative import.
+    fn display_module(&self) -> String {
+        format!("{}{}", ".".repeat(self.level), self.module.as_deref().unwrap_or_default())
+    }
+}
+
 impl CodeGen for ImportFrom {
     type Context = CodeGenContext;
     type Options = PythonOptions;
@@ -108,7 +148,7 @@ impl CodeGen for ImportFrom {
         let mut symbols = symbols;
         for alias in self.names.iter() {
             symbols.insert(
-                alias.name.clone(),
+                alias.asname.clone().unwrap_or_else(|| alias.name.clone()),
                 SymbolTableNode::ImportFrom(self.clone()),
             );
         }
@@ -118,10 +158,97 @@ impl CodeGen for ImportFrom {
     fn to_rust(
         self,
         ctx: Self::Context,
-        _options: Self::Options,
+        options: Self::Options,
         _symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
         debug!("ctx: {:?}", ctx);
-        Ok(quote! {})
+        if self.names.iter().any(|alias| alias.name == "*") {
+            return Err(CodeGenError::unsupported(format!("from {} import *", self.display_module()), None).into());
+        }
+
+        // Relative imports are of the crate's own modules, which mirror the packages.
+        let path = if self.level > 0 {
+            let module = self.relative_module(&options.package)?;
+            let idents = module.iter().map(|part| sanitize_ident(part));
+            quote!(crate #(::#idents)*)
+        } else {
+            let (path, shim) = module_path(self.module.as_deref().unwrap_or_default(), &options)?;
+            if shim {
+                return Ok(quote!());
+            }
+            path
+        };
+        let visibility = reexport(&options);
+        let imports = self.names.iter().map(|alias| {
+            let name = sanitize_ident(&alias.name);
+            match &alias.asname {
+                Some(asname) => {
+                    let asname = sanitize_ident(asname);
+                    quote!(#visibility use #path::#name as #asname;)
+                }
+                None => quote!(#visibility use #path::#name;),
+            }
+        });
+        Ok(quote!(#(#imports)*))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_options, try_module_to_rust};
+
+    #[test]
+    fn test_aliased_imports() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options
+            .map_import("numpy", ImportMapping::Crate("ndarray".to_string()))
+            .map_import("collections", ImportMapping::Path("std::collections".to_string()));
+        let code = try_module_to_rust(
+            "import os
+import numpy as np
+from collections import HashMap as Map, BTreeMap
+
+def zeros():
+    return np.zeros(3)
+",
+            options,
+        )
+        .unwrap();
+        assert!(code.starts_with("use :: ndarray as np ; use std :: collections :: HashMap as Map ; use std :: collections :: BTreeMap ;"), "{}", code);
+        assert!(code.contains("np :: zeros (3)"), "{}", code);
+        assert!(!code.contains("use os"), "{}", code);
+    }
+
+    #[test]
+    fn test_submodules_are_under_their_mapped_module() {
+        let mut options = PythonOptions::default();
+        options.map_import("pkg", ImportMapping::Path("crate::pkg".to_string()));
+        let code = try_module_to_rust("import pkg.util\nfrom pkg.util import helper\n", options).unwrap();
+        assert!(code.contains("use crate :: pkg :: util ; use crate :: pkg :: util :: helper ;"), "{}", code);
+    }
+
+    #[test]
+    fn test_unmapped_import_is_an_error() {
+        let error = try_module_to_rust("import requests\n", PythonOptions::default()).unwrap_err();
+        match error.downcast_ref::<CodeGenError>() {
+            Some(CodeGenError::UnmappedImport { module, location }) => {
+                assert_eq!(module, "requests");
+                assert_eq!(location.as_ref().map(|l| l.filename.as_str()), Some("test.py"));
+            }
+            _ => panic!("expected an unmapped import error, got {}", error),
+        }
+    }
+
+    #[test]
+    fn test_star_import_is_unsupported() {
+        let options = test_options();
+        let code = try_module_to_rust("from os import *\n", options.clone()).unwrap();
+        // A todo!() isn't an item.
+        assert!(code.starts_with("compile_error ! (\"UNSUPPORTED: from os import *\") ;"), "{}", code);
+        assert!(!code.contains("todo !"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(!options.diagnostics.is_empty());
     }
 }
@@ -0,0 +1,526 @@
+//! Structural pattern matching. A `match` statement becomes a Rust `match`, with each
+//! Python pattern translated to the Rust pattern with the same shape:
+//!
+//! | Python                  | Rust                         |
+//! |-------------------------|------------------------------|
+//! | `case 1:`, `case "a":`  | `1 =>`, `"a" =>`             |
+//! | `case None:`            | `None =>`                    |
+//! | `case Color.RED:`       | `Color::RED =>`              |
+//! | `case [a, *rest]:`      | `[a, rest @ ..] =>`          |
+//! | `case Point(x=0):`      | `Point::Data { x: 0, .. } =>` |
+//! | `case int(n):`          | `IntOrStr::Int(n) =>`        |
+//! | `case 1 \| 2:`          | `1 \| 2 =>`                  |
+//! | `case [x] as whole:`    | `whole @ [x] =>`             |
+//! | `case _:`               | `_ =>`                       |
+//!
+//! Matching a tuple of values uses tuple patterns instead of slice patterns. A class
+//! pattern on a variable annotated with a union matches the union enum's variant. Mapping
+//! patterns, and other class patterns with positional arguments, aren't supported yet.
+
+use proc_macro2::TokenStream;
+use pyo3::{Bound, FromPyObject, PyAny, PyResult, types::PyAnyMethods};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    annotation_to_rust_type, convert_ident, extract_list, impl_node_with_positions, sanitize_ident, CodeGen, CodeGenContext, CodeGenError,
+    ExprType, IdentCase, Node, PyAttributeExtractor, PythonOptions, RustType, Statement, SymbolTableNode,
+    SymbolTableScopes, UNKNOWN_FILE,
+};
+
+/// A match statement (match subject: case pattern: ...)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Match {
+    /// The value being matched
+    pub subject: ExprType,
+    /// The cases, in the order they're tried
+    pub cases: Vec<MatchCase>,
+    pub lineno: Option<usize>,
+    pub col_offset: Option<usize>,
+    pub end_lineno: Option<usize>,
+    pub end_col_offset: Option<usize>,
+}
+
+/// One case of a match statement.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    /// The condition after `if`, which must also hold for the case to be taken
+    pub guard: Option<ExprType>,
+    pub body: Vec<Statement>,
+}
+
+/// A pattern of a case in a match statement.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Pattern {
+    /// A literal or dotted name compared by equality, like `1` or `Color.RED`
+    MatchValue(ExprType),
+    /// `None`, `True` or `False`, compared by identity. None is `None`.
+    MatchSingleton(Option<bool>),
+    /// `[a, b, *rest]` or `(a, b)`
+    MatchSequence(Vec<Pattern>),
+    /// `{"key": value, **rest}`
+    MatchMapping {
+        keys: Vec<ExprType>,
+        patterns: Vec<Pattern>,
+        rest: Option<String>,
+    },
+    /// `Point(x, y=0)`
+    MatchClass {
+        cls: ExprType,
+        patterns: Vec<Pattern>,
+        kwd_attrs: Vec<String>,
+        kwd_patterns: Vec<Pattern>,
+    },
+    /// `*rest` inside a sequence pattern. `*_` has no name.
+    MatchStar(Option<String>),
+    /// `pattern as name`, a bare capture `name` if there's no pattern, or `_` if there's
+    /// neither.
+    MatchAs {
+        pattern: Option<Box<Pattern>>,
+        name: Option<String>,
+    },
+    /// `a | b`
+    MatchOr(Vec<Pattern>),
+}
+
+impl<'a> FromPyObject<'a> for Match {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let subject = ob.extract_attr_with_context("subject", "match subject")?.extract()?;
+        let cases: Vec<MatchCase> = extract_list(ob, "cases", "match cases")?;
+
+        Ok(Match {
+            subject,
+            cases,
+            lineno: ob.lineno(),
+            col_offset: ob.col_offset(),
+            end_lineno: ob.end_lineno(),
+            end_col_offset: ob.end_col_offset(),
+        })
+    }
+}
+
+impl<'a> FromPyObject<'a> for MatchCase {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        let pattern = ob.extract_attr_with_context("pattern", "case pattern")?.extract()?;
+        let guard = ob.extract_attr_with_context("guard", "case guard")?;
+        let guard = if guard.is_none() { None } else { Some(guard.extract()?) };
+        let body: Vec<Statement> = extract_list(ob, "body", "case body")?;
+
+        Ok(MatchCase { pattern, guard, body })
+    }
+}
+
+/// Extract an attribute that's either None or a string, like the name of a capture.
+fn optional_name(ob: &Bound<PyAny>, attr: &str) -> PyResult<Option<String>> {
+    let value = ob.extract_attr_with_context(attr, "pattern name")?;
+    if value.is_none() { Ok(None) } else { Ok(Some(value.extract()?)) }
+}
+
+impl<'a> FromPyObject<'a> for Pattern {
+    fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
+        match ob.extract_type_name("pattern")?.as_str() {
+            "MatchValue" => Ok(Pattern::MatchValue(
+                ob.extract_attr_with_context("value", "value pattern")?.extract()?,
+            )),
+            "MatchSingleton" => {
+                let value = ob.extract_attr_with_context("value", "singleton pattern")?;
+                Ok(Pattern::MatchSingleton(if value.is_none() { None } else { Some(value.extract()?) }))
+            }
+            "MatchSequence" => Ok(Pattern::MatchSequence(extract_list(ob, "patterns", "sequence patterns")?)),
+            "MatchMapping" => Ok(Pattern::MatchMapping {
+                keys: extract_list(ob, "keys", "mapping pattern keys")?,
+                patterns: extract_list(ob, "patterns", "mapping patterns")?,
+                rest: optional_name(ob, "rest")?,
+            }),
+            "MatchClass" => Ok(Pattern::MatchClass {
+                cls: ob.extract_attr_with_context("cls", "class pattern")?.extract()?,
+                patterns: extract_list(ob, "patterns", "class patterns")?,
+                kwd_attrs: extract_list(ob, "kwd_attrs", "class pattern attributes")?,
+                kwd_patterns: extract_list(ob, "kwd_patterns", "class pattern keyword patterns")?,
+            }),
+            "MatchStar" => Ok(Pattern::MatchStar(optional_name(ob, "name")?)),
+            "MatchAs" => {
+                let pattern = ob.extract_attr_with_context("pattern", "as pattern")?;
+                let pattern = if pattern.is_none() { None } else { Some(Box::new(pattern.extract()?)) };
+                Ok(Pattern::MatchAs { pattern, name: optional_name(ob, "name")? })
+            }
+            "MatchOr" => Ok(Pattern::MatchOr(extract_list(ob, "patterns", "or patterns")?)),
+            other => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Unimplemented pattern type {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl_node_with_positions!(Match { lineno, col_offset, end_lineno, end_col_offset });
+
+impl Pattern {
+    /// The names the pattern binds when it matches.
+    pub fn captures(&self) -> Vec<String> {
+        match self {
+            Pattern::MatchValue(_) | Pattern::MatchSingleton(_) => Vec::new(),
+            Pattern::MatchSequence(patterns) => patterns.iter().flat_map(Pattern::captures).collect(),
+            Pattern::MatchMapping { patterns, rest, .. } => patterns
+                .iter()
+                .flat_map(Pattern::captures)
+                .chain(rest.clone())
+                .collect(),
+            Pattern::MatchClass { patterns, kwd_patterns, .. } => patterns
+                .iter()
+                .chain(kwd_patterns)
+                .flat_map(Pattern::captures)
+                .collect(),
+            Pattern::MatchStar(name) => name.iter().cloned().collect(),
+            Pattern::MatchAs { pattern, name } => pattern
+                .iter()
+                .flat_map(|p| p.captures())
+                .chain(name.clone())
+                .collect(),
+            // Every alternative binds the same names.
+            Pattern::MatchOr(patterns) => patterns.first().map(Pattern::captures).unwrap_or_default(),
+        }
+    }
+
+    /// Whether the pattern matches any value, like `_` or a bare capture.
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::MatchAs { pattern: None, .. } => true,
+            Pattern::MatchAs { pattern: Some(pattern), .. } => pattern.is_irrefutable(),
+            Pattern::MatchOr(patterns) => patterns.iter().any(Pattern::is_irrefutable),
+            _ => false,
+        }
+    }
+
+    /// Whether the pattern matches a sequence, looking through `as` and `|`.
+    fn is_sequence(&self) -> bool {
+        match self {
+            Pattern::MatchSequence(_) => true,
+            Pattern::MatchAs { pattern: Some(pattern), .. } => pattern.is_sequence(),
+            Pattern::MatchOr(patterns) => patterns.iter().any(Pattern::is_sequence),
+            _ => false,
+        }
+    }
+
+    /// Whether the pattern compares with a string literal, looking through `as` and `|`.
+    fn is_str(&self) -> bool {
+        match self {
+            Pattern::MatchValue(ExprType::Constant(c)) => c.string_value().is_some(),
+            Pattern::MatchAs { pattern: Some(pattern), .. } => pattern.is_str(),
+            Pattern::MatchOr(patterns) => patterns.iter().any(Pattern::is_str),
+            _ => false,
+        }
+    }
+
+    /// Generate the Rust pattern. A sequence becomes a tuple pattern if `tuple` is set,
+    /// which is the case at the top of a pattern matching a tuple of values.
+    pub fn to_rust(
+        self,
+        tuple: bool,
+        location: &Match,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let capture = |name: &str| convert_ident(name, IdentCase::Value, &options, &symbols);
+        let unsupported = |what: &str| {
+            CodeGenError::unsupported(what, Some(location.source_location(UNKNOWN_FILE)))
+        };
+        match self {
+            Pattern::MatchValue(value) => value_pattern(value, ctx, options.clone(), symbols.clone()),
+            Pattern::MatchSingleton(None) => Ok(quote!(None)),
+            Pattern::MatchSingleton(Some(true)) => Ok(quote!(true)),
+            Pattern::MatchSingleton(Some(false)) => Ok(quote!(false)),
+            Pattern::MatchSequence(patterns) => {
+                let elements = patterns
+                    .into_iter()
+                    .map(|p| match p {
+                        Pattern::MatchStar(_) if tuple => Ok(quote!(..)),
+                        p => p.to_rust(false, location, ctx.clone(), options.clone(), symbols.clone()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if tuple {
+                    Ok(quote!((#(#elements),*)))
+                } else {
+                    Ok(quote!([#(#elements),*]))
+                }
+            }
+            Pattern::MatchMapping { .. } => Err(unsupported("mapping pattern").into()),
+            Pattern::MatchClass { cls, patterns, kwd_attrs, kwd_patterns } => {
+                // A class pattern on a value of a union's type, like `case int():`, is the
+                // enum's variant for the class.
+                if let Some(variant) = subject_type(location, &ctx, &options, &symbols).and_then(|t| t.union_variant(&cls)) {
+                    let value = match (patterns.as_slice(), kwd_patterns.is_empty()) {
+                        ([], true) => quote!(_),
+                        // `int(n)` binds the value itself.
+                        ([pattern], true) => pattern.clone().to_rust(false, location, ctx, options.clone(), symbols.clone())?,
+                        _ => return Err(unsupported("class pattern of a union member with attributes").into()),
+                    };
+                    return Ok(quote!(#variant(#value)));
+                }
+                let class = match &cls {
+                    ExprType::Name(name) if matches!(symbols.lookup(&name.id), Some(SymbolTableNode::ClassDef { .. })) => {
+                        convert_ident(&name.id, IdentCase::Type, &options, &symbols)
+                    }
+                    _ => return Err(unsupported("class pattern of a class not defined in the module").into()),
+                };
+                if !patterns.is_empty() {
+                    return Err(unsupported("class pattern with positional arguments").into());
+                }
+                let fields: Vec<_> = kwd_attrs.iter().map(|attr| capture(attr)).collect();
+                let field_patterns = kwd_patterns
+                    .into_iter()
+                    .map(|p| p.to_rust(false, location, ctx.clone(), options.clone(), symbols.clone()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(quote!(#class::Data { #(#fields: #field_patterns,)* .. }))
+            }
+            Pattern::MatchStar(Some(name)) => {
+                let name = capture(&name);
+                Ok(quote!(#name @ ..))
+            }
+            Pattern::MatchStar(None) => Ok(quote!(..)),
+            Pattern::MatchAs { pattern: None, name: None } => Ok(quote!(_)),
+            Pattern::MatchAs { pattern: None, name: Some(name) } => {
+                let name = capture(&name);
+                Ok(quote!(#name))
+            }
+            // `case int() as n:` binds the value in the union's variant.
+            Pattern::MatchAs { pattern: Some(pattern), name: Some(name) }
+                if let Pattern::MatchClass { cls, patterns, kwd_patterns, .. } = pattern.as_ref()
+                    && patterns.is_empty()
+                    && kwd_patterns.is_empty()
+                    && let Some(variant) =
+                        subject_type(location, &ctx, &options, &symbols).and_then(|t| t.union_variant(cls)) =>
+            {
+                let name = capture(&name);
+                Ok(quote!(#variant(#name)))
+            }
+            Pattern::MatchAs { pattern: Some(pattern), name } => {
+                let pattern = pattern.to_rust(tuple, location, ctx, options.clone(), symbols.clone())?;
+                match name {
+                    Some(name) => {
+                        let name = capture(&name);
+                        Ok(quote!(#name @ (#pattern)))
+                    }
+                    None => Ok(pattern),
+                }
+            }
+            Pattern::MatchOr(patterns) => {
+                let alternatives = patterns
+                    .into_iter()
+                    .map(|p| p.to_rust(tuple, location, ctx.clone(), options.clone(), symbols.clone()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(quote!(#(#alternatives)|*))
+            }
+        }
+    }
+}
+
+/// The type of the value being matched, if it's a variable with an annotation.
+fn subject_type(
+    location: &Match,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Option<RustType> {
+    let ExprType::Name(name) = &location.subject else {
+        return None;
+    };
+    let annotation = symbols.type_annotation(&name.id)?;
+    annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone()).ok()
+}
+
+/// A value pattern is a literal, which is the same in Rust, or a dotted name, which is a
+/// path like `Color::RED` in Rust.
+fn value_pattern(
+    value: ExprType,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    match value {
+        ExprType::Attribute(attribute) => {
+            let base = value_pattern(*attribute.value, ctx, options, symbols)?;
+            let attr = sanitize_ident(&attribute.attr);
+            Ok(quote!(#base::#attr))
+        }
+        value => value.to_rust(ctx, options, symbols),
+    }
+}
+
+impl CodeGen for Match {
+    type Context = CodeGenContext;
+    type Options = PythonOptions;
+    type SymbolTable = SymbolTableScopes;
+
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = self.subject.clone().find_symbols(symbols);
+        for case in self.cases {
+            // Names captured by a pattern are bound to (part of) the subject in the case body.
+            for name in case.pattern.captures() {
+                symbols.insert(name, SymbolTableNode::Assign { position: 0, value: self.subject.clone() });
+            }
+            if let Some(guard) = case.guard {
+                symbols = guard.find_symbols(symbols);
+            }
+            symbols = case.body.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc));
+        }
+        symbols
+    }
+
+    fn to_rust(
+        self,
+        ctx: Self::Context,
+        options: Self::Options,
+        symbols: Self::SymbolTable,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let tuple = matches!(self.subject, ExprType::Tuple(_));
+        let subject = self.subject.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        // Slice patterns need a slice to match, and string literals a &str.
+        let subject = if !tuple && self.cases.iter().any(|case| case.pattern.is_sequence()) {
+            quote!((#subject).as_slice())
+        } else if self.cases.iter().any(|case| case.pattern.is_str()) {
+            quote!(&*(#subject))
+        } else {
+            subject
+        };
+
+        // Python does nothing when no case matches, but a Rust match has to be exhaustive.
+        let exhaustive = self
+            .cases
+            .iter()
+            .any(|case| case.guard.is_none() && case.pattern.is_irrefutable());
+
+        let mut arms = Vec::new();
+        for case in self.cases.clone() {
+            let pattern = case.pattern.to_rust(tuple, &self, ctx.clone(), options.clone(), symbols.clone())?;
+            let guard = match case.guard {
+                Some(guard) => {
+                    let guard = guard.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+                    quote!(if #guard)
+                }
+                None => quote!(),
+            };
+            let body = case
+                .body
+                .into_iter()
+                .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            arms.push(quote!(#pattern #guard => { #(#body;)* }));
+        }
+        if !exhaustive {
+            arms.push(quote!(_ => {}));
+        }
+
+        Ok(quote! {
+            match #subject {
+                #(#arms)*
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_parse_test;
+    use crate::test_utils::{module_to_rust, test_options};
+
+    create_parse_test!(test_match_literals, "match x:\n    case 1:\n        print('one')\n    case _:\n        print('other')", "match_test.py");
+
+    #[test]
+    fn test_literal_singleton_and_or_patterns() {
+        let code = module_to_rust(
+            "def f(x):
+    match x:
+        case 1 | 2:
+            return 'small'
+        case None:
+            return 'none'
+",
+            test_options(),
+        );
+        assert!(code.contains("match x { 1 | 2 => { return \"small\" ; } None => { return \"none\" ; } _ => { } }"), "{}", code);
+    }
+
+    #[test]
+    fn test_sequence_patterns_bind_captures() {
+        let code = module_to_rust(
+            "def f(items):
+    match items:
+        case [first, *rest] if first > 0:
+            return rest
+        case [] as empty:
+            return empty
+        case _:
+            return items
+",
+            test_options(),
+        );
+        assert!(code.contains("match (items) . as_slice () { [first , rest @ ..] if (first) > (0) => {"), "{}", code);
+        assert!(code.contains("empty @ ([]) => {"), "{}", code);
+        assert!(!code.contains("_ => { } }"), "{}", code);
+    }
+
+    #[test]
+    fn test_tuple_subject_uses_tuple_patterns() {
+        let code = module_to_rust(
+            "def f(a, b):
+    match (a, b):
+        case (0, y):
+            print(y)
+        case (x, *_):
+            print(x)
+",
+            test_options(),
+        );
+        assert!(code.contains("match (a , b) { (0 , y) => {"), "{}", code);
+        assert!(code.contains("(x , ..) => {"), "{}", code);
+    }
+
+    #[test]
+    fn test_string_and_dotted_value_patterns() {
+        let code = module_to_rust(
+            "def f(command, color):
+    match command:
+        case 'go':
+            print('going')
+    match color:
+        case Color.RED:
+            print('red')
+",
+            test_options(),
+        );
+        assert!(code.contains("match & * (command) { \"go\" => {"), "{}", code);
+        assert!(code.contains("Color :: RED => {"), "{}", code);
+    }
+
+    #[test]
+    fn test_class_pattern_of_a_module_class() {
+        let code = module_to_rust(
+            "class Point:
+    pass
+
+def f(p):
+    match p:
+        case Point(x=0, y=y):
+            print(y)
+",
+            test_options(),
+        );
+        assert!(code.contains("Point :: Data { x : 0 , y : y , .. } => {"), "{}", code);
+    }
+
+    #[test]
+    fn test_mapping_pattern_is_unsupported() {
+        let code = module_to_rust(
+            "match config:
+    case {'debug': True}:
+        print('debug')
+",
+            test_options(),
+        );
+        assert!(code.contains("todo ! (\"UNSUPPORTED: mapping pattern\")"), "{}", code);
+    }
+}
@@ -10,6 +10,9 @@ pub use assign::*;
 pub mod aug_assign;
 pub use aug_assign::*;
 
+pub mod ann_assign;
+pub use ann_assign::*;
+
 pub mod await_kw;
 pub use await_kw::*;
 
@@ -31,6 +34,9 @@ pub use compare::*;
 pub mod constant;
 pub use constant::*;
 
+pub mod bytes;
+pub use bytes::*;
+
 pub mod expression;
 pub use expression::*;
 
@@ -112,8 +118,17 @@ pub use yield_expr::*;
 pub mod raise_stmt;
 pub use raise_stmt::*;
 
+pub mod assert_stmt;
+pub use assert_stmt::*;
+
 pub mod f_string;
 pub use f_string::*;
 
 pub mod with_stmt;
 pub use with_stmt::*;
+
+pub mod match_stmt;
+pub use match_stmt::*;
+
+pub mod global_stmt;
+pub use global_stmt::*;
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, default::Default};
+use std::{collections::{HashMap, HashSet}, default::Default};
 
 use tracing::info;
 use proc_macro2::TokenStream;
@@ -6,10 +6,10 @@ use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
 use quote::{format_ident, quote};
 use serde::{Deserialize, Serialize};
 
-use crate::{CodeGen, CodeGenContext, Name, Object, PythonOptions, Statement, StatementType, ExprType, SymbolTableScopes};
+use crate::{annotation_to_rust_type, annotation_truthiness, convert_ident, truthiness, Global, Truthiness, Constant, IdentCase, is_final_annotation, is_str_annotation, Node, UNKNOWN_FILE, error_in_file, helper_definitions, module_error, refers_to, sanitize_ident, ModuleLayout, ClassDef, CodeGen, CodeGenContext, Compares, Name, Object, Ops, UnaryOp, PythonOptions, Statement, StatementType, ExprType, SymbolTableNode, SymbolTableScopes, Transformer, Transformers, Try, TryStrategy};
 
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum Type {
     Unimplemented,
 }
@@ -22,14 +22,14 @@ impl<'a> FromPyObject<'a> for Type {
 }
 
 /// Represents a module as imported from an ast. See the Module struct for the processed module.
-#[derive(Clone, Debug, Default, FromPyObject, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, FromPyObject, Serialize, Deserialize, PartialEq)]
 pub struct RawModule {
     pub body: Vec<Statement>,
     pub type_ignores: Vec<Type>,
 }
 
 /// Represents a module as imported from an ast.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Module {
     pub raw: RawModule,
     pub name: Option<Name>,
@@ -40,7 +40,7 @@ pub struct Module {
 
 impl<'a> FromPyObject<'a> for Module {
     fn extract_bound(ob: &Bound<'_, PyAny>) -> PyResult<Self> {
-        let raw_module = ob.extract().expect("Failed parsing module.");
+        let raw_module = ob.extract()?;
 
         Ok(Self {
             raw: raw_module,
@@ -56,7 +56,14 @@ impl CodeGen for Module {
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
         let mut symbols = symbols;
-        symbols.new_scope();
+        symbols.push_scope();
+        // The module's functions are registered before any of their bodies are walked, so
+        // that functions calling each other see each other, whichever is defined first.
+        for s in &self.raw.body {
+            if let StatementType::FunctionDef(function) | StatementType::AsyncFunctionDef(function) = &s.statement {
+                symbols.insert(function.name.clone(), SymbolTableNode::FunctionDef(function.clone()));
+            }
+        }
         for s in self.raw.body {
             symbols = s.clone().find_symbols(symbols);
         }
@@ -69,12 +76,60 @@ impl CodeGen for Module {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // Generate code for the module as the transformers rewrote it.
+        if !options.transformers.is_empty() {
+            let module = options.transformers.apply(self);
+            let mut options = options;
+            options.transformers = Transformers::new();
+            return module.to_rust(ctx, options, symbols);
+        }
+
+        // Register the names defined by this module so that code generation can tell
+        // user definitions apart from builtins.
+        let mut symbols = self.clone().find_symbols(symbols);
+        // The functions try statements call return a Result when they can raise, so that
+        // the try statements can catch what they raise.
+        if options.try_strategy == TryStrategy::Result {
+            for name in Try::callees(&self.raw.body, &symbols) {
+                symbols.mark_called_in_try(&name);
+            }
+        }
+        // The constants of the module are renamed to SCREAMING_SNAKE_CASE everywhere
+        // they're used.
+        for statement in &self.raw.body {
+            if let Some((name, _)) = Self::final_constant(statement, &symbols) {
+                let constant = convert_ident(&name.id, IdentCase::Constant, &options, &symbols);
+                if constant != name.id {
+                    symbols.rename(&name.id, constant);
+                }
+            }
+        }
+        // The variables functions declare `global` live in cells the functions share, since
+        // they can't see the variables of the function the module is initialized in.
+        let globals = Global::declared_in(&self.raw.body);
+        for statement in &self.raw.body {
+            if let Some((name, truthiness)) = Self::module_variable(statement, &options, &symbols)
+                && globals.contains(&name.id)
+            {
+                if Self::cell_type(truthiness, &options)?.is_some() {
+                    symbols.mark_module_variable(&name.id);
+                } else {
+                    options.diagnostics.warn(
+                        format!("the global variable {} is only shared with functions if it's an int, float or bool", name.id),
+                        Some(statement.source_location(UNKNOWN_FILE)),
+                    );
+                }
+            }
+        }
+        let filename = self.filename.clone().unwrap_or_else(|| "unknown.py".to_string());
         let mut stream = TokenStream::new();
+        let mut has_module_doc = false;
         
         // Add module-level documentation if available and not just an expression
         if let Some(docstring) = self.get_module_docstring() {
             // Only add module docs if there are multiple statements or if this seems to be a real module docstring
             if self.raw.body.len() > 1 || self.looks_like_module_docstring() {
+                has_module_doc = true;
                 let doc_lines: Vec<_> = docstring
                     .lines()
                     .map(|line| {
@@ -101,6 +156,14 @@ impl CodeGen for Module {
             stream.extend(quote!(use stdpython::*;));
         }
         
+        // `@` generates `*` on the matrix crate's types, which have to be in scope.
+        if let Some(matmul_crate) = &options.matmul_crate
+            && format!("{:?}", self.raw.body).contains("MatMult")
+        {
+            let matmul_crate = format_ident!("{}", matmul_crate);
+            stream.extend(quote!(use #matmul_crate::*;));
+        }
+
         // Add async runtime dependency if async functions are detected
         // We'll check this early so we can add the import at the top
         let needs_async_runtime = self.raw.body.iter().any(|s| {
@@ -112,61 +175,105 @@ impl CodeGen for Module {
             stream.extend(quote!(use #runtime_import;));
         }
         
+        // Imports are collected ahead of the other items, wherever they are in the module.
+        let header = std::mem::take(&mut stream);
+        let mut uses = TokenStream::new();
+        let entry_visibility = options.module_layout.entry_visibility();
+
         let mut main_body_stmts = Vec::new();
         let mut has_main_code = false;
         let mut has_async_functions = false;
         let mut module_init_stmts = Vec::new();
         let mut has_module_init_code = false;
         let mut is_simple_main_call_pattern = false;
+        let module_init = format_ident!("{}", options.module_init);
+        let module_body = self.raw.body.clone();
+        let mut declared_variables = HashSet::new();
         
-        for s in self.raw.body {
+        // The docstring became the module's doc attributes.
+        for s in self.raw.body.into_iter().skip(usize::from(has_module_doc)) {
             // Check if this statement is an async function
             if let crate::StatementType::AsyncFunctionDef(_) = &s.statement {
                 has_async_functions = true;
             }
             
             // Check for if __name__ == "__main__" blocks at the AST level before generating code
-            if let crate::StatementType::If(if_stmt) = &s.statement {
-                let test_str = format!("{:?}", if_stmt.test);
-                if test_str.contains("__name__") && test_str.contains("__main__") {
-                    // Check if this is a simple main() call pattern
-                    let is_simple_main_call = Self::is_simple_main_call_block(&if_stmt.body);
-                    
-                    if is_simple_main_call {
-                        // For simple main() calls, we'll use the user's main function directly
-                        // Set a flag to indicate we should not rename the main function
-                        has_main_code = true;
-                        is_simple_main_call_pattern = true;
-                        // Don't collect the main body statements - we'll use user's main directly
-                    } else {
-                        // This is a complex __name__ == "__main__" block - collect its body for main function
-                        for body_stmt in &if_stmt.body {
-                            let stmt_token = body_stmt
-                                .clone()
-                                .to_rust(ctx.clone(), options.clone(), symbols.clone())
-                                .expect("parsing if __name__ body statement");
-                            if !stmt_token.to_string().trim().is_empty() {
-                                main_body_stmts.push(stmt_token);
-                                has_main_code = true;
-                            }
+            if let crate::StatementType::If(if_stmt) = &s.statement
+                && Self::is_main_guard(&if_stmt.test)
+            {
+                // Check if this is a simple main() call pattern
+                let is_simple_main_call = Self::is_simple_main_call_block(&if_stmt.body);
+                
+                if is_simple_main_call {
+                    // For simple main() calls, we'll use the user's main function directly
+                    // Set a flag to indicate we should not rename the main function
+                    has_main_code = true;
+                    is_simple_main_call_pattern = true;
+                    // Don't collect the main body statements - we'll use user's main directly
+                } else {
+                    // This is a complex __name__ == "__main__" block - collect its body for main function
+                    for body_stmt in &if_stmt.body {
+                        let stmt_token = body_stmt
+                            .clone()
+                            .to_rust(ctx.clone(), options.clone(), symbols.clone())
+                            .map_err(|e| error_in_file(e, &filename))?;
+                        if !stmt_token.to_string().trim().is_empty() {
+                            main_body_stmts.push(stmt_token);
+                            has_main_code = true;
                         }
                     }
-                    // Skip generating this if statement - we've processed its contents
-                    continue;
                 }
+                // Skip generating this if statement - we've processed its contents
+                continue;
             }
             
+            // The functional forms of NamedTuple and TypedDict declare a class.
+            if let StatementType::Assign(assign) = &s.statement
+                && let Some(class) = ClassDef::from_record_call(assign)
+            {
+                stream.extend(class.to_rust(ctx.clone(), options.clone(), symbols.clone()).map_err(|e| error_in_file(e, &filename))?);
+                continue;
+            }
+
+            // The first assignment to a variable functions share declares its cell.
+            if let Some(cell) = Self::module_variable_item(&s, &mut declared_variables, ctx.clone(), options.clone(), symbols.clone())
+                .map_err(|e| error_in_file(e, &filename))?
+            {
+                stream.extend(cell);
+                continue;
+            }
+
+            // UPPER_CASE names bound once to a literal are the module's constants.
+            if let Some(constant) = Self::constant_item(&s, &module_body, ctx.clone(), options.clone(), symbols.clone())
+                .map_err(|e| error_in_file(e, &filename))?
+            {
+                stream.extend(constant);
+                continue;
+            }
+
+            // So are names annotated `Final`.
+            if let Some(constant) = Self::final_item(&s, ctx.clone(), options.clone(), symbols.clone())
+                .map_err(|e| error_in_file(e, &filename))?
+            {
+                stream.extend(constant);
+                continue;
+            }
+
             // Categorize statements into declarations vs executable code
             let is_declaration = Self::is_declaration_statement(&s.statement);
             
-            let statement = s
-                .clone()
-                .to_rust(ctx.clone(), options.clone(), symbols.clone())
-                .expect(format!("parsing statement {:?} in module", s).as_str());
+            let statement = if is_declaration {
+                s.clone().item_to_rust(ctx.clone(), options.clone(), symbols.clone())
+            } else {
+                s.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())
+            }
+            .map_err(|e| error_in_file(e, &filename))?;
             
             if statement.to_string() != "" {
-                if is_declaration {
-                    // Declarations go at module level (functions, classes, imports)
+                if matches!(s.statement, StatementType::Import(_) | StatementType::ImportFrom(_)) {
+                    uses.extend(statement);
+                } else if is_declaration {
+                    // Declarations go at module level (functions, classes)
                     stream.extend(statement);
                 } else {
                     // Executable statements go in module initialization function
@@ -179,8 +286,8 @@ impl CodeGen for Module {
         // Generate module initialization function if needed
         if has_module_init_code {
             stream.extend(quote! {
-                fn __module_init__() {
-                    #(#module_init_stmts)*
+                #entry_visibility fn #module_init() {
+                    #(#module_init_stmts;)*
                 }
             });
         }
@@ -203,7 +310,7 @@ impl CodeGen for Module {
                     
                     // Replace the user's function signature and add attributes
                     let new_stream_str = stream_str
-                        .replace("pub async fn main (", &format!("#[{}] async fn main(", runtime_attr));
+                        .replace("pub async fn main (", &format!("#[{}] {} async fn main(", runtime_attr, entry_visibility));
                     stream = new_stream_str.parse::<proc_macro2::TokenStream>()
                         .unwrap_or_else(|_| stream);
                         
@@ -217,8 +324,8 @@ impl CodeGen for Module {
                         
                         stream.extend(quote! {
                             #[#attr_tokens]
-                            async fn main() {
-                                __module_init__();
+                            #entry_visibility async fn main() {
+                                #module_init();
                                 python_main();
                             }
                         });
@@ -226,9 +333,12 @@ impl CodeGen for Module {
                 } else {
                     // User's sync main becomes the Rust entry point
                     // Need to modify the function to match Rust main signature requirements
-                    let new_stream_str = Self::convert_python_main_to_rust_entry_point(&stream_str);
-                    stream = new_stream_str.parse::<proc_macro2::TokenStream>()
-                        .unwrap_or_else(|_| stream);
+                    // It's already pub for a mod to call.
+                    if options.module_layout == ModuleLayout::CrateRoot {
+                        let new_stream_str = Self::convert_python_main_to_rust_entry_point(&stream_str);
+                        stream = new_stream_str.parse::<proc_macro2::TokenStream>()
+                            .unwrap_or_else(|_| stream);
+                    }
                     
                     // If we have module init code, we need to modify the user's main to call it first
                     if has_module_init_code {
@@ -238,8 +348,8 @@ impl CodeGen for Module {
                             .unwrap_or_else(|_| stream);
                         
                         stream.extend(quote! {
-                            fn main() {
-                                __module_init__();
+                            #entry_visibility fn main() {
+                                #module_init();
                                 python_main();
                             }
                         });
@@ -278,31 +388,31 @@ impl CodeGen for Module {
                     if has_module_init_code {
                         stream.extend(quote! {
                             #[#attr_tokens]
-                            async fn main() {
-                                __module_init__();
-                                #(#main_body_stmts)*
+                            #entry_visibility async fn main() {
+                                #module_init();
+                                #(#main_body_stmts;)*
                             }
                         });
                     } else {
                         stream.extend(quote! {
                             #[#attr_tokens]
-                            async fn main() {
-                                #(#main_body_stmts)*
+                            #entry_visibility async fn main() {
+                                #(#main_body_stmts;)*
                             }
                         });
                     }
                 } else {
                     if has_module_init_code {
                         stream.extend(quote! {
-                            fn main() {
-                                __module_init__();
-                                #(#main_body_stmts)*
+                            #entry_visibility fn main() {
+                                #module_init();
+                                #(#main_body_stmts;)*
                             }
                         });
                     } else {
                         stream.extend(quote! {
-                            fn main() {
-                                #(#main_body_stmts)*
+                            #entry_visibility fn main() {
+                                #(#main_body_stmts;)*
                             }
                         });
                     }
@@ -312,16 +422,49 @@ impl CodeGen for Module {
             // No main block, but we have module initialization code
             // Generate a main function that just runs module initialization
             stream.extend(quote! {
-                fn main() {
-                    __module_init__();
+                #entry_visibility fn main() {
+                    #module_init();
                 }
             });
         }
-        Ok(stream)
+
+        // The enums of the unions in annotations come before the items using them, and
+        // the items the generated code needs are imported with the module's own imports.
+        let union_enums = options.union_enums.take();
+        let required_imports = options.required_imports.take();
+        let mut stream = quote!(#header #uses #required_imports #union_enums #stream);
+
+        // Functions that raise return the module's error type, which needs a variant for
+        // each exception the module raises or catches.
+        if refers_to(&stream, "ModuleError") {
+            stream.extend(module_error(&symbols.exceptions()));
+        }
+
+        options.diagnostics.in_file(&filename);
+
+        let helpers = helper_definitions(&stream, &options);
+        stream.extend(helpers);
+
+        match options.module_layout {
+            ModuleLayout::CrateRoot | ModuleLayout::File => Ok(stream),
+            ModuleLayout::Mod => {
+                let name = match &ctx {
+                    CodeGenContext::Module(name) if !name.is_empty() => name.clone(),
+                    _ => filename.trim_end_matches(".py").rsplit('/').next().unwrap_or_default().to_string(),
+                };
+                let name = sanitize_ident(&name);
+                Ok(quote!(pub mod #name { #stream }))
+            }
+        }
     }
 }
 
 impl Module {
+    /// Rewrite the module with a transformer, before generating code for it.
+    pub fn transform(self, transformer: &mut impl Transformer) -> Module {
+        transformer.transform_module(self)
+    }
+
     /// Check if the __name__ == "__main__" block contains only a simple call to main()
     /// This includes patterns like:
     /// - main()
@@ -353,6 +496,211 @@ impl Module {
         }
     }
     
+    /// Check if an expression is the `__name__ == "__main__"` test, either way around.
+    fn is_main_guard(test: &ExprType) -> bool {
+        let is_name = |expr: &ExprType| matches!(expr, ExprType::Name(name) if name.id == "__name__");
+        let is_main = |expr: &ExprType| {
+            matches!(expr, ExprType::Constant(c) if c.string_value().as_deref() == Some("__main__"))
+        };
+        match test {
+            ExprType::Compare(compare) if compare.ops.len() == 1 && compare.comparators.len() == 1 => {
+                let (left, right) = (compare.left.as_ref(), &compare.comparators[0]);
+                matches!(compare.ops[0], Compares::Eq)
+                    && ((is_name(left) && is_main(right)) || (is_main(left) && is_name(right)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Generate a `pub const` for an assignment of a literal to an UPPER_CASE name, unless
+    /// the module assigns to the name anywhere else, since a constant can't change.
+    fn constant_item(
+        stmt: &Statement,
+        body: &[Statement],
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+        let StatementType::Assign(assign) = &stmt.statement else {
+            return Ok(None);
+        };
+        let [ExprType::Name(name)] = assign.targets.as_slice() else {
+            return Ok(None);
+        };
+        let is_upper_case = name.id.chars().any(|c| c.is_ascii_uppercase())
+            && !name.id.chars().any(|c| c.is_lowercase());
+        let ty = match &assign.value {
+            ExprType::Constant(c) => c.rust_type(&options),
+            ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand }) => match operand.as_ref() {
+                ExprType::Constant(c) if c.int_value().is_some() || c.is_float() => c.rust_type(&options),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(ty) = ty else {
+            return Ok(None);
+        };
+        let assignments = body
+            .iter()
+            .filter(|s| match &s.statement {
+                StatementType::Assign(a) => a.targets.iter().any(|t| Self::binds(t, &name.id)),
+                StatementType::AugAssign(a) => Self::binds(&a.target, &name.id),
+                StatementType::AnnAssign(a) => Self::binds(&a.target, &name.id),
+                _ => false,
+            })
+            .count();
+        if !is_upper_case || assignments != 1 || symbols.is_mutated(&name.id) {
+            return Ok(None);
+        }
+
+        let target = ExprType::Name(name.clone()).to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let value = assign.value.clone().to_rust(ctx, options, symbols)?;
+        Ok(Some(quote!(pub const #target: #ty = #value;)))
+    }
+
+    /// The variable a statement of the module assigns, if it assigns just the one, and what
+    /// it's known to be, from its annotation or its value.
+    fn module_variable<'a>(
+        stmt: &'a Statement,
+        options: &PythonOptions,
+        symbols: &SymbolTableScopes,
+    ) -> Option<(&'a Name, Truthiness)> {
+        match &stmt.statement {
+            StatementType::Assign(assign) => match assign.targets.as_slice() {
+                [ExprType::Name(name)] => Some((name, truthiness(&assign.value, options, symbols))),
+                _ => None,
+            },
+            StatementType::AnnAssign(assign) => match (&assign.target, &assign.value) {
+                (ExprType::Name(name), Some(_)) => Some((name, annotation_truthiness(&assign.annotation, options, symbols))),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The type of the cell a variable of the module that functions share is in, which
+    /// can only be a `Cell` of a `Copy` type, so it's None for anything but an int, a
+    /// float or a bool.
+    fn cell_type(truthiness: Truthiness, options: &PythonOptions) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+        Ok(match truthiness {
+            Truthiness::Int => Some(options.int_type_tokens()?),
+            Truthiness::Float => Some(options.float_type_tokens()?),
+            Truthiness::Bool => Some(quote!(bool)),
+            _ => None,
+        })
+    }
+
+    /// Generate the thread-local cell of a variable of the module that functions declare
+    /// `global`, for the first assignment to it, like
+    /// `thread_local! { static counter: Cell<i64> = Cell::new(0); }`. The functions and
+    /// the module's init function get and set the cell.
+    fn module_variable_item(
+        stmt: &Statement,
+        declared: &mut HashSet<String>,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+        let Some((name, truthiness)) = Self::module_variable(stmt, &options, &symbols) else {
+            return Ok(None);
+        };
+        if !symbols.is_module_variable(&name.id) || !declared.insert(name.id.clone()) {
+            return Ok(None);
+        }
+        let Some(cell_type) = Self::cell_type(truthiness, &options)? else {
+            return Ok(None);
+        };
+        let value = match &stmt.statement {
+            StatementType::Assign(assign) => assign.value.clone(),
+            StatementType::AnnAssign(assign) => assign.value.clone().unwrap_or_default(),
+            _ => return Ok(None),
+        };
+        let cell = name.ident_to_rust(&options, &symbols);
+        let value = value.to_rust(ctx, options, symbols)?;
+        Ok(Some(quote! {
+            thread_local! {
+                #[allow(non_upper_case_globals)]
+                static #cell: std::cell::Cell<#cell_type> = std::cell::Cell::new(#value);
+            }
+        }))
+    }
+
+    /// Generate a `pub const` for a name annotated `Final`, like `MAX: Final[int] = 10`,
+    /// which has to be a literal to be one. Other values are reported, and assigned when
+    /// the module is initialized like other variables.
+    fn final_item(
+        stmt: &Statement,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+        let StatementType::AnnAssign(assign) = &stmt.statement else {
+            return Ok(None);
+        };
+        let (ExprType::Name(_), Some(value)) = (&assign.target, &assign.value) else {
+            return Ok(None);
+        };
+        if !is_final_annotation(&assign.annotation, &symbols) {
+            return Ok(None);
+        }
+        let Some((_, literal)) = Self::final_constant(stmt, &symbols) else {
+            options.diagnostics.warn(
+                "a Final variable of the module is only a const if its value is a literal",
+                Some(stmt.source_location(UNKNOWN_FILE)),
+            );
+            return Ok(None);
+        };
+
+        // A string literal is a `&'static str`, whatever type `str` annotations have.
+        let ty = match assign.annotation.as_ref() {
+            ExprType::Subscript(subscript) if !is_str_annotation(&subscript.slice, &symbols) => {
+                let ty = annotation_to_rust_type(&subscript.slice, ctx.clone(), options.clone(), symbols.clone())?;
+                Some(quote!(#ty))
+            }
+            _ => literal.rust_type(&options),
+        };
+        let Some(ty) = ty else {
+            return Ok(None);
+        };
+        let target = assign.target.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let value = value.clone().to_rust(ctx, options, symbols)?;
+        Ok(Some(quote!(pub const #target: #ty = #value;)))
+    }
+
+    /// The name and the literal value of a variable annotated `Final`, which becomes a
+    /// const of the module.
+    fn final_constant<'a>(stmt: &'a Statement, symbols: &SymbolTableScopes) -> Option<(&'a Name, &'a Constant)> {
+        let StatementType::AnnAssign(assign) = &stmt.statement else {
+            return None;
+        };
+        let (ExprType::Name(name), Some(value)) = (&assign.target, &assign.value) else {
+            return None;
+        };
+        if !is_final_annotation(&assign.annotation, symbols) {
+            return None;
+        }
+        let literal = match value {
+            ExprType::Constant(c) if c.0.is_some() => c,
+            ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand }) => match operand.as_ref() {
+                ExprType::Constant(c) if c.int_value().is_some() || c.is_float() => c,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some((name, literal))
+    }
+
+    /// Check if an assignment target binds `id`, directly or by unpacking.
+    fn binds(target: &ExprType, id: &str) -> bool {
+        match target {
+            ExprType::Name(name) => name.id == id,
+            ExprType::Tuple(tuple) => tuple.elts.iter().any(|t| Self::binds(t, id)),
+            ExprType::List(elts) => elts.iter().any(|t| Self::binds(t, id)),
+            ExprType::Starred(starred) => Self::binds(&starred.value, id),
+            _ => false,
+        }
+    }
+
     /// Check if an expression is a call to a function named "main"
     fn is_main_function_call(expr: &crate::ExprType) -> bool {
         match expr {
@@ -372,15 +720,18 @@ impl Module {
         match stmt_type {
             // These are declarations that can stay at module level
             FunctionDef(_) | AsyncFunctionDef(_) | ClassDef(_) | Import(_) | ImportFrom(_) => true,
+
+            // Scope declarations emit no code of their own
+            Global(_) | Nonlocal(_) => true,
             
             // Standalone expressions can stay at module level (e.g., constants, simple values)
             // These are typically used in tests or simple modules
             Expr(expr) => Self::is_simple_expression(&expr.value),
             
             // These are executable statements that must go in the init function
-            Assign(_) | AugAssign(_) | Call(_) | Return(_) |
-            If(_) | For(_) | While(_) | Try(_) | With(_) | AsyncWith(_) | AsyncFor(_) |
-            Raise(_) | Pass | Break | Continue => false,
+            Assign(_) | AugAssign(_) | AnnAssign(_) | Call(_) | Return(_) |
+            If(_) | For(_) | While(_) | Try(_) | With(_) | AsyncWith(_) | AsyncFor(_) | Match(_) |
+            Raise(_) | Assert(_) | Pass | Break | Continue => false,
             
             // Handle unimplemented statements conservatively as executable
             Unimplemented(_) => false,
@@ -392,7 +743,7 @@ impl Module {
         use crate::ExprType::*;
         match expr {
             // Simple constants and literals can stay at module level
-            Constant(_) | Name(_) | NoneType(_) => true,
+            Constant(_) | Bytes(_) | Name(_) | NoneType(_) | Ellipsis => true,
             
             // Allow unary operations for single-expression modules (test compatibility)
             UnaryOp(_) => true,
@@ -478,28 +829,10 @@ impl Module {
     }
     
     fn get_module_docstring(&self) -> Option<String> {
-        if self.raw.body.is_empty() {
-            return None;
-        }
-        
-        // Check if the first statement is a string constant (docstring)
-        let first_stmt = &self.raw.body[0];
-        match &first_stmt.statement {
-            StatementType::Expr(expr) => match &expr.value {
-                ExprType::Constant(c) => {
-                    let raw_string = c.to_string();
-                    Some(self.format_module_docstring(&raw_string))
-                },
-                _ => None,
-            },
-            _ => None,
-        }
+        Self::get_docstring_from_body(self.body()).map(|text| self.format_module_docstring(&text))
     }
     
-    fn format_module_docstring(&self, raw: &str) -> String {
-        // Remove surrounding quotes
-        let content = raw.trim_matches('"');
-        
+    fn format_module_docstring(&self, content: &str) -> String {
         // Split into lines and clean up Python-style indentation
         let lines: Vec<&str> = content.lines().collect();
         if lines.is_empty() {
@@ -529,10 +862,9 @@ impl Module {
         // Check if the first statement looks like a module docstring
         let first_stmt = &self.raw.body[0];
         if let StatementType::Expr(expr) = &first_stmt.statement {
-            if let ExprType::Constant(c) = &expr.value {
-                let raw_string = c.to_string();
-                let content = raw_string.trim_matches('"');
-                
+            if let ExprType::Constant(c) = &expr.value
+                && let Some(content) = c.string_value()
+            {
                 // Heuristics to detect if this is a module docstring vs just a string expression:
                 // 1. Contains multiple lines
                 // 2. Contains common docstring keywords
@@ -548,6 +880,10 @@ impl Module {
 }
 
 impl Object for Module {
+    fn body(&self) -> &[Statement] {
+        &self.raw.body
+    }
+
     /// __dir__ is called to list the attributes of the object.
     fn __dir__(&self) -> Vec<impl AsRef<str>> {
         // XXX - Make this meaningful.
@@ -605,6 +941,7 @@ impl Object for Module {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::module_to_rust;
 
     #[test]
     fn can_we_print() {
@@ -628,6 +965,55 @@ def foo():
         info!("module: {:?}", code);
     }
 
+    #[test]
+    fn test_mutually_recursive_functions_see_each_other() {
+        let module = crate::parse(
+            "def is_even(n: int) -> bool:
+    if n == 0:
+        return True
+    return is_odd(n=n - 1)
+
+def is_odd(n: int) -> bool:
+    if n == 0:
+        return False
+    return is_even(n - 1)
+
+def factorial(n: int) -> int:
+    return 1 if n <= 1 else n * factorial(n - 1)
+",
+            "test.py",
+        )
+        .unwrap();
+        let functions: Vec<_> = module
+            .raw
+            .body
+            .iter()
+            .filter_map(|s| match &s.statement {
+                StatementType::FunctionDef(f) => Some(f.clone()),
+                _ => None,
+            })
+            .collect();
+        let symbols = module.clone().find_symbols(SymbolTableScopes::new());
+        for function in &functions {
+            let mut scope = function.scope_symbols(symbols.clone());
+            for name in ["is_even", "is_odd", "factorial"] {
+                assert!(matches!(scope.lookup(name), Some(SymbolTableNode::FunctionDef(f)) if f.name == name), "{} in {}", name, function.name);
+            }
+            scope.pop_scope();
+        }
+
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let code = module
+            .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+            .unwrap()
+            .to_string();
+        // The keyword argument is matched to the parameters of a function defined later.
+        assert!(code.contains("is_odd ((n) - (1))"), "{}", code);
+        assert!(code.contains("is_even ((n) - (1))"), "{}", code);
+        assert!(code.contains("(n) * (factorial ((n) - (1)))"), "{}", code);
+    }
+
     #[test]
     fn can_we_import() {
         let result = crate::parse("import ast", "ast.py").unwrap();
@@ -655,4 +1041,201 @@ def foo():
         );
         info!("module: {:?}", code);
     }
+
+    #[test]
+    fn test_script_becomes_a_program() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let code = module_to_rust(
+            "MAX_COUNT = 3
+GREETING = \"hello\"
+OFFSET = -2
+
+def greet():
+    for i in range(MAX_COUNT):
+        print(GREETING, i + OFFSET)
+
+print(\"init\")
+
+if __name__ == \"__main__\":
+    greet()
+    print(\"done\")
+",
+            options,
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub const MAX_COUNT : i64 = 3 ;"), "{}", code);
+        assert!(code.contains("pub const GREETING : & 'static str = \"hello\" ;"), "{}", code);
+        assert!(code.contains("pub const OFFSET : i64 = - 2 ;"), "{}", code);
+        assert!(code.contains("fn __module_init__ () { println ! (\"init\") ; }"), "{}", code);
+        assert!(code.contains("fn main () { __module_init__ () ; greet () ; println ! (\"done\") ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_reassigned_upper_case_name_is_not_a_constant() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.module_init = "init_module".to_string();
+        let code = module_to_rust("TOTAL = 0\nTOTAL += 1\n", options);
+        assert!(!code.contains("const"), "{}", code);
+        assert!(code.contains("fn init_module ()"), "{}", code);
+        assert!(code.contains("fn main () { init_module () ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_imports_come_before_other_items() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.map_import("helpers", crate::ImportMapping::Path("crate::helpers".to_string()));
+        let code = module_to_rust(
+            "\"\"\"Uses helpers.\"\"\"
+def run():
+    return 1
+
+import helpers
+",
+            options,
+        );
+        assert!(
+            code.starts_with("# ! [doc = \"Uses helpers.\"] # ! [doc = \"Generated from Python file: test.py\"] use crate :: helpers as helpers ; pub fn run ()"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_mod_layout() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.module_layout = crate::ModuleLayout::Mod;
+        let code = module_to_rust(
+            "LIMIT = 2
+
+def greet():
+    print(LIMIT)
+
+print(\"init\")
+
+if __name__ == \"__main__\":
+    greet()
+",
+            options,
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.starts_with("pub mod test {"), "{}", code);
+        assert!(code.contains("pub fn __module_init__ ()"), "{}", code);
+        assert!(code.contains("pub fn main () { __module_init__ () ; greet () ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_union_enums_are_defined_once_before_their_use() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let code = module_to_rust(
+            "def show(value: Union[int, str]) -> str:
+    return \"value\"
+
+def parse(text: str) -> int | str:
+    return text
+",
+            options,
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.starts_with("# [derive (Clone)] pub enum IntOrStr { Int (i64) , Str (String) }"), "{}", code);
+        assert_eq!(code.matches("pub enum").count(), 1, "{}", code);
+        assert!(code.contains("pub fn show (value : IntOrStr) -> String"), "{}", code);
+        assert!(code.contains("pub fn parse (text : String) -> IntOrStr"), "{}", code);
+    }
+
+    #[test]
+    fn test_hash_map_annotations_are_imported_once() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let code = module_to_rust(
+            "import os
+from typing import Dict
+
+def count(words: list[str]) -> dict[str, int]:
+    ...
+
+def invert(counts: Dict[str, int]) -> Dict[int, str]:
+    ...
+",
+            options,
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert_eq!(code.matches("use std :: collections :: HashMap ;").count(), 1, "{}", code);
+        assert!(!code.contains("HashSet"), "{}", code);
+        assert!(code.contains("pub fn count (words : Vec < String >) -> HashMap < String , i64 >"), "{}", code);
+        assert!(code.contains("pub fn invert (counts : HashMap < String , i64 >) -> HashMap < i64 , String >"), "{}", code);
+
+        // The imports come before the other items.
+        let import = code.find("use std :: collections :: HashMap").unwrap();
+        assert!(code.find("pub fn").is_some_and(|function| import < function), "{}", code);
+    }
+
+    #[test]
+    fn test_final_names_are_constants() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let code = module_to_rust(
+            "from typing import Final
+
+MAX_SIZE: Final[int] = 42
+GREETING: Final[str] = 'hello'
+ratio: Final = -0.5
+started: Final = now()
+",
+            options.clone(),
+        );
+        assert!(code.contains("pub const MAX_SIZE : i64 = 42 ;"), "{}", code);
+        assert!(code.contains("pub const GREETING : & 'static str = \"hello\" ;"), "{}", code);
+        assert!(code.contains("pub const ratio : f64 = - 0.5 ;"), "{}", code);
+        // A value that isn't a literal can't be a const.
+        assert!(code.contains("let started = now ()"), "{}", code);
+        let diagnostics = options.diagnostics.to_vec();
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert!(diagnostics[0].message.contains("Final"), "{}", diagnostics[0]);
+    }
+
+    #[test]
+    fn test_case_conversion_renames_references() {
+        let source = "from typing import Final
+
+maxRetries: Final = 3
+
+class point:
+    pass
+
+def makePoint() -> point:
+    return point()
+
+def retryCount(p: point) -> int:
+    makePoint()
+    return maxRetries
+";
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let unchanged = module_to_rust(source, options.clone());
+
+        options.rust_case_identifiers = true;
+        let code = module_to_rust(source, options.clone());
+        assert!(code.contains("pub const MAX_RETRIES : i64 = 3 ;"), "{}", code);
+        assert!(code.contains("pub mod Point"), "{}", code);
+        assert!(code.contains("pub fn make_point () -> Point :: Data"), "{}", code);
+        assert!(code.contains("pub fn retry_count (p : Point :: Data) -> i64"), "{}", code);
+        assert!(code.contains("make_point ()"), "{}", code);
+        assert!(code.contains("MAX_RETRIES }"), "{}", code);
+        assert!(!code.contains("makePoint") && !code.contains("maxRetries"), "{}", code);
+
+        // Each kind of name can keep its Python casing.
+        options.rust_case_values = false;
+        options.rust_case_constants = false;
+        let code = module_to_rust(source, options.clone());
+        assert!(code.contains("pub fn makePoint () -> Point"), "{}", code);
+        assert!(code.contains("pub const maxRetries : i64 = 3 ;"), "{}", code);
+
+        options.rust_case_identifiers = false;
+        assert_eq!(module_to_rust(source, options), unchanged);
+    }
 }
@@ -1,8 +1,10 @@
 use proc_macro2::TokenStream;
 use pyo3::{FromPyObject, PyErr};
-use quote::{format_ident, quote};
+use quote::quote;
 
-use crate::{CodeGen, CodeGenContext, IsIdentifier, PythonOptions, SymbolTableScopes};
+use crate::{
+    convert_ident, sanitize_ident, CodeGen, CodeGenContext, IdentCase, IsIdentifier, PythonOptions, SymbolTableScopes,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -81,17 +83,34 @@ impl CodeGen for Name {
     fn to_rust(
         self,
         _ctx: Self::Context,
-        _options: Self::Options,
-        _symbols: Self::SymbolTable,
+        options: Self::Options,
+        symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let name = self.ident_to_rust(&options, &symbols);
+        // A variable of the module that functions share is read from its cell.
+        if symbols.is_module_variable(&self.id) {
+            return Ok(quote!(#name.get()));
+        }
+        Ok(name)
+    }
+}
+
+impl Name {
+    /// The name as it's spelled in Rust, which for a variable of the module that functions
+    /// declare `global` is the cell holding it.
+    pub fn ident_to_rust(&self, options: &PythonOptions, symbols: &SymbolTableScopes) -> TokenStream {
         // Handle dotted names (like "os.path") by converting them to Rust module paths
         if self.id.contains('.') {
             let parts: Vec<&str> = self.id.split('.').collect();
-            let idents: Vec<_> = parts.iter().map(|part| format_ident!("{}", part)).collect();
-            Ok(quote!(#(#idents)::*))
+            let idents: Vec<_> = parts.iter().map(|part| sanitize_ident(part)).collect();
+            quote!(#(#idents)::*)
+        } else if let Some(case) = symbols.lookup(&self.id).and_then(IdentCase::of) {
+            // Names bound in this module are renamed like their declarations are.
+            let name = convert_ident(&self.id, case, options, symbols);
+            quote!(#name)
         } else {
-            let name = format_ident!("{}", self.id);
-            Ok(quote!(#name))
+            let name = symbols.renamed(&self.id).cloned().unwrap_or_else(|| sanitize_ident(&self.id));
+            quote!(#name)
         }
     }
 }
@@ -3,47 +3,137 @@ use pyo3::{Bound, PyAny, PyResult, FromPyObject, prelude::PyAnyMethods};
 use quote::quote;
 use serde::{Deserialize, Serialize};
 
-use crate::{CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes};
+use crate::{CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableNode, SymbolTableScopes};
 
-/// A keyword argument, gnerally used in function calls.
+/// An assignment expression, `(target := value)`, which binds the target and yields the value.
+///
+/// Rust has no assignment expression, and a `let` in a block expression binds the name only
+/// inside the block. So the conditions of `if` and `while` statements have their assignment
+/// expressions taken out with [`hoist`], and bound just before the condition is tested, where
+/// the body can see them. Anywhere else, the assignment expression becomes
+/// `{ let target = value; target }`.
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct NamedExpr {
-    left: Box<ExprType>,
-    right: Box<ExprType>,
+    pub target: Box<ExprType>,
+    pub value: Box<ExprType>,
 }
 
 impl<'a> FromPyObject<'a> for NamedExpr {
     fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
-        let left = ob.getattr("left")?.extract::<ExprType>()?;
-        let right = ob.getattr("right")?.extract::<ExprType>()?;
+        let target = ob.getattr("target")?.extract::<ExprType>()?;
+        let value = ob.getattr("value")?.extract::<ExprType>()?;
         Ok(NamedExpr {
-            left: Box::new(left),
-            right: Box::new(right),
+            target: Box::new(target),
+            value: Box::new(value),
         })
     }
 }
 
+impl NamedExpr {
+    /// The statement binding the target to the value.
+    pub fn binding(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let mutated = matches!(self.target.as_ref(), ExprType::Name(name) if symbols.is_mutated(&name.id));
+        let target = self.target.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let value = self.value.to_rust(ctx, options, symbols)?;
+        if mutated {
+            Ok(quote!(let mut #target = #value;))
+        } else {
+            Ok(quote!(let #target = #value;))
+        }
+    }
+}
+
+/// Take the assignment expressions out of `expr`, replacing each with its target. They're
+/// returned in the order they're evaluated. Lambdas and comprehensions are left alone, since
+/// their bodies aren't evaluated where they're defined.
+pub fn hoist(expr: ExprType) -> (ExprType, Vec<NamedExpr>) {
+    let mut hoisted = Vec::new();
+    let expr = hoist_into(expr, &mut hoisted);
+    (expr, hoisted)
+}
+
+fn hoist_into(expr: ExprType, hoisted: &mut Vec<NamedExpr>) -> ExprType {
+    match expr {
+        ExprType::NamedExpr(mut named) => {
+            named.value = hoist_boxed(named.value, hoisted);
+            let target = (*named.target).clone();
+            hoisted.push(named);
+            target
+        }
+        ExprType::BinOp(mut b) => {
+            b.left = hoist_boxed(b.left, hoisted);
+            b.right = hoist_boxed(b.right, hoisted);
+            ExprType::BinOp(b)
+        }
+        ExprType::BoolOp(mut b) => {
+            b.left = hoist_boxed(b.left, hoisted);
+            b.right = hoist_boxed(b.right, hoisted);
+            ExprType::BoolOp(b)
+        }
+        ExprType::Compare(mut c) => {
+            c.left = hoist_boxed(c.left, hoisted);
+            c.comparators = c.comparators.into_iter().map(|e| hoist_into(e, hoisted)).collect();
+            ExprType::Compare(c)
+        }
+        ExprType::UnaryOp(mut u) => {
+            u.operand = hoist_boxed(u.operand, hoisted);
+            ExprType::UnaryOp(u)
+        }
+        ExprType::Call(mut c) => {
+            c.func = hoist_boxed(c.func, hoisted);
+            c.args = c.args.into_iter().map(|e| hoist_into(e, hoisted)).collect();
+            ExprType::Call(c)
+        }
+        ExprType::Attribute(mut a) => {
+            a.value = hoist_boxed(a.value, hoisted);
+            ExprType::Attribute(a)
+        }
+        ExprType::Subscript(mut s) => {
+            s.value = hoist_boxed(s.value, hoisted);
+            s.slice = hoist_boxed(s.slice, hoisted);
+            ExprType::Subscript(s)
+        }
+        ExprType::Tuple(mut t) => {
+            t.elts = t.elts.into_iter().map(|e| hoist_into(e, hoisted)).collect();
+            ExprType::Tuple(t)
+        }
+        expr => expr,
+    }
+}
+
+fn hoist_boxed(expr: Box<ExprType>, hoisted: &mut Vec<NamedExpr>) -> Box<ExprType> {
+    Box::new(hoist_into(*expr, hoisted))
+}
+
 impl CodeGen for NamedExpr {
     type Context = CodeGenContext;
     type Options = PythonOptions;
     type SymbolTable = SymbolTableScopes;
 
+    fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = self.value.clone().find_symbols(symbols);
+        if let ExprType::Name(name) = *self.target
+            && !symbols.is_outer_binding(&name.id)
+        {
+            symbols.insert(name.id, SymbolTableNode::Assign { position: 0, value: *self.value });
+        }
+        symbols
+    }
+
     fn to_rust(
         self,
         ctx: Self::Context,
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let left = self
-            .left
-            .clone()
-            .to_rust(ctx.clone(), options.clone(), symbols.clone())
-            .expect(format!("parsing left side of named expression {:?}", self.left).as_str());
-        let right =
-            self.right.clone().to_rust(ctx, options, symbols).expect(
-                format!("parsing right side of named expression {:?}", self.right).as_str(),
-            );
-        Ok(quote!(#left = #right))
+        let target = self.target.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        let binding = self.binding(ctx, options, symbols)?;
+        Ok(quote!({ #binding #target }))
     }
 }
 
@@ -56,10 +146,10 @@ mod test {
     #[test]
     fn test_named_expression() {
         let named_expression = NamedExpr {
-            left: Box::new(ExprType::Name(Name {
+            target: Box::new(ExprType::Name(Name {
                 id: "a".to_string(),
             })),
-            right: Box::new(ExprType::Constant(Constant(Some(Literal::Integer(
+            value: Box::new(ExprType::Constant(Constant(Some(Literal::Integer(
                 IntegerLit::parse("1".to_string()).unwrap(),
             ))))),
         };
@@ -70,6 +160,6 @@ mod test {
                 SymbolTableScopes::new(),
             )
             .unwrap();
-        assert_eq!(rust.to_string(), "a = 1");
+        assert_eq!(rust.to_string(), "{ let a = 1 ; a }");
     }
 }
@@ -1,6 +1,6 @@
 use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
-use quote::quote;
+use quote::{format_ident, quote};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -69,7 +69,9 @@ impl CodeGen for Raise {
     type SymbolTable = SymbolTableScopes;
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
+        let mut symbols = symbols;
         let symbols = if let Some(exc) = self.exc {
+            symbols.record_exception(&raised_kind(&exc));
             exc.find_symbols(symbols)
         } else {
             symbols
@@ -88,31 +90,269 @@ impl CodeGen for Raise {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        if let Some(exc) = self.exc {
-            let exc_tokens = exc.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
-            
-            if let Some(cause) = self.cause {
-                let cause_tokens = cause.to_rust(ctx, options, symbols)?;
-                // For now, generate a simple panic since Rust doesn't have the same exception model
-                Ok(quote! {
-                    panic!("Exception: {:?} caused by {:?}", #exc_tokens, #cause_tokens)
-                })
+        let handled = options.state.handled_exception.clone().unwrap_or_else(|| "e".to_string());
+        let Some(exc) = self.exc else {
+            // Bare raise - re-raises the exception being handled
+            return if options.state.returns_result {
+                let handled = format_ident!("{}", handled);
+                Ok(quote!(return Err(#handled)))
             } else {
-                Ok(quote! {
-                    panic!("Exception: {:?}", #exc_tokens)
-                })
+                Ok(quote!(panic!("Re-raising current exception")))
+            };
+        };
+
+        // An iterator's next() returns None when it's exhausted.
+        if options.state.in_iterator_next && raised_kind(&exc) == "StopIteration" {
+            return Ok(quote!(return None));
+        }
+
+        // So does raising the exception an except clause bound.
+        if options.state.returns_result
+            && let ExprType::Name(name) = &exc
+            && options.state.handled_exception.as_deref() == Some(name.id.as_str())
+        {
+            let handled = format_ident!("{}", handled);
+            return Ok(quote!(return Err(#handled)));
+        }
+
+        let exception = exception_parts(exc, ctx.clone(), options.clone(), symbols.clone())?;
+        let cause = match self.cause {
+            // `raise ... from None` hides the cause.
+            Some(ExprType::Constant(c)) if c.0.is_none() => None,
+            Some(cause) => Some(cause.to_rust(ctx, options.clone(), symbols)?),
+            None => None,
+        };
+
+        // The error is the module error's variant for the exception's type, with the cause
+        // as its source. It converts into whatever error a try statement's closure returns.
+        if options.state.returns_result {
+            let kind = format_ident!("{}", exception.kind);
+            let message = match exception.message {
+                None => quote!(String::new()),
+                Some(Message::Literal(text)) => quote!(#text.to_string()),
+                message => format_message(message, None),
+            };
+            let source = match cause {
+                Some(cause) => quote!(Some((#cause).into())),
+                None => quote!(None),
+            };
+            return Ok(quote!(return Err(ModuleError::#kind { message: #message, source: #source }.into())));
+        }
+
+        let message = exception.message.unwrap_or(Message::Literal(exception.kind));
+        let (format_string, arguments) = format_parts(Some(message), cause);
+        Ok(quote!(panic!(#format_string #(, #arguments)*)))
+    }
+}
+
+/// An exception being raised.
+struct Exception {
+    /// The name of the exception's type
+    kind: String,
+    /// The message it's raised with, if any
+    message: Option<Message>,
+}
+
+/// The message of a raised exception.
+enum Message {
+    /// Known at compile time
+    Literal(String),
+    /// An expression to format with Display
+    Display(TokenStream),
+    /// An expression to format with Debug
+    Debug(TokenStream),
+}
+
+/// A format string and its arguments for a message and the exception that caused it.
+fn format_parts(message: Option<Message>, cause: Option<TokenStream>) -> (String, Vec<TokenStream>) {
+    let (mut format_string, mut arguments) = match message {
+        Some(Message::Literal(text)) => (text.replace('{', "{{").replace('}', "}}"), Vec::new()),
+        Some(Message::Display(tokens)) => ("{}".to_string(), vec![tokens]),
+        Some(Message::Debug(tokens)) => ("{:?}".to_string(), vec![tokens]),
+        None => (String::new(), Vec::new()),
+    };
+    if let Some(cause) = cause {
+        if !format_string.is_empty() {
+            format_string.push(' ');
+        }
+        format_string.push_str("caused by {:?}");
+        arguments.push(cause);
+    }
+    (format_string, arguments)
+}
+
+fn format_message(message: Option<Message>, cause: Option<TokenStream>) -> TokenStream {
+    let (format_string, arguments) = format_parts(message, cause);
+    quote!(format!(#format_string #(, #arguments)*))
+}
+
+/// Whether a name raised looks like an exception type rather than a value holding an
+/// exception, going by Python's convention of CapWords for class names.
+fn is_type_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_uppercase())
+}
+
+/// The name of the type of the exception `exc` raises. Raising anything but an exception
+/// type, or one called with at most one positional argument, raises an Exception.
+fn raised_kind(exc: &ExprType) -> String {
+    let name = match exc {
+        ExprType::Name(name) => Some(name),
+        ExprType::Call(call) if call.keywords.is_empty() && call.args.len() <= 1 => match call.func.as_ref() {
+            ExprType::Name(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    };
+    match name {
+        Some(name) if is_type_name(&name.id) => name.id.clone(),
+        _ => "Exception".to_string(),
+    }
+}
+
+/// `raise ValueError("bad")` is a ValueError with the message "bad", while `raise ValueError`
+/// and `raise ValueError()` have no message. Anything else is an Exception formatted as it is.
+fn exception_parts(
+    exc: ExprType,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Exception, Box<dyn std::error::Error>> {
+    let kind = raised_kind(&exc);
+    if let ExprType::Name(name) = &exc
+        && is_type_name(&name.id)
+    {
+        return Ok(Exception { kind, message: None });
+    }
+    if let ExprType::Call(call) = &exc
+        && let ExprType::Name(name) = call.func.as_ref()
+        && is_type_name(&name.id)
+        && call.keywords.is_empty()
+    {
+        match call.args.as_slice() {
+            [] => return Ok(Exception { kind, message: None }),
+            [ExprType::Constant(c)] if c.string_value().is_some() => {
+                let message = Message::Literal(c.string_value().unwrap_or_default());
+                return Ok(Exception { kind, message: Some(message) });
             }
-        } else {
-            // Bare raise - re-raises current exception
-            Ok(quote! {
-                panic!("Re-raising current exception")
-            })
+            [arg] => {
+                let arg = arg.clone().to_rust(ctx, options, symbols)?;
+                return Ok(Exception { kind, message: Some(Message::Display(arg)) });
+            }
+            _ => {}
         }
     }
+    let message = Message::Debug(exc.to_rust(ctx, options, symbols)?);
+    Ok(Exception { kind, message: Some(message) })
 }
 
 #[cfg(test)]
 mod tests {
-    // Tests would go here - currently commented out as they need full AST infrastructure
-    // create_parse_test!(test_simple_raise, "raise ValueError('error')", "test.py");
+    use crate::{CodeGen, CodeGenContext, ErrorStrategy, PythonOptions, SymbolTableScopes};
+    use crate::test_utils::statement_to_rust;
+
+    const CHECK: &str = "def check(x):
+    if x < 0:
+        raise ValueError(\"bad\")
+    return x
+";
+
+    #[test]
+    fn test_raise_panics_by_default() {
+        let code = statement_to_rust(CHECK, PythonOptions::default());
+        assert!(code.contains("panic ! (\"bad\")"), "{}", code);
+        assert!(!code.contains("Result"), "{}", code);
+        assert!(code.ends_with("x }"), "{}", code);
+    }
+
+    #[test]
+    fn test_raise_returns_err_in_result_function() {
+        let mut options = PythonOptions::default();
+        options.error_strategy = ErrorStrategy::Result;
+        let code = statement_to_rust(CHECK, options);
+        assert!(code.contains("-> Result < () , ModuleError >"), "{}", code);
+        assert!(
+            code.contains("return Err (ModuleError :: ValueError { message : \"bad\" . to_string () , source : None } . into ())"),
+            "{}",
+            code
+        );
+        assert!(code.ends_with("Ok (x) }"), "{}", code);
+    }
+
+    const CALLERS: &str = "def check(x):
+    if x < 0:
+        raise ValueError(\"negative\")
+
+def load(x):
+    check(x)
+    try:
+        check(x)
+    except ValueError as err:
+        raise KeyError(\"missing\") from err
+
+load(1)
+";
+
+    #[test]
+    fn test_module_error_enum_and_call_sites() {
+        let module = crate::parse(CALLERS, "test.py").unwrap();
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.error_strategy = ErrorStrategy::Result;
+        let code = module
+            .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+            .unwrap()
+            .to_string();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+
+        // Calling a function that raises makes the caller propagate its error, except at the
+        // module level, where nothing can.
+        assert!(code.contains("pub fn load (x : impl Into < PyObject >) -> Result < () , ModuleError > { check (x) ? ;"), "{}", code);
+        assert!(code.contains("load (1) . unwrap ()"), "{}", code);
+        // The cause becomes the error's source.
+        assert!(code.contains("ModuleError :: KeyError { message : \"missing\" . to_string () , source : Some ((err) . into ()) }"), "{}", code);
+        // There's one variant per exception type, in order.
+        assert_eq!(code.matches("pub enum ModuleError").count(), 1, "{}", code);
+        assert!(code.contains("pub enum ModuleError { KeyError { message : String , source : Option < Box < dyn std :: error :: Error >> } , ValueError {"), "{}", code);
+    }
+
+    #[test]
+    fn test_raise_messages() {
+        let code = statement_to_rust("def f(msg):\n    raise RuntimeError(msg)", PythonOptions::default());
+        assert!(code.contains("panic ! (\"{}\" , msg)"), "{}", code);
+
+        let code = statement_to_rust("def f():\n    raise KeyError", PythonOptions::default());
+        assert!(code.contains("panic ! (\"KeyError\")"), "{}", code);
+    }
+
+    #[test]
+    fn test_bare_raise() {
+        let source = "def f():
+    try:
+        g()
+    except Exception as e:
+        raise
+";
+        // The handler re-raises into the try statement's Result, which panics when the
+        // function doesn't return one.
+        let code = statement_to_rust(source, PythonOptions::default());
+        assert!(code.contains("return Err (e)"), "{}", code);
+        assert!(code.contains("panic ! (\"{}\" , e)"), "{}", code);
+
+        let code = statement_to_rust("def f():\n    raise", PythonOptions::default());
+        assert!(code.contains("panic ! (\"Re-raising current exception\")"), "{}", code);
+
+        let mut options = PythonOptions::default();
+        options.error_strategy = ErrorStrategy::Result;
+        let code = statement_to_rust(source, options);
+        assert!(code.contains("return Err (e)"), "{}", code);
+    }
+
+    #[test]
+    fn test_functions_without_raise_are_unchanged() {
+        let mut options = PythonOptions::default();
+        options.error_strategy = ErrorStrategy::Result;
+        let code = statement_to_rust("def f(x):\n    return x", options);
+        assert!(!code.contains("Result"), "{}", code);
+        assert!(code.ends_with("x }"), "{}", code);
+    }
 }
\ No newline at end of file
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
-    Node, impl_node_with_positions, extract_list
+    Node, element_to_rust, impl_node_with_positions, extract_list
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -45,8 +45,8 @@ impl CodeGen for Set {
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
         let elements: Result<Vec<_>, _> = self.elts
-            .into_iter()
-            .map(|elt| elt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
+            .iter()
+            .map(|elt| element_to_rust(elt, &ctx, &options, &symbols))
             .collect();
         
         let elements = elements?;
@@ -1,10 +1,12 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods, types::PyTypeMethods};
 use quote::quote;
 
 use crate::{
-    dump, Assign, AugAssign, Call, ClassDef, CodeGen, CodeGenContext, Error, Expr, FunctionDef, Import,
-    ImportFrom, Node, PythonOptions, SymbolTableScopes, If, For, While, Try, AsyncWith, AsyncFor, Raise, With,
+    dump, locate_error, unsupported_item_to_rust, unsupported_node, unsupported_to_rust, variant_name, AnnAssign, Assign, AugAssign, Call,
+    ClassDef, CodeGen, CodeGenContext, CodeGenError, Expr, FunctionDef, Import, ImportFrom, Node,
+    PythonOptions, SymbolTableScopes, If, For, While, Try, AsyncWith, AsyncFor, Raise, Assert, With,
+    Match, Global, Name, Nonlocal, SourceLocation, UNKNOWN_FILE,
 };
 
 use tracing::debug;
@@ -66,17 +68,97 @@ impl CodeGen for Statement {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        Ok(self
-            .statement
-            .clone()
-            .to_rust(ctx, options, symbols)
-            .expect(
-                self.error_message(
-                    "<unknown>",
-                    format!("failed to compile statement {:#?}", self),
-                )
-                .as_str(),
-            ))
+        self.generate(ctx, options, symbols, unsupported_to_rust)
+    }
+}
+
+impl Statement {
+    /// Generate the statement as an item of a module, like a function or an import, where
+    /// a node that isn't supported becomes a `compile_error!` rather than a `todo!()`.
+    pub fn item_to_rust(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        self.generate(ctx, options, symbols, unsupported_item_to_rust)
+    }
+
+    /// Declare the variables that the statement's blocks assign first but that are used
+    /// after it, like `let e;` before an if whose branches both assign `e`. A variable
+    /// that not every path through the statement assigns starts out with its type's
+    /// default value.
+    fn hoisted_to_rust(&self, options: &PythonOptions, symbols: &SymbolTableScopes) -> TokenStream {
+        let Some(position) = self.lineno.zip(self.col_offset) else {
+            return TokenStream::new();
+        };
+        let declarations = symbols.hoisted(position).into_iter().map(|(id, definite)| {
+            let mutable = symbols.is_mutated(&id).then(|| quote!(mut));
+            let name = Name { id }.ident_to_rust(options, symbols);
+            if definite {
+                quote!(let #mutable #name;)
+            } else {
+                quote!(let #mutable #name = Default::default();)
+            }
+        });
+        quote!(#(#declarations)*)
+    }
+
+    fn generate(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+        unsupported: fn(&str, Option<SourceLocation>, &PythonOptions) -> TokenStream,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let declarations = self.hoisted_to_rust(&options, &symbols);
+        // A node that isn't supported becomes a placeholder with a warning, so the rest of
+        // the code is still generated. Other errors from nodes that don't know their own
+        // position get the statement's.
+        let tokens = match self.statement.clone().to_rust(ctx, options.clone(), symbols) {
+            Err(e) => match unsupported_node(&*e) {
+                Some((node_type, location)) => {
+                    let location = location.or_else(|| Some(self.source_location(UNKNOWN_FILE)));
+                    unsupported(&node_type, location, &options)
+                }
+                None => return Err(locate_error(e, &self)),
+            },
+            Ok(tokens) => quote!(#declarations #tokens),
+        };
+        // A statement that generates nothing has nothing to put the comments on, and
+        // neither does one whose code can't have attributes.
+        let line = match self.lineno() {
+            Some(line) if takes_attributes(&tokens) => line,
+            _ => return Ok(tokens),
+        };
+        let mut comments = match &options.source_comments {
+            Some(source) => source.leading(line).into_iter().map(|comment| format!("//{}", comment)).collect(),
+            None => Vec::new(),
+        };
+        if options.line_comments {
+            comments.push(format!(" line {}", line));
+        }
+        Ok(quote!(#(#[doc = #comments])* #tokens))
+    }
+}
+
+/// Whether the code of a statement can have the `#[doc]` attributes that carry its
+/// comments. Items, `let` statements, macro calls and statements that are blocks or
+/// control flow can. Others, like an assignment or an operator, can't, since attributes
+/// on those expressions aren't stable.
+fn takes_attributes(tokens: &TokenStream) -> bool {
+    let mut tokens = tokens.clone().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(punct)), _) => punct.as_char() == '#',
+        (Some(TokenTree::Ident(ident)), next) => {
+            matches!(
+                ident.to_string().as_str(),
+                "let" | "pub" | "fn" | "async" | "const" | "static" | "struct" | "enum" | "trait" | "impl" | "mod" | "use"
+                    | "type" | "if" | "for" | "while" | "loop" | "match" | "return" | "break" | "continue"
+            ) || matches!(next, Some(TokenTree::Punct(punct)) if punct.as_char() == '!')
+        }
+        (Some(TokenTree::Group(group)), _) => group.delimiter() == Delimiter::Brace,
+        _ => false,
     }
 }
 
@@ -85,6 +167,7 @@ pub enum StatementType {
     AsyncFunctionDef(FunctionDef),
     Assign(Assign),
     AugAssign(AugAssign),
+    AnnAssign(AnnAssign),
     Break,
     Continue,
     ClassDef(ClassDef),
@@ -102,11 +185,39 @@ pub enum StatementType {
     AsyncWith(AsyncWith),
     AsyncFor(AsyncFor),
     Raise(Raise),
+    Assert(Assert),
     With(With),
+    Match(Match),
+    Global(Global),
+    Nonlocal(Nonlocal),
 
     Unimplemented(String),
 }
 
+impl StatementType {
+    /// The blocks of statements nested directly inside this one. Function and class bodies
+    /// aren't included, since they run separately from the code around them.
+    pub fn nested_bodies(&self) -> Vec<&[Statement]> {
+        match self {
+            StatementType::If(s) => vec![&s.body, &s.orelse],
+            StatementType::For(s) => vec![&s.body, &s.orelse],
+            StatementType::While(s) => vec![&s.body, &s.orelse],
+            StatementType::AsyncFor(s) => vec![&s.body, &s.orelse],
+            StatementType::With(s) => vec![&s.body],
+            StatementType::AsyncWith(s) => vec![&s.body],
+            StatementType::Match(s) => s.cases.iter().map(|case| case.body.as_slice()).collect(),
+            StatementType::Try(s) => {
+                let mut bodies: Vec<&[Statement]> = vec![&s.body];
+                bodies.extend(s.handlers.iter().map(|handler| handler.body.as_slice()));
+                bodies.push(&s.orelse);
+                bodies.push(&s.finalbody);
+                bodies
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
 impl<'a> FromPyObject<'a> for StatementType {
     fn extract_bound(ob: &Bound<'a, PyAny>) -> PyResult<Self> {
         let err_msg = format!("getting type for statement {:?}", ob);
@@ -130,6 +241,10 @@ impl<'a> FromPyObject<'a> for StatementType {
                 let aug_assignment = AugAssign::extract_bound(ob).expect("reading augmented assignment");
                 Ok(StatementType::AugAssign(aug_assignment))
             }
+            "AnnAssign" => {
+                let ann_assignment = AnnAssign::extract_bound(ob).expect("reading annotated assignment");
+                Ok(StatementType::AnnAssign(ann_assignment))
+            }
             "Pass" => Ok(StatementType::Pass),
             "Call" => {
                 let call =
@@ -228,11 +343,31 @@ impl<'a> FromPyObject<'a> for StatementType {
                     .unwrap_or_else(|_| panic!("Raise statement {:?}", dump(ob, None)));
                 Ok(StatementType::Raise(raise_stmt))
             }
+            "Assert" => {
+                let assert_stmt = Assert::extract_bound(ob)
+                    .unwrap_or_else(|_| panic!("Assert statement {:?}", dump(ob, None)));
+                Ok(StatementType::Assert(assert_stmt))
+            }
             "With" => {
                 let with_stmt = With::extract_bound(ob)
                     .unwrap_or_else(|_| panic!("With statement {:?}", dump(ob, None)));
                 Ok(StatementType::With(with_stmt))
             }
+            "Match" => {
+                let match_stmt = Match::extract_bound(ob)
+                    .unwrap_or_else(|_| panic!("Match statement {:?}", dump(ob, None)));
+                Ok(StatementType::Match(match_stmt))
+            }
+            "Global" => {
+                let global_stmt = Global::extract_bound(ob)
+                    .unwrap_or_else(|_| panic!("Global statement {:?}", dump(ob, None)));
+                Ok(StatementType::Global(global_stmt))
+            }
+            "Nonlocal" => {
+                let nonlocal_stmt = Nonlocal::extract_bound(ob)
+                    .unwrap_or_else(|_| panic!("Nonlocal statement {:?}", dump(ob, None)));
+                Ok(StatementType::Nonlocal(nonlocal_stmt))
+            }
             _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
                 "Unimplemented statement type {}, {}",
                 ob_type,
@@ -251,6 +386,7 @@ impl CodeGen for StatementType {
         match self {
             StatementType::Assign(a) => a.find_symbols(symbols),
             StatementType::AugAssign(a) => a.find_symbols(symbols),
+            StatementType::AnnAssign(a) => a.find_symbols(symbols),
             StatementType::ClassDef(c) => c.find_symbols(symbols),
             StatementType::FunctionDef(f) => f.find_symbols(symbols),
             StatementType::Import(i) => i.find_symbols(symbols),
@@ -263,7 +399,11 @@ impl CodeGen for StatementType {
             StatementType::AsyncWith(aw) => aw.find_symbols(symbols),
             StatementType::AsyncFor(af) => af.find_symbols(symbols),
             StatementType::Raise(r) => r.find_symbols(symbols),
+            StatementType::Assert(a) => a.find_symbols(symbols),
             StatementType::With(w) => w.find_symbols(symbols),
+            StatementType::Match(m) => m.find_symbols(symbols),
+            StatementType::Global(g) => g.find_symbols(symbols),
+            StatementType::Nonlocal(n) => n.find_symbols(symbols),
             _ => symbols,
         }
     }
@@ -276,29 +416,54 @@ impl CodeGen for StatementType {
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
         match self {
             StatementType::AsyncFunctionDef(s) => {
-                let func_def = s
-                    .to_rust(Self::Context::Async(Box::new(ctx)), options, symbols)
-                    .expect("Parsing async function");
+                let func_def = s.to_rust(Self::Context::Async(Box::new(ctx)), options, symbols)?;
                 Ok(quote!(#func_def))
             }
             StatementType::Assign(a) => a.to_rust(ctx, options, symbols),
             StatementType::AugAssign(a) => a.to_rust(ctx, options, symbols),
-            StatementType::Break => Ok(quote! {break;}),
+            StatementType::AnnAssign(a) => a.to_rust(ctx, options, symbols),
+            StatementType::Break if options.state.in_loop_with_else => {
+                let label = ctx.loop_label();
+                Ok(quote!(broke = true; break #label))
+            }
+            StatementType::Break => {
+                let label = ctx.loop_label();
+                Ok(quote!(break #label))
+            }
             StatementType::Call(c) => c.to_rust(ctx, options, symbols),
             StatementType::ClassDef(c) => c.to_rust(ctx, options, symbols),
-            StatementType::Continue => Ok(quote! {continue;}),
+            StatementType::Continue => {
+                let label = ctx.loop_label();
+                Ok(quote!(continue #label))
+            }
             StatementType::Pass => Ok(quote! {}),
             StatementType::FunctionDef(s) => s.to_rust(ctx, options, symbols),
             StatementType::Import(s) => s.to_rust(ctx, options, symbols),
             StatementType::ImportFrom(s) => s.to_rust(ctx, options, symbols),
             StatementType::Expr(s) => s.to_rust(ctx, options, symbols),
+            // Generators return what they yielded so far, Python's return value would only
+            // end up in the StopIteration.
+            StatementType::Return(_) if options.state.in_generator && options.state.returns_result => {
+                Ok(quote!(return Ok(__yielded.into_iter())))
+            }
+            StatementType::Return(_) if options.state.in_generator => Ok(quote!(return __yielded.into_iter())),
+            StatementType::Return(None) if options.state.returns_from_try => Ok(quote!(return Ok(Some(())))),
+            StatementType::Return(None) if options.state.returns_result => Ok(quote!(return Ok(()))),
             StatementType::Return(None) => Ok(quote!(return)),
             StatementType::Return(Some(e)) => {
-                let exp = e
-                    .clone()
-                    .to_rust(ctx, options, symbols)
-                    .unwrap_or_else(|_| panic!("parsing expression {:#?}", e));
-                Ok(quote!(return #exp))
+                let returns_from_try = options.state.returns_from_try;
+                let returns_result = options.state.returns_result;
+                let in_iterator_next = options.state.in_iterator_next;
+                let exp = e.returned_to_rust(ctx, options, symbols)?;
+                if returns_from_try {
+                    Ok(quote!(return Ok(Some(#exp))))
+                } else if returns_result {
+                    Ok(quote!(return Ok(#exp)))
+                } else if in_iterator_next {
+                    Ok(quote!(return Some(#exp)))
+                } else {
+                    Ok(quote!(return #exp))
+                }
             }
             StatementType::If(i) => i.to_rust(ctx, options, symbols),
             StatementType::For(f) => f.to_rust(ctx, options, symbols),
@@ -307,11 +472,12 @@ impl CodeGen for StatementType {
             StatementType::AsyncWith(aw) => aw.to_rust(ctx, options, symbols),
             StatementType::AsyncFor(af) => af.to_rust(ctx, options, symbols),
             StatementType::Raise(r) => r.to_rust(ctx, options, symbols),
+            StatementType::Assert(a) => a.to_rust(ctx, options, symbols),
             StatementType::With(w) => w.to_rust(ctx, options, symbols),
-            _ => {
-                let error = Error::StatementNotYetImplemented(self);
-                Err(Box::new(error))
-            }
+            StatementType::Match(m) => m.to_rust(ctx, options, symbols),
+            StatementType::Global(g) => g.to_rust(ctx, options, symbols),
+            StatementType::Nonlocal(n) => n.to_rust(ctx, options, symbols),
+            _ => Err(CodeGenError::unsupported(variant_name(&self), None).into()),
         }
     }
 }
@@ -345,7 +511,7 @@ mod tests {
         );
 
         debug!("statement: {:?}, tokens: {:?}", statement, tokens);
-        assert_eq!(tokens.unwrap().is_empty(), false);
+        assert_eq!(tokens.unwrap().to_string(), "break");
     }
 
     #[test]
@@ -359,7 +525,7 @@ mod tests {
         );
 
         debug!("statement: {:?}, tokens: {:?}", statement, tokens);
-        assert_eq!(tokens.unwrap().is_empty(), false);
+        assert_eq!(tokens.unwrap().to_string(), "continue");
     }
 
     #[test]
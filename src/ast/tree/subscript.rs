@@ -4,7 +4,7 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
+    convert_ident, CodeGen, CodeGenContext, ExprType, IdentCase, PythonOptions, Ops, SymbolTableNode, SymbolTableScopes, UnaryOp,
     Node, impl_node_with_positions, PyAttributeExtractor
 };
 
@@ -50,6 +50,18 @@ impl CodeGen for Subscript {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // The items of a NamedTuple are its fields.
+        if let ExprType::Name(name) = self.value.as_ref()
+            && let Some(index) = constant_index(&self.slice)
+            && let Some(ExprType::Name(class)) = symbols.type_annotation(&name.id)
+            && let Some(SymbolTableNode::ClassDef { class, .. }) = symbols.lookup(&class.id)
+            && let Some(field) = class.named_tuple_field(index)
+        {
+            let value = self.value.clone().to_rust(ctx, options.clone(), symbols.clone())?;
+            let field = convert_ident(&field, IdentCase::Value, &options, &symbols);
+            return Ok(quote!(#value.#field));
+        }
+
         let value = self.value.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
         let slice = self.slice.to_rust(ctx, options, symbols)?;
         
@@ -59,6 +71,18 @@ impl CodeGen for Subscript {
     }
 }
 
+/// The value of an index that is an integer constant, like `0` or `-1`.
+fn constant_index(slice: &ExprType) -> Option<i64> {
+    match slice {
+        ExprType::Constant(index) => index.int_value(),
+        ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand }) => match operand.as_ref() {
+            ExprType::Constant(index) => index.int_value().map(|index| -index),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
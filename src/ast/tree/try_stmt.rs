@@ -1,11 +1,15 @@
+use std::collections::HashSet;
+
 use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult, prelude::PyAnyMethods};
 use quote::quote;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, Node, PythonOptions, Statement, SymbolTableScopes,
-    extract_list,
+    CodeGen, CodeGenContext, CodeGenError, ClassDef, ExprType, FunctionDef, Node, PythonOptions, SourceLocation,
+    Statement, StatementType, sanitize_ident, SymbolTableNode, SymbolTableScopes, TryStrategy, UNKNOWN_FILE, Visitor,
+    extract_list, walk_statement,
 };
 
 /// Try statement (try/except/else/finally)
@@ -131,7 +135,13 @@ impl CodeGen for Try {
         // Process body, handlers, orelse, and finalbody
         let symbols = self.body.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc));
         let symbols = self.handlers.into_iter().fold(symbols, |acc, handler| {
-            let symbols = handler.body.into_iter().fold(acc, |acc, stmt| stmt.find_symbols(acc));
+            let mut symbols = handler.body.into_iter().fold(acc, |acc, stmt| stmt.find_symbols(acc));
+            // The module's error type needs a variant for each type an except clause catches.
+            if let Ok(Some(types)) = caught_types(&handler.exception_type) {
+                for kind in types {
+                    symbols.record_exception(&kind);
+                }
+            }
             if let Some(exception_type) = handler.exception_type {
                 exception_type.find_symbols(symbols)
             } else {
@@ -148,74 +158,264 @@ impl CodeGen for Try {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        // Generate the try body
-        let try_body_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.body.into_iter()
-            .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
-            .collect();
-        let try_body_tokens = try_body_tokens?;
-
-        // Generate catch blocks for each handler
-        let catch_blocks: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.handlers.into_iter()
-            .map(|handler| {
-                let handler_body_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = handler.body.into_iter()
-                    .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
-                    .collect();
-                let handler_body_tokens = handler_body_tokens?;
-
-                // For now, generate a generic catch block
-                // In a real implementation, you'd want to match specific exception types
-                Ok::<TokenStream, Box<dyn std::error::Error>>(quote! {
-                    // Exception handler - simplified translation
-                    #(#handler_body_tokens)*
-                })
-            })
-            .collect();
-        let catch_blocks = catch_blocks?;
-
-        // Generate else clause if present
-        let else_tokens = if !self.orelse.is_empty() {
-            let else_body_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.orelse.into_iter()
-                .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
-                .collect();
-            let else_body_tokens = else_body_tokens?;
-            quote! {
-                // Else clause (executed when no exception occurs)
-                #(#else_body_tokens)*
+        match options.try_strategy {
+            TryStrategy::Result => self.result_to_rust(ctx, options, symbols),
+            TryStrategy::Panic => self.panic_to_rust(ctx, options, symbols),
+        }
+    }
+}
+
+/// The type the closures that try bodies and except clauses run in return. A try statement
+/// that returns from its clauses has them return what's returned in a Some.
+fn closure_result(returns: bool) -> TokenStream {
+    if returns {
+        quote!(Result<Option<_>, Box<dyn std::error::Error>>)
+    } else {
+        quote!(Result<(), Box<dyn std::error::Error>>)
+    }
+}
+
+fn block_to_rust(
+    block: Vec<Statement>,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let statements = block
+        .into_iter()
+        .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(quote!(#(#statements;)*))
+}
+
+/// The names of the exception types an except clause catches, or None if it catches
+/// everything, like a bare `except:` or `except Exception:` does.
+fn caught_types(exception_type: &Option<ExprType>) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+    let types = match exception_type {
+        None => return Ok(None),
+        Some(ExprType::Tuple(types)) => types.elts.iter().collect(),
+        Some(exception_type) => vec![exception_type],
+    };
+    let mut names = Vec::new();
+    for exception_type in types {
+        let name = match exception_type {
+            ExprType::Name(name) => name.id.clone(),
+            ExprType::Attribute(attribute) => attribute.attr.clone(),
+            other => {
+                return Err(CodeGenError::unsupported(format!("except clause for {:?}", other), None).into());
             }
-        } else {
-            quote!()
         };
+        if matches!(name.as_str(), "Exception" | "BaseException") {
+            return Ok(None);
+        }
+        names.push(name);
+    }
+    Ok(Some(names))
+}
+
+/// Finds the first `return` of a block, outside the functions and classes it defines.
+#[derive(Default)]
+struct FirstReturn(Option<SourceLocation>);
+
+impl FirstReturn {
+    fn of<'a>(blocks: impl IntoIterator<Item = &'a [Statement]>) -> Option<SourceLocation> {
+        let mut first = FirstReturn::default();
+        blocks.into_iter().flatten().for_each(|statement| first.visit_statement(statement));
+        first.0
+    }
+}
 
-        // Generate finally clause if present
-        let finally_tokens = if !self.finalbody.is_empty() {
-            let finally_body_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.finalbody.into_iter()
-                .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
-                .collect();
-            let finally_body_tokens = finally_body_tokens?;
-            quote! {
-                // Finally clause (always executed)
-                #(#finally_body_tokens)*
+impl Visitor for FirstReturn {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match &stmt.statement {
+            _ if self.0.is_some() => {}
+            StatementType::Return(_) => self.0 = Some(stmt.source_location(UNKNOWN_FILE)),
+            _ => walk_statement(stmt, self),
+        }
+    }
+
+    fn visit_function_def(&mut self, _node: &FunctionDef) {}
+
+    fn visit_class_def(&mut self, _node: &ClassDef) {}
+}
+
+/// Collects the names of the functions called in the clauses of try statements that run
+/// in closures: the body, the except clauses and the else clause.
+#[derive(Default)]
+struct TryCalls(Vec<String>);
+
+impl Visitor for TryCalls {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        if let StatementType::Try(t) = &stmt.statement {
+            for block in t.clauses() {
+                self.0.extend(FunctionDef::calls_in(block).into_iter().map(String::from));
+            }
+        }
+        walk_statement(stmt, self);
+    }
+}
+
+impl Try {
+    /// The functions of the module that the try statements in `body` call, and the
+    /// functions those call. Raising in them has to return an Err for the try statements
+    /// to catch it.
+    pub fn callees(body: &[Statement], symbols: &SymbolTableScopes) -> HashSet<String> {
+        let mut calls = TryCalls::default();
+        body.iter().for_each(|statement| calls.visit_statement(statement));
+        let mut callees = HashSet::new();
+        while let Some(name) = calls.0.pop() {
+            if callees.insert(name.clone())
+                && let Some(SymbolTableNode::FunctionDef(function)) = symbols.lookup(&name)
+            {
+                calls.0.extend(FunctionDef::calls_in(&function.body).into_iter().map(String::from));
+            }
+        }
+        callees
+    }
+
+    /// Whether the body and every except clause end by returning or raising.
+    fn always_returns(&self) -> bool {
+        fn block_returns(block: &[Statement]) -> bool {
+            match block.last().map(|statement| &statement.statement) {
+                Some(StatementType::Return(_) | StatementType::Raise(_)) => true,
+                Some(StatementType::If(i)) => block_returns(&i.body) && block_returns(&i.orelse),
+                Some(StatementType::Try(t)) => t.always_returns(),
+                _ => false,
             }
+        }
+        block_returns(&self.body) && self.handlers.iter().all(|handler| block_returns(&handler.body))
+    }
+
+    /// The clauses that run in closures: the body, the except clauses and the else clause.
+    fn clauses(&self) -> impl Iterator<Item = &[Statement]> {
+        std::iter::once(self.body.as_slice())
+            .chain(self.handlers.iter().map(|handler| handler.body.as_slice()))
+            .chain(std::iter::once(self.orelse.as_slice()))
+    }
+
+    /// The try body runs in an immediately invoked closure, where raising returns an Err.
+    /// The except clauses match on its type, and an error none of them catches, or one they
+    /// raise, is propagated after the finally clause has run: returned from a function that
+    /// returns a Result, and a panic otherwise. `return` inside the closures returns its
+    /// value from them in a Some, which the function returns after the finally clause.
+    fn result_to_rust(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let returns = FirstReturn::of(self.clauses()).is_some();
+        let closure_result = closure_result(returns);
+        let finished = if returns { quote!(Ok(None)) } else { quote!(Ok(())) };
+        // Code after a try statement whose body and except clauses all return can't be
+        // reached.
+        let finished_without_return = if self.always_returns() { quote!(unreachable!()) } else { quote!({}) };
+        let mut inner = options.clone();
+        inner.state.returns_result = true;
+        inner.state.returns_from_try = returns;
+
+        let body = block_to_rust(self.body, &ctx, &inner, &symbols)?;
+        let orelse = block_to_rust(self.orelse, &ctx, &inner, &symbols)?;
+        let finalbody = block_to_rust(self.finalbody, &ctx, &options, &symbols)?;
+
+        let mut arms = Vec::new();
+        let mut catches_all = false;
+        for handler in self.handlers {
+            let name = handler.name.unwrap_or_else(|| "e".to_string());
+            let binding = sanitize_ident(&name);
+            let guard = match caught_types(&handler.exception_type)? {
+                None => {
+                    catches_all = true;
+                    quote!()
+                }
+                Some(types) => quote!(if #(ModuleError::is(&*#binding, #types))||*),
+            };
+            let mut handler_options = inner.clone();
+            handler_options.state.handled_exception = Some(name);
+            let handler_body = block_to_rust(handler.body, &ctx, &handler_options, &symbols)?;
+            arms.push(quote! {
+                Err(#binding) #guard => (|| -> #closure_result { #handler_body #finished })(),
+            });
+            // Anything after a clause that catches everything can't be reached.
+            if catches_all {
+                break;
+            }
+        }
+        if !catches_all {
+            arms.push(quote!(Err(e) => Err(e),));
+        }
+
+        // What the clauses returned is returned once the finally clause has run, from the
+        // closure of the try statement this one is in if there is one.
+        let returned = if options.state.returns_from_try {
+            quote!(return Ok(Some(__value));)
+        } else if options.state.returns_result {
+            quote!(return Ok(__value);)
         } else {
-            quote!()
+            quote!(return __value;)
         };
+        let propagate = match (returns, options.state.returns_result) {
+            (false, true) => quote!(__outcome?;),
+            (false, false) => quote!(if let Err(e) = __outcome { panic!("{}", e); }),
+            (true, true) => quote! {
+                match __outcome? {
+                    Some(__value) => { #returned }
+                    None => #finished_without_return,
+                }
+            },
+            (true, false) => quote! {
+                match __outcome {
+                    Ok(Some(__value)) => { #returned }
+                    Ok(None) => #finished_without_return,
+                    Err(e) => panic!("{}", e),
+                }
+            },
+        };
+        // The else clause doesn't run if the body returned.
+        let (ran, returned_arm) = if returns {
+            (quote!(Ok(None)), quote!(Ok(Some(__value)) => Ok(Some(__value)),))
+        } else {
+            (quote!(Ok(())), quote!())
+        };
+
+        // The body's closure is called before the match, so that it's gone by the time the
+        // clauses' closures borrow the variables it assigns.
+        Ok(quote! {
+            {
+                let __body: #closure_result = (|| -> #closure_result { #body #finished })();
+                let __outcome: #closure_result = match __body {
+                    #ran => (|| -> #closure_result { #orelse #finished })(),
+                    #returned_arm
+                    #(#arms)*
+                };
+                #finalbody
+                #propagate
+            }
+        })
+    }
 
-        // Generate a simplified try-catch structure
-        // Note: This is a basic translation - Rust's error handling is quite different from Python's
+    /// Everything runs in order as if nothing raised, since raising panics anyway.
+    fn panic_to_rust(
+        self,
+        ctx: CodeGenContext,
+        options: PythonOptions,
+        symbols: SymbolTableScopes,
+    ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        if !self.handlers.is_empty() {
+            warn!(
+                "Dropping {} except clause(s) at line {:?}, exceptions panic with TryStrategy::Panic",
+                self.handlers.len(),
+                self.lineno
+            );
+        }
+        let body = block_to_rust(self.body, &ctx, &options, &symbols)?;
+        let orelse = block_to_rust(self.orelse, &ctx, &options, &symbols)?;
+        let finalbody = block_to_rust(self.finalbody, &ctx, &options, &symbols)?;
         Ok(quote! {
             {
-                // Try block - simplified translation to Rust
-                // Python's exception handling doesn't map directly to Rust's Result/Option patterns
-                #(#try_body_tokens)*
-                
-                // Catch blocks (simplified)
-                #(#catch_blocks)*
-                
-                // Else clause
-                #else_tokens
-                
-                // Finally clause  
-                #finally_tokens
+                #body
+                #orelse
+                #finalbody
             }
         })
     }
@@ -223,6 +423,162 @@ impl CodeGen for Try {
 
 #[cfg(test)]
 mod tests {
-    // Tests would go here - currently commented out as they need full AST infrastructure
-    // create_parse_test!(test_simple_try, "try:\n    pass\nexcept:\n    pass", "test.py");
+    use crate::TryStrategy;
+    use crate::test_utils::{assert_compiles, module_to_rust, test_options};
+
+    const ALL_CLAUSES: &str = "def parse(text):
+    try:
+        if text == \"\":
+            raise ValueError(\"empty\")
+        print(text)
+    except (ValueError, KeyError) as err:
+        print(err)
+    except:
+        raise
+    else:
+        print(\"parsed\")
+    finally:
+        print(\"done\")
+";
+
+    #[test]
+    fn test_try_except_else_finally() {
+        let code = module_to_rust(ALL_CLAUSES, test_options());
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+
+        // The body raises into the closure's Result.
+        assert!(
+            code.contains("let __body : Result < () , Box < dyn std :: error :: Error >> = (|| -> Result < () , Box < dyn std :: error :: Error >> {"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(
+                "return Err (ModuleError :: ValueError { message : \"empty\" . to_string () , source : None } . into ())"
+            ),
+            "{}",
+            code
+        );
+        // The clauses match on the exception type, and bare except catches everything.
+        assert!(
+            code.contains(
+                "Err (err) if ModuleError :: is (& * err , \"ValueError\") || ModuleError :: is (& * err , \"KeyError\") =>"
+            ),
+            "{}",
+            code
+        );
+        assert!(code.contains("Err (e) => (||"), "{}", code);
+        assert!(!code.contains("Err (e) => Err (e)"), "{}", code);
+        // Re-raising propagates, and else only runs when nothing was raised.
+        assert!(code.contains("return Err (e)"), "{}", code);
+        assert!(code.contains("Ok (()) => (|| -> Result < () , Box < dyn std :: error :: Error >> { println ! (\"parsed\")"), "{}", code);
+        // Finally runs on both paths, before an uncaught error panics.
+        assert!(
+            code.contains("println ! (\"done\") ; if let Err (e) = __outcome { panic ! (\"{}\" , e) ; }"),
+            "{}",
+            code
+        );
+        // The module's error type has a variant for each exception raised or caught.
+        assert!(code.contains("pub enum ModuleError { KeyError {"), "{}", code);
+        assert!(code.contains("ValueError { message : String"), "{}", code);
+    }
+
+    #[test]
+    fn test_uncaught_errors_propagate_from_result_functions() {
+        let mut options = test_options();
+        options.error_strategy = crate::ErrorStrategy::Result;
+        let code = module_to_rust(
+            "def f():
+    try:
+        g()
+    except KeyError:
+        pass
+    raise ValueError(\"after\")
+",
+            options,
+        );
+        assert!(code.contains("Err (e) => Err (e) ,"), "{}", code);
+        assert!(code.contains("__outcome ? ;"), "{}", code);
+    }
+
+    #[test]
+    fn test_clauses_share_variables_and_catch_what_functions_raise() {
+        let code = module_to_rust(
+            "def check(x: int) -> int:
+    if x < 0:
+        raise ValueError(\"negative\")
+    return x * 2
+def run(x: int) -> int:
+    try:
+        y = check(x)
+    except ValueError as err:
+        print(err)
+        y = 0
+    else:
+        print(y)
+    finally:
+        print(\"done\")
+    return y
+",
+            test_options(),
+        );
+        // What the try statement calls raises into its Result, under the Panic strategy too.
+        assert!(code.contains("pub fn check (x : i64) -> Result < i64 , ModuleError > {"), "{}", code);
+        assert!(code.contains("y = check (x) ? ;"), "{}", code);
+        // The variable the clauses assign is declared before the closures.
+        assert!(code.contains("pub fn run (x : i64) -> i64 { let mut y = Default :: default () ; {"), "{}", code);
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_return_inside_try_returns_from_the_function() {
+        let code = module_to_rust(
+            "def safe_div(a: int, b: int) -> int:
+    try:
+        return a // b
+    except ZeroDivisionError:
+        return 0
+def first_even(items: list[int]) -> int:
+    for x in items:
+        try:
+            if x % 2 == 0:
+                return x
+        finally:
+            print(\"checked\")
+    return -1
+",
+            test_options(),
+        );
+        // The closures return what's returned in a Some, which the function returns after
+        // the finally clause.
+        assert!(code.contains("return Ok (Some (python_floor_div (a , b))) ;"), "{}", code);
+        assert!(code.contains("Ok (Some (__value)) => { return __value ; } Ok (None) => unreachable ! () ,"), "{}", code);
+        assert!(code.contains("Ok (None) => { } ,"), "{}", code);
+        assert_compiles(&code);
+
+        // A function defined in the try body returns from itself.
+        let code = module_to_rust(
+            "def f():
+    try:
+        def g():
+            return 1
+        print(g())
+    finally:
+        print(\"done\")
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(!code.contains("Option < _ >"), "{}", code);
+    }
+
+    #[test]
+    fn test_panic_strategy_drops_handlers() {
+        let mut options = test_options();
+        options.try_strategy = TryStrategy::Panic;
+        let code = module_to_rust(ALL_CLAUSES, options);
+        assert!(code.contains("panic ! (\"empty\")"), "{}", code);
+        assert!(!code.contains("print (err)") && !code.contains("err"), "{}", code);
+        assert!(code.contains("println ! (\"parsed\") ; println ! (\"done\")"), "{}", code);
+    }
 }
\ No newline at end of file
@@ -4,8 +4,8 @@ use quote::quote;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
-    Node, impl_node_with_positions, PyAttributeExtractor, extract_list
+    condition_to_rust, CodeGen, CodeGenContext, ExprType, PythonOptions, SymbolTableScopes,
+    Node, impl_node_with_positions, PyAttributeExtractor, extract_list, hoist
 };
 
 use super::Statement;
@@ -49,7 +49,9 @@ impl CodeGen for While {
     type SymbolTable = SymbolTableScopes;
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
-        let symbols = self.test.find_symbols(symbols);
+        let (test, named) = hoist(self.test);
+        let symbols = named.into_iter().fold(symbols, |acc, n| n.find_symbols(acc));
+        let symbols = test.find_symbols(symbols);
         let symbols = self.body.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc));
         self.orelse.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc))
     }
@@ -60,22 +62,49 @@ impl CodeGen for While {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let test = self.test.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+        // Assignment expressions in the condition are bound at the top of each iteration,
+        // so the condition is tested there too.
+        let (test, named) = hoist(self.test);
+        let bindings = named
+            .into_iter()
+            .map(|n| n.binding(ctx.clone(), options.clone(), symbols.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let test = condition_to_rust(test, ctx.clone(), options.clone(), symbols.clone())?;
         
+        // breaks in the body belong to this loop, not to one the loop is nested in.
+        let mut body_options = options.clone();
+        body_options.state.in_loop_with_else = !self.orelse.is_empty();
+        let body_ctx = ctx.enter_loop();
+        let label = body_ctx.loop_label();
+        let label_definition = body_ctx.loop_label_definition();
         let body_stmts: Result<Vec<_>, _> = self.body
             .into_iter()
-            .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
+            .map(|stmt| stmt.to_rust(body_ctx.clone(), body_options.clone(), symbols.clone()))
             .collect();
         let body_stmts = body_stmts?;
+
+        let looped = if bindings.is_empty() {
+            quote! {
+                #label_definition while #test {
+                    #(#body_stmts;)*
+                }
+            }
+        } else {
+            quote! {
+                #label_definition loop {
+                    #(#bindings)*
+                    if !(#test) {
+                        break #label;
+                    }
+                    #(#body_stmts;)*
+                }
+            }
+        };
         
         if self.orelse.is_empty() {
-            Ok(quote! {
-                while #test {
-                    #(#body_stmts)*
-                }
-            })
+            Ok(looped)
         } else {
-            // Note: Rust doesn't have while-else, so we need to track completion
+            // Rust doesn't have while-else. The else clause runs unless a break set the flag.
             let else_stmts: Result<Vec<_>, _> = self.orelse
                 .into_iter()
                 .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
@@ -85,11 +114,9 @@ impl CodeGen for While {
             Ok(quote! {
                 {
                     let mut broke = false;
-                    while #test {
-                        #(#body_stmts)*
-                    }
+                    #looped
                     if !broke {
-                        #(#else_stmts)*
+                        #(#else_stmts;)*
                     }
                 }
             })
@@ -105,4 +132,49 @@ mod tests {
     create_parse_test!(test_simple_while, "while x > 0:\n    x -= 1", "while_test.py");
     create_parse_test!(test_while_else, "while x > 0:\n    x -= 1\nelse:\n    print('done')", "while_test.py");
     create_parse_test!(test_while_true, "while True:\n    break", "while_test.py");
+
+    #[test]
+    fn test_break_and_continue_in_loop_body() {
+        let module = crate::parse(
+            "while x > 0:\n    x -= 1\n    if x == 5:\n        continue\n    if x == 2:\n        break\n",
+            "while_test.py",
+        )
+        .unwrap();
+        let code = module.raw.body[0]
+            .clone()
+            .to_rust(
+                CodeGenContext::Module("while_test".to_string()),
+                PythonOptions::default(),
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+        assert!(code.contains("{ continue ; }"), "{}", code);
+        assert!(code.contains("{ break ; }"), "{}", code);
+    }
+
+    #[test]
+    fn test_walrus_in_condition_is_bound_each_iteration() {
+        let module = crate::parse(
+            "while (line := next_line()) != '':\n    print(line)\n",
+            "while_test.py",
+        )
+        .unwrap();
+        let symbols = module.clone().find_symbols(SymbolTableScopes::new());
+        assert!(symbols.lookup("line").is_some());
+        let code = module.raw.body[0]
+            .clone()
+            .to_rust(
+                CodeGenContext::Module("while_test".to_string()),
+                PythonOptions::default(),
+                symbols,
+            )
+            .unwrap()
+            .to_string();
+        assert!(
+            code.starts_with("loop { let line = next_line () ; if ! ((line) != (\"\")) { break ; } println !"),
+            "{}",
+            code
+        );
+    }
 }
\ No newline at end of file
@@ -1,11 +1,11 @@
 use proc_macro2::TokenStream;
 use pyo3::{Bound, FromPyObject, PyAny, PyResult};
-use quote::quote;
+use quote::{format_ident, quote};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CodeGen, CodeGenContext, Node, PythonOptions, Statement, SymbolTableScopes,
-    extract_list, WithItem,
+    CodeGen, CodeGenContext, ExprType, Node, PythonOptions, Statement, SymbolTableNode,
+    SymbolTableScopes, extract_list, is_file_expr, WithItem,
 };
 
 /// Regular with statement (with context as var: ...)
@@ -54,14 +54,17 @@ impl CodeGen for With {
     type SymbolTable = SymbolTableScopes;
 
     fn find_symbols(self, symbols: Self::SymbolTable) -> Self::SymbolTable {
-        // Process items and body
+        // Process items and body. A name bound with `as` is assigned the context manager,
+        // which is what __enter__ returns for files and locks.
         let symbols = self.items.into_iter().fold(symbols, |acc, item| {
-            let acc = item.context_expr.find_symbols(acc);
-            if let Some(vars) = item.optional_vars {
-                vars.find_symbols(acc)
-            } else {
-                acc
+            let mut acc = item.context_expr.clone().find_symbols(acc);
+            if let Some(ExprType::Name(name)) = &item.optional_vars {
+                acc.insert(
+                    name.id.clone(),
+                    SymbolTableNode::Assign { position: 0, value: item.context_expr },
+                );
             }
+            acc
         });
         self.body.into_iter().fold(symbols, |acc, stmt| stmt.find_symbols(acc))
     }
@@ -72,19 +75,38 @@ impl CodeGen for With {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // Each context manager is bound for the length of a block, and dropping it at the
+        // end of the block does what __exit__ would. Rust drops in reverse order, which is
+        // also the order Python exits nested context managers in.
+        let mut bindings = Vec::new();
+        for (i, item) in self.items.into_iter().enumerate() {
+            let mutable = is_file_expr(&item.context_expr, &symbols);
+            let value = item.context_expr.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            let target = match item.optional_vars {
+                Some(vars) => vars.to_rust(ctx.clone(), options.clone(), symbols.clone())?,
+                // `let _ = ...` would drop the value right away, so it needs a name.
+                None => {
+                    let guard = format_ident!("_context_{}", i);
+                    quote!(#guard)
+                }
+            };
+            if mutable {
+                bindings.push(quote!(let mut #target = #value;));
+            } else {
+                bindings.push(quote!(let #target = #value;));
+            }
+        }
+
         // Generate body
         let body_tokens: Result<Vec<TokenStream>, Box<dyn std::error::Error>> = self.body.into_iter()
             .map(|stmt| stmt.to_rust(ctx.clone(), options.clone(), symbols.clone()))
             .collect();
         let body_tokens = body_tokens?;
 
-        // For now, generate a simplified block
-        // In practice, this would need proper context management
         Ok(quote! {
             {
-                // With block - simplified translation
-                // Python's with doesn't map directly to Rust patterns
-                #(#body_tokens)*
+                #(#bindings)*
+                #(#body_tokens);*
             }
         })
     }
@@ -92,6 +114,62 @@ impl CodeGen for With {
 
 #[cfg(test)]
 mod tests {
-    // Tests would go here - currently commented out as they need full AST infrastructure
-    // create_parse_test!(test_simple_with, "with context:\n    pass", "test.py");
+    use crate::test_utils::{module_to_rust, test_options};
+
+    #[test]
+    fn test_with_binds_for_the_block() {
+        let code = module_to_rust(
+            "def locked(lock):
+    with lock:
+        work()
+",
+            test_options(),
+        );
+        assert!(code.contains("{ let _context_0 = lock ; work () }"), "{}", code);
+    }
+
+    #[test]
+    fn test_with_multiple_items() {
+        let code = module_to_rust(
+            "def copy(src: str, dst: str):
+    with open(src) as a, open(dst, \"w\") as b:
+        b.write(a.read())
+",
+            test_options(),
+        );
+        assert!(code.contains("let mut a = std :: fs :: File :: open (src) . unwrap () ;"), "{}", code);
+        assert!(code.contains("let mut b = std :: fs :: File :: create (dst) . unwrap () ;"), "{}", code);
+        assert!(code.contains("std :: io :: Write :: write_all (& mut b"), "{}", code);
+    }
+
+    #[test]
+    fn test_open_modes() {
+        let code = module_to_rust(
+            "def log(path: str, line: str):
+    with open(path, mode=\"a\") as f:
+        f.write(line)
+",
+            test_options(),
+        );
+        assert!(
+            code.contains("std :: fs :: OpenOptions :: new () . append (true) . create (true) . open (path)"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_reading_a_file_compiles() {
+        let code = module_to_rust(
+            "def show():
+    with open(\"notes.txt\") as f:
+        text = f.read()
+        print(text.upper())
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("read_to_string (& mut f , & mut contents)"), "{}", code);
+        assert!(code.contains("text . to_uppercase ()"), "{}", code);
+    }
 }
\ No newline at end of file
@@ -102,6 +102,18 @@ impl CodeGen for Yield {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        // Generator functions collect what they yield, see FunctionDef.
+        if options.state.in_generator {
+            let value = match self.value.as_deref() {
+                Some(ExprType::Constant(constant)) if options.state.yields_string && constant.string_value().is_some() => {
+                    constant.string_to_rust(&options).unwrap_or_default()
+                }
+                Some(value) => value.clone().to_rust(ctx, options, symbols)?,
+                None => quote!(()),
+            };
+            return Ok(quote!(__yielded.push(#value)));
+        }
+
         if let Some(value) = self.value {
             let value_tokens = (*value).to_rust(ctx, options, symbols)?;
             // For now, generate a simple return since Rust doesn't have yield expressions
@@ -135,7 +147,11 @@ impl CodeGen for YieldFrom {
         options: Self::Options,
         symbols: Self::SymbolTable,
     ) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        let in_generator = options.state.in_generator;
         let value_tokens = (*self.value).to_rust(ctx, options, symbols)?;
+        if in_generator {
+            return Ok(quote!(__yielded.extend(#value_tokens)));
+        }
         // For now, generate a simple expression since Rust doesn't have yield from
         Ok(quote! {
             // Yield from expression - simplified translation
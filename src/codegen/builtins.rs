@@ -0,0 +1,765 @@
+//! Recognition of calls to Python builtins that map onto Rust macros or methods.
+//!
+//! Each entry in [`BUILTINS`] gets to inspect the whole call, including its argument count
+//! and keywords, and may decline to rewrite it, in which case the call is generated as an
+//! ordinary function call.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    floor_div_to_rust, int_range, is_bytes_expr, is_float_operand, mod_to_rust, Call, CodeGen, CodeGenContext, CodeGenError, ExprType, Ops,
+    PythonOptions, SymbolTableScopes,
+};
+
+/// Generates the Rust code for a builtin call, or returns None to fall back to a plain call.
+pub type BuiltinGenerator = fn(
+    &Call,
+    CodeGenContext,
+    PythonOptions,
+    SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>>;
+
+/// A Python builtin with special code generation.
+pub struct Builtin {
+    /// The Python name of the builtin.
+    pub name: &'static str,
+    /// The code generator for calls to it.
+    pub generate: BuiltinGenerator,
+}
+
+/// The table of builtins consulted by the Call code generation.
+pub static BUILTINS: &[Builtin] = &[
+    Builtin { name: "print", generate: print },
+    Builtin { name: "len", generate: len },
+    Builtin { name: "range", generate: range },
+    Builtin { name: "list", generate: list },
+    Builtin { name: "set", generate: set },
+    Builtin { name: "dict", generate: dict },
+    Builtin { name: "enumerate", generate: enumerate },
+    Builtin { name: "zip", generate: zip },
+    Builtin { name: "min", generate: min },
+    Builtin { name: "max", generate: max },
+    Builtin { name: "sum", generate: sum },
+    Builtin { name: "abs", generate: abs },
+    Builtin { name: "divmod", generate: divmod },
+    Builtin { name: "bytearray", generate: bytearray },
+    Builtin { name: "sorted", generate: sorted },
+    Builtin { name: "open", generate: open },
+];
+
+/// Find the builtin for a call to `name`, unless the name is shadowed by a user definition.
+pub fn lookup_builtin(name: &str, symbols: &SymbolTableScopes) -> Option<&'static Builtin> {
+    if symbols.lookup(name).is_some() {
+        return None;
+    }
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Generates an argument expression.
+pub(crate) fn arg_to_rust(
+    arg: &ExprType,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    arg.clone().to_rust(ctx.clone(), options.clone(), symbols.clone())
+}
+
+/// Generates an expression that a method is called on, parenthesized unless it binds tightly.
+pub(crate) fn receiver_to_rust(
+    arg: &ExprType,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let tokens = arg_to_rust(arg, ctx, options, symbols)?;
+    let binds_tightly = match arg {
+        ExprType::Name(_) | ExprType::Attribute(_) | ExprType::Subscript(_) | ExprType::List(_) => true,
+        // Builtins can expand to ranges and blocks, ordinary calls can't.
+        ExprType::Call(call) => !matches!(
+            call.func.as_ref(),
+            ExprType::Name(name) if lookup_builtin(&name.id, symbols).is_some()
+        ),
+        ExprType::Constant(_) => true,
+        _ => false,
+    };
+    Ok(if binds_tightly { tokens } else { quote!((#tokens)) })
+}
+
+/// If `arg` is a negated constant like `-2`, the constant being negated.
+fn negated_constant(arg: &ExprType) -> Option<&ExprType> {
+    match arg {
+        ExprType::UnaryOp(u) if matches!(u.op, Ops::USub) && matches!(u.operand.as_ref(), ExprType::Constant(_)) => {
+            Some(u.operand.as_ref())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the positional arguments if the call has exactly `count` of them and no keywords.
+pub(crate) fn exact_args(call: &Call, count: std::ops::RangeInclusive<usize>) -> Option<&[ExprType]> {
+    if call.keywords.is_empty()
+        && count.contains(&call.args.len())
+        && !call.args.iter().any(|arg| matches!(arg, ExprType::Starred(_)))
+    {
+        Some(&call.args)
+    } else {
+        None
+    }
+}
+
+/// `len(x)` becomes `x.len()`.
+fn len(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([value]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    let value = receiver_to_rust(value, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#value.len())))
+}
+
+/// `range(n)`, `range(a, b)` and `range(a, b, step)` become Rust ranges.
+fn range(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 1..=3) else {
+        return Ok(None);
+    };
+    let bound = |arg: &ExprType| receiver_to_rust(arg, &ctx, &options, &symbols);
+
+    match args {
+        [stop] => {
+            let stop = bound(stop)?;
+            Ok(Some(quote!(0..#stop)))
+        }
+        [start, stop] => {
+            let (start, stop) = (bound(start)?, bound(stop)?);
+            Ok(Some(quote!(#start..#stop)))
+        }
+        [start, stop, step] => {
+            let (start, stop) = (bound(start)?, bound(stop)?);
+            // step_by only takes positive steps, so count down over the reversed range.
+            if let Some(step) = negated_constant(step) {
+                let step = arg_to_rust(step, &ctx, &options, &symbols)?;
+                return Ok(Some(quote!(((#stop + 1)..=#start).rev().step_by(#step))));
+            }
+            let step = match step {
+                ExprType::Constant(_) => arg_to_rust(step, &ctx, &options, &symbols)?,
+                _ => {
+                    let step = bound(step)?;
+                    quote!(#step as usize)
+                }
+            };
+            Ok(Some(quote!((#start..#stop).step_by(#step))))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `list()` becomes `Vec::new()` and `list(iterable)` collects it.
+fn list(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    match exact_args(call, 0..=1) {
+        Some([]) => Ok(Some(quote!(Vec::new()))),
+        Some([iterable]) => {
+            let iterable = receiver_to_rust(iterable, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#iterable.into_iter().collect::<Vec<_>>())))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `set()` becomes `HashSet::new()`, and `set(xs)` collects `xs` into a `HashSet`.
+fn set(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    match exact_args(call, 0..=1) {
+        Some([]) => Ok(Some(quote!(std::collections::HashSet::new()))),
+        Some([iterable]) => {
+            let iterable = receiver_to_rust(iterable, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#iterable.into_iter().collect::<std::collections::HashSet<_>>())))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `dict()` becomes `HashMap::new()`, and `dict(pairs)` collects the key and value pairs
+/// into a `HashMap`.
+fn dict(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    match exact_args(call, 0..=1) {
+        Some([]) => Ok(Some(quote!(std::collections::HashMap::new()))),
+        Some([pairs]) => {
+            let pairs = receiver_to_rust(pairs, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#pairs.into_iter().collect::<std::collections::HashMap<_, _>>())))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `enumerate(xs)` becomes `xs.iter().enumerate()`, with the index shifted for `start`.
+fn enumerate(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if call.args.is_empty() || call.args.len() > 2 || call.args.iter().any(|a| matches!(a, ExprType::Starred(_))) {
+        return Ok(None);
+    }
+    let mut start = call.args.get(1).cloned();
+    for keyword in call.keywords.iter() {
+        match keyword.arg.as_deref() {
+            Some("start") if start.is_none() => start = Some(keyword.value.clone()),
+            _ => return Ok(None),
+        }
+    }
+
+    let iterable = receiver_to_rust(&call.args[0], &ctx, &options, &symbols)?;
+    match start {
+        None => Ok(Some(quote!(#iterable.iter().enumerate()))),
+        Some(start) => {
+            let start = receiver_to_rust(&start, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#iterable.iter().enumerate().map(|(i, x)| (i + #start, x)))))
+        }
+    }
+}
+
+/// `zip(a, b, ...)` becomes `a.iter().zip(b.iter())...`, flattened back into one tuple.
+fn zip(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 2..=usize::MAX) else {
+        return Ok(None);
+    };
+    let mut iterables = Vec::new();
+    for arg in args {
+        iterables.push(receiver_to_rust(arg, &ctx, &options, &symbols)?);
+    }
+
+    let first = &iterables[0];
+    let rest = &iterables[1..];
+    let mut tokens = quote!(#first.iter() #(.zip(#rest.iter()))*);
+    if iterables.len() > 2 {
+        // zip nests its tuples: ((a, b), c) -> (a, b, c)
+        let names: Vec<_> = (0..iterables.len())
+            .map(|i| quote::format_ident!("v{}", i))
+            .collect();
+        let mut pattern = {
+            let (a, b) = (&names[0], &names[1]);
+            quote!((#a, #b))
+        };
+        for name in names.iter().skip(2) {
+            pattern = quote!((#pattern, #name));
+        }
+        tokens = quote!(#tokens.map(|#pattern| (#(#names),*)));
+    }
+    Ok(Some(tokens))
+}
+
+/// `min(xs)` and `min(a, b, ...)`; see [`min_max`].
+fn min(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    min_max(call, quote!(min), ctx, options, symbols)
+}
+
+/// `max(xs)` and `max(a, b, ...)`; see [`min_max`].
+fn max(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    min_max(call, quote!(max), ctx, options, symbols)
+}
+
+/// A single iterable argument is reduced with the iterator method, several arguments are
+/// chained pairwise, which works for both `Ord` and float types.
+fn min_max(
+    call: &Call,
+    method: TokenStream,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 1..=usize::MAX) else {
+        return Ok(None);
+    };
+    let mut values = Vec::new();
+    for arg in args {
+        values.push(receiver_to_rust(arg, &ctx, &options, &symbols)?);
+    }
+
+    let first = &values[0];
+    if values.len() == 1 {
+        Ok(Some(quote!(#first.iter().#method().cloned().unwrap())))
+    } else {
+        let rest = &values[1..];
+        Ok(Some(quote!(#first #(.#method(#rest))*)))
+    }
+}
+
+/// `sum(xs)` becomes `xs.iter().sum()`, and `sum(xs, start)` a fold from `start`.
+fn sum(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    match exact_args(call, 1..=2) {
+        Some([iterable]) => {
+            let iterable = receiver_to_rust(iterable, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#iterable.iter().sum())))
+        }
+        Some([iterable, start]) => {
+            let iterable = receiver_to_rust(iterable, &ctx, &options, &symbols)?;
+            let start = arg_to_rust(start, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#iterable.iter().fold(#start, |acc, x| acc + x))))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `abs(x)` becomes `x.abs()`.
+fn abs(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([value]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    let value = receiver_to_rust(value, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#value.abs())))
+}
+
+/// `divmod(a, b)` becomes the tuple of `a // b` and `a % b`, which floor like Python.
+fn divmod(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([dividend, divisor]) = exact_args(call, 2..=2) else {
+        return Ok(None);
+    };
+    let float_type = if is_float_operand(dividend, &options, &symbols) || is_float_operand(divisor, &options, &symbols) {
+        Some(options.float_type_tokens()?)
+    } else {
+        None
+    };
+    // A bignum isn't Copy, so the quotient takes copies of the operands.
+    let (a, b) = if float_type.is_none() && int_range(options.int_rust_type()).is_none() {
+        (quote!(a.clone()), quote!(b.clone()))
+    } else {
+        (quote!(a), quote!(b))
+    };
+    let quotient = floor_div_to_rust(&a, &b, float_type.as_ref());
+    let remainder = mod_to_rust(&quote!(a), &quote!(b), float_type.as_ref());
+    let dividend = arg_to_rust(dividend, &ctx, &options, &symbols)?;
+    let divisor = arg_to_rust(divisor, &ctx, &options, &symbols)?;
+    Ok(Some(quote!({
+        let (a, b) = (#dividend, #divisor);
+        (#quotient, #remainder)
+    })))
+}
+
+/// `bytearray()` is an empty `Vec<u8>`, and `bytearray(b)` copies the bytes `b` into one.
+fn bytearray(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    match exact_args(call, 0..=1) {
+        Some([]) => Ok(Some(quote!(Vec::<u8>::new()))),
+        Some([ExprType::Bytes(bytes)]) => Ok(Some(bytes.to_vec_rust())),
+        Some([value]) if is_bytes_expr(value, &symbols) => {
+            let value = receiver_to_rust(value, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#value.to_vec())))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// How a failed I/O operation is handled: propagated with `?` from a function returning
+/// Result, and otherwise unwrapped.
+pub(crate) fn io_result(options: &PythonOptions) -> TokenStream {
+    if options.state.returns_result {
+        quote!(?)
+    } else {
+        quote!(.unwrap())
+    }
+}
+
+/// `open(path, mode)` becomes a `std::fs::File`, opened with the `OpenOptions` matching the
+/// mode. The mode has to be a literal, and text and binary modes open files the same way.
+fn open(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if call.args.is_empty() || call.args.len() > 2 || matches!(call.args[0], ExprType::Starred(_)) {
+        return Ok(None);
+    }
+    let mut mode = call.args.get(1);
+    for keyword in call.keywords.iter() {
+        match keyword.arg.as_deref() {
+            Some("mode") => mode = Some(&keyword.value),
+            // Rust reads and writes UTF-8, which is what encoding is almost always set to.
+            Some("encoding") => {}
+            _ => return Ok(None),
+        }
+    }
+    let mode = match mode {
+        None => "r".to_string(),
+        Some(ExprType::Constant(c)) if c.string_value().is_some() => {
+            c.string_value().unwrap_or_default().replace(['t', 'b'], "")
+        }
+        Some(_) => return Ok(None),
+    };
+
+    let path = arg_to_rust(&call.args[0], &ctx, &options, &symbols)?;
+    let result = io_result(&options);
+    let open_options = match mode.as_str() {
+        "r" => return Ok(Some(quote!(std::fs::File::open(#path)#result))),
+        "w" => return Ok(Some(quote!(std::fs::File::create(#path)#result))),
+        "a" => quote!(.append(true).create(true)),
+        "x" => quote!(.write(true).create_new(true)),
+        "r+" => quote!(.read(true).write(true)),
+        "w+" => quote!(.read(true).write(true).create(true).truncate(true)),
+        "a+" => quote!(.read(true).append(true).create(true)),
+        "x+" => quote!(.read(true).write(true).create_new(true)),
+        _ => return Err(CodeGenError::unsupported(format!("open() mode {:?}", mode), None).into()),
+    };
+    Ok(Some(quote!(std::fs::OpenOptions::new()#open_options.open(#path)#result)))
+}
+
+/// `sorted(xs, key=..., reverse=...)` becomes a block sorting a copy of the values.
+fn sorted(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if call.args.len() != 1 || matches!(call.args[0], ExprType::Starred(_)) {
+        return Ok(None);
+    }
+    let mut key = None;
+    let mut reverse = None;
+    for keyword in call.keywords.iter() {
+        match keyword.arg.as_deref() {
+            Some("key") => key = Some(arg_to_rust(&keyword.value, &ctx, &options, &symbols)?),
+            Some("reverse") => reverse = Some(arg_to_rust(&keyword.value, &ctx, &options, &symbols)?),
+            _ => return Ok(None),
+        }
+    }
+
+    let iterable = receiver_to_rust(&call.args[0], &ctx, &options, &symbols)?;
+    let sort = match key {
+        Some(key) => quote!(sorted.sort_by_key(#key);),
+        None => quote!(sorted.sort();),
+    };
+    let reverse = reverse.map(|reverse| quote!(if #reverse { sorted.reverse(); }));
+    Ok(Some(quote!({
+        let mut sorted: Vec<_> = #iterable.clone().into_iter().collect();
+        #sort
+        #reverse
+        sorted
+    })))
+}
+
+/// Escapes literal text for use in a format! string.
+fn escape_format_text(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+/// `print(*values, sep=' ', end='\n', file=sys.stdout)` becomes a `println!` family macro.
+fn print(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    // Unpacked arguments need runtime iteration; leave them to the runtime print.
+    if call.args.iter().any(|arg| matches!(arg, ExprType::Starred(_))) {
+        return Ok(None);
+    }
+
+    let mut sep = None;
+    let mut end = None;
+    let mut file = None;
+    for keyword in call.keywords.iter() {
+        match keyword.arg.as_deref() {
+            Some("sep") => sep = Some(keyword.value.clone()),
+            Some("end") => end = Some(keyword.value.clone()),
+            Some("file") => file = Some(keyword.value.clone()),
+            // Output is flushed by the macros where it matters.
+            Some("flush") => (),
+            _ => return Ok(None),
+        }
+    }
+
+    let constant_text = |e: &Option<ExprType>, default: &str| -> Option<String> {
+        match e {
+            None | Some(ExprType::NoneType(_)) => Some(default.to_string()),
+            Some(ExprType::Constant(c)) => c.string_value(),
+            _ => None,
+        }
+    };
+
+    // (macro without newline, macro with newline, optional writer argument)
+    let file = file.filter(|f| !matches!(f, ExprType::NoneType(_)));
+    let (print_macro, println_macro, writer) = match file {
+        None => (quote!(print), quote!(println), None),
+        Some(ExprType::Attribute(a)) if matches!(a.value.as_ref(), ExprType::Name(n) if n.id == "sys") => {
+            match a.attr.as_str() {
+                "stdout" => (quote!(print), quote!(println), None),
+                "stderr" => (quote!(eprint), quote!(eprintln), None),
+                _ => return Ok(None),
+            }
+        }
+        Some(f) => {
+            let f = f.to_rust(ctx.clone(), options.clone(), symbols.clone())?;
+            (quote!(write), quote!(writeln), Some(f))
+        }
+    };
+
+    let args = call.args.clone();
+
+    let finish = |tokens: TokenStream| -> TokenStream {
+        if writer.is_some() {
+            quote!(#tokens.unwrap())
+        } else {
+            tokens
+        }
+    };
+    let writer_arg = writer.as_ref().map(|w| quote!(#w,));
+
+    match (constant_text(&sep, " "), constant_text(&end, "\n")) {
+        (Some(sep), Some(end)) => {
+            // Constant separators and endings are folded into the format string, as are
+            // string constant arguments.
+            let mut format_string = String::new();
+            let mut values = Vec::new();
+            for (i, arg) in args.into_iter().enumerate() {
+                if i > 0 {
+                    format_string.push_str(&escape_format_text(&sep));
+                }
+                match &arg {
+                    ExprType::Constant(c) if c.string_value().is_some() => {
+                        format_string.push_str(&escape_format_text(&c.string_value().unwrap()));
+                    }
+                    _ => {
+                        format_string.push_str("{}");
+                        values.push(arg.to_rust(ctx.clone(), options.clone(), symbols.clone())?);
+                    }
+                }
+            }
+
+            let (mac, end_text) = match end.strip_suffix('\n') {
+                Some(rest) => (println_macro, rest.to_string()),
+                None => (print_macro, end),
+            };
+            format_string.push_str(&escape_format_text(&end_text));
+
+            if format_string.is_empty() && values.is_empty() {
+                Ok(Some(finish(quote!(#mac!(#writer_arg)))))
+            } else {
+                Ok(Some(finish(quote!(#mac!(#writer_arg #format_string #(, #values)*)))))
+            }
+        }
+        _ => {
+            // A separator or ending only known at runtime: join the values at runtime.
+            let to_tokens = |e: Option<ExprType>, default: &str| -> Result<TokenStream, Box<dyn std::error::Error>> {
+                match e {
+                    Some(e) if !matches!(e, ExprType::NoneType(_)) => {
+                        e.to_rust(ctx.clone(), options.clone(), symbols.clone())
+                    }
+                    _ => Ok(quote!(#default)),
+                }
+            };
+            let sep = to_tokens(sep, " ")?;
+            let end = to_tokens(end, "\n")?;
+            let mut values = Vec::new();
+            for arg in args {
+                values.push(arg.to_rust(ctx.clone(), options.clone(), symbols.clone())?);
+            }
+            Ok(Some(finish(quote! {
+                #print_macro!(#writer_arg "{}{}", [#((#values).to_string()),*].join(&(#sep).to_string()), #end)
+            })))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{expr_to_rust, module_to_rust, test_options};
+
+    #[test]
+    fn test_len() {
+        assert_eq!(expr_to_rust("len(items)", PythonOptions::default()), "items . len ()");
+        assert_eq!(expr_to_rust("len(foo())", PythonOptions::default()), "foo () . len ()");
+        // Strings and containers all have a len().
+        assert_eq!(expr_to_rust("len('abc')", PythonOptions::default()), "\"abc\" . len ()");
+        assert_eq!(expr_to_rust("len(a + b)", PythonOptions::default()), "((a) + (b)) . len ()");
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(expr_to_rust("range(10)", PythonOptions::default()), "0 .. 10");
+        assert_eq!(expr_to_rust("range(a, b)", PythonOptions::default()), "a .. b");
+        assert_eq!(expr_to_rust("range(a, b, 2)", PythonOptions::default()), "(a .. b) . step_by (2)");
+        assert_eq!(expr_to_rust("range(a, b, s)", PythonOptions::default()), "(a .. b) . step_by (s as usize)");
+        assert_eq!(expr_to_rust("range(10, 0, -2)", PythonOptions::default()), "((0 + 1) ..= 10) . rev () . step_by (2)");
+        assert_eq!(expr_to_rust("list(range(n))", PythonOptions::default()), "(0 .. n) . into_iter () . collect :: < Vec < _ >> ()");
+    }
+
+    #[test]
+    fn test_enumerate_and_zip() {
+        assert_eq!(expr_to_rust("enumerate(xs)", PythonOptions::default()), "xs . iter () . enumerate ()");
+        assert_eq!(
+            expr_to_rust("enumerate(xs, start=1)", PythonOptions::default()),
+            "xs . iter () . enumerate () . map (| (i , x) | (i + 1 , x))"
+        );
+        assert_eq!(expr_to_rust("zip(a, b)", PythonOptions::default()), "a . iter () . zip (b . iter ())");
+        assert_eq!(
+            expr_to_rust("zip(a, b, c)", PythonOptions::default()),
+            "a . iter () . zip (b . iter ()) . zip (c . iter ()) . map (| ((v0 , v1) , v2) | (v0 , v1 , v2))"
+        );
+    }
+
+    #[test]
+    fn test_min_max_sum_abs() {
+        assert_eq!(expr_to_rust("min(xs)", PythonOptions::default()), "xs . iter () . min () . cloned () . unwrap ()");
+        assert_eq!(expr_to_rust("max(a, b)", PythonOptions::default()), "a . max (b)");
+        assert_eq!(expr_to_rust("sum(xs)", PythonOptions::default()), "xs . iter () . sum ()");
+        assert_eq!(expr_to_rust("abs(a - b)", PythonOptions::default()), "((a) - (b)) . abs ()");
+    }
+
+    #[test]
+    fn test_shadowed_builtin_is_a_plain_call() {
+        let code = module_to_rust("def len(x):\n    return 0\nlen(items)", test_options());
+        assert!(code.contains("len (items)"), "{}", code);
+    }
+
+    #[test]
+    fn test_builtins_compile_in_function_body() {
+        let code = module_to_rust(
+            "def stats(xs, ys):
+    n = len(xs)
+    for i in range(n):
+        pass
+    for i in range(1, n, 2):
+        pass
+    nums = list(range(n))
+    for i, x in enumerate(xs):
+        pass
+    for x, y in zip(xs, ys):
+        pass
+    lo = min(xs)
+    hi = max(lo, 0)
+    total = sum(xs)
+    dist = abs(lo - hi)
+    ordered = sorted(xs, reverse=True)
+",
+            test_options(),
+        );
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("for i in 0 .. n"), "{}", code);
+        assert!(code.contains("sorted . sort ()"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_default() {
+        let code = module_to_rust("print(a, b)", test_options());
+        assert!(code.contains("println ! (\"{} {}\" , a , b)"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_string() {
+        let code = module_to_rust("print(\"hi\")", test_options());
+        assert!(code.contains("println ! (\"hi\")"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_no_arguments() {
+        let code = module_to_rust("print()", test_options());
+        assert!(code.contains("println ! ()"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_string_constants_are_inlined() {
+        let code = module_to_rust("print('x = {}', x)", test_options());
+        assert!(code.contains("println ! (\"x = {{}} {}\" , x)"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_sep() {
+        let code = module_to_rust("print(a, b, sep=', ')", test_options());
+        assert!(code.contains("println ! (\"{}, {}\" , a , b)"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_end() {
+        let code = module_to_rust("print(a, end='')", test_options());
+        assert!(code.contains("print ! (\"{}\" , a)"), "{}", code);
+        assert!(!code.contains("println"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_sep_and_end() {
+        let code = module_to_rust("print(a, b, sep='-', end='!\\n')\nprint(a, b, sep=', ', end=' ')", test_options());
+        // An ending with a newline is println!'s, anything else is part of the text.
+        assert!(code.contains("println ! (\"{}-{}!\" , a , b)"), "{}", code);
+        assert!(code.contains("print ! (\"{}, {} \" , a , b)"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_stderr() {
+        let code = module_to_rust("import sys\nprint('oops', file=sys.stderr)", test_options());
+        assert!(code.contains("eprintln ! (\"oops\")"), "{}", code);
+    }
+
+    #[test]
+    fn test_print_runtime_sep() {
+        let code = module_to_rust("print(a, b, sep=s)", test_options());
+        assert!(code.contains("print ! (\"{}{}\" , [(a) . to_string () , (b) . to_string ()] . join (& (s) . to_string ()) , \"\\n\")"), "{}", code);
+    }
+
+    #[test]
+    fn test_shadowed_print_is_not_rewritten() {
+        let code = module_to_rust("def print(x):\n    pass\nprint(1)", test_options());
+        assert!(!code.contains("println"), "{}", code);
+        assert!(code.contains("print (1)"), "{}", code);
+    }
+}
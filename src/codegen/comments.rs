@@ -0,0 +1,50 @@
+//! The comments of the Python source, which Python's syntax tree leaves out.
+//!
+//! A comment on the lines right above a statement is taken to be about it, so it's put
+//! before the statement's code, like [`line_comments`](crate::PythonOptions::line_comments)
+//! are. Comments at the end of a line, comments that aren't right above a statement and
+//! comments above a statement whose code can't have attributes, like an assignment to an
+//! existing variable, are dropped.
+
+use std::sync::Arc;
+
+/// The lines of the Python source, kept to find the comments above each statement.
+/// Clones share the lines, since the options are cloned for every node.
+#[derive(Clone, Debug, Default)]
+pub struct SourceComments(Arc<Vec<String>>);
+
+impl SourceComments {
+    pub fn new(source: &str) -> Self {
+        Self(Arc::new(source.lines().map(str::to_string).collect()))
+    }
+
+    /// The comments on the lines right above `line`, counting from 1, in order and without
+    /// their `#`. Decorators between the comments and a definition are skipped.
+    pub fn leading(&self, line: usize) -> Vec<String> {
+        let mut comments = Vec::new();
+        for text in self.0[..line.saturating_sub(1).min(self.0.len())].iter().rev() {
+            let text = text.trim();
+            if let Some(comment) = text.strip_prefix('#') {
+                comments.push(comment.strip_prefix(' ').unwrap_or(comment).trim_end().to_string());
+            } else if !text.starts_with('@') {
+                break;
+            }
+        }
+        comments.reverse();
+        comments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leading_comments() {
+        let comments = SourceComments::new("x = 1\n\n# first\n#second\n@decorator\ndef f():\n    # body\n    pass\n");
+        assert_eq!(comments.leading(1), Vec::<String>::new());
+        assert_eq!(comments.leading(6), vec!["first", "second"]);
+        assert_eq!(comments.leading(8), vec!["body"]);
+        assert_eq!(comments.leading(20), Vec::<String>::new());
+    }
+}
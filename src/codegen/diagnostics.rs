@@ -0,0 +1,204 @@
+//! Diagnostics about Python code that couldn't be translated faithfully.
+//!
+//! Code generation doesn't stop at the first node it can't translate. The node becomes a
+//! `todo!()` in the output instead, and a diagnostic about it is collected in
+//! [`PythonOptions::diagnostics`]: a warning for a node that isn't supported, and an error
+//! for a statement in a function body that failed. Callers can inspect the diagnostics
+//! afterwards to decide whether the output is good enough, or treat warnings as errors.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use tracing::warn;
+
+use crate::{CodeGenError, Error, PythonOptions, SourceLocation, UNKNOWN_FILE};
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The output is correct, but worth knowing about
+    Note,
+    /// The output compiles, but doesn't do what the Python code does
+    Warning,
+    /// The output is wrong
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Something found about the Python code while generating Rust for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub location: Option<SourceLocation>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        Ok(())
+    }
+}
+
+/// The diagnostics of a code generation run. The options are cloned for every node, and
+/// the clones share one collection, so diagnostics pushed anywhere are seen by the caller.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics(Arc<Mutex<Vec<Diagnostic>>>);
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, diagnostic: Diagnostic) {
+        if let Ok(mut diagnostics) = self.0.lock() {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Push a warning.
+    pub fn warn(&self, message: impl Into<String>, location: Option<SourceLocation>) {
+        self.push(Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            location,
+        });
+    }
+
+    /// All of the diagnostics so far, in the order they were pushed.
+    pub fn to_vec(&self) -> Vec<Diagnostic> {
+        self.0.lock().map(|diagnostics| diagnostics.clone()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().map(|diagnostics| diagnostics.is_empty()).unwrap_or(true)
+    }
+
+    /// Returns true if any diagnostic is at least as serious as `severity`, so passing
+    /// [`Severity::Warning`] treats warnings as errors.
+    pub fn any_at_least(&self, severity: Severity) -> bool {
+        self.0
+            .lock()
+            .map(|diagnostics| diagnostics.iter().any(|d| d.severity >= severity))
+            .unwrap_or(false)
+    }
+
+    /// Fill in the file of locations taken from nodes, once they reach the module.
+    pub fn in_file(&self, filename: &str) {
+        if let Ok(mut diagnostics) = self.0.lock() {
+            for location in diagnostics.iter_mut().filter_map(|d| d.location.as_mut()) {
+                if location.filename == UNKNOWN_FILE {
+                    location.filename = filename.to_string();
+                }
+            }
+        }
+    }
+}
+
+/// Warn that `node_type` isn't supported, and generate a `todo!()` standing in for it.
+pub fn unsupported_to_rust(
+    node_type: &str,
+    location: Option<SourceLocation>,
+    options: &PythonOptions,
+) -> TokenStream {
+    warn!("{} is not supported, generating todo!()", node_type);
+    options
+        .diagnostics
+        .warn(format!("{} is not supported", node_type), location);
+    let message = format!("UNSUPPORTED: {}", node_type);
+    quote!(todo!(#message))
+}
+
+/// Warn that `node_type` isn't supported where a module expects an item, like a function
+/// or an import, and generate a `compile_error!` standing in for it, since a `todo!()`
+/// isn't an item.
+pub fn unsupported_item_to_rust(
+    node_type: &str,
+    location: Option<SourceLocation>,
+    options: &PythonOptions,
+) -> TokenStream {
+    warn!("{} is not supported, generating compile_error!()", node_type);
+    options
+        .diagnostics
+        .warn(format!("{} is not supported", node_type), location);
+    let message = format!("UNSUPPORTED: {}", node_type);
+    quote!(compile_error!(#message);)
+}
+
+/// Record that generating code for a statement failed, and generate a `todo!()` standing
+/// in for it, so that the code around it is still generated. The error's own location is
+/// used if it has one, and `location` otherwise.
+pub fn failed_to_rust(
+    error: &(dyn std::error::Error + 'static),
+    location: SourceLocation,
+    options: &PythonOptions,
+) -> TokenStream {
+    warn!("Generating todo!() for a statement that failed: {}", error);
+    let location = error
+        .downcast_ref::<CodeGenError>()
+        .and_then(|e| e.location().cloned())
+        .unwrap_or(location);
+    options.diagnostics.push(Diagnostic {
+        severity: Severity::Error,
+        message: error.to_string(),
+        location: Some(location),
+    });
+    let message = format!("FAILED: {}", error);
+    quote!(todo!(#message))
+}
+
+/// The node type and location of an error about a node that isn't supported.
+pub fn unsupported_node(error: &(dyn std::error::Error + 'static)) -> Option<(String, Option<SourceLocation>)> {
+    match (error.downcast_ref::<CodeGenError>(), error.downcast_ref::<Error>()) {
+        (Some(CodeGenError::UnsupportedNode { node_type, location }), _) => {
+            Some((node_type.clone(), location.clone()))
+        }
+        (_, Some(Error::UnsupportedFeature { feature, location, .. })) => {
+            Some((feature.clone(), Some(location.clone())))
+        }
+        _ => None,
+    }
+}
+
+/// The name of the enum variant `node` is, like `Unknown` or `NamedExpr`.
+pub fn variant_name(node: &impl fmt::Debug) -> String {
+    let debug = format!("{:?}", node);
+    debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_diagnostics() {
+        let diagnostics = Diagnostics::new();
+        let clone = diagnostics.clone();
+        clone.warn("lossy", Some(SourceLocation::with_position(UNKNOWN_FILE, Some(2), Some(4))));
+        diagnostics.in_file("test.py");
+
+        let all = diagnostics.to_vec();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].to_string(), "warning: lossy at test.py:2:4");
+        assert!(diagnostics.any_at_least(Severity::Warning));
+        assert!(!diagnostics.any_at_least(Severity::Error));
+    }
+}
@@ -0,0 +1,171 @@
+//! Formatting generated code for people to read.
+//!
+//! `to_rust` produces a `TokenStream`, which prints on a single line. [`format_tokens`] runs
+//! it through rustfmt instead, which has to be installed, like it is with any Rust toolchain
+//! that has the rustfmt component. The line markers that
+//! [`line_comments`](crate::PythonOptions::line_comments) adds become `// line N` comments,
+//! and the ones for [`source_comments`](crate::PythonOptions::source_comments) become the
+//! comments they hold.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use proc_macro2::TokenStream;
+use regex::Regex;
+
+/// Format generated code with rustfmt. Fails if rustfmt can't be run, or if it can't
+/// parse the code, in which case the error holds what rustfmt reported.
+pub fn format_tokens(tokens: TokenStream) -> io::Result<String> {
+    let mut rustfmt = Command::new("rustfmt")
+        .args(["--edition", "2024", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // rustfmt reads all of its input before it writes anything, so writing first can't
+    // deadlock, and dropping stdin closes it.
+    if let Some(mut stdin) = rustfmt.stdin.take() {
+        stdin.write_all(tokens.to_string().as_bytes())?;
+    }
+
+    let output = rustfmt.wait_with_output()?;
+    if output.status.success() {
+        let code = String::from_utf8(output.stdout).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(line_comments(&code))
+    } else {
+        Err(io::Error::other(String::from_utf8_lossy(&output.stderr).into_owned()))
+    }
+}
+
+/// Turn the `#[doc = " line N"]` markers on statements into `// line N` comments, and the
+/// `#[doc = "//comment"]` markers into the comments.
+fn line_comments(code: &str) -> String {
+    let marker = Regex::new(r#"(?m)^(\s*)#\[doc = " line (\d+)"\]$"#).unwrap();
+    let code = marker.replace_all(code, "${1}// line ${2}");
+    // The comment is escaped like any string literal.
+    let comment = Regex::new(r#"(?m)^(\s*)#\[doc = ("//.*")\]$"#).unwrap();
+    comment
+        .replace_all(&code, |captures: &regex::Captures| {
+            let text = syn::parse_str::<syn::LitStr>(&captures[2]).map_or_else(|_| captures[2].to_string(), |lit| lit.value());
+            match text.strip_prefix("//").unwrap_or(&text) {
+                "" => format!("{}//", &captures[1]),
+                text => format!("{}// {}", &captures[1], text),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CodeGen, CodeGenContext, PythonOptions, SourceComments, SymbolTableScopes};
+    use crate::test_utils::{assert_compiles, module_to_rust, test_options};
+
+    #[test]
+    fn test_format_tokens() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let module = crate::parse("def add(a, b):\n    return a + b\n", "test.py").unwrap();
+        let tokens = module
+            .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+            .unwrap();
+
+        let code = format_tokens(tokens).unwrap();
+        assert!(code.lines().count() > 1, "{}", code);
+        assert!(code.lines().any(|line| line.starts_with("    ")), "{}", code);
+        assert!(code.ends_with('\n'), "{}", code);
+    }
+
+    #[test]
+    fn test_line_comments() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.line_comments = true;
+        let module = crate::parse("x = 1\n\ndef add(a, b):\n    total = a + b\n    print(total)\n", "test.py").unwrap();
+        let tokens = module
+            .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+            .unwrap();
+
+        let code = format_tokens(tokens).unwrap();
+        let lines: Vec<_> = code.lines().map(str::trim).collect();
+        let after = |comment: &str| lines.iter().position(|line| *line == comment).map(|i| lines[i + 1]);
+        assert!(after("// line 3").is_some_and(|line| line.starts_with("pub fn add")), "{}", code);
+        assert!(after("// line 4").is_some_and(|line| line.starts_with("let total")), "{}", code);
+        assert!(after("// line 5").is_some(), "{}", code);
+        assert!(!code.contains("#[doc = \" line"), "{}", code);
+    }
+
+    #[test]
+    fn test_source_comments() {
+        let source = "# The starting value\nx = 1\n\n# Adds \"a\" and b\ndef add(a, b):\n    #\n    # keep the sum\n    total = a + b  # dropped\n    print(total)\n";
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.source_comments = Some(SourceComments::new(source));
+        let module = crate::parse(source, "test.py").unwrap();
+        let tokens = module
+            .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+            .unwrap();
+
+        let code = format_tokens(tokens).unwrap();
+        let lines: Vec<_> = code.lines().map(str::trim).collect();
+        let after = |comment: &str| lines.iter().position(|line| *line == comment).map(|i| lines[i + 1]);
+        assert!(after("// The starting value").is_some_and(|line| line.contains("x")), "{}", code);
+        assert!(after("// Adds \"a\" and b").is_some_and(|line| line.starts_with("pub fn add")), "{}", code);
+        assert!(after("//").is_some_and(|line| line == "// keep the sum"), "{}", code);
+        assert!(after("// keep the sum").is_some_and(|line| line.starts_with("let total")), "{}", code);
+        assert!(!code.contains("dropped"), "{}", code);
+        assert!(!code.contains("#[doc = \"//"), "{}", code);
+    }
+
+    #[test]
+    fn test_line_markers_compile() {
+        let source = "def add(a: int, b: int) -> int:
+    total = a + b
+    total += 1
+    total = total * 2
+    print(total)
+    if total > 2:
+        return total
+    return a - b
+";
+        let mut options = test_options();
+        options.line_comments = true;
+        let code = module_to_rust(source, options);
+        assert!(code.contains("# [doc = \" line 2\"] let mut total"), "{}", code);
+        assert!(code.contains("# [doc = \" line 7\"] return total"), "{}", code);
+        // An assignment can't have attributes.
+        assert!(!code.contains("line 3") && !code.contains("line 4"), "{}", code);
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_source_comment_markers_compile() {
+        let source = "# Adds them
+def add(a: int, b: int) -> int:
+    # keep the sum
+    total = a + b
+    # then grow it
+    total += 1
+    if total > 2:
+        # too big
+        return total
+    return a - b
+";
+        let mut options = test_options();
+        options.source_comments = Some(SourceComments::new(source));
+        let code = module_to_rust(source, options);
+        assert!(code.starts_with("# [doc = \"//Adds them\"] pub fn add"), "{}", code);
+        assert!(code.contains("# [doc = \"//keep the sum\"] let mut total"), "{}", code);
+        assert!(code.contains("# [doc = \"//too big\"] return total"), "{}", code);
+        // An assignment can't have attributes.
+        assert!(!code.contains("then grow it"), "{}", code);
+        assert_compiles(&code);
+    }
+
+    #[test]
+    fn test_format_tokens_reports_invalid_code() {
+        let tokens: TokenStream = "fn () {}".parse().unwrap();
+        assert!(format_tokens(tokens).is_err());
+    }
+}
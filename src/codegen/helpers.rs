@@ -0,0 +1,230 @@
+//! Helper functions emitted into generated modules.
+//!
+//! Some Python operators have semantics no Rust operator matches, so they're generated as
+//! calls to small helper functions. A module only gets the definitions of the helpers its
+//! code actually calls.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote};
+
+use crate::{int_range, PythonOptions};
+
+/// A helper function that generated code can call by name.
+pub struct Helper {
+    /// The name generated code calls the helper by.
+    pub name: &'static str,
+    /// Generates the helper's definition, for the types of the options.
+    pub definition: fn(&PythonOptions) -> TokenStream,
+}
+
+/// All of the helpers, in the order their definitions are emitted.
+pub static HELPERS: &[Helper] = &[
+    Helper {
+        name: "python_floor_div",
+        definition: python_floor_div,
+    },
+    Helper {
+        name: "python_mod",
+        definition: python_mod,
+    },
+    Helper {
+        name: "py_truthy",
+        definition: py_truthy,
+    },
+];
+
+/// The int_type of the options, and whether it's a primitive, which the helpers on ints
+/// are written for. A bignum is taken by reference, since it isn't `Copy`.
+fn int_type(options: &PythonOptions) -> (TokenStream, bool) {
+    let primitive = int_range(options.int_rust_type()).is_some();
+    (options.int_type_tokens().unwrap_or_else(|_| quote!(i64)), primitive)
+}
+
+/// Python's `//` floors toward negative infinity, while Rust's `/` truncates toward zero.
+fn python_floor_div(options: &PythonOptions) -> TokenStream {
+    match int_type(options) {
+        (int, true) => quote! {
+            fn python_floor_div(a: #int, b: #int) -> #int {
+                let d = a / b;
+                if (a % b != 0) && ((a < 0) != (b < 0)) { d - 1 } else { d }
+            }
+        },
+        (int, false) => quote! {
+            fn python_floor_div(a: #int, b: #int) -> #int {
+                let zero = #int::default();
+                let d = &a / &b;
+                if (&a % &b != zero) && ((a < zero) != (b < zero)) { d - #int::from(1) } else { d }
+            }
+        },
+    }
+}
+
+/// Python's `%` takes the sign of the divisor, while Rust's takes the sign of the dividend.
+fn python_mod(options: &PythonOptions) -> TokenStream {
+    match int_type(options) {
+        (int, true) => quote! {
+            fn python_mod(a: #int, b: #int) -> #int {
+                ((a % b) + b) % b
+            }
+        },
+        (int, false) => quote! {
+            fn python_mod(a: #int, b: #int) -> #int {
+                ((&a % &b) + &b) % &b
+            }
+        },
+    }
+}
+
+/// Python's truthiness, for conditions whose type isn't known when they're generated.
+fn py_truthy(_options: &PythonOptions) -> TokenStream {
+    quote! {
+        trait PyTruthy {
+            fn py_truthy(&self) -> bool;
+        }
+
+        impl PyTruthy for bool {
+            fn py_truthy(&self) -> bool { *self }
+        }
+
+        impl PyTruthy for i64 {
+            fn py_truthy(&self) -> bool { *self != 0 }
+        }
+
+        impl PyTruthy for f64 {
+            fn py_truthy(&self) -> bool { *self != 0.0 }
+        }
+
+        impl PyTruthy for String {
+            fn py_truthy(&self) -> bool { !self.is_empty() }
+        }
+
+        impl PyTruthy for &str {
+            fn py_truthy(&self) -> bool { !self.is_empty() }
+        }
+
+        impl<T> PyTruthy for Vec<T> {
+            fn py_truthy(&self) -> bool { !self.is_empty() }
+        }
+
+        impl<K, V, S> PyTruthy for std::collections::HashMap<K, V, S> {
+            fn py_truthy(&self) -> bool { !self.is_empty() }
+        }
+
+        impl<T, S> PyTruthy for std::collections::HashSet<T, S> {
+            fn py_truthy(&self) -> bool { !self.is_empty() }
+        }
+
+        impl<T> PyTruthy for Option<T> {
+            fn py_truthy(&self) -> bool { self.is_some() }
+        }
+
+        fn py_truthy<T: PyTruthy>(value: &T) -> bool {
+            value.py_truthy()
+        }
+    }
+}
+
+/// The error type of a module's functions that raise, with a variant for each exception
+/// type raised or caught in the module. A variant remembers the exception's message and the
+/// error it was raised from, and errors that aren't Python exceptions, like I/O errors, are
+/// wrapped as they are. Except clauses match on a variant by its name with `is`.
+pub fn module_error(kinds: &[String]) -> TokenStream {
+    let variants: Vec<_> = kinds.iter().map(|kind| format_ident!("{}", kind)).collect();
+    quote! {
+        #[derive(Debug)]
+        pub enum ModuleError {
+            #(#variants { message: String, source: Option<Box<dyn std::error::Error>> },)*
+            Other(Box<dyn std::error::Error>),
+        }
+
+        impl ModuleError {
+            fn kind(&self) -> &'static str {
+                match self {
+                    #(ModuleError::#variants { .. } => #kinds,)*
+                    ModuleError::Other(_) => "Exception",
+                }
+            }
+
+            fn is(error: &(dyn std::error::Error + 'static), kind: &str) -> bool {
+                error.downcast_ref::<ModuleError>().is_some_and(|e| e.kind() == kind)
+            }
+        }
+
+        impl std::fmt::Display for ModuleError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    #(ModuleError::#variants { message, .. } => write!(f, "{}: {}", #kinds, message),)*
+                    ModuleError::Other(error) => write!(f, "{}", error),
+                }
+            }
+        }
+
+        impl std::error::Error for ModuleError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    #(ModuleError::#variants { source, .. } => source.as_deref(),)*
+                    ModuleError::Other(error) => error.source(),
+                }
+            }
+        }
+
+        impl From<Box<dyn std::error::Error>> for ModuleError {
+            fn from(error: Box<dyn std::error::Error>) -> Self {
+                match error.downcast::<ModuleError>() {
+                    Ok(error) => *error,
+                    Err(error) => ModuleError::Other(error),
+                }
+            }
+        }
+
+        impl From<std::io::Error> for ModuleError {
+            fn from(error: std::io::Error) -> Self {
+                ModuleError::Other(Box::new(error))
+            }
+        }
+    }
+}
+
+/// Whether `stream` refers to the identifier `name` anywhere.
+pub fn refers_to(stream: &TokenStream, name: &str) -> bool {
+    stream.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => refers_to(&group.stream(), name),
+        _ => false,
+    })
+}
+/// The definitions of the helpers that `stream` refers to.
+pub fn helper_definitions(stream: &TokenStream, options: &PythonOptions) -> TokenStream {
+    HELPERS
+        .iter()
+        .filter(|helper| refers_to(stream, helper.name))
+        .map(|helper| (helper.definition)(options))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_used_helpers_are_defined() {
+        let definitions = helper_definitions(&quote!(fn f() { python_mod(a, b) }), &PythonOptions::default()).to_string();
+        assert!(definitions.contains("fn python_mod"));
+        assert!(!definitions.contains("fn python_floor_div"));
+
+        assert!(helper_definitions(&quote!(a / b), &PythonOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_module_error_has_a_variant_per_exception() {
+        let kinds = vec!["KeyError".to_string(), "ValueError".to_string()];
+        let definition = module_error(&kinds).to_string();
+        syn::parse_str::<syn::File>(&definition).unwrap_or_else(|e| panic!("{}: {}", e, definition));
+        assert!(
+            definition.contains("KeyError { message : String , source : Option < Box < dyn std :: error :: Error >> } , ValueError {"),
+            "{}",
+            definition
+        );
+        assert!(definition.contains("ModuleError :: ValueError { .. } => \"ValueError\""), "{}", definition);
+    }
+}
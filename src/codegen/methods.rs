@@ -0,0 +1,1152 @@
+//! Translation of method calls on values whose Python type is known.
+//!
+//! Python's string, list and dict methods have Rust counterparts with different names,
+//! argument orders or return types. A method call is only rewritten when the receiver's type
+//! is known, from a literal or an annotation, so that methods of user-defined types with the
+//! same names are left alone. For lists and dicts, [`PythonOptions::container_method_heuristic`]
+//! can also have methods only containers have, like `append`, rewritten on unknown receivers.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    arg_to_rust, attribute_annotation, element_to_rust, exact_args, io_result, is_bytes_annotation, receiver_to_rust, translate_format_spec, Call, CodeGenContext,
+    CodeGenError, ExprType, Ops, PythonOptions, SymbolTableNode, SymbolTableScopes,
+};
+
+/// Generates the Rust code for a method call, or returns None to leave the call as it is.
+/// The generator is given the receiver of the method along with the call.
+pub type MethodGenerator = fn(
+    &ExprType,
+    &Call,
+    CodeGenContext,
+    PythonOptions,
+    SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>>;
+
+/// A Python method with special code generation.
+pub struct Method {
+    /// The method name, as called from Python.
+    pub name: &'static str,
+    /// Generates the Rust code for a call to the method.
+    pub generate: MethodGenerator,
+}
+
+/// Methods of `str`.
+pub static STRING_METHODS: &[Method] = &[
+    Method { name: "upper", generate: upper },
+    Method { name: "lower", generate: lower },
+    Method { name: "strip", generate: strip },
+    Method { name: "lstrip", generate: lstrip },
+    Method { name: "rstrip", generate: rstrip },
+    Method { name: "split", generate: split },
+    Method { name: "join", generate: join },
+    Method { name: "startswith", generate: startswith },
+    Method { name: "endswith", generate: endswith },
+    Method { name: "replace", generate: replace },
+    Method { name: "format", generate: format },
+    Method { name: "encode", generate: encode },
+];
+
+/// Methods of `bytes` and `bytearray`.
+pub static BYTES_METHODS: &[Method] = &[
+    Method { name: "decode", generate: decode },
+];
+
+/// Methods of `list`.
+pub static LIST_METHODS: &[Method] = &[
+    Method { name: "append", generate: append },
+    Method { name: "extend", generate: extend },
+    Method { name: "pop", generate: list_pop },
+    Method { name: "insert", generate: insert },
+];
+
+/// Methods of `dict`.
+pub static DICT_METHODS: &[Method] = &[
+    Method { name: "get", generate: dict_get },
+    Method { name: "keys", generate: keys },
+    Method { name: "values", generate: values },
+    Method { name: "items", generate: items },
+    Method { name: "setdefault", generate: setdefault },
+    Method { name: "pop", generate: dict_pop },
+];
+
+/// Methods of the files `open()` returns.
+pub static FILE_METHODS: &[Method] = &[
+    Method { name: "read", generate: read },
+    Method { name: "readlines", generate: readlines },
+    Method { name: "write", generate: write },
+];
+
+/// Methods that change their receiver, which then has to be bound with `let mut`.
+pub const MUTATING_METHODS: &[&str] = &[
+    "append", "extend", "pop", "insert", "remove", "clear", "sort", "reverse", "setdefault",
+    "update", "add", "discard", "popitem",
+];
+
+/// A container type methods can be translated for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContainerType {
+    List,
+    Dict,
+}
+
+impl ContainerType {
+    fn methods(&self) -> &'static [Method] {
+        match self {
+            ContainerType::List => LIST_METHODS,
+            ContainerType::Dict => DICT_METHODS,
+        }
+    }
+
+    /// The container a method belongs to, for methods only one of them has.
+    fn of_method(call: &Call, method: &str) -> Option<Self> {
+        match method {
+            "append" | "extend" | "insert" => Some(ContainerType::List),
+            "pop" if call.args.is_empty() => Some(ContainerType::List),
+            "get" | "keys" | "values" | "items" | "setdefault" => Some(ContainerType::Dict),
+            _ => None,
+        }
+    }
+
+    /// The container an annotation like `List[int]` or `dict` names.
+    fn of_annotation(annotation: &ExprType) -> Option<Self> {
+        let name = match annotation {
+            ExprType::Name(name) => name,
+            ExprType::Subscript(subscript) => match subscript.value.as_ref() {
+                ExprType::Name(name) => name,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        match name.id.as_str() {
+            "list" | "List" => Some(ContainerType::List),
+            "dict" | "Dict" => Some(ContainerType::Dict),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the container type `expr` is known to evaluate to.
+pub fn container_type(expr: &ExprType, symbols: &SymbolTableScopes) -> Option<ContainerType> {
+    container_type_within(expr, symbols, MAX_INFERENCE_DEPTH)
+}
+
+fn container_type_within(
+    expr: &ExprType,
+    symbols: &SymbolTableScopes,
+    depth: usize,
+) -> Option<ContainerType> {
+    if depth == 0 {
+        return None;
+    }
+    match expr {
+        ExprType::List(_) | ExprType::ListComp(_) => Some(ContainerType::List),
+        ExprType::Dict(_) | ExprType::DictComp(_) => Some(ContainerType::Dict),
+        ExprType::Call(call) => match call.func.as_ref() {
+            ExprType::Name(name) if symbols.lookup(&name.id).is_none() => match name.id.as_str() {
+                "list" => Some(ContainerType::List),
+                "dict" => Some(ContainerType::Dict),
+                _ => None,
+            },
+            _ => None,
+        },
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::Assign { value, .. }) => {
+                container_type_within(value, symbols, depth - 1)
+            }
+            _ => symbols.type_annotation(&name.id).and_then(ContainerType::of_annotation),
+        },
+        ExprType::Attribute(attribute) => attribute_annotation(attribute, symbols).as_ref().and_then(ContainerType::of_annotation),
+        _ => None,
+    }
+}
+
+/// Returns true if `expr` is known to be a file returned by `open()`.
+pub fn is_file_expr(expr: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    is_file_expr_within(expr, symbols, MAX_INFERENCE_DEPTH)
+}
+
+fn is_file_expr_within(expr: &ExprType, symbols: &SymbolTableScopes, depth: usize) -> bool {
+    if depth == 0 {
+        return false;
+    }
+    match expr {
+        ExprType::Call(call) => matches!(
+            call.func.as_ref(),
+            ExprType::Name(name) if name.id == "open" && symbols.lookup("open").is_none()
+        ),
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::Assign { value, .. }) => {
+                is_file_expr_within(value, symbols, depth - 1)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// How deeply names are followed to their assigned values when inferring types.
+const MAX_INFERENCE_DEPTH: usize = 8;
+
+/// Returns true if `expr` is known to evaluate to a `str`.
+pub fn is_str_expr(expr: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    is_str_expr_within(expr, symbols, MAX_INFERENCE_DEPTH)
+}
+
+fn is_str_expr_within(expr: &ExprType, symbols: &SymbolTableScopes, depth: usize) -> bool {
+    if depth == 0 {
+        return false;
+    }
+    match expr {
+        ExprType::Constant(c) => c.string_value().is_some(),
+        ExprType::JoinedStr(_) => true,
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::Assign { value, .. }) => {
+                is_str_expr_within(value, symbols, depth - 1)
+            }
+            _ => symbols
+                .type_annotation(&name.id)
+                .is_some_and(|annotation| matches!(annotation, ExprType::Name(n) if n.id == "str")),
+        },
+        ExprType::Attribute(attribute) => {
+            matches!(attribute_annotation(attribute, symbols), Some(ExprType::Name(n)) if n.id == "str")
+        }
+        // Methods like upper() return strings themselves, so chains can be followed.
+        ExprType::Call(call) => match call.func.as_ref() {
+            ExprType::Name(name) => name.id == "str" && symbols.lookup("str").is_none(),
+            ExprType::Attribute(attribute) => {
+                let string_method = matches!(
+                    attribute.attr.as_str(),
+                    "upper" | "lower" | "strip" | "lstrip" | "rstrip" | "replace" | "format" | "join"
+                );
+                (string_method && is_str_expr_within(&attribute.value, symbols, depth - 1))
+                    || (attribute.attr == "read" && is_file_expr(&attribute.value, symbols))
+                    || (attribute.attr == "decode" && is_bytes_expr(&attribute.value, symbols))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns true if `expr` is known to evaluate to `bytes` or a `bytearray`.
+pub fn is_bytes_expr(expr: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    is_bytes_expr_within(expr, symbols, MAX_INFERENCE_DEPTH)
+}
+
+fn is_bytes_expr_within(expr: &ExprType, symbols: &SymbolTableScopes, depth: usize) -> bool {
+    if depth == 0 {
+        return false;
+    }
+    match expr {
+        ExprType::Bytes(_) => true,
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::Assign { value, .. }) => is_bytes_expr_within(value, symbols, depth - 1),
+            _ => symbols
+                .type_annotation(&name.id)
+                .is_some_and(|annotation| is_bytes_annotation(annotation, symbols)),
+        },
+        ExprType::Call(call) => match call.func.as_ref() {
+            ExprType::Name(name) => {
+                matches!(name.id.as_str(), "bytes" | "bytearray") && symbols.lookup(&name.id).is_none()
+            }
+            ExprType::Attribute(attribute) => attribute.attr == "encode" && is_str_expr(&attribute.value, symbols),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Generates a method call through the method tables, if the receiver's type has one and
+/// the method is in it.
+pub fn method_call_to_rust(
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let ExprType::Attribute(attribute) = call.func.as_ref() else {
+        return Ok(None);
+    };
+    let methods = if is_str_expr(&attribute.value, &symbols) {
+        STRING_METHODS
+    } else if is_bytes_expr(&attribute.value, &symbols) {
+        BYTES_METHODS
+    } else if is_file_expr(&attribute.value, &symbols) {
+        FILE_METHODS
+    } else if let Some(container) = container_type(&attribute.value, &symbols) {
+        container.methods()
+    } else if options.container_method_heuristic
+        && let Some(container) = ContainerType::of_method(call, &attribute.attr)
+    {
+        container.methods()
+    } else {
+        return Ok(None);
+    };
+    match methods.iter().find(|method| method.name == attribute.attr) {
+        Some(method) => (method.generate)(&attribute.value, call, ctx, options, symbols),
+        None => Ok(None),
+    }
+}
+
+/// The receiver followed by a call to a Rust method taking no arguments.
+fn no_arg_method(
+    receiver: &ExprType,
+    call: &Call,
+    method: TokenStream,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if exact_args(call, 0..=0).is_none() {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.#method)))
+}
+
+/// `s.upper()` becomes `s.to_uppercase()`.
+fn upper(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(to_uppercase()), ctx, options, symbols)
+}
+
+/// `s.lower()` becomes `s.to_lowercase()`.
+fn lower(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(to_lowercase()), ctx, options, symbols)
+}
+
+/// `s.strip()` becomes `s.trim().to_string()`. Stripping given characters isn't translated.
+fn strip(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(trim().to_string()), ctx, options, symbols)
+}
+
+/// `s.lstrip()` becomes `s.trim_start().to_string()`.
+fn lstrip(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(trim_start().to_string()), ctx, options, symbols)
+}
+
+/// `s.rstrip()` becomes `s.trim_end().to_string()`.
+fn rstrip(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(trim_end().to_string()), ctx, options, symbols)
+}
+
+/// `s.split(sep)` becomes a `Vec<String>` of the parts, since Rust's split is lazy and
+/// borrows from the string. Without a separator, Python splits on runs of whitespace.
+fn split(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 0..=2) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let parts = match args {
+        [] => quote!(#receiver.split_whitespace()),
+        [sep] => {
+            let sep = arg_to_rust(sep, &ctx, &options, &symbols)?;
+            quote!(#receiver.split(#sep))
+        }
+        [sep, maxsplit] => {
+            // Python counts splits, Rust counts parts.
+            let Some(maxsplit) = as_int(maxsplit).filter(|i| *i >= 0) else {
+                return Ok(None);
+            };
+            let sep = arg_to_rust(sep, &ctx, &options, &symbols)?;
+            let parts = proc_macro2::Literal::i64_unsuffixed(maxsplit + 1);
+            quote!(#receiver.splitn(#parts, #sep))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(quote!(#parts.map(str::to_string).collect::<Vec<_>>())))
+}
+
+/// `sep.join(parts)` becomes `parts.join(sep)`; the separator is the receiver in Python but
+/// the argument in Rust.
+fn join(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([parts]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    let parts = receiver_to_rust(parts, &ctx, &options, &symbols)?;
+    let sep = arg_to_rust(receiver, &ctx, &options, &symbols)?;
+    // A literal is already a &str, anything else is made into one.
+    let sep = match receiver {
+        ExprType::Constant(_) => sep,
+        _ => quote!(&*#sep),
+    };
+    Ok(Some(quote!(#parts.join(#sep))))
+}
+
+/// `s.startswith(prefix)` becomes `s.starts_with(prefix)`.
+fn startswith(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    affix_method(receiver, call, quote!(starts_with), ctx, options, symbols)
+}
+
+/// Whether a call to `encode()` or `decode()` can be translated, which fails if its
+/// encoding argument isn't UTF-8, the encoding of Rust strings.
+fn utf8_encoding(call: &Call, method: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 0..=1) else {
+        return Ok(false);
+    };
+    let is_utf8 = |encoding: &ExprType| match encoding {
+        ExprType::Constant(c) => c
+            .string_value()
+            .is_some_and(|name| matches!(name.to_lowercase().replace('_', "-").as_str(), "utf-8" | "utf8")),
+        _ => false,
+    };
+    match args {
+        [encoding] if !is_utf8(encoding) => {
+            Err(CodeGenError::unsupported(format!("{}() with an encoding other than UTF-8", method), None).into())
+        }
+        _ => Ok(true),
+    }
+}
+
+/// `s.encode()` becomes `s.as_bytes().to_vec()`.
+fn encode(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if !utf8_encoding(call, "encode")? {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.as_bytes().to_vec())))
+}
+
+/// `b.decode()` becomes `String::from_utf8(b.to_vec()).unwrap()`, which panics on bytes
+/// that aren't UTF-8, where Python raises.
+fn decode(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if !utf8_encoding(call, "decode")? {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(String::from_utf8(#receiver.to_vec()).unwrap())))
+}
+
+/// `s.endswith(suffix)` becomes `s.ends_with(suffix)`.
+fn endswith(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    affix_method(receiver, call, quote!(ends_with), ctx, options, symbols)
+}
+
+/// Python also accepts a tuple of affixes and start/end positions, which aren't translated.
+fn affix_method(
+    receiver: &ExprType,
+    call: &Call,
+    method: TokenStream,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([affix]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    if matches!(affix, ExprType::Tuple(_)) {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let affix = string_arg(affix, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.#method(#affix))))
+}
+
+/// `s.replace(old, new)` is the same in Rust, and `s.replace(old, new, count)` becomes
+/// `s.replacen(old, new, count)`.
+fn replace(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 2..=3) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let old = string_arg(&args[0], &ctx, &options, &symbols)?;
+    let new = string_arg(&args[1], &ctx, &options, &symbols)?;
+    match args.get(2) {
+        None => Ok(Some(quote!(#receiver.replace(#old, #new)))),
+        Some(count) => {
+            let count = receiver_to_rust(count, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#receiver.replacen(#old, #new, #count as usize))))
+        }
+    }
+}
+
+/// `"template".format(...)` becomes `format!("template", ...)` when the template is a
+/// literal whose replacement fields can all be translated.
+fn format(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let ExprType::Constant(template) = receiver else {
+        return Ok(None);
+    };
+    let Some(template) = template.string_value() else {
+        return Ok(None);
+    };
+    let Some(template) = translate_format_template(&template) else {
+        return Ok(None);
+    };
+    if call.args.iter().any(|arg| matches!(arg, ExprType::Starred(_))) {
+        return Ok(None);
+    }
+
+    let mut arguments = Vec::new();
+    for arg in call.args.iter() {
+        arguments.push(arg_to_rust(arg, &ctx, &options, &symbols)?);
+    }
+    for keyword in call.keywords.iter() {
+        // **kwargs can't be expanded at compile time.
+        let Some(name) = &keyword.arg else {
+            return Ok(None);
+        };
+        let name = quote::format_ident!("{}", name);
+        let value = arg_to_rust(&keyword.value, &ctx, &options, &symbols)?;
+        arguments.push(quote!(#name = #value));
+    }
+
+    Ok(Some(quote!(format!(#template #(, #arguments)*))))
+}
+
+/// Translates the replacement fields of a `str.format` template to Rust's syntax, keeping
+/// positional and named fields as they are. Returns None for fields Rust can't express,
+/// like attribute access or indexing.
+fn translate_format_template(template: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push_str("}}");
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        '{' => return None,
+                        c => field.push(c),
+                    }
+                }
+                out.push_str(&translate_format_field(&field)?);
+            }
+            '}' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Translates `name[!conversion][:spec]` into a Rust placeholder.
+fn translate_format_field(field: &str) -> Option<String> {
+    let (field, spec) = match field.split_once(':') {
+        Some((field, spec)) => (field, Some(spec)),
+        None => (field, None),
+    };
+    let (name, debug) = match field.split_once('!') {
+        Some((name, "s")) => (name, false),
+        Some((name, "r" | "a")) => (name, true),
+        Some(_) => return None,
+        None => (field, false),
+    };
+    if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let spec = match spec {
+        Some(spec) => translate_format_spec(spec).ok()?,
+        None => String::new(),
+    };
+    if debug && spec.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Some(match (spec.is_empty(), debug) {
+        (true, false) => format!("{{{}}}", name),
+        (_, true) => format!("{{{}:{}?}}", name, spec),
+        (false, false) => format!("{{{}:{}}}", name, spec),
+    })
+}
+
+/// A string argument to a method taking a pattern, which must be borrowed unless it's a
+/// literal.
+fn string_arg(
+    arg: &ExprType,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let tokens = arg_to_rust(arg, ctx, options, symbols)?;
+    Ok(match arg {
+        ExprType::Constant(_) => tokens,
+        _ => quote!(&*#tokens),
+    })
+}
+
+/// `xs.append(v)` becomes `xs.push(v)`.
+fn append(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([value]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let value = arg_to_rust(value, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.push(#value))))
+}
+
+/// `xs.extend(ys)` is the same in Rust.
+fn extend(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([values]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let values = arg_to_rust(values, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.extend(#values))))
+}
+
+/// `xs.pop()` and `xs.pop(-1)` become `xs.pop().unwrap()`, and `xs.pop(i)` becomes
+/// `xs.remove(i)`.
+fn list_pop(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 0..=1) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    match args {
+        [] => Ok(Some(quote!(#receiver.pop().unwrap()))),
+        [index] if negated_int(index) == Some(1) => Ok(Some(quote!(#receiver.pop().unwrap()))),
+        // Other negative indexes count from the end, which remove() can't do.
+        [index] if negated_int(index).is_some() => Ok(None),
+        [index] => {
+            let index = index_arg(index, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#receiver.remove(#index))))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `xs.insert(i, v)` is the same in Rust.
+fn insert(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([index, value]) = exact_args(call, 2..=2) else {
+        return Ok(None);
+    };
+    if negated_int(index).is_some() {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let index = index_arg(index, &ctx, &options, &symbols)?;
+    let value = arg_to_rust(value, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.insert(#index, #value))))
+}
+
+/// `d.get(k)` becomes `d.get(&k).cloned()`, an Option, and `d.get(k, default)` unwraps it
+/// with the default.
+fn dict_get(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 1..=2) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let key = key_arg(&args[0], &ctx, &options, &symbols)?;
+    match args.get(1) {
+        None => Ok(Some(quote!(#receiver.get(#key).cloned()))),
+        Some(default) => {
+            let default = arg_to_rust(default, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#receiver.get(#key).cloned().unwrap_or(#default))))
+        }
+    }
+}
+
+/// `d.keys()` is the same in Rust.
+fn keys(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(keys()), ctx, options, symbols)
+}
+
+/// `d.values()` is the same in Rust.
+fn values(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(values()), ctx, options, symbols)
+}
+
+/// `d.items()` becomes `d.iter()`, which yields the same pairs.
+fn items(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    no_arg_method(receiver, call, quote!(iter()), ctx, options, symbols)
+}
+
+/// `d.setdefault(k, v)` becomes `d.entry(k).or_insert(v)`. The entry takes the key it
+/// inserts, so a string constant is a value of the `string_type`, as in dict literals.
+fn setdefault(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([key, value]) = exact_args(call, 2..=2) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let key = element_to_rust(key, &ctx, &options, &symbols)?;
+    let value = arg_to_rust(value, &ctx, &options, &symbols)?;
+    Ok(Some(quote!(#receiver.entry(#key).or_insert(#value))))
+}
+
+/// `d.pop(k)` becomes `d.remove(&k).unwrap()`, and `d.pop(k, default)` unwraps with the
+/// default instead.
+fn dict_pop(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some(args) = exact_args(call, 1..=2) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let key = key_arg(&args[0], &ctx, &options, &symbols)?;
+    match args.get(1) {
+        None => Ok(Some(quote!(#receiver.remove(#key).unwrap()))),
+        Some(default) => {
+            let default = arg_to_rust(default, &ctx, &options, &symbols)?;
+            Ok(Some(quote!(#receiver.remove(#key).unwrap_or(#default))))
+        }
+    }
+}
+
+/// `f.read()` reads the whole file into a String.
+fn read(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if exact_args(call, 0..=0).is_none() {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let result = io_result(&options);
+    Ok(Some(quote!({
+        let mut contents = String::new();
+        std::io::Read::read_to_string(&mut #receiver, &mut contents)#result;
+        contents
+    })))
+}
+
+/// `f.readlines()` reads the lines of the file, which keep their line endings in Python.
+fn readlines(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    if exact_args(call, 0..=0).is_none() {
+        return Ok(None);
+    }
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let result = io_result(&options);
+    Ok(Some(quote!(
+        std::io::BufRead::lines(std::io::BufReader::new(&mut #receiver))
+            .map(|line| line.map(|line| line + "\n"))
+            .collect::<Result<Vec<String>, _>>()#result
+    )))
+}
+
+/// `f.write(s)` writes all of `s` to the file.
+fn write(
+    receiver: &ExprType,
+    call: &Call,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<Option<TokenStream>, Box<dyn std::error::Error>> {
+    let Some([text]) = exact_args(call, 1..=1) else {
+        return Ok(None);
+    };
+    let receiver = receiver_to_rust(receiver, &ctx, &options, &symbols)?;
+    let text = receiver_to_rust(text, &ctx, &options, &symbols)?;
+    let result = io_result(&options);
+    Ok(Some(quote!(std::io::Write::write_all(&mut #receiver, #text.as_bytes())#result)))
+}
+
+/// A key to look up in a map, which is borrowed. String literals already are.
+fn key_arg(
+    arg: &ExprType,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let tokens = arg_to_rust(arg, ctx, options, symbols)?;
+    Ok(match arg {
+        ExprType::Constant(c) if c.string_value().is_some() => tokens,
+        _ => quote!(&#tokens),
+    })
+}
+
+/// An index into a Vec, which has to be a usize.
+fn index_arg(
+    arg: &ExprType,
+    ctx: &CodeGenContext,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    Ok(match arg {
+        ExprType::Constant(_) => arg_to_rust(arg, ctx, options, symbols)?,
+        _ => {
+            let index = receiver_to_rust(arg, ctx, options, symbols)?;
+            quote!(#index as usize)
+        }
+    })
+}
+
+/// If `expr` is a negative integer constant like `-1`, the number being negated.
+fn negated_int(expr: &ExprType) -> Option<i64> {
+    match expr {
+        ExprType::UnaryOp(u) if matches!(u.op, Ops::USub) => as_int(&u.operand),
+        _ => None,
+    }
+}
+
+fn as_int(expr: &ExprType) -> Option<i64> {
+    match expr {
+        ExprType::Constant(c) => c.int_value(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CodeGen;
+    use crate::test_utils::{module_to_rust, test_options};
+
+    #[test]
+    fn test_case_and_strip_methods() {
+        let code = module_to_rust(
+            "def shout(s: str):
+    a = s.upper()
+    b = s.lower()
+    c = s.strip()
+    d = s.strip().upper()
+",
+            test_options(),
+        );
+        assert!(code.contains("let a = s . to_uppercase ()"), "{}", code);
+        assert!(code.contains("let b = s . to_lowercase ()"), "{}", code);
+        assert!(code.contains("let c = s . trim () . to_string ()"), "{}", code);
+        assert!(code.contains("let d = s . trim () . to_string () . to_uppercase ()"), "{}", code);
+    }
+
+    #[test]
+    fn test_split_collects_owned_parts() {
+        let code = module_to_rust(
+            "def fields(line: str):
+    a = line.split(\",\")
+    b = line.split()
+    c = line.split(\",\", 1)
+",
+            test_options(),
+        );
+        assert!(
+            code.contains("let a = line . split (\",\") . map (str :: to_string) . collect :: < Vec < _ >> ()"),
+            "{}",
+            code
+        );
+        assert!(code.contains("line . split_whitespace () . map (str :: to_string)"), "{}", code);
+        assert!(code.contains("line . splitn (2 , \",\") . map (str :: to_string)"), "{}", code);
+    }
+
+    #[test]
+    fn test_join_swaps_receiver() {
+        let code = module_to_rust(
+            "def csv(parts, sep: str):
+    a = \",\".join(parts)
+    b = sep.join(parts)
+",
+            test_options(),
+        );
+        assert!(code.contains("let a = parts . join (\",\")"), "{}", code);
+        assert!(code.contains("let b = parts . join (& * sep)"), "{}", code);
+    }
+
+    #[test]
+    fn test_affix_and_replace_methods() {
+        let code = module_to_rust(
+            "def check(s: str, prefix: str):
+    a = s.startswith(\"x\")
+    b = s.endswith(prefix)
+    c = s.replace(\"a\", \"b\")
+",
+            test_options(),
+        );
+        assert!(code.contains("let a = s . starts_with (\"x\")"), "{}", code);
+        assert!(code.contains("let b = s . ends_with (& * prefix)"), "{}", code);
+        assert!(code.contains("let c = s . replace (\"a\" , \"b\")"), "{}", code);
+    }
+
+    #[test]
+    fn test_format_with_literal_template() {
+        let code = module_to_rust(
+            "def greet(name, score):
+    a = \"Hello, {}! {{ok}}\".format(name)
+    b = \"{0} scored {1:.2f}\".format(name, score)
+    c = \"{who}\".format(who=name)
+",
+            test_options(),
+        );
+        assert!(code.contains("let a = format ! (\"Hello, {}! {{ok}}\" , name)"), "{}", code);
+        assert!(code.contains("let b = format ! (\"{0} scored {1:.2}\" , name , score)"), "{}", code);
+        assert!(code.contains("let c = format ! (\"{who}\" , who = name)"), "{}", code);
+    }
+
+    #[test]
+    fn test_unknown_receivers_pass_through() {
+        let code = module_to_rust(
+            "def f(obj, s: str):
+    a = obj.upper()
+    b = s.casefold()
+",
+            test_options(),
+        );
+        assert!(code.contains("let a = obj . upper ()"), "{}", code);
+        assert!(code.contains("let b = s . casefold ()"), "{}", code);
+    }
+
+    #[test]
+    fn test_bytes_methods() {
+        let code = module_to_rust(
+            "def f(data: bytes, name: str):
+    header = b'\\x89PNG\\xff'
+    buf: bytearray = b'ab'
+    copy = bytearray(header)
+    raw = name.encode('utf-8')
+    return raw.decode().upper(), data.decode('UTF-8')
+",
+            test_options(),
+        );
+        assert!(code.contains("pub fn f (data : & [u8] , name : String)"), "{}", code);
+        assert!(code.contains("let header = b\"\\x89PNG\\xFF\" ;"), "{}", code);
+        assert!(code.contains("let buf : Vec < u8 > = b\"ab\" . to_vec () ;"), "{}", code);
+        assert!(code.contains("let copy = header . to_vec () ;"), "{}", code);
+        assert!(code.contains("let raw = name . as_bytes () . to_vec () ;"), "{}", code);
+        assert!(code.contains("String :: from_utf8 (raw . to_vec ()) . unwrap () . to_uppercase ()"), "{}", code);
+        assert!(code.contains("String :: from_utf8 (data . to_vec ()) . unwrap ()"), "{}", code);
+
+        let code = module_to_rust("def f(data: bytes):\n    return data.decode('latin-1')\n", test_options());
+        assert!(code.contains("decode() with an encoding other than UTF-8"), "{}", code);
+    }
+
+    #[test]
+    fn test_inferred_string_variable() {
+        let code = module_to_rust(
+            "def f():
+    s = \"a,b\"
+    parts = s.split(\",\")
+",
+            test_options(),
+        );
+        assert!(code.contains("s . split (\",\") . map (str :: to_string)"), "{}", code);
+    }
+
+    #[test]
+    fn test_append_in_loop_makes_list_mutable() {
+        let code = module_to_rust(
+            "def squares(n: int):
+    xs = []
+    for i in range(n):
+        xs.append(i * i)
+    return xs
+",
+            test_options(),
+        );
+        assert!(code.contains("let mut xs = vec ! []"), "{}", code);
+        assert!(code.contains("xs . push ((i) * (i))"), "{}", code);
+    }
+
+    #[test]
+    fn test_list_pop_and_insert() {
+        let code = module_to_rust(
+            "def shuffle(xs: list, i: int):
+    a = xs.pop()
+    b = xs.pop(0)
+    c = xs.pop(-1)
+    d = xs.pop(i)
+    xs.insert(0, a)
+",
+            test_options(),
+        );
+        assert!(code.contains("fn shuffle (mut xs"), "{}", code);
+        assert!(code.contains("let a = xs . pop () . unwrap ()"), "{}", code);
+        assert!(code.contains("let b = xs . remove (0)"), "{}", code);
+        assert!(code.contains("let c = xs . pop () . unwrap ()"), "{}", code);
+        assert!(code.contains("let d = xs . remove (i as usize)"), "{}", code);
+        assert!(code.contains("xs . insert (0 , a)"), "{}", code);
+    }
+
+    #[test]
+    fn test_dict_methods() {
+        let code = module_to_rust(
+            "def lookup(k: str):
+    d = {}
+    a = d.get(k, 0)
+    b = d.get(\"x\")
+    c = d.items()
+    d.setdefault(k, 1)
+    e = d.pop(k)
+",
+            test_options(),
+        );
+        let typed = module_to_rust(
+            "def count(d: Dict[str, int]):
+    d.setdefault(\"a\", 1)
+",
+            test_options(),
+        );
+        assert!(typed.contains("d . entry (String :: from (\"a\")) . or_insert (1)"), "{}", typed);
+        assert!(code.contains("let mut d ="), "{}", code);
+        assert!(code.contains("let a = d . get (& k) . cloned () . unwrap_or (0)"), "{}", code);
+        assert!(code.contains("let b = d . get (\"x\") . cloned ()"), "{}", code);
+        assert!(code.contains("let c = d . iter ()"), "{}", code);
+        assert!(code.contains("d . entry (k) . or_insert (1)"), "{}", code);
+        assert!(code.contains("let e = d . remove (& k) . unwrap ()"), "{}", code);
+    }
+
+    #[test]
+    fn test_unknown_receivers_need_the_heuristic() {
+        let source = "def add(xs, v):
+    xs.append(v)
+";
+        let code = module_to_rust(source, test_options());
+        assert!(code.contains("xs . append (v)"), "{}", code);
+
+        let module = crate::parse(source, "test.py").unwrap();
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.container_method_heuristic = true;
+        let code = module
+            .to_rust(
+                CodeGenContext::Module("test".to_string()),
+                options,
+                SymbolTableScopes::new(),
+            )
+            .unwrap()
+            .to_string();
+        assert!(code.contains("xs . push (v)"), "{}", code);
+    }
+}
@@ -8,11 +8,114 @@ pub use python_options::*;
 /// Reexport the CodeGen from to_tokenstream
 pub use to_tokenstream::CodeGen;
 
+use proc_macro2::{Punct, Spacing, TokenStream};
+use quote::{format_ident, quote};
+
 /// A type to track the context of code generation.
 #[derive(Clone, Debug)]
 pub enum CodeGenContext {
     Module(String),
+    /// The methods of a class, which are in an impl of its Data
     Class,
+    /// The methods of a trait or an impl of one, which have no visibility
+    Trait,
     Function,
     Async(Box<CodeGenContext>),
+    /// The body of a Python loop, which is labeled `label` in Rust if it needs to be.
+    Loop {
+        label: String,
+        outer: Box<CodeGenContext>,
+    },
+}
+
+impl CodeGenContext {
+    /// Whether code is generated for the body of an async function.
+    pub fn is_async(&self) -> bool {
+        match self {
+            CodeGenContext::Async(_) => true,
+            CodeGenContext::Loop { outer, .. } => outer.is_async(),
+            _ => false,
+        }
+    }
+
+    /// The number of loops around the code being generated.
+    pub fn loop_depth(&self) -> usize {
+        match self {
+            CodeGenContext::Loop { outer, .. } => 1 + outer.loop_depth(),
+            _ => 0,
+        }
+    }
+
+    /// The context for the body of a loop in this context, with a label of its own.
+    pub fn enter_loop(&self) -> CodeGenContext {
+        CodeGenContext::Loop {
+            label: format!("py_loop_{}", self.loop_depth() + 1),
+            outer: Box::new(self.clone()),
+        }
+    }
+
+    /// The context without the loops around it, for the body of a function, which break
+    /// and continue can't leave.
+    pub fn outside_loops(&self) -> CodeGenContext {
+        match self {
+            CodeGenContext::Loop { outer, .. } => outer.outside_loops(),
+            CodeGenContext::Async(inner) => CodeGenContext::Async(Box::new(inner.outside_loops())),
+            other => other.clone(),
+        }
+    }
+
+    /// The label break and continue use for the innermost loop. Labels are only used when
+    /// more than one loop is in scope, so that code with a single loop stays readable.
+    pub fn loop_label(&self) -> TokenStream {
+        match self {
+            CodeGenContext::Loop { label, .. } if self.loop_depth() > 1 => {
+                let apostrophe = Punct::new('\'', Spacing::Joint);
+                let label = format_ident!("{}", label);
+                quote!(#apostrophe #label)
+            }
+            _ => TokenStream::new(),
+        }
+    }
+
+    /// The label to put in front of a loop whose body is generated in this context.
+    pub fn loop_label_definition(&self) -> TokenStream {
+        let label = self.loop_label();
+        if label.is_empty() { label } else { quote!(#label:) }
+    }
 }
+
+pub mod builtins;
+pub use builtins::*;
+
+pub mod naming;
+pub use naming::*;
+
+pub mod diagnostics;
+pub use diagnostics::*;
+
+pub mod helpers;
+pub use helpers::*;
+
+pub mod methods;
+pub use methods::*;
+
+pub mod format;
+pub use format::*;
+
+pub mod comments;
+pub use comments::*;
+
+pub mod package;
+pub use package::*;
+
+pub mod type_resolver;
+pub use type_resolver::*;
+
+pub mod required_imports;
+pub use required_imports::*;
+
+pub mod truthiness;
+pub use truthiness::*;
+
+pub mod special_methods;
+pub use special_methods::*;
@@ -0,0 +1,233 @@
+//! Converting Python identifiers to idiomatic Rust ones.
+//!
+//! Python code doesn't always follow the casing Rust expects, so when
+//! [`PythonOptions::rust_case_identifiers`] is enabled, types are renamed to CamelCase,
+//! functions and variables to snake_case, and the constants of a module to
+//! SCREAMING_SNAKE_CASE; each of those can be turned off on its own. Renaming never produces an invalid identifier,
+//! and a name is left alone if its converted form is already taken by another symbol.
+
+use proc_macro2::{Ident, Span};
+use quote::format_ident;
+
+use crate::{PythonOptions, SymbolTableNode, SymbolTableScopes};
+
+/// The Rust naming convention an identifier should follow.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdentCase {
+    /// CamelCase, for types
+    Type,
+    /// snake_case, for functions and variables
+    Value,
+    /// SCREAMING_SNAKE_CASE, for the constants of a module
+    Constant,
+}
+
+impl IdentCase {
+    /// The convention for a name, based on what it's bound to.
+    pub fn of(node: &SymbolTableNode) -> Option<Self> {
+        match node {
+            SymbolTableNode::ClassDef { .. } => Some(IdentCase::Type),
+            SymbolTableNode::FunctionDef(_)
+            | SymbolTableNode::Assign { .. }
+            | SymbolTableNode::VariableDef { .. }
+            | SymbolTableNode::Parameter(_) => Some(IdentCase::Value),
+            _ => None,
+        }
+    }
+
+    /// Convert a name to this convention.
+    pub fn convert(&self, name: &str) -> String {
+        match self {
+            IdentCase::Type => to_camel_case(name),
+            IdentCase::Value => to_snake_case(name),
+            IdentCase::Constant => to_snake_case(name).to_uppercase(),
+        }
+    }
+
+    /// Whether the options ask for names following this convention to be renamed.
+    pub fn is_enabled(&self, options: &PythonOptions) -> bool {
+        options.rust_case_identifiers
+            && match self {
+                IdentCase::Type => options.rust_case_types,
+                IdentCase::Value => options.rust_case_values,
+                IdentCase::Constant => options.rust_case_constants,
+            }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+    "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do",
+    "final", "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual",
+    "yield",
+];
+
+/// Split a name into its leading underscores, the name itself, and its trailing underscores,
+/// which carry meaning in Python and are kept as they are.
+fn split_underscores(name: &str) -> (&str, &str, &str) {
+    let start = name.len() - name.trim_start_matches('_').len();
+    let end = name.trim_end_matches('_').len().max(start);
+    (&name[..start], &name[start..end], &name[end..])
+}
+
+/// Convert a name to snake_case, so `getHTTPResponse` becomes `get_http_response`.
+pub fn to_snake_case(name: &str) -> String {
+    let (prefix, core, suffix) = split_underscores(name);
+    let chars: Vec<char> = core.chars().collect();
+    let mut converted = String::from(prefix);
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // A word starts after a lowercase letter or digit, or at the last capital of an
+            // acronym that's followed by a lowercase letter.
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                converted.push('_');
+            }
+        }
+        converted.extend(c.to_lowercase());
+    }
+
+    converted.push_str(suffix);
+    converted
+}
+
+/// Convert a name to CamelCase, so `my_class` becomes `MyClass`.
+pub fn to_camel_case(name: &str) -> String {
+    let (prefix, core, suffix) = split_underscores(name);
+    let mut converted = String::from(prefix);
+
+    for word in core.split('_').filter(|word| !word.is_empty()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            converted.extend(first.to_uppercase());
+            converted.push_str(chars.as_str());
+        }
+    }
+
+    converted.push_str(suffix);
+    converted
+}
+
+/// The identifier of the variant an `Enum` member becomes, which is CamelCase if the
+/// options ask for it, so `DARK_RED` becomes `DarkRed`.
+pub fn variant_ident(name: &str, options: &PythonOptions) -> Ident {
+    if !options.rust_case_enum_variants {
+        return sanitize_ident(name);
+    }
+    // SCREAMING_SNAKE_CASE words are capitalized, not kept as they are.
+    if name.chars().any(|c| c.is_lowercase()) {
+        sanitize_ident(&to_camel_case(name))
+    } else {
+        sanitize_ident(&to_camel_case(&name.to_lowercase()))
+    }
+}
+
+/// Make an identifier out of a name, even if it isn't a valid Rust identifier as it is.
+/// Keywords become raw identifiers, except for those that can't be raw, which get an
+/// underscore appended, like names starting with a digit get one prepended. Characters
+/// that can't be in an identifier are replaced with underscores.
+pub fn sanitize_ident(name: &str) -> Ident {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    let name = name.as_str();
+    if name.is_empty() {
+        format_ident!("_")
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format_ident!("_{}", name)
+    } else if matches!(name, "self" | "Self" | "super" | "crate" | "_") {
+        format_ident!("{}_", name)
+    } else if KEYWORDS.contains(&name) {
+        Ident::new_raw(name, Span::call_site())
+    } else {
+        format_ident!("{}", name)
+    }
+}
+
+/// The identifier to generate for a Python name, following `case` if the options ask for
+/// Rust casing. Dunder names are never renamed, and neither is a name whose converted form
+/// is bound to something else, since the two would collide.
+pub fn convert_ident(
+    name: &str,
+    case: IdentCase,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Ident {
+    // A symbol that was renamed when it was bound is referred to by its new name.
+    if let Some(renamed) = symbols.renamed(name) {
+        return renamed.clone();
+    }
+    if !case.is_enabled(options) {
+        return sanitize_ident(name);
+    }
+    if name.starts_with("__") && name.ends_with("__") {
+        return sanitize_ident(name);
+    }
+
+    let converted = case.convert(name);
+    if converted != name && symbols.lookup(&converted).is_some() {
+        sanitize_ident(name)
+    } else {
+        sanitize_ident(&converted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("getValue"), "get_value");
+        assert_eq!(to_snake_case("GetValue"), "get_value");
+        assert_eq!(to_snake_case("getHTTPResponse"), "get_http_response");
+        assert_eq!(to_snake_case("already_snake"), "already_snake");
+        assert_eq!(to_snake_case("_privateName"), "_private_name");
+        assert_eq!(to_snake_case("value2Str"), "value2_str");
+    }
+
+    #[test]
+    fn test_variant_ident() {
+        let mut options = PythonOptions::default();
+        assert_eq!(variant_ident("DARK_RED", &options), "DarkRed");
+        assert_eq!(variant_ident("Red", &options), "Red");
+        options.rust_case_enum_variants = false;
+        assert_eq!(variant_ident("DARK_RED", &options), "DARK_RED");
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("my_class"), "MyClass");
+        assert_eq!(to_camel_case("MyClass"), "MyClass");
+        assert_eq!(to_camel_case("_private_class"), "_PrivateClass");
+    }
+
+    #[test]
+    fn test_sanitize_ident_is_always_valid() {
+        assert_eq!(sanitize_ident("type").to_string(), "r#type");
+        assert_eq!(sanitize_ident("crate").to_string(), "crate_");
+        assert_eq!(sanitize_ident("my-name").to_string(), "my_name");
+        assert_eq!(sanitize_ident("self").to_string(), "self_");
+        assert_eq!(sanitize_ident("2d").to_string(), "_2d");
+        assert_eq!(sanitize_ident("value").to_string(), "value");
+    }
+
+    #[test]
+    fn test_convert_ident_respects_option() {
+        let symbols = SymbolTableScopes::new();
+        let mut options = PythonOptions::default();
+        assert_eq!(convert_ident("getValue", IdentCase::Value, &options, &symbols), "getValue");
+
+        options.rust_case_identifiers = true;
+        assert_eq!(convert_ident("getValue", IdentCase::Value, &options, &symbols), "get_value");
+        assert_eq!(convert_ident("__init__", IdentCase::Value, &options, &symbols), "__init__");
+    }
+}
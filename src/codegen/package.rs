@@ -0,0 +1,306 @@
+//! Transpiling a Python package, a directory of modules that import each other.
+//!
+//! The packages and modules become the modules of a Rust crate with the same structure:
+//! the directory's `__init__.py` is the crate root, a subpackage's `__init__.py` is its
+//! `mod.rs`, and a relative import like `from .sibling import thing` becomes
+//! `use crate::sibling::thing;`.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    parse_enhanced, sanitize_ident, CodeGen, CodeGenContext, CodeGenError, ImportMapping, Module, ModuleLayout,
+    PythonOptions, StatementType, SymbolTableScopes,
+};
+
+/// A module of the package being transpiled.
+struct PackageModule {
+    /// The names of the packages it's in, followed by its own, which is empty for the
+    /// root's `__init__.py`
+    path: Vec<String>,
+    /// Whether it's the `__init__.py` of a package, or stands in for one that is missing
+    is_init: bool,
+    /// The file, relative to the package root
+    file: String,
+    module: Module,
+}
+
+impl PackageModule {
+    /// The package that relative imports in the module are relative to.
+    fn package(&self) -> &[String] {
+        if self.is_init { &self.path } else { &self.path[..self.path.len() - 1] }
+    }
+}
+
+/// Generate the code for every module of the package in `root`. The result maps each
+/// module's path in the crate, like `crate::sub::module`, to its code, which has the
+/// `pub mod` items declaring its submodules, so it can be written to `lib.rs`, `mod.rs`
+/// or a file named after the module. A directory without an `__init__.py` becomes a
+/// module that only declares its submodules.
+///
+/// Absolute imports of the package's own modules are mapped to the crate, and imports
+/// that form a cycle are a [`CodeGenError::CircularImport`].
+pub fn transpile_package(root: &Path, options: &PythonOptions) -> Result<BTreeMap<String, TokenStream>, Box<dyn Error>> {
+    let mut modules = Vec::new();
+    collect_modules(root, Path::new(""), &mut modules)?;
+
+    // The root is imported by its name if it's a package.
+    let root_name = root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|_| root.join("__init__.py").is_file());
+    let mut options = options.clone();
+    if let Some(name) = root_name {
+        options.map_import(name, ImportMapping::Path("crate".to_string()));
+    }
+    for module in modules.iter().filter(|module| module.path.len() == 1) {
+        options.map_import(&module.path[0], ImportMapping::Path(crate_path(&module.path)));
+    }
+
+    let order = import_order(&modules, root_name)?;
+
+    // The modules share the package's scope, so that the exceptions any of them raise
+    // are variants of every module's error type.
+    let mut symbols = SymbolTableScopes::new();
+    symbols.push_scope();
+    for &i in &order {
+        symbols = modules[i].module.clone().find_symbols(symbols);
+        symbols.pop_scope();
+    }
+
+    let mut generated = BTreeMap::new();
+    for &i in &order {
+        let module = &modules[i];
+        let mut module_options = options.clone();
+        module_options.module_layout = ModuleLayout::File;
+        module_options.package = module.package().to_vec();
+        module_options.reexport_imports = module.is_init;
+
+        let name = module.path.last().cloned().unwrap_or_default();
+        let mut tokens = module
+            .module
+            .clone()
+            .to_rust(CodeGenContext::Module(name), module_options, symbols.clone())?;
+        if module.is_init {
+            let submodules = modules
+                .iter()
+                .filter(|other| other.path.len() == module.path.len() + 1 && other.path.starts_with(&module.path))
+                .map(|other| sanitize_ident(&other.path[module.path.len()]));
+            tokens.extend(quote!(#(pub mod #submodules;)*));
+        }
+        generated.insert(crate_path(&module.path), tokens);
+    }
+    Ok(generated)
+}
+
+/// The path of a module in the crate.
+fn crate_path(path: &[String]) -> String {
+    std::iter::once("crate".to_string())
+        .chain(path.iter().map(|part| sanitize_ident(part).to_string()))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Parse the modules in `dir`, which is `relative` to the package root, and in the
+/// directories under it. Returns whether there were any.
+fn collect_modules(root: &Path, relative: &Path, modules: &mut Vec<PackageModule>) -> Result<bool, Box<dyn Error>> {
+    let mut entries = fs::read_dir(root.join(relative))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    let package: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let mut has_init = false;
+    let mut found = false;
+    for entry in entries {
+        let Some(name) = entry.file_name().and_then(|name| name.to_str()).map(String::from) else {
+            continue;
+        };
+        if entry.is_dir() {
+            if !name.starts_with('.') && name != "__pycache__" {
+                found |= collect_modules(root, &relative.join(&name), modules)?;
+            }
+            continue;
+        }
+        let Some(stem) = name.strip_suffix(".py") else {
+            continue;
+        };
+
+        let file = relative.join(&name).to_string_lossy().replace('\\', "/");
+        let module = parse_enhanced(fs::read_to_string(&entry)?, &file)?;
+        let is_init = stem == "__init__";
+        let mut path = package.clone();
+        if !is_init {
+            path.push(stem.to_string());
+        }
+        has_init |= is_init;
+        found = true;
+        modules.push(PackageModule { path, is_init, file, module });
+    }
+
+    // A namespace package, which has no __init__.py, still needs a module for its
+    // submodules to be in.
+    if found && !has_init {
+        modules.push(PackageModule {
+            path: package,
+            is_init: true,
+            file: relative.join("__init__.py").to_string_lossy().replace('\\', "/"),
+            module: Module::default(),
+        });
+    }
+    Ok(found)
+}
+
+/// The modules of the package a module imports at the top level, which run before it.
+fn dependencies(module: &PackageModule, modules: &[PackageModule], root_name: Option<&str>) -> Vec<usize> {
+    let find = |path: &[String]| modules.iter().position(|other| other.path == path);
+    // The path of a module imported by its absolute name, which starts with the root's
+    // name if the root is a package.
+    let absolute = |name: &str| {
+        let mut path: Vec<String> = name.split('.').map(String::from).collect();
+        if root_name.is_some_and(|root_name| path[0] == root_name) {
+            path.remove(0);
+        }
+        path
+    };
+    let mut dependencies = Vec::new();
+    for statement in &module.module.raw.body {
+        match &statement.statement {
+            StatementType::ImportFrom(import) if import.level > 0 => {
+                let Ok(base) = import.relative_module(module.package()) else {
+                    continue;
+                };
+                // `from . import sibling` only needs the sibling, not the package
+                // importing it.
+                for alias in &import.names {
+                    let mut submodule = base.clone();
+                    submodule.push(alias.name.clone());
+                    dependencies.extend(find(&submodule).or_else(|| find(&base)));
+                }
+            }
+            StatementType::ImportFrom(import) => {
+                let base = absolute(import.module.as_deref().unwrap_or_default());
+                for alias in &import.names {
+                    let mut submodule = base.clone();
+                    submodule.push(alias.name.clone());
+                    dependencies.extend(find(&submodule).or_else(|| find(&base)));
+                }
+            }
+            StatementType::Import(import) => {
+                for alias in &import.names {
+                    dependencies.extend(find(&absolute(&alias.name)));
+                }
+            }
+            _ => {}
+        }
+    }
+    dependencies.retain(|&dependency| modules[dependency].path != module.path);
+    dependencies
+}
+
+/// The order to generate the modules in, each after the modules it imports.
+fn import_order(modules: &[PackageModule], root_name: Option<&str>) -> Result<Vec<usize>, Box<dyn Error>> {
+    fn visit(
+        i: usize,
+        modules: &[PackageModule],
+        stack: &mut Vec<usize>,
+        done: &mut [bool],
+        order: &mut Vec<usize>,
+        root_name: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        if done[i] {
+            return Ok(());
+        }
+        if let Some(start) = stack.iter().position(|&j| j == i) {
+            let cycle = stack[start..].iter().chain([&i]).map(|&j| modules[j].file.clone()).collect();
+            return Err(CodeGenError::CircularImport { cycle, location: None }.into());
+        }
+        stack.push(i);
+        for dependency in dependencies(&modules[i], modules, root_name) {
+            visit(dependency, modules, stack, done, order, root_name)?;
+        }
+        stack.pop();
+        done[i] = true;
+        order.push(i);
+        Ok(())
+    }
+
+    let mut done = vec![false; modules.len()];
+    let mut order = Vec::new();
+    for i in 0..modules.len() {
+        visit(i, modules, &mut Vec::new(), &mut done, &mut order, root_name)?;
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Write the files of a package to a fresh directory named `name`.
+    fn package(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("python_ast_{}_{}", name, std::process::id())).join(name);
+        let _ = fs::remove_dir_all(&root);
+        for (file, source) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        root
+    }
+
+    fn options() -> PythonOptions {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options
+    }
+
+    #[test]
+    fn test_package_becomes_crate_modules() {
+        let root = package(
+            "shapes",
+            &[
+                ("__init__.py", "from .square import area\n"),
+                ("square.py", "from .util.numbers import double\n\ndef area(side):\n    return side * side\n"),
+                ("util/__init__.py", ""),
+                ("util/helpers.py", "def twice(x):\n    return x * 2\n"),
+                ("util/numbers.py", "from . import helpers\nfrom shapes.util.helpers import twice\n\ndef double(x):\n    return twice(x)\n"),
+            ],
+        );
+        let generated = transpile_package(&root, &options()).unwrap();
+        let keys: Vec<_> = generated.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["crate", "crate::square", "crate::util", "crate::util::helpers", "crate::util::numbers"]);
+
+        let code = |key: &str| generated[key].to_string();
+        assert_eq!(code("crate"), "pub use crate :: square :: area ; pub mod square ; pub mod util ;");
+        assert!(code("crate::square").starts_with("use crate :: util :: numbers :: double ;"), "{}", code("crate::square"));
+        assert_eq!(code("crate::util"), "pub mod helpers ; pub mod numbers ;");
+        assert!(
+            code("crate::util::numbers").starts_with("use crate :: util :: helpers ; use crate :: util :: helpers :: twice ;"),
+            "{}",
+            code("crate::util::numbers")
+        );
+    }
+
+    #[test]
+    fn test_circular_imports_are_reported() {
+        let root = package(
+            "cycle",
+            &[("a.py", "from .b import g\n\ndef f():\n    pass\n"), ("b.py", "from .a import f\n\ndef g():\n    pass\n")],
+        );
+        let error = transpile_package(&root, &options()).unwrap_err();
+        match error.downcast_ref::<CodeGenError>() {
+            Some(CodeGenError::CircularImport { cycle, .. }) => assert_eq!(cycle, &["a.py", "b.py", "a.py"]),
+            _ => panic!("expected a circular import, got {}", error),
+        }
+    }
+}
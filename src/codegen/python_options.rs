@@ -1,11 +1,13 @@
 //! Options for Python compilation.
 
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     default::Default,
 };
 
-use crate::Scope;
+use crate::{CodeGenError, Diagnostics, SourceComments, RequiredImports, Scope, Transformers, UnionEnums, UnknownTruthiness};
+use proc_macro2::TokenStream;
+use quote::quote;
 use pyo3::{prelude::*, PyResult};
 use std::ffi::CString;
 
@@ -55,6 +57,185 @@ impl AsyncRuntime {
     }
 }
 
+/// The Rust visibility given to generated items.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Visibility {
+    /// No visibility modifier
+    Private,
+    /// pub(crate)
+    Crate,
+    /// pub (default)
+    #[default]
+    Public,
+}
+
+impl Visibility {
+    /// Get the visibility modifier tokens
+    pub fn tokens(&self) -> TokenStream {
+        match self {
+            Visibility::Private => quote!(),
+            Visibility::Crate => quote!(pub(crate)),
+            Visibility::Public => quote!(pub),
+        }
+    }
+}
+
+/// How the visibility of generated items is decided.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum VisibilityPolicy {
+    /// Names starting with an underscore are private, dunder names crate-visible, and the
+    /// rest get [`PythonOptions::default_visibility`] (default)
+    #[default]
+    Underscore,
+    /// Every item is pub
+    Public,
+    /// Every item is private
+    Private,
+}
+
+/// The numeric type assumed for `**` when the operand types aren't known.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PowType {
+    /// Integer powers, `x.pow(n)` (default)
+    #[default]
+    Int,
+    /// Float powers, `x.powf(n)`
+    Float,
+}
+
+/// The Rust type Python strings become.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StringType {
+    /// Owned strings, `String` (default)
+    #[default]
+    String,
+    /// Borrowed strings, `&str`
+    Str,
+    /// Either, `Cow<str>`
+    Cow,
+}
+
+impl StringType {
+    /// Get the type's tokens
+    pub fn tokens(&self) -> TokenStream {
+        match self {
+            StringType::String => quote!(String),
+            StringType::Str => quote!(&str),
+            StringType::Cow => quote!(Cow<'_, str>),
+        }
+    }
+
+    /// Get the tokens of a string literal as a value of the type
+    pub fn literal(&self, literal: TokenStream) -> TokenStream {
+        match self {
+            StringType::String => quote!(String::from(#literal)),
+            StringType::Str => literal,
+            StringType::Cow => quote!(Cow::Borrowed(#literal)),
+        }
+    }
+}
+
+/// The Rust type `Callable` annotations become.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CallableStyle {
+    /// Anything that can be called, `impl Fn(A) -> R` (default)
+    #[default]
+    FnTrait,
+    /// Function pointers, `fn(A) -> R`
+    FnPointer,
+    /// Boxed closures, `Box<dyn Fn(A) -> R>`, which can be stored and returned
+    Boxed,
+}
+
+/// What is generated for keyword arguments to a callee whose parameters aren't known.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnknownKeywords {
+    /// Pass their values positionally, in the order they're written (default)
+    #[default]
+    Positional,
+    /// Fail, since which parameters the values are for can't be known
+    Error,
+}
+
+/// How Python exceptions are translated.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ErrorStrategy {
+    /// Raising an exception panics (default)
+    #[default]
+    Panic,
+    /// Functions that raise return a Result, and raising returns an Err
+    Result,
+}
+
+/// How assert statements are translated.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AssertMode {
+    /// `assert!`, which is always checked (default)
+    #[default]
+    Assert,
+    /// `debug_assert!`, which release builds strip like `python -O` does
+    DebugAssert,
+    /// A failed assertion returns an AssertionError in functions under the Result error
+    /// strategy, and panics with `assert!` elsewhere
+    Result,
+}
+
+/// How try statements are translated.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TryStrategy {
+    /// The try body runs in a closure returning a Result, raising in it or in the functions
+    /// it calls returns an Err, and the except clauses match on it (default)
+    #[default]
+    Result,
+    /// Raising panics and except clauses are dropped
+    Panic,
+}
+
+/// What an imported Python module is in Rust, in [`PythonOptions::import_map`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportMapping {
+    /// A Rust module, by its path from the generated code, like `std::collections` or
+    /// `crate::shims::math`
+    Path(String),
+    /// An external crate, like `ndarray`, that the generated code's crate depends on
+    Crate(String),
+    /// A module the code generator translates itself, like `os`, whose names the runtime
+    /// provides, so importing it doesn't generate anything
+    Shim,
+}
+
+/// The modules the code generator and the stdpython runtime translate.
+pub fn default_import_map() -> HashMap<String, ImportMapping> {
+    ["os", "sys", "subprocess", "json", "urllib", "xml", "asyncio", "typing", "enum", "dataclasses", "abc"]
+        .into_iter()
+        .map(|module| (module.to_string(), ImportMapping::Shim))
+        .collect()
+}
+
+/// How the code generated for a module is laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ModuleLayout {
+    /// A crate root, whose `main()` is the program's entry point (default)
+    #[default]
+    CrateRoot,
+    /// A `pub mod` named after the module, whose `main()` and init function are pub so that
+    /// the crate it's in can run them
+    Mod,
+    /// A module in a file of its own, whose `main()` and init function are pub like a
+    /// `Mod`'s
+    File,
+}
+
+impl ModuleLayout {
+    /// The visibility of the generated `main()` and init function.
+    pub fn entry_visibility(&self) -> TokenStream {
+        match self {
+            ModuleLayout::CrateRoot => quote!(),
+            ModuleLayout::Mod | ModuleLayout::File => quote!(pub),
+        }
+    }
+}
+
 pub fn sys_path() -> PyResult<Vec<String>> {
     let pymodule_code = include_str!("path.py");
 
@@ -84,6 +265,16 @@ pub struct PythonOptions {
     /// Collects all of the things we need to compile imports[module][asnames]
     pub imports: BTreeMap<String, HashSet<String>>,
 
+    /// What imported Python modules are in Rust, by module name. Submodules of a module
+    /// in the map, like `os.path`, are mapped under it, and importing a module that isn't
+    /// in the map is an error.
+    pub import_map: HashMap<String, ImportMapping>,
+
+    /// Rust types for Python types, like `rust_decimal::Decimal` for `Decimal`, which
+    /// annotations naming them resolve to instead of the built in mappings. Names can be
+    /// dotted, like `decimal.Decimal`.
+    pub type_map: HashMap<String, String>,
+
     pub scope: Scope,
 
     pub stdpython: String,
@@ -93,6 +284,200 @@ pub struct PythonOptions {
 
     /// The async runtime to use for async Python code
     pub async_runtime: AsyncRuntime,
+
+    /// The visibility of generated functions whose names don't start with an underscore.
+    pub default_visibility: Visibility,
+
+    /// Whether visibility follows the underscore naming convention, or is the same for
+    /// every item.
+    pub visibility_policy: VisibilityPolicy,
+
+    /// The operand type assumed for `**` when neither operand is a float constant.
+    pub default_pow_type: PowType,
+
+    /// The Rust type of `int` annotations and integer constants, like `i32`, `i64` or
+    /// `i128`. `bigint` is the arbitrary precision `num_bigint::BigInt`, which the crate
+    /// the code is generated for then depends on. A constant that doesn't fit a primitive
+    /// type is an error.
+    pub int_type: String,
+
+    /// The Rust type of `float` annotations.
+    pub float_type: String,
+
+    /// Whether `Callable[[A], R]` annotations accept closures, as `impl Fn(A) -> R` or
+    /// `Box<dyn Fn(A) -> R>`, or only functions, as `fn(A) -> R`.
+    pub callable_style: CallableStyle,
+
+    /// The Rust type of `str` annotations, which string constants are converted to where
+    /// one is expected, like the value of a variable annotated with `str`.
+    pub string_type: StringType,
+
+    /// The Rust type of annotations that are a union of more than one type, like
+    /// `Union[int, str]`. Without one, each union becomes an enum with a variant for each
+    /// of its types.
+    pub union_fallback: Option<String>,
+
+    /// The trait a type variable's `bound` becomes a bound of, like `Into` for
+    /// `T: Into<i64>` from `TypeVar("T", bound=int)`.
+    pub type_var_bound: String,
+
+    /// The enums generated for unions, which the module they're used in defines. Clones of
+    /// the options share them.
+    pub union_enums: UnionEnums,
+
+    /// The items the generated code needs imported, like `HashMap` for the `dict`
+    /// annotations. Clones of the options share them, and the module imports them.
+    pub required_imports: RequiredImports,
+
+    /// The crate providing the matrix types `@` operates on. Without one, `a @ b` calls a
+    /// `matmul(&a, &b)` function the user has to provide; with one that overloads `Mul` for
+    /// matrices, like `"nalgebra"`, it becomes `a * b` and the crate is imported.
+    pub matmul_crate: Option<String>,
+
+    /// Rename identifiers to Rust casing: classes to CamelCase, functions and variables
+    /// to snake_case, and the `Final` constants of a module to SCREAMING_SNAKE_CASE.
+    pub rust_case_identifiers: bool,
+
+    /// With `rust_case_identifiers`, rename classes.
+    pub rust_case_types: bool,
+
+    /// With `rust_case_identifiers`, rename functions, methods, parameters and variables.
+    pub rust_case_values: bool,
+
+    /// With `rust_case_identifiers`, rename the constants of a module.
+    pub rust_case_constants: bool,
+
+    /// Rename the members of `Enum` classes to CamelCase, the case of Rust enum variants,
+    /// so `DARK_RED` becomes `DarkRed`.
+    pub rust_case_enum_variants: bool,
+
+    /// What conditions are generated as when their type isn't known, so Python's
+    /// truthiness can't be checked.
+    pub unknown_truthiness: UnknownTruthiness,
+
+    /// What keyword arguments are generated as when the parameters of the callee aren't
+    /// known. Keyword arguments to functions and classes of the module are matched to
+    /// their parameters.
+    pub unknown_keywords: UnknownKeywords,
+
+    /// How raise statements are translated.
+    pub error_strategy: ErrorStrategy,
+
+    /// How try statements are translated. Exceptions raised where nothing catches them are
+    /// translated according to [`PythonOptions::error_strategy`].
+    pub try_strategy: TryStrategy,
+
+    /// How assert statements are translated.
+    pub assert_mode: AssertMode,
+
+    /// Translate methods only lists or dicts have, like `append` or `items`, even when the
+    /// receiver isn't known to be one.
+    pub container_method_heuristic: bool,
+
+    /// The name of the function that runs a module's top-level statements, other than
+    /// declarations and constants. The generated `main()` calls it first.
+    pub module_init: String,
+
+    /// Whether a module becomes a crate root or a `mod` block.
+    pub module_layout: ModuleLayout,
+
+    /// The package the module is in, as the names of the packages from the crate root.
+    /// Relative imports are resolved against it.
+    pub package: Vec<String>,
+
+    /// Make the imports of the module `pub use`, which is how a package's `__init__.py`
+    /// re-exports the names it imports.
+    pub reexport_imports: bool,
+
+    /// Put a `// line N` comment before the code of each statement, with the line of the
+    /// Python it came from. The tokens carry them as `#[doc]` attributes, which
+    /// [`format_tokens`](crate::format_tokens) turns into comments. Statements whose code
+    /// can't have attributes, like assignments to an existing variable, get no comment, so
+    /// the tokens compile as they are.
+    pub line_comments: bool,
+
+    /// The comments of the Python source, to put the ones right above a statement before
+    /// its code as `//` comments. The syntax tree doesn't have the comments, so this needs
+    /// the source, like `SourceComments::new(source)`. Like `line_comments`, they're
+    /// `#[doc]` attributes in the tokens until [`format_tokens`](crate::format_tokens),
+    /// and are left out where those can't go.
+    pub source_comments: Option<SourceComments>,
+
+    /// Collects warnings about code that couldn't be translated faithfully, like nodes
+    /// that became `todo!()`. Clones of the options share it, so the caller can inspect
+    /// it once code generation is done.
+    pub diagnostics: Diagnostics,
+
+    /// Rewrite the syntax tree of a module before code is generated for it. Clones of the
+    /// options share them.
+    pub transformers: Transformers,
+
+    /// What code is being generated for, which the nodes inside it generate differently.
+    pub(crate) state: CodeGenState,
+}
+
+/// The state of code generation that the options carry down the syntax tree, like whether
+/// the function being generated returns a Result. It's not an option, so it isn't public.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CodeGenState {
+    /// The type variables of the generic class whose methods are being generated, which
+    /// they use without declaring them again.
+    pub type_parameters: Vec<String>,
+
+    /// The methods being generated are of a Protocol, whose methods without a body are
+    /// the ones its trait requires.
+    pub in_protocol: bool,
+
+    /// The methods of the class being generated that change the instance, which take
+    /// `&mut self` rather than `&self`.
+    pub mutating_methods: HashSet<String>,
+
+    /// The attributes of `self` the constructor being generated assigns, which are its
+    /// local variables until it builds the instance out of them.
+    pub constructor_fields: Vec<String>,
+
+    /// The functions nested in the function being generated that it uses as values, like
+    /// returning them, rather than only calling them. As closures, they take what they
+    /// capture with them.
+    pub escaping_closures: Vec<String>,
+
+    /// Set while generating the body of a function that returns a Result, so that raise
+    /// and return statements produce Err and Ok.
+    pub returns_result: bool,
+
+    /// Set while generating the body of a function annotated to return `str`, so that the
+    /// string constants it returns have the `string_type`.
+    pub returns_string: bool,
+
+    /// Set while generating the body of a function annotated to return an `Optional`, so
+    /// that the values other than None it returns are `Some`.
+    pub returns_option: bool,
+
+    /// Set while generating the body of a generator annotated to yield `str`, so that the
+    /// string constants it yields have the `string_type`.
+    pub yields_string: bool,
+
+    /// Set while generating the body of a generator function, so that yields collect the
+    /// values into a Vec that is returned as an iterator.
+    pub in_generator: bool,
+
+    /// Set while generating the body of a class's `__next__`, which becomes
+    /// `Iterator::next()`, so that returns produce Some and raising StopIteration returns
+    /// None.
+    pub in_iterator_next: bool,
+
+    /// The variable holding the exception being handled, set while generating an except
+    /// clause, so that raise can re-raise it.
+    pub handled_exception: Option<String>,
+
+    /// Set while generating the clauses of a try statement that returns from them, which
+    /// run in closures, so that return statements return their value from the closure in
+    /// a Some, for the function to return once the try statement has finished.
+    pub returns_from_try: bool,
+
+    /// Set while generating the body of a loop with an else clause, so that break sets the
+    /// `broke` flag that keeps the else clause from running.
+    pub in_loop_with_else: bool,
 }
 
 impl Default for PythonOptions {
@@ -102,11 +487,45 @@ impl Default for PythonOptions {
             // XXX: Remove unwrap.
             python_path: sys_path().unwrap(),
             imports: BTreeMap::new(),
+            import_map: default_import_map(),
+            type_map: HashMap::new(),
             scope: Scope::default(),
             stdpython: "stdpython".to_string(),
             with_std_python: true,
             allow_unsafe: false,
             async_runtime: AsyncRuntime::default(),
+            default_visibility: Visibility::default(),
+            visibility_policy: VisibilityPolicy::default(),
+            default_pow_type: PowType::default(),
+            int_type: "i64".to_string(),
+            float_type: "f64".to_string(),
+            string_type: StringType::default(),
+            callable_style: CallableStyle::default(),
+            union_fallback: None,
+            type_var_bound: "Into".to_string(),
+            union_enums: UnionEnums::new(),
+            required_imports: RequiredImports::new(),
+            matmul_crate: None,
+            rust_case_identifiers: false,
+            rust_case_types: true,
+            rust_case_values: true,
+            rust_case_constants: true,
+            rust_case_enum_variants: true,
+            unknown_truthiness: UnknownTruthiness::default(),
+            unknown_keywords: UnknownKeywords::default(),
+            error_strategy: ErrorStrategy::default(),
+            try_strategy: TryStrategy::default(),
+            assert_mode: AssertMode::default(),
+            container_method_heuristic: false,
+            module_init: "__module_init__".to_string(),
+            module_layout: ModuleLayout::default(),
+            package: Vec::new(),
+            reexport_imports: false,
+            line_comments: false,
+            source_comments: None,
+            diagnostics: Diagnostics::new(),
+            transformers: Transformers::new(),
+            state: CodeGenState::default(),
         }
     }
 }
@@ -148,4 +567,67 @@ impl PythonOptions {
         self.async_runtime = runtime;
         self
     }
+
+    /// Map a Python type to a Rust type
+    pub fn map_type(&mut self, python: impl Into<String>, rust: impl Into<String>) -> &mut Self {
+        self.type_map.insert(python.into(), rust.into());
+        self
+    }
+
+    /// The Rust type of ints, [`PythonOptions::int_type`] with `bigint` spelled out.
+    pub fn int_rust_type(&self) -> &str {
+        match self.int_type.as_str() {
+            "bigint" => "num_bigint::BigInt",
+            int_type => int_type,
+        }
+    }
+
+    /// The tokens of [`PythonOptions::int_rust_type`].
+    pub fn int_type_tokens(&self) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        self.int_rust_type()
+            .parse()
+            .map_err(|_| CodeGenError::malformed(format!("the int_type isn't a Rust type: {}", self.int_type)).into())
+    }
+
+    /// The float_type as tokens.
+    pub fn float_type_tokens(&self) -> Result<TokenStream, Box<dyn std::error::Error>> {
+        self.float_type
+            .parse()
+            .map_err(|_| CodeGenError::malformed(format!("the float_type isn't a Rust type: {}", self.float_type)).into())
+    }
+
+    /// Map an imported Python module to Rust
+    pub fn map_import(&mut self, module: impl Into<String>, mapping: ImportMapping) -> &mut Self {
+        self.import_map.insert(module.into(), mapping);
+        self
+    }
+
+    /// Create PythonOptions that make every generated function pub, with the
+    /// [`VisibilityPolicy::Public`] policy
+    pub fn with_force_pub() -> Self {
+        Self {
+            visibility_policy: VisibilityPolicy::Public,
+            ..Self::default()
+        }
+    }
+
+    /// The visibility of a generated item with the given Python name.
+    ///
+    /// The Python convention is that names beginning with a single underscore are
+    /// private, and dunder names are kept crate-visible. Everything else gets
+    /// [`PythonOptions::default_visibility`], unless the
+    /// [`PythonOptions::visibility_policy`] overrides it all.
+    pub fn visibility_for(&self, name: &str) -> Visibility {
+        if self.visibility_policy == VisibilityPolicy::Public {
+            Visibility::Public
+        } else if self.visibility_policy == VisibilityPolicy::Private {
+            Visibility::Private
+        } else if name.starts_with("__") && name.ends_with("__") {
+            Visibility::Crate
+        } else if name.starts_with('_') && !name.starts_with("__") {
+            Visibility::Private
+        } else {
+            self.default_visibility
+        }
+    }
 }
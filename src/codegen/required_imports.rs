@@ -0,0 +1,61 @@
+//! The `use` items generated code needs.
+//!
+//! Some types are generated by their names, like `HashMap<K, V>` for `dict[K, V]`. The code
+//! generating one records the item it needs in [`PythonOptions::required_imports`], and the
+//! module imports each item once, with the Python module's own imports.
+//!
+//! [`PythonOptions::required_imports`]: crate::PythonOptions::required_imports
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// The paths of the items a module's code needs imported. Clones of the options share them,
+/// so the module sees the items needed anywhere in it.
+#[derive(Clone, Debug, Default)]
+pub struct RequiredImports(Arc<Mutex<BTreeSet<String>>>);
+
+impl RequiredImports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the code needs the item at `path`, like `std::collections::HashMap`.
+    pub fn require(&self, path: &str) {
+        if let Ok(mut paths) = self.0.lock() {
+            paths.insert(path.to_string());
+        }
+    }
+
+    /// The `use` items for the paths, in alphabetical order. They're removed, so that the
+    /// next module imports the ones it needs again.
+    pub fn take(&self) -> TokenStream {
+        let paths = self.0.lock().map(|mut paths| std::mem::take(&mut *paths)).unwrap_or_default();
+        paths
+            .iter()
+            .filter_map(|path| path.parse::<TokenStream>().ok())
+            .map(|path| quote!(use #path;))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_are_deduplicated() {
+        let imports = RequiredImports::new();
+        let shared = imports.clone();
+        shared.require("std::collections::HashSet");
+        imports.require("std::collections::HashMap");
+        shared.require("std::collections::HashMap");
+        assert_eq!(
+            imports.take().to_string(),
+            "use std :: collections :: HashMap ; use std :: collections :: HashSet ;"
+        );
+        assert!(imports.take().is_empty());
+    }
+}
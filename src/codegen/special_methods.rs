@@ -0,0 +1,799 @@
+//! Special methods of classes, which become implementations of Rust traits.
+//!
+//! Python calls methods like `__add__` for its operators and builtins, where Rust uses
+//! traits like `std::ops::Add`, so a class's special methods are generated as the
+//! implementations of those traits for the class's struct, rather than as methods of the
+//! class's trait. The others, like `__len__`, become the inherent methods Rust code calls
+//! instead.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    annotation_to_rust_type, convert_ident, is_str_annotation, ClassDef, CodeGen, CodeGenContext, CodeGenError, Expr,
+    ExprType, FunctionDef, IdentCase, is_docstring, Parameter, PythonOptions, StatementType, SymbolTableScopes, UNKNOWN_FILE, Node,
+};
+
+/// The struct a class's special methods are implemented for.
+pub struct ImplTarget<'a> {
+    /// The class the special methods are defined in
+    pub class: &'a ClassDef,
+    /// The class's generic parameters, like `<T>`
+    pub generics: TokenStream,
+    /// The class's type arguments, like `<T>`, which the struct is named with
+    pub arguments: TokenStream,
+    /// The visibility of the class's inherent methods
+    pub visibility: TokenStream,
+}
+
+/// Generates the implementation a special method becomes.
+pub type SpecialMethodGenerator = fn(
+    &FunctionDef,
+    &ImplTarget,
+    PythonOptions,
+    SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>>;
+
+/// A special method with a Rust counterpart.
+pub struct SpecialMethod {
+    /// The Python name of the method.
+    pub name: &'static str,
+    /// The code generator for the method.
+    pub generate: SpecialMethodGenerator,
+}
+
+/// The table of special methods consulted by the ClassDef code generation.
+pub static SPECIAL_METHODS: &[SpecialMethod] = &[
+    SpecialMethod { name: "__add__", generate: add },
+    SpecialMethod { name: "__sub__", generate: sub },
+    SpecialMethod { name: "__mul__", generate: mul },
+    SpecialMethod { name: "__neg__", generate: neg },
+    SpecialMethod { name: "__len__", generate: len },
+    SpecialMethod { name: "__contains__", generate: contains },
+    SpecialMethod { name: "__getitem__", generate: getitem },
+    SpecialMethod { name: "__setitem__", generate: setitem },
+    SpecialMethod { name: "__repr__", generate: repr },
+    SpecialMethod { name: "__str__", generate: str },
+];
+
+/// The methods that compare instances, which are implemented together by
+/// [`comparison_impls`], since the traits they become build on each other.
+pub const COMPARISON_METHODS: &[&str] = &["__eq__", "__hash__", "__lt__", "__le__", "__gt__", "__ge__"];
+
+/// Whether a method of a class is one of the [`COMPARISON_METHODS`].
+pub fn is_comparison_method(function: &FunctionDef) -> bool {
+    function.args.without_receiver().1 && COMPARISON_METHODS.contains(&function.name.as_str())
+}
+
+/// Whether a method of a class makes it an iterator: `__next__`, and an `__iter__` that
+/// returns `self`, which are implemented together by [`iterator_impls`]. Any other
+/// `__iter__`, like a generator, stays a method.
+pub fn is_iterator_method(function: &FunctionDef) -> bool {
+    function.args.without_receiver().1
+        && (function.name == "__next__" || function.name == "__iter__" && returns_self(function))
+}
+
+/// Whether the body of a method only returns `self`, after its docstring.
+fn returns_self(function: &FunctionDef) -> bool {
+    let body: Vec<_> = function.body.iter().filter(|s| !is_docstring(s)).collect();
+    matches!(body.as_slice(), [s] if matches!(&s.statement,
+        StatementType::Return(Some(Expr { value: ExprType::Name(name), .. })) if name.id == "self"))
+}
+
+/// Looks up the special method a method of a class is, if it has a Rust counterpart. Only
+/// methods taking `self` are special.
+pub fn lookup_special_method(function: &FunctionDef) -> Option<&'static SpecialMethod> {
+    if !function.args.without_receiver().1 {
+        return None;
+    }
+    SPECIAL_METHODS.iter().find(|special| special.name == function.name)
+}
+
+/// Whether `annotation` names the class itself, like `Vector` or `"Vector"`.
+fn is_own_class(annotation: &ExprType, class: &ClassDef) -> bool {
+    match annotation {
+        ExprType::Name(name) => name.id == class.name,
+        ExprType::Constant(constant) => constant.string_value().is_some_and(|value| value == class.name),
+        _ => false,
+    }
+}
+
+/// The Rust type of an annotation of a special method, `Self` if it's the class or if
+/// there's no annotation and `inferred` is None.
+fn special_type(
+    annotation: Option<&ExprType>,
+    inferred: Option<TokenStream>,
+    target: &ImplTarget,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    match annotation {
+        Some(annotation) if is_own_class(annotation, target.class) => Ok(quote!(Self)),
+        Some(annotation) => {
+            let rust_type = annotation_to_rust_type(annotation, CodeGenContext::Class, options.clone(), symbols.clone())?;
+            Ok(quote!(#rust_type))
+        }
+        None => Ok(inferred.unwrap_or_else(|| quote!(Self))),
+    }
+}
+
+/// The parameters of a special method after `self`, which has to take `count` of them.
+fn parameters(function: &FunctionDef, count: usize) -> Result<Vec<Parameter>, Box<dyn std::error::Error>> {
+    let (arguments, _) = function.args.without_receiver();
+    let parameters: Vec<Parameter> = arguments.parameters().cloned().collect();
+    if parameters.len() != count || arguments.vararg.is_some() || arguments.kwarg.is_some() {
+        return Err(CodeGenError::unsupported(
+            format!("{} with parameters other than {} after self", function.name, count),
+            Some(function.source_location(UNKNOWN_FILE)),
+        )
+        .into());
+    }
+    Ok(parameters)
+}
+
+/// The body of a special method, in the scope of its parameters, as the statements and
+/// the value it returns at its end.
+fn body(
+    function: &FunctionDef,
+    options: &PythonOptions,
+    symbols: SymbolTableScopes,
+) -> (TokenStream, Option<TokenStream>) {
+    let symbols = function.scope_symbols(symbols);
+    let mut options = options.clone();
+    // A trait's method can't change its return type to a Result or an iterator.
+    options.state.returns_result = false;
+    options.state.in_generator = false;
+    options.state.returns_string = function.returns.as_ref().is_some_and(|returns| is_str_annotation(returns, &symbols));
+    function.body_to_rust(&CodeGenContext::Class, &options, &symbols)
+}
+
+/// The body of a special method as a block.
+fn block(function: &FunctionDef, options: &PythonOptions, symbols: SymbolTableScopes) -> TokenStream {
+    let (statements, tail) = body(function, options, symbols);
+    quote!({ #statements #tail })
+}
+
+fn parameter_name(parameter: &Parameter, options: &PythonOptions, symbols: &SymbolTableScopes) -> TokenStream {
+    let name = convert_ident(&parameter.arg, IdentCase::Value, options, symbols);
+    quote!(#name)
+}
+
+/// A binary operator's trait, like `impl Add<Rhs> for Data`, which the method implements.
+fn binary_operator(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    op_trait: TokenStream,
+    method: TokenStream,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let [other] = <[Parameter; 1]>::try_from(parameters(function, 1)?).unwrap();
+    let other_type = special_type(other.annotation.as_deref(), None, target, &options, &symbols)?;
+    let output = special_type(function.returns.as_deref(), None, target, &options, &symbols)?;
+    let other = parameter_name(&other, &options, &symbols);
+    let block = block(function, &options, symbols);
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        impl #generics #op_trait<#other_type> for Data #arguments {
+            type Output = #output;
+            fn #method(self, #other: #other_type) -> Self::Output #block
+        }
+    })
+}
+
+fn add(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    binary_operator(function, target, quote!(std::ops::Add), quote!(add), options, symbols)
+}
+
+fn sub(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    binary_operator(function, target, quote!(std::ops::Sub), quote!(sub), options, symbols)
+}
+
+fn mul(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    binary_operator(function, target, quote!(std::ops::Mul), quote!(mul), options, symbols)
+}
+
+fn neg(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    parameters(function, 0)?;
+    let output = special_type(function.returns.as_deref(), None, target, &options, &symbols)?;
+    let block = block(function, &options, symbols);
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        impl #generics std::ops::Neg for Data #arguments {
+            type Output = #output;
+            fn neg(self) -> Self::Output #block
+        }
+    })
+}
+
+/// `len()` calls `len()` in Rust, which returns a `usize`.
+fn len(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    parameters(function, 0)?;
+    let block = block(function, &options, symbols);
+    let ImplTarget { generics, arguments, visibility, .. } = target;
+    Ok(quote! {
+        impl #generics Data #arguments {
+            #visibility fn len(&self) -> usize {
+                (#block) as usize
+            }
+        }
+    })
+}
+
+/// `in` calls `contains()` in Rust, which takes the item by reference.
+fn contains(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let [item] = <[Parameter; 1]>::try_from(parameters(function, 1)?).unwrap();
+    let item_type = special_type(item.annotation.as_deref(), Some(quote!(PyObject)), target, &options, &symbols)?;
+    let item = parameter_name(&item, &options, &symbols);
+    let block = block(function, &options, symbols);
+    let ImplTarget { generics, arguments, visibility, .. } = target;
+    Ok(quote! {
+        impl #generics Data #arguments {
+            #visibility fn contains(&self, #item: &#item_type) -> bool #block
+        }
+    })
+}
+
+/// Indexing returns a reference to an element, so the value `__getitem__` returns has to
+/// be one that can be borrowed, like an element of one of the fields.
+fn getitem(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let [key] = <[Parameter; 1]>::try_from(parameters(function, 1)?).unwrap();
+    let key_type = special_type(key.annotation.as_deref(), Some(quote!(usize)), target, &options, &symbols)?;
+    let output = special_type(function.returns.as_deref(), Some(quote!(PyObject)), target, &options, &symbols)?;
+    let key = parameter_name(&key, &options, &symbols);
+    let (statements, tail) = body(function, &options, symbols);
+    let Some(tail) = tail else {
+        return Err(CodeGenError::unsupported(
+            "__getitem__ that doesn't end by returning its value",
+            Some(function.source_location(UNKNOWN_FILE)),
+        )
+        .into());
+    };
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        impl #generics std::ops::Index<#key_type> for Data #arguments {
+            type Output = #output;
+            fn index(&self, #key: #key_type) -> &Self::Output {
+                #statements
+                &(#tail)
+            }
+        }
+    })
+}
+
+/// Assigning to an index borrows the element mutably and assigns to it, so `__setitem__`
+/// has to be an assignment to an element, like `self.items[key] = value`, which becomes
+/// the element `index_mut()` borrows. It also needs the `__getitem__` it extends.
+fn setitem(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let location = Some(function.source_location(UNKNOWN_FILE));
+    if !target.class.body.iter().any(|s| matches!(&s.statement, StatementType::FunctionDef(f) if f.name == "__getitem__")) {
+        return Err(CodeGenError::unsupported("__setitem__ without __getitem__", location).into());
+    }
+    let [key, value] = <[Parameter; 2]>::try_from(parameters(function, 2)?).unwrap();
+    let element = match function.body.as_slice() {
+        [statement] => match &statement.statement {
+            StatementType::Assign(assign)
+                if assign.targets.len() == 1
+                    && matches!(&assign.value, ExprType::Name(name) if name.id == value.arg) =>
+            {
+                Some(assign.targets[0].clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(element) = element else {
+        return Err(CodeGenError::unsupported("__setitem__ that does more than assign the value to an element", location).into());
+    };
+    let key_type = special_type(key.annotation.as_deref(), Some(quote!(usize)), target, &options, &symbols)?;
+    let symbols = function.scope_symbols(symbols);
+    let element = element.to_rust(CodeGenContext::Class, options.clone(), symbols.clone())?;
+    let key = parameter_name(&key, &options, &symbols);
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        impl #generics std::ops::IndexMut<#key_type> for Data #arguments {
+            fn index_mut(&mut self, #key: #key_type) -> &mut Self::Output {
+                &mut #element
+            }
+        }
+    })
+}
+
+/// A comparison method's parameter, which is the other instance, by reference, and its body.
+fn comparison(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: &PythonOptions,
+    symbols: &SymbolTableScopes,
+) -> Result<(TokenStream, TokenStream), Box<dyn std::error::Error>> {
+    let location = Some(function.source_location(UNKNOWN_FILE));
+    let [other] = <[Parameter; 1]>::try_from(parameters(function, 1)?).unwrap();
+    if other.annotation.as_deref().is_some_and(|annotation| !is_own_class(annotation, target.class)) {
+        return Err(CodeGenError::unsupported(format!("{} comparing with something other than the class", function.name), location).into());
+    }
+    if function.returns.as_deref().is_some_and(|returns| !matches!(returns, ExprType::Name(name) if name.id == "bool")) {
+        return Err(CodeGenError::unsupported(format!("{} that doesn't return a bool", function.name), location).into());
+    }
+    let other = parameter_name(&other, options, symbols);
+    Ok((other, block(function, options, symbols.clone())))
+}
+
+/// `==`, hashing and ordering are `PartialEq`, `Hash` and `PartialOrd` in Rust, each of
+/// which needs the one before. `__eq__` becomes `PartialEq`, and with `__hash__` the class
+/// also gets `Eq` and `Hash`. The ordering methods override those of `PartialOrd`, whose
+/// `partial_cmp()` is worked out from `__lt__` or `__gt__`, and a class with all four is
+/// taken to be totally ordered, so it also gets `Ord`. The other instance is compared by
+/// reference.
+pub fn comparison_impls(
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let method = |name: &str| {
+        target.class.body.iter().find_map(|s| match &s.statement {
+            StatementType::FunctionDef(f) if f.name == name && is_comparison_method(f) => Some(f),
+            _ => None,
+        })
+    };
+    let ImplTarget { generics, arguments, .. } = target;
+    let mut impls = TokenStream::new();
+
+    let Some(eq) = method("__eq__") else {
+        if let Some(function) = ["__hash__", "__lt__", "__le__", "__gt__", "__ge__"].into_iter().find_map(method) {
+            return Err(CodeGenError::unsupported(
+                format!("{} without __eq__", function.name),
+                Some(function.source_location(UNKNOWN_FILE)),
+            )
+            .into());
+        }
+        return Ok(impls);
+    };
+    let (other, eq) = comparison(eq, target, &options, &symbols)?;
+    impls.extend(quote! {
+        impl #generics PartialEq for Data #arguments {
+            fn eq(&self, #other: &Self) -> bool #eq
+        }
+    });
+
+    let orderings: Vec<_> = [("__lt__", quote!(lt)), ("__le__", quote!(le)), ("__gt__", quote!(gt)), ("__ge__", quote!(ge))]
+        .into_iter()
+        .filter_map(|(name, rust_name)| method(name).map(|function| (function, rust_name)))
+        .collect();
+    let total = orderings.len() == 4;
+    if let Some(hash) = method("__hash__") {
+        parameters(hash, 0)?;
+        let hash = block(hash, &options, symbols.clone());
+        impls.extend(quote! {
+            impl #generics std::hash::Hash for Data #arguments {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    std::hash::Hash::hash(&#hash, state)
+                }
+            }
+        });
+    }
+    if total || method("__hash__").is_some() {
+        impls.extend(quote!(impl #generics Eq for Data #arguments {}));
+    }
+    if orderings.is_empty() {
+        return Ok(impls);
+    }
+
+    // partial_cmp() only calls the methods the class overrides, since the others call it.
+    let less = match (method("__lt__"), method("__gt__")) {
+        (Some(_), _) => quote!(self.lt(other)),
+        (None, Some(_)) => quote!(other.gt(self)),
+        (None, None) => {
+            return Err(CodeGenError::unsupported(
+                "ordering methods without __lt__ or __gt__",
+                Some(orderings[0].0.source_location(UNKNOWN_FILE)),
+            )
+            .into());
+        }
+    };
+    let greater = if method("__gt__").is_some() { quote!(self.gt(other)) } else { quote!(other.lt(self)) };
+    let mut methods = TokenStream::new();
+    for (function, rust_name) in &orderings {
+        let (other, block) = comparison(function, target, &options, &symbols)?;
+        methods.extend(quote!(fn #rust_name(&self, #other: &Self) -> bool #block));
+    }
+    impls.extend(quote! {
+        impl #generics PartialOrd for Data #arguments {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                if #less {
+                    Some(std::cmp::Ordering::Less)
+                } else if #greater {
+                    Some(std::cmp::Ordering::Greater)
+                } else if self == other {
+                    Some(std::cmp::Ordering::Equal)
+                } else {
+                    None
+                }
+            }
+            #methods
+        }
+    });
+    if total {
+        impls.extend(quote! {
+            impl #generics Ord for Data #arguments {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    self.partial_cmp(other).expect("the ordering methods aren't a total order")
+                }
+            }
+        });
+    }
+    Ok(impls)
+}
+
+/// `__next__` is `Iterator::next()`, which returns an Option: the values it returns are
+/// Some, raising StopIteration returns None, and the item type is the one `__next__` is
+/// annotated to return. An `__iter__` that returns `self` needs no code, since every
+/// Iterator is already IntoIterator, and implementing it again would conflict.
+pub fn iterator_impls(
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let method = |name: &str| {
+        target.class.body.iter().find_map(|s| match &s.statement {
+            StatementType::FunctionDef(f) if f.name == name && is_iterator_method(f) => Some(f),
+            _ => None,
+        })
+    };
+    let Some(next) = method("__next__") else {
+        return match method("__iter__") {
+            Some(iter) => Err(CodeGenError::unsupported(
+                "__iter__ returning self without __next__",
+                Some(iter.source_location(UNKNOWN_FILE)),
+            )
+            .into()),
+            None => Ok(TokenStream::new()),
+        };
+    };
+    parameters(next, 0)?;
+    let item = special_type(next.returns.as_deref(), Some(quote!(PyObject)), target, &options, &symbols)?;
+    let mut options = options;
+    options.state.in_iterator_next = true;
+    let (statements, tail) = body(next, &options, symbols);
+    let tail = tail.map(|tail| quote!(Some(#tail)));
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        impl #generics Iterator for Data #arguments {
+            type Item = #item;
+            fn next(&mut self) -> Option<Self::Item> {
+                #statements
+                #tail
+            }
+        }
+    })
+}
+
+/// `repr()` and `str()` are `Debug` and `Display` in Rust, whose `fmt()` writes the string
+/// to a formatter, so an f-string returned at the end of the method is written with
+/// `write!`, and any other string returned is written as it is.
+fn formatter(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    fmt_trait: TokenStream,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    parameters(function, 0)?;
+    let (statements, write) = match function.body.split_last().map(|(last, init)| (&last.statement, init)) {
+        Some((StatementType::Return(Some(Expr { value: ExprType::JoinedStr(joined), .. })), init)) => {
+            let mut head = function.clone();
+            head.body = init.to_vec();
+            let (statements, _) = body(&head, &options, symbols.clone());
+            let scope = function.scope_symbols(symbols);
+            let (format_string, arguments) = joined.clone().format_arguments(CodeGenContext::Class, options, scope)?;
+            (statements, quote!(write!(f, #format_string #(, #arguments)*)))
+        }
+        _ => match body(function, &options, symbols) {
+            (statements, Some(tail)) => (statements, quote!(write!(f, "{}", #tail))),
+            (_, None) => {
+                return Err(CodeGenError::unsupported(
+                    format!("{} that doesn't end by returning its string", function.name),
+                    Some(function.source_location(UNKNOWN_FILE)),
+                )
+                .into());
+            }
+        },
+    };
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        impl #generics #fmt_trait for Data #arguments {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                #statements
+                #write
+            }
+        }
+    })
+}
+
+/// `repr()` is `Debug`. Python's `str()` falls back to `repr()`, so without a `__str__`,
+/// `Display` is `Debug` too.
+fn repr(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    let debug = formatter(function, target, quote!(std::fmt::Debug), options, symbols)?;
+    if target.class.body.iter().any(|s| matches!(&s.statement, StatementType::FunctionDef(f) if f.name == "__str__")) {
+        return Ok(debug);
+    }
+    let ImplTarget { generics, arguments, .. } = target;
+    Ok(quote! {
+        #debug
+        impl #generics std::fmt::Display for Data #arguments {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                std::fmt::Debug::fmt(self, f)
+            }
+        }
+    })
+}
+
+fn str(
+    function: &FunctionDef,
+    target: &ImplTarget,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    formatter(function, target, quote!(std::fmt::Display), options, symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{test_options, try_module_to_rust};
+
+    #[test]
+    fn test_special_methods_implement_traits() {
+        let code = try_module_to_rust(
+            "class Vector:
+    def __init__(self, x: int, y: int):
+        self.x = x
+        self.y = y
+
+    def __add__(self, other: 'Vector') -> 'Vector':
+        return Vector(self.x + other.x, self.y + other.y)
+
+    def __sub__(self, other: Vector) -> Vector:
+        return Vector(self.x - other.x, self.y - other.y)
+
+    def __mul__(self, factor: int) -> Vector:
+        return Vector(self.x * factor, self.y * factor)
+
+    def __neg__(self) -> Vector:
+        return Vector(-self.x, -self.y)
+
+    def __len__(self) -> int:
+        return 2
+
+    def __contains__(self, value: int) -> bool:
+        return self.x == value or self.y == value
+
+    def norm(self) -> int:
+        return self.x * self.x + self.y * self.y
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("pub fn norm (& self) -> i64"), "{}", code);
+        assert!(
+            code.contains("impl std :: ops :: Add < Self > for Data { type Output = Self ; fn add (self , other : Self) -> Self :: Output { Vector :: Data :: new"),
+            "{}",
+            code
+        );
+        assert!(code.contains("impl std :: ops :: Sub < Self > for Data { type Output = Self ; fn sub (self , other : Self)"), "{}", code);
+        assert!(code.contains("impl std :: ops :: Mul < i64 > for Data { type Output = Self ; fn mul (self , factor : i64)"), "{}", code);
+        assert!(code.contains("impl std :: ops :: Neg for Data { type Output = Self ; fn neg (self) -> Self :: Output {"), "{}", code);
+        assert!(code.contains("impl Data { pub fn len (& self) -> usize { ({ 2 }) as usize } }"), "{}", code);
+        assert!(code.contains("impl Data { pub fn contains (& self , value : & i64) -> bool {"), "{}", code);
+        assert!(!code.contains("__add__"), "{}", code);
+    }
+
+    #[test]
+    fn test_item_access_implements_index() {
+        let code = try_module_to_rust(
+            "class Row:
+    def __init__(self, cells: list[str]):
+        self.cells = cells
+
+    def __getitem__(self, index: int) -> str:
+        return self.cells[index]
+
+    def __setitem__(self, index: int, value: str):
+        self.cells[index] = value
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("impl std :: ops :: Index < i64 > for Data { type Output = String ; fn index (& self , index : i64) -> & Self :: Output { & ("), "{}", code);
+        assert!(code.contains("impl std :: ops :: IndexMut < i64 > for Data { fn index_mut (& mut self , index : i64) -> & mut Self :: Output { & mut "), "{}", code);
+
+        let code = try_module_to_rust("class Log:\n    def __setitem__(self, index: int, value: str):\n        print(value)\n", test_options()).unwrap();
+        assert!(code.contains("UNSUPPORTED: __setitem__ without __getitem__"), "{}", code);
+    }
+
+    #[test]
+    fn test_comparisons_implement_eq_hash_and_ordering() {
+        let code = try_module_to_rust(
+            "class Version:
+    def __init__(self, major: int, minor: int):
+        self.major = major
+        self.minor = minor
+
+    def __eq__(self, other: 'Version') -> bool:
+        return self.major == other.major and self.minor == other.minor
+
+    def __hash__(self) -> int:
+        return self.major * 1000 + self.minor
+
+    def __lt__(self, other: 'Version') -> bool:
+        return self.major < other.major or (self.major == other.major and self.minor < other.minor)
+
+    def __le__(self, other):
+        return self == other or self < other
+
+    def __gt__(self, other: 'Version') -> bool:
+        return other < self
+
+    def __ge__(self, other: 'Version') -> bool:
+        return other <= self
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("impl PartialEq for Data { fn eq (& self , other : & Self) -> bool { ((self . major) == (other . major))"), "{}", code);
+        assert!(code.contains("impl std :: hash :: Hash for Data { fn hash < H : std :: hash :: Hasher > (& self , state : & mut H) {"), "{}", code);
+        assert!(code.contains("impl Eq for Data { }"), "{}", code);
+        assert!(code.contains("if self . lt (other) { Some (std :: cmp :: Ordering :: Less) } else if self . gt (other) {"), "{}", code);
+        assert!(code.contains("fn le (& self , other : & Self) -> bool { ((self) == (other)) || ((self) < (other)) }"), "{}", code);
+        assert!(code.contains("impl Ord for Data { fn cmp (& self , other : & Self) -> std :: cmp :: Ordering {"), "{}", code);
+        assert!(!code.contains("__eq__") && !code.contains("__lt__"), "{}", code);
+
+        // Without all four ordering methods, the order is only partial, and without a
+        // __hash__ the class isn't Eq.
+        let code = try_module_to_rust(
+            "class Score:
+    def __init__(self, value: float):
+        self.value = value
+
+    def __eq__(self, other):
+        return self.value == other.value
+
+    def __lt__(self, other):
+        return self.value < other.value
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("if self . lt (other) { Some (std :: cmp :: Ordering :: Less) } else if other . lt (self) {"), "{}", code);
+        assert!(!code.contains("impl Ord") && !code.contains("impl Eq"), "{}", code);
+
+        let code = try_module_to_rust("class Point:\n    def __lt__(self, other):\n        return True\n", test_options()).unwrap();
+        assert!(code.contains("UNSUPPORTED: __lt__ without __eq__"), "{}", code);
+    }
+
+    #[test]
+    fn test_repr_and_str_implement_debug_and_display() {
+        let code = try_module_to_rust(
+            "class Point:
+    def __init__(self, x: int, y: int):
+        self.x = x
+        self.y = y
+
+    def __repr__(self) -> str:
+        return f'Point({self.x}, {self.y})'
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(
+            code.contains("impl std :: fmt :: Debug for Data { fn fmt (& self , f : & mut std :: fmt :: Formatter) -> std :: fmt :: Result { write ! (f , \"Point({}, {})\" , self . x , self . y) } }"),
+            "{}",
+            code
+        );
+        // Without a __str__, str() is repr().
+        assert!(code.contains("impl std :: fmt :: Display for Data { fn fmt (& self , f : & mut std :: fmt :: Formatter) -> std :: fmt :: Result { std :: fmt :: Debug :: fmt (self , f) } }"), "{}", code);
+
+        let code = try_module_to_rust(
+            "class Name:
+    def __init__(self, first: str):
+        self.first = first
+
+    def __repr__(self) -> str:
+        return f'Name({self.first!r})'
+
+    def __str__(self) -> str:
+        label = self.first
+        return label
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(code.contains("impl std :: fmt :: Debug for Data { fn fmt (& self , f : & mut std :: fmt :: Formatter) -> std :: fmt :: Result { write ! (f , \"Name({:?})\" , self . first) } }"), "{}", code);
+        assert!(code.contains("impl std :: fmt :: Display for Data { fn fmt (& self , f : & mut std :: fmt :: Formatter) -> std :: fmt :: Result { let label = self . first ; ; write ! (f , \"{}\" , label) } }"), "{}", code);
+        assert!(!code.contains("Debug :: fmt (self , f)"), "{}", code);
+    }
+
+    #[test]
+    fn test_iter_and_next_implement_iterator() {
+        let code = try_module_to_rust(
+            "class Countdown:
+    def __init__(self, start: int):
+        self.current = start
+
+    def __iter__(self):
+        \"\"\"Counts down.\"\"\"
+        return self
+
+    def __next__(self) -> int:
+        if self.current <= 0:
+            raise StopIteration()
+        self.current -= 1
+        return self.current + 1
+",
+            test_options(),
+        )
+        .unwrap();
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+        assert!(
+            code.contains("impl Iterator for Data { type Item = i64 ; fn next (& mut self) -> Option < Self :: Item > { if (self . current) <= (0) { return None ; } ; self . current -= 1 ; Some ((self . current) + (1)) } }"),
+            "{}",
+            code
+        );
+        assert!(!code.contains("__iter__") && !code.contains("__next__") && !code.contains("IntoIterator"), "{}", code);
+
+        // An __iter__ that doesn't return self is an ordinary method.
+        let code = try_module_to_rust("class Bag:\n    def __iter__(self):\n        yield 1\n", test_options()).unwrap();
+        assert!(code.contains("fn __iter__ (& self)"), "{}", code);
+        let code = try_module_to_rust("class Bag:\n    def __iter__(self):\n        return self\n", test_options()).unwrap();
+        assert!(code.contains("UNSUPPORTED: __iter__ returning self without __next__"), "{}", code);
+    }
+}
@@ -0,0 +1,194 @@
+//! Python's truthiness, for values tested as conditions.
+//!
+//! Python tests any value in an `if` or `while`: empty containers and strings, zero and
+//! `None` are false. Rust conditions are bools, so a condition whose type is known is
+//! turned into the check Python makes, like `!items.is_empty()` for a list. What happens
+//! to conditions of unknown types is up to [`PythonOptions::unknown_truthiness`].
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    annotation_truthiness, CodeGen, CodeGenContext, CodeGenError, ExprType, Node, Ops, PythonOptions, SymbolTableNode,
+    SymbolTableScopes, UNKNOWN_FILE, UnaryOp,
+};
+
+/// How a value is tested when it's used as a condition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Truthiness {
+    /// A bool, which is tested as it is
+    Bool,
+    /// An `Option`, which is true if it's `Some`
+    Option,
+    /// A string or container, which is true if it isn't empty
+    Collection,
+    /// An integer, which is true if it isn't zero
+    Int,
+    /// A float, which is true if it isn't zero
+    Float,
+    /// A value of a type that isn't known
+    Unknown,
+}
+
+/// What is generated for a condition whose type isn't known.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnknownTruthiness {
+    /// Use the condition as it is, assuming it's a bool (default)
+    #[default]
+    AssumeBool,
+    /// Call the `py_truthy()` helper, which is defined for the types Python values become
+    Helper,
+    /// Fail, since the condition can't be translated faithfully
+    Error,
+}
+
+/// How deeply names are followed to their assigned values when inferring truthiness.
+const MAX_INFERENCE_DEPTH: usize = 8;
+
+/// How `expr` is tested when it's used as a condition.
+pub fn truthiness(expr: &ExprType, options: &PythonOptions, symbols: &SymbolTableScopes) -> Truthiness {
+    truthiness_within(expr, options, symbols, MAX_INFERENCE_DEPTH)
+}
+
+fn truthiness_within(expr: &ExprType, options: &PythonOptions, symbols: &SymbolTableScopes, depth: usize) -> Truthiness {
+    if depth == 0 {
+        return Truthiness::Unknown;
+    }
+    match expr {
+        ExprType::Constant(c) if c.bool_value().is_some() => Truthiness::Bool,
+        ExprType::Constant(c) if c.int_value().is_some() => Truthiness::Int,
+        ExprType::Constant(c) if c.is_float() => Truthiness::Float,
+        ExprType::Constant(c) if c.string_value().is_some() => Truthiness::Collection,
+        // Boolean operators test their operands, so they're bools themselves.
+        ExprType::Compare(_) | ExprType::BoolOp(_) | ExprType::UnaryOp(UnaryOp { op: Ops::Not, .. }) => Truthiness::Bool,
+        ExprType::List(_)
+        | ExprType::Dict(_)
+        | ExprType::Set(_)
+        | ExprType::ListComp(_)
+        | ExprType::DictComp(_)
+        | ExprType::SetComp(_)
+        | ExprType::JoinedStr(_)
+        | ExprType::Bytes(_) => Truthiness::Collection,
+        ExprType::Call(call) => match call.func.as_ref() {
+            ExprType::Name(name) if symbols.lookup(&name.id).is_none() => match name.id.as_str() {
+                "len" => Truthiness::Int,
+                "bool" | "isinstance" | "issubclass" | "callable" | "all" | "any" | "hasattr" => Truthiness::Bool,
+                "str" | "list" | "dict" | "set" | "sorted" => Truthiness::Collection,
+                _ => Truthiness::Unknown,
+            },
+            _ => Truthiness::Unknown,
+        },
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::Assign { value, .. }) => truthiness_within(value, options, symbols, depth - 1),
+            _ => symbols
+                .type_annotation(&name.id)
+                .map_or(Truthiness::Unknown, |annotation| annotation_truthiness(annotation, options, symbols)),
+        },
+        _ => Truthiness::Unknown,
+    }
+}
+
+/// Generate `test` as a Rust condition, which tests it like Python would.
+pub fn condition_to_rust(
+    test: ExprType,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    // `not x` is true when x is false, whatever x is.
+    if let ExprType::UnaryOp(UnaryOp { op: Ops::Not, operand }) = &test {
+        let operand = condition_to_rust(*operand.clone(), ctx, options, symbols)?;
+        return Ok(quote!(!(#operand)));
+    }
+    let kind = truthiness(&test, &options, &symbols);
+    let location = test.source_location(UNKNOWN_FILE);
+    let tokens = test.to_rust(ctx, options.clone(), symbols)?;
+    match kind {
+        Truthiness::Bool => Ok(tokens),
+        Truthiness::Option => Ok(quote!((#tokens).is_some())),
+        Truthiness::Collection => Ok(quote!(!(#tokens).is_empty())),
+        Truthiness::Int => Ok(quote!((#tokens) != 0)),
+        Truthiness::Float => Ok(quote!((#tokens) != 0.0)),
+        Truthiness::Unknown => match options.unknown_truthiness {
+            UnknownTruthiness::AssumeBool => Ok(tokens),
+            UnknownTruthiness::Helper => Ok(quote!(py_truthy(&(#tokens)))),
+            UnknownTruthiness::Error => {
+                Err(CodeGenError::unsupported("a condition whose type isn't known", Some(location)).into())
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_options, try_module_to_rust};
+
+    #[test]
+    fn test_known_types_are_tested_like_python() {
+        let code = try_module_to_rust(
+            "def f(flag: bool, name: Optional[str], items: list[int], text: str, count: int, ratio: float, scores: dict):
+    if flag:
+        pass
+    if name:
+        pass
+    if items:
+        pass
+    while text:
+        pass
+    if not count:
+        pass
+    if ratio and scores:
+        pass
+    return 1 if len(items) else 0
+",
+            test_options(),
+        )
+        .unwrap();
+        assert!(code.contains("if flag {"), "{}", code);
+        assert!(code.contains("if (name) . is_some () {"), "{}", code);
+        assert!(code.contains("if ! (items) . is_empty () {"), "{}", code);
+        assert!(code.contains("while ! (text) . is_empty () {"), "{}", code);
+        assert!(code.contains("if ! ((count) != 0) {"), "{}", code);
+        assert!(code.contains("if ((ratio) != 0.0) && (! (scores) . is_empty ()) {"), "{}", code);
+        assert!(code.contains("if (items . len ()) != 0 { 1 } else { 0 }"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+    }
+
+    #[test]
+    fn test_unknown_types() {
+        let source = "def f(value):\n    if value:\n        pass\n";
+        let code = try_module_to_rust(source, test_options()).unwrap();
+        assert!(code.contains("if value {"), "{}", code);
+
+        let mut options = test_options();
+        options.unknown_truthiness = UnknownTruthiness::Helper;
+        let code = try_module_to_rust(source, options.clone()).unwrap();
+        assert!(code.contains("if py_truthy (& (value)) {"), "{}", code);
+        assert!(code.contains("fn py_truthy"), "{}", code);
+        syn::parse_str::<syn::File>(&code).unwrap_or_else(|e| panic!("{}: {}", e, code));
+
+        options.unknown_truthiness = UnknownTruthiness::Error;
+        let code = try_module_to_rust(source, options).unwrap();
+        assert!(code.contains("UNSUPPORTED: a condition whose type isn't known"), "{}", code);
+    }
+
+    #[test]
+    fn test_none_checks_of_options() {
+        let code = try_module_to_rust(
+            "def f(name: Optional[str], other):
+    if name is None:
+        pass
+    if name is not None:
+        pass
+    if other is None:
+        pass
+",
+            test_options(),
+        )
+        .unwrap();
+        assert!(code.contains("if (name) . is_none () {"), "{}", code);
+        assert!(code.contains("if (name) . is_some () {"), "{}", code);
+        assert!(code.contains("if & other == & None {"), "{}", code);
+    }
+}
@@ -0,0 +1,927 @@
+//! Resolving Python type annotations to Rust types.
+//!
+//! Builtin types and the generics of the `typing` module map to their Rust counterparts,
+//! in both spellings, like `List[int]` and `list[int]`, which become `Vec<i64>`. Other
+//! names are generated as they are, so that a class in the module names its struct.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+
+use crate::{
+    BinOps, Truthiness, CodeGen, CodeGenContext, CodeGenError, Constant, convert_ident, ExprType, IdentCase, Name, Node, Ops, UnaryOp, sanitize_ident,
+    CallableStyle, PythonOptions, StringType, SymbolTableNode, SymbolTableScopes, UNKNOWN_FILE,
+};
+
+/// The Rust type a Python annotation stands for.
+#[derive(Clone, Debug)]
+pub enum RustType {
+    /// `()`, for `None`
+    Unit,
+    /// A type named by a path, like `i64`, `String` or the struct of a class
+    Path(TokenStream),
+    /// `Option<T>`, for `Optional[T]` and `T | None`
+    Option(Box<RustType>),
+    /// `Vec<T>`, for `list[T]`
+    Vec(Box<RustType>),
+    /// `HashMap<K, V>`, for `dict[K, V]`
+    HashMap(Box<RustType>, Box<RustType>),
+    /// `HashSet<T>`, for `set[T]`
+    HashSet(Box<RustType>),
+    /// A tuple, for `tuple[A, B]`
+    Tuple(Vec<RustType>),
+    /// `impl Fn(A, B) -> R`, `fn(A, B) -> R` or `Box<dyn Fn(A, B) -> R>`, for
+    /// `Callable[[A, B], R]`
+    Callable {
+        params: Vec<RustType>,
+        returns: Box<RustType>,
+        style: CallableStyle,
+    },
+    /// An enum with a variant for each member of a union, like `IntOrStr` for
+    /// `Union[int, str]`
+    Union {
+        name: String,
+        variants: Vec<(String, RustType)>,
+    },
+}
+
+impl RustType {
+    pub fn is_option(&self) -> bool {
+        matches!(self, RustType::Option(_))
+    }
+
+    /// The path of the variant of a union enum that holds values of the type `member`
+    /// names, like `IntOrStr::Int` for `int`.
+    pub fn union_variant(&self, member: &ExprType) -> Option<TokenStream> {
+        let RustType::Union { name, variants } = self else {
+            return None;
+        };
+        let variant = member_name(member);
+        variants.iter().any(|(existing, _)| *existing == variant).then(|| {
+            let name = format_ident!("{}", name);
+            let variant = format_ident!("{}", variant);
+            quote!(#name::#variant)
+        })
+    }
+
+    /// `Option<T>` of the type, which is the type itself if it's optional already, like
+    /// `Optional[Optional[T]]` is the same as `Optional[T]` in Python.
+    pub fn optional(self) -> RustType {
+        if self.is_option() { self } else { RustType::Option(Box::new(self)) }
+    }
+}
+
+impl ToTokens for RustType {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            RustType::Unit => quote!(()),
+            RustType::Path(path) => path.clone(),
+            RustType::Option(inner) => quote!(Option<#inner>),
+            RustType::Vec(item) => quote!(Vec<#item>),
+            RustType::HashMap(key, value) => quote!(HashMap<#key, #value>),
+            RustType::HashSet(item) => quote!(HashSet<#item>),
+            // A tuple of one type needs the comma.
+            RustType::Tuple(items) if items.len() == 1 => {
+                let item = &items[0];
+                quote!((#item,))
+            }
+            RustType::Tuple(items) => quote!((#(#items),*)),
+            RustType::Callable { params, returns, style } => {
+                let function = match style {
+                    CallableStyle::FnTrait => quote!(impl Fn),
+                    CallableStyle::FnPointer => quote!(fn),
+                    CallableStyle::Boxed => quote!(dyn Fn),
+                };
+                let function = match returns.as_ref() {
+                    RustType::Unit => quote!(#function(#(#params),*)),
+                    returns => quote!(#function(#(#params),*) -> #returns),
+                };
+                match style {
+                    CallableStyle::Boxed => quote!(Box<#function>),
+                    _ => function,
+                }
+            }
+            RustType::Union { name, .. } => {
+                let name = format_ident!("{}", name);
+                quote!(#name)
+            }
+        });
+    }
+}
+
+/// Returns true for the `None` of an annotation like `int | None`.
+fn is_none(annotation: &ExprType) -> bool {
+    matches!(annotation, ExprType::Constant(Constant(None)) | ExprType::NoneType(_))
+}
+
+/// Returns true if the annotation names the `typing` construct `name`, as `name` or as
+/// `typing.name`.
+pub fn is_typing_name(annotation: &ExprType, name: &str) -> bool {
+    match annotation {
+        ExprType::Name(n) => n.id == name,
+        ExprType::Attribute(attribute) => {
+            attribute.attr == name && matches!(attribute.value.as_ref(), ExprType::Name(module) if module.id == "typing")
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the expression names `name` of the `abc` module, like `ABC` or
+/// `abc.ABC`.
+pub fn is_abc_name(expr: &ExprType, name: &str) -> bool {
+    match expr {
+        ExprType::Name(n) => n.id == name,
+        ExprType::Attribute(attribute) => {
+            attribute.attr == name && matches!(attribute.value.as_ref(), ExprType::Name(module) if module.id == "abc")
+        }
+        _ => false,
+    }
+}
+
+/// The name an expression refers to in `module`, if it's imported from the module, like
+/// `Enum` after `from enum import Enum` or `import enum as e; e.Enum`.
+pub fn imported_name(expr: &ExprType, module: &str, symbols: &SymbolTableScopes) -> Option<String> {
+    match expr {
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            Some(SymbolTableNode::ImportFrom(import)) if import.level == 0 && import.module.as_deref() == Some(module) => import
+                .names
+                .iter()
+                .find(|alias| alias.asname.as_deref().unwrap_or(&alias.name) == name.id)
+                .map(|alias| alias.name.clone()),
+            _ => None,
+        },
+        ExprType::Attribute(attribute) => match attribute.value.as_ref() {
+            ExprType::Name(name) => match symbols.lookup(&name.id) {
+                Some(SymbolTableNode::Import(_)) if name.id == module => Some(attribute.attr.clone()),
+                Some(SymbolTableNode::Alias(aliased)) if aliased == module => Some(attribute.attr.clone()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The name of the builtin or `typing` construct an annotation names, unless the module
+/// binds the name to something of its own, like a class. A name imported from `typing`,
+/// like `Union` after `from typing import Union`, is still the construct.
+fn builtin_name<'a>(annotation: &'a ExprType, symbols: &'a SymbolTableScopes) -> Option<&'a str> {
+    match annotation {
+        ExprType::Name(name) => match symbols.lookup(&name.id) {
+            None => Some(&name.id),
+            Some(SymbolTableNode::ImportFrom(import)) if import.level == 0 && import.module.as_deref() == Some("typing") => {
+                import
+                    .names
+                    .iter()
+                    .find(|alias| alias.asname.as_deref().unwrap_or(&alias.name) == name.id)
+                    .map(|alias| alias.name.as_str())
+            }
+            Some(_) => None,
+        },
+        ExprType::Attribute(attribute) if is_typing_name(annotation, &attribute.attr) => Some(&attribute.attr),
+        _ => None,
+    }
+}
+
+/// The members of a union, flattening nested unions like `int | str | None`.
+fn union_members(annotation: &ExprType) -> Vec<&ExprType> {
+    match annotation {
+        ExprType::BinOp(union) if union.op == BinOps::BitOr => {
+            let mut members = union_members(&union.left);
+            members.extend(union_members(&union.right));
+            members
+        }
+        other => vec![other],
+    }
+}
+
+/// The type of a primitive named by a `PythonOptions` setting, like `int_type`.
+fn configured_type(setting: &str, value: &str) -> Result<RustType, Box<dyn std::error::Error>> {
+    let path = value
+        .parse::<TokenStream>()
+        .map_err(|_| CodeGenError::malformed(format!("the {} isn't a Rust type: {}", setting, value)))?;
+    Ok(RustType::Path(path))
+}
+
+/// The name of an annotation that's a name or an attribute of one, like `decimal.Decimal`.
+fn dotted_name(annotation: &ExprType) -> Option<String> {
+    match annotation {
+        ExprType::Name(name) => Some(name.id.clone()),
+        ExprType::Attribute(attribute) => Some(format!("{}.{}", dotted_name(&attribute.value)?, attribute.attr)),
+        _ => None,
+    }
+}
+
+/// The `string_type` of the options, importing `Cow` if it's needed.
+fn string_type(options: &PythonOptions) -> RustType {
+    if options.string_type == StringType::Cow {
+        options.required_imports.require("std::borrow::Cow");
+    }
+    RustType::Path(options.string_type.tokens())
+}
+
+/// Returns true if the annotation is `Final` or `Final[T]`, declaring a variable that is
+/// never assigned again.
+pub fn is_final_annotation(annotation: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    match annotation {
+        ExprType::Subscript(subscript) => builtin_name(&subscript.value, symbols) == Some("Final"),
+        annotation => builtin_name(annotation, symbols) == Some("Final"),
+    }
+}
+
+/// Returns true if the annotation is `str`.
+pub fn is_str_annotation(annotation: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    builtin_name(annotation, symbols) == Some("str")
+}
+
+/// Whether `annotation` is `bytes` or `bytearray`.
+pub fn is_bytes_annotation(annotation: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    matches!(builtin_name(annotation, symbols), Some("bytes" | "bytearray"))
+}
+
+/// Whether `annotation` is `bytearray`, whose values are owned.
+pub fn is_bytearray_annotation(annotation: &ExprType, symbols: &SymbolTableScopes) -> bool {
+    builtin_name(annotation, symbols) == Some("bytearray")
+}
+
+/// `HashMap<K, V>`, which the module imports.
+/// How a value with the type `annotation` is tested when it's used as a condition.
+pub fn annotation_truthiness(annotation: &ExprType, options: &PythonOptions, symbols: &SymbolTableScopes) -> Truthiness {
+    // The user decides what their types are.
+    if dotted_name(annotation).is_some_and(|name| options.type_map.contains_key(&name)) {
+        return Truthiness::Unknown;
+    }
+    if let ExprType::BinOp(union) = annotation
+        && union.op == BinOps::BitOr
+        && union_members(annotation).into_iter().any(is_none)
+    {
+        return Truthiness::Option;
+    }
+    if let ExprType::Subscript(subscript) = annotation {
+        return match builtin_name(&subscript.value, symbols) {
+            Some("Optional") => Truthiness::Option,
+            Some("Final") => annotation_truthiness(&subscript.slice, options, symbols),
+            Some("List" | "list" | "Sequence" | "Dict" | "dict" | "Mapping" | "Set" | "set" | "FrozenSet" | "frozenset") => {
+                Truthiness::Collection
+            }
+            _ => Truthiness::Unknown,
+        };
+    }
+    match builtin_name(annotation, symbols) {
+        Some("bool") => Truthiness::Bool,
+        Some("int") => Truthiness::Int,
+        Some("float") => Truthiness::Float,
+        Some("str" | "bytes" | "bytearray" | "List" | "list" | "Dict" | "dict" | "Set" | "set") => Truthiness::Collection,
+        _ => Truthiness::Unknown,
+    }
+}
+
+fn hash_map(key: RustType, value: RustType, options: &PythonOptions) -> RustType {
+    options.required_imports.require("std::collections::HashMap");
+    RustType::HashMap(Box::new(key), Box::new(value))
+}
+
+/// `HashSet<T>`, which the module imports.
+fn hash_set(item: RustType, options: &PythonOptions) -> RustType {
+    options.required_imports.require("std::collections::HashSet");
+    RustType::HashSet(Box::new(item))
+}
+
+/// The Rust type of a type annotation.
+pub fn annotation_to_rust_type(
+    annotation: &ExprType,
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<RustType, Box<dyn std::error::Error>> {
+    let resolve = |annotation: &ExprType| annotation_to_rust_type(annotation, ctx.clone(), options.clone(), symbols.clone());
+    let resolve_all = |annotations: &[ExprType]| annotations.iter().map(resolve).collect::<Result<Vec<_>, _>>();
+
+    // A string annotation is a forward reference to a type defined further down.
+    if let ExprType::Constant(constant) = annotation
+        && let Some(name) = constant.string_value()
+        && let Ok(name) = Name::try_from(name.as_str())
+    {
+        return resolve(&ExprType::Name(name));
+    }
+
+    if is_none(annotation) {
+        return Ok(RustType::Unit);
+    }
+
+    // The types the user mapped come before the built in ones.
+    if let Some(name) = dotted_name(annotation)
+        && let Some(rust_type) = options.type_map.get(&name)
+    {
+        return configured_type(&format!("type_map entry for {}", name), rust_type);
+    }
+
+    // A type variable is the generic parameter of the same name.
+    if let ExprType::Name(name) = annotation
+        && let Some(SymbolTableNode::TypeVar(type_var)) = symbols.lookup(&name.id)
+    {
+        let name = sanitize_ident(&type_var.name);
+        return Ok(RustType::Path(quote!(#name)));
+    }
+
+    // A Protocol is a trait, which any type implementing it can be passed as.
+    if let ExprType::Name(name) = annotation
+        && let Some(SymbolTableNode::ClassDef { class, .. }) = symbols.lookup(&name.id)
+        && class.is_protocol()
+    {
+        let name = sanitize_ident(&class.name);
+        return Ok(RustType::Path(quote!(impl #name)));
+    }
+
+    // An instance of any other class is the Data struct of the class's module, except that
+    // a NamedTuple or TypedDict is a struct, and an Enum an enum, named after the class.
+    if let ExprType::Name(name) = annotation
+        && let Some(SymbolTableNode::ClassDef { class, .. }) = symbols.lookup(&name.id)
+        && class.record_kind().is_none()
+        && class.enum_kind(&symbols).is_none()
+    {
+        let name = convert_ident(&class.name, IdentCase::Type, &options, &symbols);
+        return Ok(RustType::Path(quote!(#name::Data)));
+    }
+
+    if let ExprType::BinOp(union) = annotation
+        && union.op == BinOps::BitOr
+    {
+        return resolve_union(&union_members(annotation), ctx, options, symbols);
+    }
+
+    if let ExprType::Subscript(subscript) = annotation
+        && let Some(generic) = builtin_name(&subscript.value, &symbols)
+    {
+        // The parameters of a generic with more than one are a tuple.
+        let parameters = match subscript.slice.as_ref() {
+            ExprType::Tuple(tuple) => tuple.elts.clone(),
+            parameter => vec![parameter.clone()],
+        };
+        return match (generic, parameters.as_slice()) {
+            ("Optional", [parameter]) => Ok(resolve(parameter)?.optional()),
+            // A Final variable has the type it's declared with, it's just never assigned again.
+            ("Final", [parameter]) => resolve(parameter),
+            ("Literal", values) => literal_enum(values, annotation, &options),
+            ("Union", members) => resolve_union(&members.iter().collect::<Vec<_>>(), ctx, options, symbols),
+            ("List" | "list" | "Sequence" | "Iterable", [item]) => Ok(RustType::Vec(Box::new(resolve(item)?))),
+            ("Dict" | "dict" | "Mapping", [key, value]) => {
+                Ok(hash_map(resolve(key)?, resolve(value)?, &options))
+            }
+            ("Set" | "set" | "FrozenSet" | "frozenset", [item]) => Ok(hash_set(resolve(item)?, &options)),
+            // `tuple[int, ...]` is a tuple of any length.
+            ("Tuple" | "tuple", [item, ExprType::Ellipsis]) => {
+                Ok(RustType::Vec(Box::new(resolve(item)?)))
+            }
+            ("Tuple" | "tuple", items) => Ok(RustType::Tuple(resolve_all(items)?)),
+            ("Callable", [ExprType::List(params), returns]) => Ok(RustType::Callable {
+                params: resolve_all(params)?,
+                returns: Box::new(resolve(returns)?),
+                style: options.callable_style,
+            }),
+            (generic, _) => Err(CodeGenError::unsupported(
+                format!("the type annotation {}[...]", generic),
+                Some(annotation.source_location(UNKNOWN_FILE)),
+            )
+            .into()),
+        };
+    }
+
+    let any = || RustType::Path(quote!(PyObject));
+    match builtin_name(annotation, &symbols) {
+        Some("int") => configured_type("int_type", options.int_rust_type()),
+        Some("float") => configured_type("float_type", &options.float_type),
+        Some("bool") => Ok(RustType::Path(quote!(bool))),
+        Some("str") => Ok(string_type(&options)),
+        Some("bytes") => Ok(RustType::Path(quote!(&[u8]))),
+        Some("bytearray") => Ok(RustType::Vec(Box::new(RustType::Path(quote!(u8))))),
+        Some("Any" | "object") => Ok(any()),
+        // Containers without parameters hold anything.
+        Some("List" | "list") => Ok(RustType::Vec(Box::new(any()))),
+        Some("Dict" | "dict") => Ok(hash_map(any(), any(), &options)),
+        Some("Set" | "set") => Ok(hash_set(any(), &options)),
+        // A callable whose signature isn't given is taken to take and return nothing.
+        Some("Callable") => Ok(RustType::Callable { params: Vec::new(), returns: Box::new(RustType::Unit), style: options.callable_style }),
+        _ => Ok(RustType::Path(annotation.clone().to_rust(ctx, options, symbols)?)),
+    }
+}
+
+/// A type variable, declared like `T = TypeVar("T", bound=int)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeVar {
+    pub name: String,
+    /// The type the variable's types are subtypes of
+    pub bound: Option<ExprType>,
+    /// The types the variable can be, like `int` and `str` for `TypeVar("T", int, str)`
+    pub constraints: Vec<ExprType>,
+}
+
+impl TypeVar {
+    /// The type variable a `TypeVar(...)` call declares, if `value` is one.
+    pub fn from_call(value: &ExprType) -> Option<TypeVar> {
+        let ExprType::Call(call) = value else {
+            return None;
+        };
+        if !is_typing_name(&call.func, "TypeVar") {
+            return None;
+        }
+        let (name, constraints) = call.args.split_first()?;
+        let ExprType::Constant(name) = name else {
+            return None;
+        };
+        Some(TypeVar {
+            name: name.string_value()?,
+            bound: call
+                .keywords
+                .iter()
+                .find(|keyword| keyword.arg.as_deref() == Some("bound"))
+                .map(|keyword| keyword.value.clone()),
+            constraints: constraints.to_vec(),
+        })
+    }
+}
+
+/// Add the type variables `annotation` refers to to `found`, in the order they first
+/// appear.
+pub fn type_variables(annotation: &ExprType, symbols: &SymbolTableScopes, found: &mut Vec<TypeVar>) {
+    match annotation {
+        ExprType::Name(name) => {
+            if let Some(SymbolTableNode::TypeVar(type_var)) = symbols.lookup(&name.id)
+                && !found.contains(type_var)
+            {
+                found.push(type_var.clone());
+            }
+        }
+        ExprType::Constant(constant) => {
+            if let Some(name) = constant.string_value()
+                && let Ok(name) = Name::try_from(name.as_str())
+            {
+                type_variables(&ExprType::Name(name), symbols, found);
+            }
+        }
+        ExprType::Subscript(subscript) => type_variables(&subscript.slice, symbols, found),
+        ExprType::Tuple(tuple) => tuple.elts.iter().for_each(|elt| type_variables(elt, symbols, found)),
+        ExprType::List(items) => items.iter().for_each(|item| type_variables(item, symbols, found)),
+        ExprType::BinOp(union) => {
+            type_variables(&union.left, symbols, found);
+            type_variables(&union.right, symbols, found);
+        }
+        _ => {}
+    }
+}
+
+/// The generic parameters for type variables, like `<T, N: Into<i64>>`. A variable bound
+/// to a class of the module is bound to the class's trait, and one bound to another type
+/// is bound to [`PythonOptions::type_var_bound`] of it.
+pub fn generic_parameters(
+    type_vars: &[TypeVar],
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<TokenStream, Box<dyn std::error::Error>> {
+    if type_vars.is_empty() {
+        return Ok(TokenStream::new());
+    }
+    let mut parameters = Vec::new();
+    for type_var in type_vars {
+        let name = sanitize_ident(&type_var.name);
+        if !type_var.constraints.is_empty() {
+            return Err(CodeGenError::unsupported(format!("the constrained type variable {}", type_var.name), None).into());
+        }
+        parameters.push(match &type_var.bound {
+            None => quote!(#name),
+            Some(ExprType::Name(class)) if matches!(symbols.lookup(&class.id), Some(SymbolTableNode::ClassDef { .. })) => {
+                let class = sanitize_ident(&class.id);
+                quote!(#name: #class::Cls)
+            }
+            Some(bound) => {
+                let bound = annotation_to_rust_type(bound, ctx.clone(), options.clone(), symbols.clone())?;
+                let bound_trait = options
+                    .type_var_bound
+                    .parse::<TokenStream>()
+                    .map_err(|_| CodeGenError::malformed(format!("the type_var_bound isn't a trait: {}", options.type_var_bound)))?;
+                quote!(#name: #bound_trait<#bound>)
+            }
+        });
+    }
+    Ok(quote!(<#(#parameters),*>))
+}
+
+/// The enums generated for the unions and Literals in annotations, by name. Clones of the
+/// options share them, so that the module can define the enums its functions refer to.
+#[derive(Clone, Debug, Default)]
+pub struct UnionEnums(Arc<Mutex<BTreeMap<String, TokenStream>>>);
+
+impl UnionEnums {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define the enum of a union, unless it's defined already.
+    pub fn define(&self, name: &str, definition: impl FnOnce() -> TokenStream) {
+        if let Ok(mut enums) = self.0.lock() {
+            enums.entry(name.to_string()).or_insert_with(definition);
+        }
+    }
+
+    /// The definitions of the enums, which are removed, so that the next module defines
+    /// the ones it needs again.
+    pub fn take(&self) -> TokenStream {
+        self.0
+            .lock()
+            .map(|mut enums| std::mem::take(&mut *enums).into_values().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The name of the variant of a type in a union enum, like `Int` for `int` or `ListStr`
+/// for `list[str]`.
+fn member_name(annotation: &ExprType) -> String {
+    match annotation {
+        ExprType::Name(name) => IdentCase::Type.convert(&name.id),
+        ExprType::Attribute(attribute) => IdentCase::Type.convert(&attribute.attr),
+        ExprType::Subscript(subscript) => member_name(&subscript.value) + &member_name(&subscript.slice),
+        ExprType::Tuple(tuple) => tuple.elts.iter().map(member_name).collect(),
+        ExprType::List(items) => items.iter().map(member_name).collect(),
+        ExprType::Constant(constant) => constant
+            .string_value()
+            .map(|name| IdentCase::Type.convert(&name))
+            .unwrap_or_else(|| "Value".to_string()),
+        _ => "Value".to_string(),
+    }
+}
+
+/// The enum for a union of more than one type. Its name is made of the names of the
+/// variants, in alphabetical order, so that every spelling of a union is the same enum.
+fn union_enum(
+    members: &[&ExprType],
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<RustType, Box<dyn std::error::Error>> {
+    let mut variants = BTreeMap::new();
+    for member in members {
+        let rust_type = annotation_to_rust_type(member, ctx.clone(), options.clone(), symbols.clone())?;
+        variants.entry(member_name(member)).or_insert(rust_type);
+    }
+    let variants: Vec<(String, RustType)> = variants.into_iter().collect();
+    let name = variants.iter().map(|(variant, _)| variant.as_str()).collect::<Vec<_>>().join("Or");
+
+    options.union_enums.define(&name, || {
+        let enum_name = format_ident!("{}", name);
+        let idents: Vec<_> = variants.iter().map(|(variant, _)| format_ident!("{}", variant)).collect();
+        let types: Vec<_> = variants.iter().map(|(_, rust_type)| rust_type).collect();
+
+        // A type can only be converted into one variant.
+        let mut converted = Vec::new();
+        let conversions = idents.iter().zip(&types).filter(|(_, rust_type)| {
+            let rust_type = rust_type.to_token_stream().to_string();
+            !converted.contains(&rust_type) && {
+                converted.push(rust_type);
+                true
+            }
+        });
+        let conversions = conversions.map(|(variant, rust_type)| {
+            quote! {
+                impl From<#rust_type> for #enum_name {
+                    fn from(value: #rust_type) -> Self {
+                        #enum_name::#variant(value)
+                    }
+                }
+            }
+        });
+        quote! {
+            #[derive(Clone)]
+            pub enum #enum_name {
+                #(#idents(#types)),*
+            }
+            #(#conversions)*
+        }
+    });
+    Ok(RustType::Union { name, variants })
+}
+
+/// Generates the conversions of a Literal's enum, given its name and variants.
+type LiteralConversions = Box<dyn Fn(&Ident, &[Ident]) -> TokenStream>;
+
+/// The enum for `Literal[...]`, with a unit variant for each of the values, like
+/// `Literal_1_2 { V1, V2 }` for `Literal[1, 2]` or `Literal_a_b { A, B }` for
+/// `Literal["a", "b"]`. It converts from and to the values, with `from_i64` and `to_i64`
+/// for ints, and `from_str` and `as_str` for strings.
+fn literal_enum(values: &[ExprType], annotation: &ExprType, options: &PythonOptions) -> Result<RustType, Box<dyn std::error::Error>> {
+    let unsupported = || {
+        CodeGenError::unsupported(
+            "a Literal of values that aren't all ints or all strings",
+            Some(annotation.source_location(UNKNOWN_FILE)),
+        )
+    };
+    let ints: Option<Vec<i64>> = values
+        .iter()
+        .map(|value| match value {
+            ExprType::Constant(constant) => constant.int_value(),
+            ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand }) => match operand.as_ref() {
+                ExprType::Constant(constant) => constant.int_value().map(|value| -value),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let strings: Option<Vec<String>> = values
+        .iter()
+        .map(|value| match value {
+            ExprType::Constant(constant) => constant.string_value(),
+            _ => None,
+        })
+        .collect();
+
+    // The name is made of the values, so that every spelling of a Literal is the same enum.
+    let sanitize = |text: &str| text.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect::<String>();
+    let (name, variants, conversions): (String, Vec<String>, LiteralConversions) = match (ints, strings) {
+        (Some(ints), _) if !ints.is_empty() => {
+            let names: Vec<_> = ints.iter().map(|value| value.to_string().replace('-', "neg")).collect();
+            let variants = names.iter().map(|name| format!("V{}", name)).collect();
+            let conversions = move |enum_name: &Ident, idents: &[Ident]| {
+                let ints = ints.iter().map(|value| proc_macro2::Literal::i64_unsuffixed(*value));
+                let values = ints.clone();
+                quote! {
+                    pub fn from_i64(value: i64) -> Option<Self> {
+                        match value {
+                            #(#ints => Some(#enum_name::#idents),)*
+                            _ => None,
+                        }
+                    }
+
+                    pub fn to_i64(&self) -> i64 {
+                        match self {
+                            #(#enum_name::#idents => #values,)*
+                        }
+                    }
+                }
+            };
+            (format!("Literal_{}", names.join("_")), variants, Box::new(conversions))
+        }
+        (_, Some(strings)) if !strings.is_empty() => {
+            let variants = strings
+                .iter()
+                .enumerate()
+                .map(|(i, value)| match IdentCase::Type.convert(&sanitize(value)).trim_matches('_') {
+                    variant if variant.starts_with(|c: char| c.is_ascii_alphabetic()) => variant.to_string(),
+                    _ => format!("V{}", i),
+                })
+                .collect();
+            let names: Vec<_> = strings.iter().map(|value| sanitize(value)).collect();
+            let conversions = move |enum_name: &Ident, idents: &[Ident]| {
+                quote! {
+                    pub fn from_str(value: &str) -> Option<Self> {
+                        match value {
+                            #(#strings => Some(#enum_name::#idents),)*
+                            _ => None,
+                        }
+                    }
+
+                    pub fn as_str(&self) -> &'static str {
+                        match self {
+                            #(#enum_name::#idents => #strings,)*
+                        }
+                    }
+                }
+            };
+            (format!("Literal_{}", names.join("_")), variants, Box::new(conversions))
+        }
+        _ => return Err(unsupported().into()),
+    };
+    let mut unique = variants.clone();
+    unique.sort();
+    unique.dedup();
+    if unique.len() < variants.len() {
+        return Err(unsupported().into());
+    }
+
+    options.union_enums.define(&name, || {
+        let enum_name = format_ident!("{}", name);
+        let idents: Vec<_> = variants.iter().map(|variant| format_ident!("{}", variant)).collect();
+        let conversions = conversions(&enum_name, &idents);
+        quote! {
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub enum #enum_name {
+                #(#idents),*
+            }
+            impl #enum_name {
+                #conversions
+            }
+        }
+    });
+    let name = format_ident!("{}", name);
+    Ok(RustType::Path(quote!(#name)))
+}
+
+/// The type of a union. With `None` among its members, it's an `Option` of the others.
+/// A union of more than one type is the `union_fallback` type if there is one, and an
+/// enum otherwise.
+fn resolve_union(
+    members: &[&ExprType],
+    ctx: CodeGenContext,
+    options: PythonOptions,
+    symbols: SymbolTableScopes,
+) -> Result<RustType, Box<dyn std::error::Error>> {
+    let types: Vec<&ExprType> = members.iter().copied().filter(|member| !is_none(member)).collect();
+    let rust_type = match types.as_slice() {
+        [] => RustType::Unit,
+        [member] => annotation_to_rust_type(member, ctx, options, symbols)?,
+        types => match &options.union_fallback {
+            Some(fallback) => configured_type("union_fallback", fallback)?,
+            None => union_enum(types, ctx, options, symbols)?,
+        },
+    };
+    Ok(if types.len() < members.len() { rust_type.optional() } else { rust_type })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(source: &str) -> ExprType {
+        let module = crate::parse(format!("x: {}", source), "test.py").unwrap();
+        match &module.raw.body[0].statement {
+            crate::StatementType::AnnAssign(ann_assign) => *ann_assign.annotation.clone(),
+            other => panic!("expected an annotated assignment, got {:?}", other),
+        }
+    }
+
+    fn resolve_with(source: &str, options: PythonOptions) -> Result<String, Box<dyn std::error::Error>> {
+        let symbols = crate::parse("class Point:\n    pass\n", "test.py")
+            .unwrap()
+            .find_symbols(SymbolTableScopes::new());
+        let rust_type =
+            annotation_to_rust_type(&annotation(source), CodeGenContext::Module("test".to_string()), options, symbols)?;
+        Ok(rust_type.to_token_stream().to_string())
+    }
+
+    fn resolve(source: &str) -> String {
+        resolve_with(source, PythonOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_optional_annotations() {
+        assert_eq!(resolve("Optional[Point]"), "Option < Point :: Data >");
+        assert_eq!(resolve("typing.Optional[Point]"), "Option < Point :: Data >");
+        assert_eq!(resolve("Point | None"), "Option < Point :: Data >");
+        assert_eq!(resolve("None | Point"), "Option < Point :: Data >");
+        assert_eq!(resolve("Optional[Point | None]"), "Option < Point :: Data >");
+        assert_eq!(resolve("int | None"), "Option < i64 >");
+    }
+
+    #[test]
+    fn test_none_is_unit() {
+        assert_eq!(resolve("None"), "()");
+        assert_eq!(resolve("Point"), "Point :: Data");
+        assert_eq!(resolve("'Point'"), "Point :: Data");
+    }
+
+    #[test]
+    fn test_classes_are_their_data_struct() {
+        let source = "from dataclasses import dataclass
+from enum import Enum
+from typing import NamedTuple
+
+@dataclass
+class Point:
+    x: int
+
+class Pair(NamedTuple):
+    a: int
+
+class Color(Enum):
+    RED = 1
+";
+        let symbols = crate::parse(source, "test.py").unwrap().find_symbols(SymbolTableScopes::new());
+        let resolve = |source: &str| {
+            annotation_to_rust_type(&annotation(source), CodeGenContext::Module("test".to_string()), PythonOptions::default(), symbols.clone())
+                .unwrap()
+                .to_token_stream()
+                .to_string()
+        };
+        assert_eq!(resolve("Point"), "Point :: Data");
+        assert_eq!(resolve("list[Point]"), "Vec < Point :: Data >");
+        // A NamedTuple and an Enum aren't modules.
+        assert_eq!(resolve("Pair"), "Pair");
+        assert_eq!(resolve("Color"), "Color");
+    }
+
+    #[test]
+    fn test_builtin_types() {
+        assert_eq!(resolve("int"), "i64");
+        assert_eq!(resolve("float"), "f64");
+        assert_eq!(resolve("bool"), "bool");
+        assert_eq!(resolve("str"), "String");
+        assert_eq!(resolve("bytes"), "& [u8]");
+        assert_eq!(resolve("bytearray"), "Vec < u8 >");
+    }
+
+    #[test]
+    fn test_containers() {
+        for (typing, builtin, rust) in [
+            ("List[int]", "list[int]", "Vec < i64 >"),
+            ("Dict[str, float]", "dict[str, float]", "HashMap < String , f64 >"),
+            ("Set[str]", "set[str]", "HashSet < String >"),
+            ("Tuple[int, str]", "tuple[int, str]", "(i64 , String)"),
+            ("Tuple[int]", "tuple[int]", "(i64 ,)"),
+            ("Tuple[int, ...]", "tuple[int, ...]", "Vec < i64 >"),
+        ] {
+            assert_eq!(resolve(typing), rust);
+            assert_eq!(resolve(builtin), rust);
+        }
+        assert_eq!(resolve("List[Dict[str, Point]]"), "Vec < HashMap < String , Point :: Data > >");
+        assert_eq!(resolve("list"), "Vec < PyObject >");
+    }
+
+    #[test]
+    fn test_callable() {
+        assert_eq!(resolve("Callable[[int], str]"), "impl Fn (i64) -> String");
+        assert_eq!(resolve("Callable[[int, Point], None]"), "impl Fn (i64 , Point :: Data)");
+
+        let mut options = PythonOptions::default();
+        options.callable_style = CallableStyle::FnPointer;
+        assert_eq!(resolve_with("Callable[[int, str], bool]", options.clone()).unwrap(), "fn (i64 , String) -> bool");
+        assert_eq!(resolve_with("Callable[[], None]", options).unwrap(), "fn ()");
+
+        let mut options = PythonOptions::default();
+        options.callable_style = CallableStyle::Boxed;
+        assert_eq!(resolve_with("Callable[[int], bool]", options.clone()).unwrap(), "Box < dyn Fn (i64) -> bool >");
+        assert_eq!(resolve_with("Callable[[int, str], None]", options.clone()).unwrap(), "Box < dyn Fn (i64 , String) >");
+        assert_eq!(resolve_with("Callable", options).unwrap(), "Box < dyn Fn () >");
+        assert_eq!(resolve("Callable"), "impl Fn ()");
+    }
+
+    #[test]
+    fn test_literals_become_enums() {
+        let options = PythonOptions::default();
+        assert_eq!(resolve_with("Literal[1, 2, -3]", options.clone()).unwrap(), "Literal_1_2_neg3");
+        let enums = options.union_enums.take().to_string();
+        assert!(enums.contains("pub enum Literal_1_2_neg3 { V1 , V2 , Vneg3 }"), "{}", enums);
+        assert!(enums.contains("pub fn from_i64 (value : i64) -> Option < Self > { match value { 1 => Some (Literal_1_2_neg3 :: V1) ,"), "{}", enums);
+        assert!(enums.contains("Literal_1_2_neg3 :: Vneg3 => - 3 ,"), "{}", enums);
+        syn::parse_str::<syn::File>(&enums).unwrap_or_else(|e| panic!("{}: {}", e, enums));
+
+        assert_eq!(resolve_with("typing.Literal['read', 'write']", options.clone()).unwrap(), "Literal_read_write");
+        let enums = options.union_enums.take().to_string();
+        assert!(enums.contains("pub enum Literal_read_write { Read , Write }"), "{}", enums);
+        assert!(enums.contains("pub fn as_str (& self) -> & 'static str { match self { Literal_read_write :: Read => \"read\" ,"), "{}", enums);
+        syn::parse_str::<syn::File>(&enums).unwrap_or_else(|e| panic!("{}: {}", e, enums));
+
+        assert!(resolve_with("Literal[1, 'a']", options).is_err());
+    }
+
+    #[test]
+    fn test_unions() {
+        assert_eq!(resolve("Union[int, None]"), "Option < i64 >");
+        assert_eq!(resolve("Union[None, str]"), "Option < String >");
+        assert_eq!(resolve("typing.Union[float, None]"), "Option < f64 >");
+        assert_eq!(resolve("Union[int, str]"), "IntOrStr");
+        assert_eq!(resolve("Union[Point]"), "Point :: Data");
+    }
+
+    #[test]
+    fn test_configured_types() {
+        let mut options = PythonOptions::default();
+        options.int_type = "i32".to_string();
+        options.float_type = "f32".to_string();
+        options.union_fallback = Some("PyObject".to_string());
+        assert_eq!(resolve_with("List[int]", options.clone()).unwrap(), "Vec < i32 >");
+        assert_eq!(resolve_with("float", options.clone()).unwrap(), "f32");
+        assert_eq!(resolve_with("Union[int, str]", options.clone()).unwrap(), "PyObject");
+        assert_eq!(resolve_with("int | str | None", options).unwrap(), "Option < PyObject >");
+    }
+
+    #[test]
+    fn test_unions_become_enums() {
+        let options = PythonOptions::default();
+        assert_eq!(resolve_with("Union[str, int]", options.clone()).unwrap(), "IntOrStr");
+        assert_eq!(resolve_with("int | str", options.clone()).unwrap(), "IntOrStr");
+        assert_eq!(resolve_with("Union[int, str, None]", options.clone()).unwrap(), "Option < IntOrStr >");
+        assert_eq!(resolve_with("Union[int, list[str], Point]", options.clone()).unwrap(), "IntOrListStrOrPoint");
+
+        let definitions = options.union_enums.take().to_string();
+        syn::parse_str::<syn::File>(&definitions).unwrap_or_else(|e| panic!("{}: {}", e, definitions));
+        assert_eq!(definitions.matches("pub enum IntOrStr").count(), 1, "{}", definitions);
+        assert!(definitions.contains("pub enum IntOrStr { Int (i64) , Str (String) }"), "{}", definitions);
+        assert!(definitions.contains("impl From < i64 > for IntOrStr"), "{}", definitions);
+        assert!(
+            definitions.contains("pub enum IntOrListStrOrPoint { Int (i64) , ListStr (Vec < String >) , Point (Point :: Data) }"),
+            "{}",
+            definitions
+        );
+        assert!(options.union_enums.take().is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_annotation_points_at_itself() {
+        let error = resolve_with("Frob[int]", PythonOptions::default()).unwrap_err();
+        let location = error.downcast_ref::<CodeGenError>().and_then(CodeGenError::location).expect("a location");
+        assert_eq!((location.line, location.column), (Some(1), Some(3)));
+        assert_eq!((location.end_line, location.end_column), (Some(1), Some(12)));
+    }
+}
@@ -1,6 +1,8 @@
 //! Module representing the Python Data Model in Rust form.
 //! See: [here](https://docs.python.org/3/reference/datamodel.html).
 
+use crate::{ExprType, Statement, StatementType};
+
 /// The Python Object. Anything that implements this trait is a Python Object.
 pub trait Object: Sized {
     /// Returns the unique identifier of the object, which is the memory address of the object.
@@ -18,6 +20,29 @@ pub trait Object: Sized {
         self.id() == other.id()
     }
 
+    /// The statements defining the object, for objects that have a body, like modules,
+    /// classes and functions.
+    fn body(&self) -> &[Statement] {
+        &[]
+    }
+
+    /// The text of the docstring of a body, the string constant that's its first statement,
+    /// as it was written.
+    fn get_docstring_from_body(body: &[Statement]) -> Option<String> {
+        let StatementType::Expr(expr) = &body.first()?.statement else {
+            return std::option::Option::None;
+        };
+        let ExprType::Constant(constant) = &expr.value else {
+            return std::option::Option::None;
+        };
+        let text = constant.string_value()?;
+        // The quotes around the docstring aren't part of its text.
+        let unquoted = ["\"\"\"", "'''"]
+            .iter()
+            .find_map(|quotes| text.strip_prefix(quotes).and_then(|text| text.strip_suffix(quotes)));
+        Some(unquoted.map(String::from).unwrap_or(text))
+    }
+
     /// __getattribute__ is called to look up an attribute of the object.
     fn __getattribute__(&self, _name: impl AsRef<str>) -> Option<impl Object> {
         std::option::Option::<i32>::None
@@ -160,4 +185,21 @@ mod tests {
         assert_eq!(y.is(&None), true);
         assert_ne!(y.is(&NotImplemented), true);
     }
+
+    #[test]
+    fn test_docstring_from_body() {
+        let source = "\"\"\"The module.\"\"\"\n\nclass Shape:\n    \"\"\"A shape.\"\"\"\n    def area(self):\n        \"Its area.\"\n        return 0\n";
+        let module = crate::parse(source, "test.py").unwrap();
+        assert_eq!(crate::Module::get_docstring_from_body(module.body()).as_deref(), Some("The module."));
+
+        let StatementType::ClassDef(class) = &module.body()[1].statement else {
+            panic!("expected a class");
+        };
+        assert_eq!(crate::ClassDef::get_docstring_from_body(class.body()).as_deref(), Some("A shape."));
+        let StatementType::FunctionDef(function) = &class.body()[1].statement else {
+            panic!("expected a method");
+        };
+        assert_eq!(crate::FunctionDef::get_docstring_from_body(function.body()).as_deref(), Some("Its area."));
+        assert_eq!(crate::FunctionDef::get_docstring_from_body(&function.body()[1..]), std::option::Option::None);
+    }
 }
@@ -14,6 +14,12 @@ pub use scope::*;
 pub mod symbols;
 pub use symbols::*;
 
+pub mod visitor;
+pub use visitor::*;
+
+pub mod transformer;
+pub use transformer::*;
+
 pub mod pytypes;
 
 pub use pyo3::PyResult;
@@ -34,3 +40,6 @@ pub use traits::*;
 
 pub mod parser_utils;
 pub use parser_utils::*;
+
+#[cfg(test)]
+mod test_utils;
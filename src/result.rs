@@ -254,6 +254,152 @@ impl Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Errors from generating Rust code for a tree that parsed. The `CodeGen` trait returns
+/// them boxed, so callers that need to tell them apart downcast the error:
+/// `error.downcast_ref::<CodeGenError>()`.
+#[derive(E, Debug, Clone, PartialEq)]
+pub enum CodeGenError {
+    #[error("{node_type} is not supported{}", at(location))]
+    UnsupportedNode {
+        node_type: String,
+        location: Option<SourceLocation>,
+    },
+
+    #[error("Type mismatch: expected {expected}, found {found}{}", at(location))]
+    TypeMismatch {
+        expected: String,
+        found: String,
+        location: Option<SourceLocation>,
+    },
+
+    #[error("Undefined symbol: {name}{}", at(location))]
+    UndefinedSymbol {
+        name: String,
+        location: Option<SourceLocation>,
+    },
+
+    #[error("Malformed AST: {message}{}", at(location))]
+    MalformedAst {
+        message: String,
+        location: Option<SourceLocation>,
+    },
+
+    #[error("No Rust mapping for imported module {module}{}; add one to PythonOptions::import_map", at(location))]
+    UnmappedImport {
+        module: String,
+        location: Option<SourceLocation>,
+    },
+
+    #[error("Invalid call to {function}: {message}{}", at(location))]
+    InvalidCall {
+        function: String,
+        message: String,
+        location: Option<SourceLocation>,
+    },
+
+    #[error("Circular import: {}{}", cycle.join(" -> "), at(location))]
+    CircularImport {
+        cycle: Vec<String>,
+        location: Option<SourceLocation>,
+    },
+}
+
+/// The filename of locations taken from nodes, which don't know which file they're in,
+/// until the module they're in fills it in.
+pub const UNKNOWN_FILE: &str = "<unknown>";
+
+fn at(location: &Option<SourceLocation>) -> String {
+    location.as_ref().map(|l| format!(" at {}", l)).unwrap_or_default()
+}
+
+impl CodeGenError {
+    /// Create an error for a node code can't be generated for.
+    pub fn unsupported(node_type: impl Into<String>, location: Option<SourceLocation>) -> Self {
+        CodeGenError::UnsupportedNode {
+            node_type: node_type.into(),
+            location,
+        }
+    }
+
+    /// Create an error for a call whose arguments don't match the parameters of `function`.
+    pub fn invalid_call(function: impl Into<String>, message: impl Into<String>, location: Option<SourceLocation>) -> Self {
+        CodeGenError::InvalidCall {
+            function: function.into(),
+            message: message.into(),
+            location,
+        }
+    }
+
+    /// Create an error for a tree that doesn't have the shape Python's ast module gives it.
+    pub fn malformed(message: impl Into<String>) -> Self {
+        CodeGenError::MalformedAst {
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    /// Where in the Python source the error is, if that's known.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        match self {
+            CodeGenError::UnsupportedNode { location, .. }
+            | CodeGenError::TypeMismatch { location, .. }
+            | CodeGenError::UndefinedSymbol { location, .. }
+            | CodeGenError::MalformedAst { location, .. }
+            | CodeGenError::UnmappedImport { location, .. }
+            | CodeGenError::InvalidCall { location, .. }
+            | CodeGenError::CircularImport { location, .. } => location.as_ref(),
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut Option<SourceLocation> {
+        match self {
+            CodeGenError::UnsupportedNode { location, .. }
+            | CodeGenError::TypeMismatch { location, .. }
+            | CodeGenError::UndefinedSymbol { location, .. }
+            | CodeGenError::MalformedAst { location, .. }
+            | CodeGenError::UnmappedImport { location, .. }
+            | CodeGenError::InvalidCall { location, .. }
+            | CodeGenError::CircularImport { location, .. } => location,
+        }
+    }
+
+    /// Give the error a location, unless a node nested deeper already gave it a more precise one.
+    pub fn or_at(mut self, location: SourceLocation) -> Self {
+        self.location_mut().get_or_insert(location);
+        self
+    }
+
+    /// Fill in the file a location taken from a node is in.
+    pub fn in_file(mut self, filename: &str) -> Self {
+        if let Some(location) = self.location_mut()
+            && location.filename == UNKNOWN_FILE
+        {
+            location.filename = filename.to_string();
+        }
+        self
+    }
+}
+
+/// Give a boxed error the location of `node` if it's a [`CodeGenError`] without one, as
+/// errors pass up through the statements they happened in.
+pub fn locate_error(
+    error: Box<dyn std::error::Error>,
+    node: &impl crate::Node,
+) -> Box<dyn std::error::Error> {
+    match error.downcast::<CodeGenError>() {
+        Ok(error) => Box::new(error.or_at(node.source_location(UNKNOWN_FILE))),
+        Err(error) => error,
+    }
+}
+
+/// Fill in the file of a boxed [`CodeGenError`]'s location, once it reaches the module.
+pub fn error_in_file(error: Box<dyn std::error::Error>, filename: &str) -> Box<dyn std::error::Error> {
+    match error.downcast::<CodeGenError>() {
+        Ok(error) => Box::new(error.in_file(filename)),
+        Err(error) => error,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,6 +449,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_codegen_error_display() {
+        let location = SourceLocation::with_position("test.py", Some(3), Some(4));
+        let error = CodeGenError::unsupported("async with", Some(location));
+        assert_eq!(error.to_string(), "async with is not supported at test.py:3:4");
+        assert_eq!(
+            CodeGenError::unsupported("async with", None).to_string(),
+            "async with is not supported"
+        );
+    }
+
+    #[test]
+    fn test_codegen_error_keeps_innermost_location() {
+        let inner = SourceLocation::with_position(UNKNOWN_FILE, Some(3), Some(4));
+        let outer = SourceLocation::with_position(UNKNOWN_FILE, Some(2), Some(0));
+        let error = CodeGenError::malformed("missing body").or_at(inner).or_at(outer).in_file("test.py");
+        assert_eq!(error.to_string(), "Malformed AST: missing body at test.py:3:4");
+    }
+
+    #[test]
+    fn test_codegen_error_downcast() {
+        let boxed: Box<dyn std::error::Error> = CodeGenError::malformed("missing body").into();
+        match boxed.downcast_ref::<CodeGenError>() {
+            Some(CodeGenError::MalformedAst { message, .. }) => assert_eq!(message, "missing body"),
+            other => panic!("Expected MalformedAst, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_error_chaining() {
         let result: Result<i32> = Err(Error::UnknownType("ChainTest".to_string()));
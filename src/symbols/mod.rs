@@ -1,50 +1,235 @@
 //! Implements a Python-compatilble symbol table for Rust.
 
+use std::collections::BTreeSet;
 use std::collections::HashMap;
-use std::collections::VecDeque;
+use std::collections::HashSet;
 use std::fmt;
 
-use crate::tree::{ClassDef, FunctionDef, Import, ImportFrom};
+use crate::tree::{ClassDef, FunctionDef, Import, ImportFrom, Parameter};
 
 //use tracing::{debug, info};
 
 //use crate::codegen::{CodeGen, PythonOptions, CodeGenContext};
 use crate::tree::ExprType;
+use crate::codegen::{sanitize_ident, TypeVar};
+use proc_macro2::Ident;
 
-/// A stack of symbol tables of different scopes. Topmost is the current scope.
+/// A stack of symbol tables of different scopes. The last one is the current scope, and
+/// the first one is the module's.
 #[derive(Clone, Debug)]
-pub struct SymbolTableScopes(VecDeque<SymbolTable>);
+pub struct SymbolTableScopes(Vec<SymbolTable>);
 
 impl SymbolTableScopes {
     pub fn new() -> Self {
-        Self(VecDeque::new())
+        Self(Vec::new())
     }
 
     pub fn push(&mut self, table: SymbolTable) {
-        self.0.push_front(table);
+        self.0.push(table);
     }
 
-    pub fn pop(&mut self) -> Option<SymbolTable> {
-        self.0.pop_front()
+    /// Enter a new, empty scope, like the body of a function.
+    pub fn push_scope(&mut self) {
+        self.0.push(SymbolTable::new());
     }
 
-    pub fn new_scope(&mut self) {
-        self.0.push_front(SymbolTable::new());
+    /// Enter the scope of the body of the function `name`.
+    pub fn push_function_scope(&mut self, name: &str) {
+        let mut table = SymbolTable::new();
+        table.function = Some(name.to_string());
+        self.0.push(table);
+    }
+
+    /// Whether the current scope is the body of a function nested in another function.
+    pub fn is_nested_function(&self) -> bool {
+        match self.0.split_last() {
+            Some((current, outer)) => current.function.is_some() && outer.iter().any(|table| table.function.is_some()),
+            None => false,
+        }
+    }
+
+    /// Whether a name the current scope doesn't bind itself is a variable of an enclosing
+    /// function, which a function nested in it captures.
+    pub fn is_captured(&self, key: &str) -> bool {
+        let Some((current, outer)) = self.0.split_last() else {
+            return false;
+        };
+        if current.get(key).is_some_and(|node| !matches!(node, SymbolTableNode::Nonlocal)) {
+            return false;
+        }
+        outer
+            .iter()
+            .rev()
+            .find(|table| table.get(key).is_some_and(|node| !matches!(node, SymbolTableNode::Nonlocal | SymbolTableNode::Global)))
+            .is_some_and(|table| table.function.is_some())
+    }
+
+    /// Leave the current scope, returning its symbols.
+    pub fn pop_scope(&mut self) -> Option<SymbolTable> {
+        self.0.pop()
     }
 
+    /// Bind a name in the current scope. A name that isn't a valid Rust identifier, like
+    /// `self` outside of a method, is renamed to one that isn't bound in the scope yet, so
+    /// that every reference to it gets the same name.
     pub fn insert(&mut self, key: String, value: SymbolTableNode) {
-        if let Some(table) = self.0.front_mut() {
+        if let Some(table) = self.0.last_mut() {
+            let ident = sanitize_ident(&key);
+            let spelling = ident.to_string();
+            if spelling.trim_start_matches("r#") != key && !table.renames.contains_key(&key) {
+                let mut spelling = spelling;
+                while table.symbols.contains_key(&spelling) {
+                    spelling.push('_');
+                }
+                table.renames.insert(key.clone(), sanitize_ident(&spelling));
+            }
             table.insert(key, value);
         }
     }
 
-    pub fn get(&self, key: &str) -> Option<&SymbolTableNode> {
-        for table in self.0.iter() {
-            if let Some(value) = table.get(key) {
-                return Some(value);
+    /// Give a name of the current scope the identifier it has in Rust.
+    pub fn rename(&mut self, key: &str, ident: Ident) {
+        if let Some(table) = self.0.last_mut() {
+            table.renames.insert(key.to_string(), ident);
+        }
+    }
+
+    /// The identifier a name was renamed to in the scope it's bound in, if it was.
+    pub fn renamed(&self, key: &str) -> Option<&Ident> {
+        self.0
+            .iter()
+            .rev()
+            .find(|table| table.symbols.contains_key(key) || table.renames.contains_key(key))
+            .and_then(|table| table.renames.get(key))
+    }
+
+    /// What a name is bound to, looking from the current scope out to the module's, so
+    /// that a function sees the names of the functions enclosing it.
+    pub fn lookup(&self, key: &str) -> Option<&SymbolTableNode> {
+        self.0.iter().rev().find_map(|table| table.get(key))
+    }
+
+    /// What a name is bound to in the current scope only.
+    pub fn lookup_current(&self, key: &str) -> Option<&SymbolTableNode> {
+        self.0.last().and_then(|table| table.get(key))
+    }
+
+    /// Returns true if the name was declared `global` or `nonlocal` in the current scope,
+    /// meaning assignments to it rebind an outer variable rather than creating a new one.
+    pub fn is_outer_binding(&self, key: &str) -> bool {
+        matches!(
+            self.0.last().and_then(|table| table.get(key)),
+            Some(SymbolTableNode::Global) | Some(SymbolTableNode::Nonlocal)
+        )
+    }
+
+    /// Records that the variable is changed in place, by augmented assignment or a mutating
+    /// method call, so that it has to be bound with `let mut`. The mark goes on the scope
+    /// that binds the name, or the current scope if none does yet.
+    pub fn mark_mutated(&mut self, key: &str) {
+        let current = self.0.len().saturating_sub(1);
+        let index = self.0.iter().rposition(|table| table.get(key).is_some()).unwrap_or(current);
+        if let Some(table) = self.0.get_mut(index) {
+            table.mutated.insert(key.to_string());
+            if let Some(SymbolTableNode::VariableDef { is_mutable, .. }) = table.symbols.get_mut(key) {
+                *is_mutable = true;
             }
         }
-        None
+    }
+
+    /// Records that the assignment at `position`, its line and column, changes a variable
+    /// declared before it rather than declaring a new one.
+    pub fn mark_reassignment(&mut self, position: (usize, usize)) {
+        if let Some(table) = self.0.last_mut() {
+            table.reassignments.insert(position);
+        }
+    }
+
+    /// Returns true if the assignment at `position` was marked as changing a variable.
+    pub fn is_reassignment(&self, position: (usize, usize)) -> bool {
+        self.0.iter().any(|table| table.reassignments.contains(&position))
+    }
+
+    /// Records a variable that's declared ahead of the block statement at `position`,
+    /// because the block assigns it first but it's used after the block. `definite` is
+    /// whether every path through the statement assigns it.
+    pub fn mark_hoisted(&mut self, position: (usize, usize), key: String, definite: bool) {
+        if let Some(table) = self.0.last_mut() {
+            table.hoisted.entry(position).or_default().push((key, definite));
+        }
+    }
+
+    /// The variables declared ahead of the block statement at `position`.
+    pub fn hoisted(&self, position: (usize, usize)) -> Vec<(String, bool)> {
+        self.0.iter().rev().find_map(|table| table.hoisted.get(&position)).cloned().unwrap_or_default()
+    }
+
+    /// The annotated type of a variable or parameter, if it has one.
+    pub fn type_annotation(&self, key: &str) -> Option<&ExprType> {
+        match self.lookup(key)? {
+            SymbolTableNode::VariableDef { type_annotation, .. } => type_annotation.as_ref(),
+            SymbolTableNode::Parameter(parameter) => parameter.annotation.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Records an exception type raised or caught in the module, which its error type needs a
+    /// variant for. The module's scope is the outermost one, and outlives function scopes.
+    pub fn record_exception(&mut self, kind: &str) {
+        if let Some(table) = self.0.first_mut() {
+            table.exceptions.insert(kind.to_string());
+        }
+    }
+
+    /// Records a function that a try statement calls, directly or through other functions,
+    /// which returns a Result when it can raise so that the try can catch what it raises.
+    pub fn mark_called_in_try(&mut self, key: &str) {
+        if let Some(table) = self.0.first_mut() {
+            table.called_in_try.insert(key.to_string());
+        }
+    }
+
+    /// Whether a function was marked with [`mark_called_in_try`](Self::mark_called_in_try).
+    pub fn is_called_in_try(&self, key: &str) -> bool {
+        self.0.first().is_some_and(|table| table.called_in_try.contains(key))
+    }
+
+    /// Records a variable of the module that functions declare `global`, which is shared
+    /// with them through a thread-local cell. The module's scope is the outermost one.
+    pub fn mark_module_variable(&mut self, key: &str) {
+        if let Some(table) = self.0.first_mut() {
+            table.module_variables.insert(key.to_string());
+        }
+    }
+
+    /// Whether a name refers to a variable of the module marked with
+    /// [`mark_module_variable`](Self::mark_module_variable), rather than a variable of a
+    /// function that has one of the same name.
+    pub fn is_module_variable(&self, key: &str) -> bool {
+        self.0.first().is_some_and(|module| module.module_variables.contains(key))
+            && self
+                .0
+                .iter()
+                .rposition(|table| table.get(key).is_some_and(|node| !matches!(node, SymbolTableNode::Global)))
+                .is_none_or(|index| index == 0)
+    }
+
+    /// The exception types raised or caught in the module, in alphabetical order.
+    pub fn exceptions(&self) -> Vec<String> {
+        self.0
+            .first()
+            .map(|table| table.exceptions.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the variable was marked as changed in place in the scope that binds it.
+    pub fn is_mutated(&self, key: &str) -> bool {
+        self.0
+            .iter()
+            .rev()
+            .find(|table| table.get(key).is_some())
+            .or(self.0.last())
+            .is_some_and(|table| table.mutated.contains(key))
     }
 }
 
@@ -63,22 +248,68 @@ impl Default for SymbolTable {
 #[derive(Clone, Debug)]
 pub enum SymbolTableNode {
     Assign { position: usize, value: ExprType },
-    ClassDef(ClassDef),
+    /// A class, with the methods that change the instance and so take `&mut self`.
+    ClassDef {
+        class: ClassDef,
+        mutating_methods: HashSet<String>,
+    },
     FunctionDef(FunctionDef),
     Import(Import),
     ImportFrom(ImportFrom),
     Alias(String),
+    /// A parameter of the enclosing function.
+    Parameter(Parameter),
+    /// A variable declared with an annotation, like `x: int = 5`. Assigning to it again
+    /// reassigns it rather than declaring a new one, which makes it mutable.
+    VariableDef {
+        name: String,
+        type_annotation: Option<ExprType>,
+        is_mutable: bool,
+    },
+    /// A type variable, like `T = TypeVar("T")`.
+    TypeVar(TypeVar),
+    /// The name was declared with `global` and refers to the module scope.
+    Global,
+    /// The name was declared with `nonlocal` and refers to an enclosing function scope.
+    Nonlocal,
 }
 
 #[derive(Clone, Debug)]
 pub struct SymbolTable {
     pub symbols: HashMap<String, SymbolTableNode>,
+    /// Variables that are changed in place.
+    pub mutated: HashSet<String>,
+    /// The positions of the assignments that change a variable rather than declare one.
+    pub reassignments: HashSet<(usize, usize)>,
+    /// The variables declared ahead of the block statements at the positions, since the
+    /// blocks assign them first, and whether the statements assign them on every path.
+    pub hoisted: HashMap<(usize, usize), Vec<(String, bool)>>,
+    /// Exception types raised or caught, recorded on the module's scope.
+    pub exceptions: BTreeSet<String>,
+    /// The variables of the module that functions declare `global`, recorded on the
+    /// module's scope.
+    pub module_variables: HashSet<String>,
+    /// The functions try statements call, and the functions those call, recorded on the
+    /// module's scope.
+    pub called_in_try: HashSet<String>,
+    /// The identifiers of names that can't be used in Rust as they are.
+    pub renames: HashMap<String, Ident>,
+    /// The function whose body the scope is, if it's a function's.
+    pub function: Option<String>,
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         Self {
             symbols: HashMap::new(),
+            mutated: HashSet::new(),
+            reassignments: HashSet::new(),
+            hoisted: HashMap::new(),
+            exceptions: BTreeSet::new(),
+            module_variables: HashSet::new(),
+            called_in_try: HashSet::new(),
+            renames: HashMap::new(),
+            function: None,
         }
     }
 
@@ -148,31 +379,31 @@ mod tests {
         scopes.push(table);
         assert_eq!(scopes.0.len(), 1);
         
-        let popped = scopes.pop();
+        let popped = scopes.pop_scope();
         assert!(popped.is_some());
         assert_eq!(scopes.0.len(), 0);
     }
 
     #[test]
-    fn test_symbol_table_scopes_new_scope() {
+    fn test_symbol_table_scopes_push_scope() {
         let mut scopes = SymbolTableScopes::new();
         
-        scopes.new_scope();
+        scopes.push_scope();
         assert_eq!(scopes.0.len(), 1);
         
-        scopes.new_scope();
+        scopes.push_scope();
         assert_eq!(scopes.0.len(), 2);
     }
 
     #[test]
     fn test_symbol_table_scopes_insert_and_get() {
         let mut scopes = SymbolTableScopes::new();
-        scopes.new_scope();
+        scopes.push_scope();
         
         let node = SymbolTableNode::Alias("scoped_alias".to_string());
         scopes.insert("test_key".to_string(), node);
         
-        let retrieved = scopes.get("test_key");
+        let retrieved = scopes.lookup("test_key");
         assert!(retrieved.is_some());
         
         match retrieved.unwrap() {
@@ -186,24 +417,24 @@ mod tests {
         let mut scopes = SymbolTableScopes::new();
         
         // Outer scope
-        scopes.new_scope();
+        scopes.push_scope();
         let outer_node = SymbolTableNode::Alias("outer_alias".to_string());
         scopes.insert("outer_key".to_string(), outer_node);
         
         // Inner scope
-        scopes.new_scope();
+        scopes.push_scope();
         let inner_node = SymbolTableNode::Alias("inner_alias".to_string());
         scopes.insert("inner_key".to_string(), inner_node);
         
         // Should find both keys
-        assert!(scopes.get("inner_key").is_some());
-        assert!(scopes.get("outer_key").is_some());
+        assert!(scopes.lookup("inner_key").is_some());
+        assert!(scopes.lookup("outer_key").is_some());
         
         // Inner scope should shadow outer scope for same key
         let shadow_node = SymbolTableNode::Alias("shadow_alias".to_string());
         scopes.insert("outer_key".to_string(), shadow_node);
         
-        match scopes.get("outer_key").unwrap() {
+        match scopes.lookup("outer_key").unwrap() {
             SymbolTableNode::Alias(alias) => assert_eq!(alias, "shadow_alias"),
             _ => panic!("Expected shadowed alias"),
         }
@@ -212,7 +443,7 @@ mod tests {
     #[test]
     fn test_symbol_table_scopes_empty_get() {
         let scopes = SymbolTableScopes::new();
-        assert!(scopes.get("any_key").is_none());
+        assert!(scopes.lookup("any_key").is_none());
     }
 
     #[test]
@@ -224,7 +455,7 @@ mod tests {
         scopes.insert("key".to_string(), node);
         
         // Should return None since no scopes exist
-        assert!(scopes.get("key").is_none());
+        assert!(scopes.lookup("key").is_none());
     }
 
     #[test]
@@ -261,6 +492,51 @@ mod tests {
         assert!(display_string.contains("test_display"));
     }
 
+    #[test]
+    fn test_symbol_table_scopes_outer_binding() {
+        let mut scopes = SymbolTableScopes::new();
+        scopes.push_scope();
+        scopes.insert("counter".to_string(), SymbolTableNode::Alias("counter".to_string()));
+
+        scopes.push_scope();
+        assert!(!scopes.is_outer_binding("counter"));
+
+        scopes.insert("counter".to_string(), SymbolTableNode::Global);
+        assert!(scopes.is_outer_binding("counter"));
+
+        // A nested scope does not inherit the declaration.
+        scopes.push_scope();
+        assert!(!scopes.is_outer_binding("counter"));
+    }
+
+    #[test]
+    fn test_exceptions_are_recorded_on_the_module_scope() {
+        let mut scopes = SymbolTableScopes::new();
+        scopes.push_scope();
+        scopes.push_scope();
+        scopes.record_exception("ValueError");
+        scopes.record_exception("KeyError");
+        scopes.record_exception("ValueError");
+        scopes.pop_scope();
+        assert_eq!(scopes.exceptions(), vec!["KeyError".to_string(), "ValueError".to_string()]);
+    }
+
+    #[test]
+    fn test_symbol_table_scopes_mutated() {
+        let mut scopes = SymbolTableScopes::new();
+        scopes.push_scope();
+        scopes.insert("items".to_string(), SymbolTableNode::Alias("items".to_string()));
+
+        scopes.push_scope();
+        assert!(!scopes.is_mutated("items"));
+
+        // Marks go on the binding scope, so they're seen from there too.
+        scopes.mark_mutated("items");
+        assert!(scopes.is_mutated("items"));
+        scopes.pop_scope();
+        assert!(scopes.is_mutated("items"));
+    }
+
     #[test]
     fn test_symbol_table_scopes_default() {
         let scopes = SymbolTableScopes::default();
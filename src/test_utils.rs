@@ -0,0 +1,84 @@
+//! Helpers the unit tests share for generating Rust code from Python source.
+
+use std::fs;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{CodeGen, CodeGenContext, Module, PythonOptions, StatementType, SymbolTableScopes};
+
+/// The options tests generate code with, which leave out the `stdpython` import.
+pub fn test_options() -> PythonOptions {
+    let mut options = PythonOptions::default();
+    options.with_std_python = false;
+    options
+}
+
+/// The Rust code of a module, or the error generating it failed with.
+pub fn try_module_to_rust(source: &str, options: PythonOptions) -> Result<String, Box<dyn std::error::Error>> {
+    let module = crate::parse(source, "test.py")?;
+    Ok(module.to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())?.to_string())
+}
+
+/// The Rust code of a module that's been parsed already.
+pub fn parsed_module_to_rust(module: Module, options: PythonOptions) -> String {
+    module
+        .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+        .unwrap()
+        .to_string()
+}
+
+/// The Rust code of a module.
+pub fn module_to_rust(source: &str, options: PythonOptions) -> String {
+    try_module_to_rust(source, options).unwrap()
+}
+
+/// The Rust code of the first statement of the source, on its own rather than in a
+/// module, like a function.
+pub fn statement_to_rust(source: &str, options: PythonOptions) -> String {
+    let module = crate::parse(source, "test.py").unwrap();
+    module.raw.body[0]
+        .clone()
+        .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())
+        .unwrap()
+        .to_string()
+}
+
+/// The Rust code of the expression the source is, or the error generating it failed with.
+pub fn try_expr_to_rust(source: &str, options: PythonOptions) -> Result<String, Box<dyn std::error::Error>> {
+    let module = crate::parse(source, "test.py")?;
+    match &module.raw.body[0].statement {
+        StatementType::Expr(e) => Ok(e
+            .value
+            .clone()
+            .to_rust(CodeGenContext::Module("test".to_string()), options, SymbolTableScopes::new())?
+            .to_string()),
+        s => panic!("expected an expression statement, got {:?}", s),
+    }
+}
+
+/// The Rust code of the expression the source is.
+pub fn expr_to_rust(source: &str, options: PythonOptions) -> String {
+    try_expr_to_rust(source, options).unwrap()
+}
+
+/// Compile generated code as a library crate with rustc, and fail with what rustc reported
+/// if it doesn't compile. Warnings are allowed, since generated code has plenty.
+pub fn assert_compiles(code: &str) {
+    static CRATES: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "python_ast_compiles_{}_{}",
+        std::process::id(),
+        CRATES.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("generated.rs");
+    fs::write(&source, code).unwrap();
+    let output = Command::new("rustc")
+        .args(["--edition", "2024", "--crate-type", "lib", "--emit", "metadata", "-A", "warnings", "--out-dir"])
+        .arg(&dir)
+        .arg(&source)
+        .output()
+        .expect("running rustc");
+    let _ = fs::remove_dir_all(&dir);
+    assert!(output.status.success(), "{}\n{}", String::from_utf8_lossy(&output.stderr), code);
+}
@@ -0,0 +1,733 @@
+//! Rewriting of the syntax tree before code generation.
+//!
+//! [`Transformer`] mirrors [`Visitor`](crate::Visitor), except that each method takes a node
+//! and returns the node to use in its place. The default implementation of each method
+//! transforms the node's children with the matching `fold_*` function and otherwise returns
+//! the node as it was, so a transformer that overrides nothing returns the tree unchanged.
+//!
+//! Transformers pushed onto [`PythonOptions::transformers`](crate::PythonOptions) are applied
+//! to a module, in order, before code is generated for it, and [`Module::transform`] applies
+//! one on its own. [`ConstantFolder`], [`PrintInstrumenter`] and [`StripDocstrings`] are
+//! examples.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    is_docstring, Arguments, BinOp, BinOps, Call, ClassDef, Comprehension, Constant, ExceptHandler, Expr,
+    ExprType, FunctionDef, Keyword, MatchCase, Module, Name, Ops, Pattern, Statement,
+    StatementType, UnaryOp, WithItem,
+};
+
+/// A pass that rewrites the syntax tree. Every method transforms the children of its node by
+/// default.
+pub trait Transformer {
+    fn transform_module(&mut self, node: Module) -> Module {
+        fold_module(node, self)
+    }
+
+    /// Returning `None` deletes the statement.
+    fn transform_statement(&mut self, stmt: Statement) -> Option<Statement> {
+        Some(fold_statement(stmt, self))
+    }
+
+    /// Transforms both regular and async function definitions.
+    fn transform_function_def(&mut self, node: FunctionDef) -> FunctionDef {
+        fold_function_def(node, self)
+    }
+
+    fn transform_class_def(&mut self, node: ClassDef) -> ClassDef {
+        fold_class_def(node, self)
+    }
+
+    fn transform_arguments(&mut self, node: Arguments) -> Arguments {
+        fold_arguments(node, self)
+    }
+
+    fn transform_except_handler(&mut self, node: ExceptHandler) -> ExceptHandler {
+        fold_except_handler(node, self)
+    }
+
+    fn transform_with_item(&mut self, node: WithItem) -> WithItem {
+        fold_with_item(node, self)
+    }
+
+    fn transform_match_case(&mut self, node: MatchCase) -> MatchCase {
+        fold_match_case(node, self)
+    }
+
+    fn transform_pattern(&mut self, node: Pattern) -> Pattern {
+        fold_pattern(node, self)
+    }
+
+    fn transform_expr(&mut self, expr: ExprType) -> ExprType {
+        fold_expr(expr, self)
+    }
+
+    fn transform_call(&mut self, node: Call) -> Call {
+        fold_call(node, self)
+    }
+
+    fn transform_keyword(&mut self, node: Keyword) -> Keyword {
+        fold_keyword(node, self)
+    }
+
+    fn transform_comprehension(&mut self, node: Comprehension) -> Comprehension {
+        fold_comprehension(node, self)
+    }
+
+    /// Names have no children, so they're returned as they are.
+    fn transform_name(&mut self, node: Name) -> Name {
+        node
+    }
+}
+
+/// Transform the statements of a module.
+pub fn fold_module<T: Transformer + ?Sized>(mut node: Module, transformer: &mut T) -> Module {
+    node.raw.body = fold_body(node.raw.body, transformer);
+    node
+}
+
+/// Transform the statements of a body, leaving out the ones the transformer deletes. A body
+/// whose statements are all deleted is a `pass` instead, since Python's bodies are never
+/// empty.
+fn fold_body<T: Transformer + ?Sized>(body: Vec<Statement>, transformer: &mut T) -> Vec<Statement> {
+    let first = body.first().map(pass_at);
+    let body: Vec<_> = body.into_iter().filter_map(|stmt| transformer.transform_statement(stmt)).collect();
+    match first {
+        Some(pass) if body.is_empty() => vec![pass],
+        _ => body,
+    }
+}
+
+/// A `pass` at the position of a statement, to take its place.
+fn pass_at(stmt: &Statement) -> Statement {
+    Statement { statement: StatementType::Pass, ..stmt.clone() }
+}
+
+fn fold_exprs<T: Transformer + ?Sized>(exprs: Vec<ExprType>, transformer: &mut T) -> Vec<ExprType> {
+    exprs.into_iter().map(|expr| transformer.transform_expr(expr)).collect()
+}
+
+fn fold_boxed<T: Transformer + ?Sized>(expr: Box<ExprType>, transformer: &mut T) -> Box<ExprType> {
+    Box::new(transformer.transform_expr(*expr))
+}
+
+fn fold_optional<T: Transformer + ?Sized>(expr: Option<ExprType>, transformer: &mut T) -> Option<ExprType> {
+    expr.map(|expr| transformer.transform_expr(expr))
+}
+
+/// Transform the expressions and nested statements of a statement.
+pub fn fold_statement<T: Transformer + ?Sized>(mut stmt: Statement, transformer: &mut T) -> Statement {
+    stmt.statement = match stmt.statement {
+        StatementType::FunctionDef(f) => StatementType::FunctionDef(transformer.transform_function_def(f)),
+        StatementType::AsyncFunctionDef(f) => {
+            StatementType::AsyncFunctionDef(transformer.transform_function_def(f))
+        }
+        StatementType::ClassDef(c) => StatementType::ClassDef(transformer.transform_class_def(c)),
+        StatementType::Assign(mut a) => {
+            a.targets = fold_exprs(a.targets, transformer);
+            a.value = transformer.transform_expr(a.value);
+            StatementType::Assign(a)
+        }
+        StatementType::AugAssign(mut a) => {
+            a.target = transformer.transform_expr(a.target);
+            a.value = transformer.transform_expr(a.value);
+            StatementType::AugAssign(a)
+        }
+        StatementType::AnnAssign(mut a) => {
+            a.target = transformer.transform_expr(a.target);
+            a.annotation = fold_boxed(a.annotation, transformer);
+            a.value = a.value.map(|value| transformer.transform_expr(value));
+            StatementType::AnnAssign(a)
+        }
+        StatementType::Call(c) => StatementType::Call(transformer.transform_call(c)),
+        StatementType::Return(value) => StatementType::Return(value.map(|mut value| {
+            value.value = transformer.transform_expr(value.value);
+            value
+        })),
+        StatementType::Expr(mut e) => {
+            e.value = transformer.transform_expr(e.value);
+            StatementType::Expr(e)
+        }
+        StatementType::If(mut i) => {
+            i.test = transformer.transform_expr(i.test);
+            i.body = fold_body(i.body, transformer);
+            i.orelse = fold_body(i.orelse, transformer);
+            StatementType::If(i)
+        }
+        StatementType::For(mut f) => {
+            f.target = transformer.transform_expr(f.target);
+            f.iter = transformer.transform_expr(f.iter);
+            f.body = fold_body(f.body, transformer);
+            f.orelse = fold_body(f.orelse, transformer);
+            StatementType::For(f)
+        }
+        StatementType::AsyncFor(mut f) => {
+            f.target = transformer.transform_expr(f.target);
+            f.iter = transformer.transform_expr(f.iter);
+            f.body = fold_body(f.body, transformer);
+            f.orelse = fold_body(f.orelse, transformer);
+            StatementType::AsyncFor(f)
+        }
+        StatementType::While(mut w) => {
+            w.test = transformer.transform_expr(w.test);
+            w.body = fold_body(w.body, transformer);
+            w.orelse = fold_body(w.orelse, transformer);
+            StatementType::While(w)
+        }
+        StatementType::Try(mut t) => {
+            t.body = fold_body(t.body, transformer);
+            t.handlers = t
+                .handlers
+                .into_iter()
+                .map(|handler| transformer.transform_except_handler(handler))
+                .collect();
+            t.orelse = fold_body(t.orelse, transformer);
+            t.finalbody = fold_body(t.finalbody, transformer);
+            StatementType::Try(t)
+        }
+        StatementType::With(mut w) => {
+            w.items = w.items.into_iter().map(|item| transformer.transform_with_item(item)).collect();
+            w.body = fold_body(w.body, transformer);
+            StatementType::With(w)
+        }
+        StatementType::AsyncWith(mut w) => {
+            w.items = w.items.into_iter().map(|item| transformer.transform_with_item(item)).collect();
+            w.body = fold_body(w.body, transformer);
+            StatementType::AsyncWith(w)
+        }
+        StatementType::Match(mut m) => {
+            m.subject = transformer.transform_expr(m.subject);
+            m.cases = m.cases.into_iter().map(|case| transformer.transform_match_case(case)).collect();
+            StatementType::Match(m)
+        }
+        StatementType::Raise(mut r) => {
+            r.exc = fold_optional(r.exc, transformer);
+            r.cause = fold_optional(r.cause, transformer);
+            StatementType::Raise(r)
+        }
+        StatementType::Assert(mut a) => {
+            a.test = transformer.transform_expr(a.test);
+            a.msg = fold_optional(a.msg, transformer);
+            StatementType::Assert(a)
+        }
+        other @ (StatementType::Import(_)
+        | StatementType::ImportFrom(_)
+        | StatementType::Global(_)
+        | StatementType::Nonlocal(_)
+        | StatementType::Pass
+        | StatementType::Break
+        | StatementType::Continue
+        | StatementType::Unimplemented(_)) => other,
+    };
+    stmt
+}
+
+/// Transform the decorators, parameters, return annotation and body of a function.
+pub fn fold_function_def<T: Transformer + ?Sized>(mut node: FunctionDef, transformer: &mut T) -> FunctionDef {
+    node.decorator_list = fold_exprs(node.decorator_list, transformer);
+    node.args = transformer.transform_arguments(node.args);
+    node.returns = node.returns.map(|returns| fold_boxed(returns, transformer));
+    node.body = fold_body(node.body, transformer);
+    node
+}
+
+/// Transform the decorators, bases and body of a class.
+pub fn fold_class_def<T: Transformer + ?Sized>(mut node: ClassDef, transformer: &mut T) -> ClassDef {
+    node.decorator_list = fold_exprs(node.decorator_list, transformer);
+    node.bases = node.bases.into_iter().map(|base| transformer.transform_expr(base)).collect();
+    node.keywords = node.keywords.into_iter().map(|k| transformer.transform_keyword(k)).collect();
+    node.body = fold_body(node.body, transformer);
+    node
+}
+
+/// Transform the annotations and default values of parameters.
+pub fn fold_arguments<T: Transformer + ?Sized>(mut node: Arguments, transformer: &mut T) -> Arguments {
+    let parameters = node
+        .posonlyargs
+        .iter_mut()
+        .chain(&mut node.args)
+        .chain(&mut node.vararg)
+        .chain(&mut node.kwonlyargs)
+        .chain(&mut node.kwarg);
+    for parameter in parameters {
+        parameter.annotation = parameter.annotation.take().map(|a| fold_boxed(a, transformer));
+    }
+    node.defaults = node.defaults.into_iter().map(|d| fold_boxed(d, transformer)).collect();
+    node.kw_defaults = node
+        .kw_defaults
+        .into_iter()
+        .map(|d| d.map(|d| fold_boxed(d, transformer)))
+        .collect();
+    node
+}
+
+/// Transform the exception type and body of an except clause.
+pub fn fold_except_handler<T: Transformer + ?Sized>(mut node: ExceptHandler, transformer: &mut T) -> ExceptHandler {
+    node.exception_type = fold_optional(node.exception_type, transformer);
+    node.body = fold_body(node.body, transformer);
+    node
+}
+
+/// Transform the context manager and target of a with item.
+pub fn fold_with_item<T: Transformer + ?Sized>(mut node: WithItem, transformer: &mut T) -> WithItem {
+    node.context_expr = transformer.transform_expr(node.context_expr);
+    node.optional_vars = fold_optional(node.optional_vars, transformer);
+    node
+}
+
+/// Transform the pattern, guard and body of a case.
+pub fn fold_match_case<T: Transformer + ?Sized>(mut node: MatchCase, transformer: &mut T) -> MatchCase {
+    node.pattern = transformer.transform_pattern(node.pattern);
+    node.guard = fold_optional(node.guard, transformer);
+    node.body = fold_body(node.body, transformer);
+    node
+}
+
+fn fold_patterns<T: Transformer + ?Sized>(patterns: Vec<Pattern>, transformer: &mut T) -> Vec<Pattern> {
+    patterns.into_iter().map(|p| transformer.transform_pattern(p)).collect()
+}
+
+/// Transform the subpatterns and values of a pattern.
+pub fn fold_pattern<T: Transformer + ?Sized>(node: Pattern, transformer: &mut T) -> Pattern {
+    match node {
+        Pattern::MatchValue(value) => Pattern::MatchValue(transformer.transform_expr(value)),
+        Pattern::MatchSequence(patterns) => Pattern::MatchSequence(fold_patterns(patterns, transformer)),
+        Pattern::MatchOr(patterns) => Pattern::MatchOr(fold_patterns(patterns, transformer)),
+        Pattern::MatchMapping { keys, patterns, rest } => Pattern::MatchMapping {
+            keys: fold_exprs(keys, transformer),
+            patterns: fold_patterns(patterns, transformer),
+            rest,
+        },
+        Pattern::MatchClass { cls, patterns, kwd_attrs, kwd_patterns } => Pattern::MatchClass {
+            cls: transformer.transform_expr(cls),
+            patterns: fold_patterns(patterns, transformer),
+            kwd_attrs,
+            kwd_patterns: fold_patterns(kwd_patterns, transformer),
+        },
+        Pattern::MatchAs { pattern, name } => Pattern::MatchAs {
+            pattern: pattern.map(|p| Box::new(transformer.transform_pattern(*p))),
+            name,
+        },
+        other @ (Pattern::MatchSingleton(_) | Pattern::MatchStar(_)) => other,
+    }
+}
+
+/// Transform the subexpressions of an expression.
+pub fn fold_expr<T: Transformer + ?Sized>(expr: ExprType, transformer: &mut T) -> ExprType {
+    match expr {
+        ExprType::BoolOp(mut b) => {
+            b.left = fold_boxed(b.left, transformer);
+            b.right = fold_boxed(b.right, transformer);
+            ExprType::BoolOp(b)
+        }
+        ExprType::NamedExpr(mut n) => {
+            n.target = fold_boxed(n.target, transformer);
+            n.value = fold_boxed(n.value, transformer);
+            ExprType::NamedExpr(n)
+        }
+        ExprType::BinOp(mut b) => {
+            b.left = fold_boxed(b.left, transformer);
+            b.right = fold_boxed(b.right, transformer);
+            ExprType::BinOp(b)
+        }
+        ExprType::UnaryOp(mut u) => {
+            u.operand = fold_boxed(u.operand, transformer);
+            ExprType::UnaryOp(u)
+        }
+        ExprType::Lambda(mut l) => {
+            l.args = transformer.transform_arguments(l.args);
+            l.body = fold_boxed(l.body, transformer);
+            ExprType::Lambda(l)
+        }
+        ExprType::IfExp(mut i) => {
+            i.test = fold_boxed(i.test, transformer);
+            i.body = fold_boxed(i.body, transformer);
+            i.orelse = fold_boxed(i.orelse, transformer);
+            ExprType::IfExp(i)
+        }
+        ExprType::Dict(mut d) => {
+            d.keys = d.keys.into_iter().map(|k| fold_optional(k, transformer)).collect();
+            d.values = fold_exprs(d.values, transformer);
+            ExprType::Dict(d)
+        }
+        ExprType::Set(mut s) => {
+            s.elts = fold_exprs(s.elts, transformer);
+            ExprType::Set(s)
+        }
+        ExprType::ListComp(mut c) => {
+            c.elt = fold_boxed(c.elt, transformer);
+            c.generators = fold_comprehensions(c.generators, transformer);
+            ExprType::ListComp(c)
+        }
+        ExprType::SetComp(mut c) => {
+            c.elt = fold_boxed(c.elt, transformer);
+            c.generators = fold_comprehensions(c.generators, transformer);
+            ExprType::SetComp(c)
+        }
+        ExprType::GeneratorExp(mut c) => {
+            c.elt = fold_boxed(c.elt, transformer);
+            c.generators = fold_comprehensions(c.generators, transformer);
+            ExprType::GeneratorExp(c)
+        }
+        ExprType::DictComp(mut c) => {
+            c.key = fold_boxed(c.key, transformer);
+            c.value = fold_boxed(c.value, transformer);
+            c.generators = fold_comprehensions(c.generators, transformer);
+            ExprType::DictComp(c)
+        }
+        ExprType::Await(mut a) => {
+            a.value = fold_boxed(a.value, transformer);
+            ExprType::Await(a)
+        }
+        ExprType::Yield(mut y) => {
+            y.value = y.value.map(|v| fold_boxed(v, transformer));
+            ExprType::Yield(y)
+        }
+        ExprType::YieldFrom(mut y) => {
+            y.value = fold_boxed(y.value, transformer);
+            ExprType::YieldFrom(y)
+        }
+        ExprType::Compare(mut c) => {
+            c.left = fold_boxed(c.left, transformer);
+            c.comparators = fold_exprs(c.comparators, transformer);
+            ExprType::Compare(c)
+        }
+        ExprType::Call(c) => ExprType::Call(transformer.transform_call(c)),
+        ExprType::FormattedValue(mut f) => {
+            f.value = fold_boxed(f.value, transformer);
+            f.format_spec = f.format_spec.map(|spec| fold_boxed(spec, transformer));
+            ExprType::FormattedValue(f)
+        }
+        ExprType::JoinedStr(mut j) => {
+            j.values = fold_exprs(j.values, transformer);
+            ExprType::JoinedStr(j)
+        }
+        ExprType::Attribute(mut a) => {
+            a.value = fold_boxed(a.value, transformer);
+            ExprType::Attribute(a)
+        }
+        ExprType::Subscript(mut s) => {
+            s.value = fold_boxed(s.value, transformer);
+            s.slice = fold_boxed(s.slice, transformer);
+            ExprType::Subscript(s)
+        }
+        ExprType::Starred(mut s) => {
+            s.value = fold_boxed(s.value, transformer);
+            ExprType::Starred(s)
+        }
+        ExprType::Name(n) => ExprType::Name(transformer.transform_name(n)),
+        ExprType::List(elts) => ExprType::List(fold_exprs(elts, transformer)),
+        ExprType::Tuple(mut t) => {
+            t.elts = fold_exprs(t.elts, transformer);
+            ExprType::Tuple(t)
+        }
+        other @ (ExprType::Constant(_) | ExprType::Bytes(_) | ExprType::NoneType(_) | ExprType::Ellipsis | ExprType::Unimplemented(_) | ExprType::Unknown) => {
+            other
+        }
+    }
+}
+
+fn fold_comprehensions<T: Transformer + ?Sized>(
+    generators: Vec<Comprehension>,
+    transformer: &mut T,
+) -> Vec<Comprehension> {
+    generators.into_iter().map(|g| transformer.transform_comprehension(g)).collect()
+}
+
+/// Transform the function, arguments and keyword arguments of a call.
+pub fn fold_call<T: Transformer + ?Sized>(mut node: Call, transformer: &mut T) -> Call {
+    node.func = fold_boxed(node.func, transformer);
+    node.args = fold_exprs(node.args, transformer);
+    node.keywords = node.keywords.into_iter().map(|k| transformer.transform_keyword(k)).collect();
+    node
+}
+
+/// Transform the value of a keyword argument.
+pub fn fold_keyword<T: Transformer + ?Sized>(mut node: Keyword, transformer: &mut T) -> Keyword {
+    node.value = transformer.transform_expr(node.value);
+    node
+}
+
+/// Transform the target, iterable and conditions of a comprehension's `for` clause.
+pub fn fold_comprehension<T: Transformer + ?Sized>(mut node: Comprehension, transformer: &mut T) -> Comprehension {
+    node.target = transformer.transform_expr(node.target);
+    node.iter = transformer.transform_expr(node.iter);
+    node.ifs = fold_exprs(node.ifs, transformer);
+    node
+}
+
+/// The transformers applied to a module before code is generated for it. Clones of the
+/// options share them.
+#[derive(Clone, Default)]
+pub struct Transformers(Arc<Mutex<Vec<Box<dyn Transformer + Send>>>>);
+
+impl fmt::Debug for Transformers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.0.lock().map(|transformers| transformers.len()).unwrap_or_default();
+        write!(f, "Transformers({})", count)
+    }
+}
+
+impl Transformers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a transformer to the end of the chain.
+    pub fn push(&self, transformer: Box<dyn Transformer + Send>) {
+        if let Ok(mut transformers) = self.0.lock() {
+            transformers.push(transformer);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().map(|transformers| transformers.is_empty()).unwrap_or(true)
+    }
+
+    /// Apply each of the transformers to the module, in the order they were pushed.
+    pub fn apply(&self, module: Module) -> Module {
+        match self.0.lock() {
+            Ok(mut transformers) => transformers
+                .iter_mut()
+                .fold(module, |module, transformer| transformer.transform_module(module)),
+            Err(_) => module,
+        }
+    }
+}
+
+/// Evaluates arithmetic on integer constants, like `60 * 60 * 24`, so that the generated
+/// code has the result instead. Operations that would overflow an i64, or that Python and
+/// Rust disagree on, like `//` and `%`, are left alone.
+#[derive(Clone, Debug, Default)]
+pub struct ConstantFolder;
+
+impl ConstantFolder {
+    fn int_value(expr: &ExprType) -> Option<i64> {
+        match expr {
+            ExprType::Constant(c) => c.int_value(),
+            ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand }) => Self::int_value(operand)?.checked_neg(),
+            _ => None,
+        }
+    }
+
+    /// The expression Python's parser produces for an integer: negative integers are the
+    /// negation of a positive constant.
+    fn int_expr(value: i64) -> Option<ExprType> {
+        let constant = ExprType::Constant(Constant::parse(&value.unsigned_abs().to_string())?);
+        if value < 0 {
+            Some(ExprType::UnaryOp(UnaryOp { op: Ops::USub, operand: Box::new(constant) }))
+        } else {
+            Some(constant)
+        }
+    }
+
+    fn fold(op: &BinOps, left: i64, right: i64) -> Option<i64> {
+        match op {
+            BinOps::Add => left.checked_add(right),
+            BinOps::Sub => left.checked_sub(right),
+            BinOps::Mult => left.checked_mul(right),
+            BinOps::BitOr => Some(left | right),
+            BinOps::BitAnd => Some(left & right),
+            BinOps::BitXor => Some(left ^ right),
+            _ => None,
+        }
+    }
+}
+
+impl Transformer for ConstantFolder {
+    fn transform_expr(&mut self, expr: ExprType) -> ExprType {
+        // Fold the operands first, so that nested arithmetic folds from the inside out.
+        match fold_expr(expr, self) {
+            ExprType::BinOp(binop) => {
+                let BinOp { op, left, right, .. } = &binop;
+                match (Self::int_value(left), Self::int_value(right)) {
+                    (Some(l), Some(r)) => Self::fold(op, l, r)
+                        .and_then(Self::int_expr)
+                        .unwrap_or(ExprType::BinOp(binop)),
+                    _ => ExprType::BinOp(binop),
+                }
+            }
+            expr => expr,
+        }
+    }
+}
+
+/// Inserts `print("enter <function>")` at the start of every function, to trace which
+/// functions a program runs.
+#[derive(Clone, Debug, Default)]
+pub struct PrintInstrumenter;
+
+impl Transformer for PrintInstrumenter {
+    fn transform_function_def(&mut self, node: FunctionDef) -> FunctionDef {
+        let mut node = fold_function_def(node, self);
+        let message = format!("\"enter {}\"", node.name);
+        if let Some(message) = Constant::parse(&message) {
+            let print = Call {
+                func: Box::new(ExprType::Name(Name { id: "print".to_string() })),
+                args: vec![ExprType::Constant(message)],
+                ..Default::default()
+            };
+            let stmt = Statement {
+                lineno: node.lineno,
+                col_offset: node.col_offset,
+                end_lineno: node.lineno,
+                end_col_offset: node.col_offset,
+                statement: StatementType::Expr(Expr { value: ExprType::Call(print), ..Default::default() }),
+            };
+            node.body.insert(0, stmt);
+        }
+        node
+    }
+}
+
+/// Removes the docstrings of the module, its classes and its functions, so that the
+/// generated code has no doc comments.
+#[derive(Clone, Debug, Default)]
+pub struct StripDocstrings;
+
+impl StripDocstrings {
+    fn strip(body: Vec<Statement>) -> Vec<Statement> {
+        let mut body = body;
+        if body.first().is_some_and(is_docstring) {
+            let docstring = body.remove(0);
+            if body.is_empty() {
+                body.push(pass_at(&docstring));
+            }
+        }
+        body
+    }
+}
+
+impl Transformer for StripDocstrings {
+    fn transform_module(&mut self, node: Module) -> Module {
+        let mut node = fold_module(node, self);
+        node.raw.body = Self::strip(node.raw.body);
+        node
+    }
+
+    fn transform_function_def(&mut self, node: FunctionDef) -> FunctionDef {
+        let mut node = fold_function_def(node, self);
+        node.body = Self::strip(node.body);
+        node
+    }
+
+    fn transform_class_def(&mut self, node: ClassDef) -> ClassDef {
+        let mut node = fold_class_def(node, self);
+        node.body = Self::strip(node.body);
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, PythonOptions};
+    use crate::test_utils::parsed_module_to_rust;
+
+    #[test]
+    fn test_identity_transformer_keeps_the_tree() {
+        struct Identity;
+        impl Transformer for Identity {}
+
+        let module = parse("def f(a=1):\n    for x in a:\n        print(x * 2)\n", "test.py").unwrap();
+        assert_eq!(Identity.transform_module(module.clone()).raw.body, module.raw.body);
+    }
+
+    #[test]
+    fn test_constant_folder() {
+        let module = parse("x = 60 * 60 * 24\ny = 1 - 3\nz = a + 2 * 3\nw = 7 // 2\n", "test.py").unwrap();
+        let module = ConstantFolder.transform_module(module);
+        let values: Vec<_> = module
+            .raw
+            .body
+            .iter()
+            .map(|stmt| match &stmt.statement {
+                StatementType::Assign(a) => a.value.clone(),
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        assert_eq!(ConstantFolder::int_value(&values[0]), Some(86400));
+        assert_eq!(ConstantFolder::int_value(&values[1]), Some(-2));
+        assert!(matches!(&values[2], ExprType::BinOp(b) if ConstantFolder::int_value(&b.right) == Some(6)));
+        assert!(matches!(&values[3], ExprType::BinOp(_)));
+    }
+
+    #[test]
+    fn test_deleted_statements_leave_a_pass() {
+        /// Deletes the calls to log().
+        struct StripLogging;
+        impl Transformer for StripLogging {
+            fn transform_statement(&mut self, stmt: Statement) -> Option<Statement> {
+                match &stmt.statement {
+                    StatementType::Expr(expr)
+                        if matches!(&expr.value, ExprType::Call(call) if matches!(call.func.as_ref(), ExprType::Name(name) if name.id == "log")) =>
+                    {
+                        None
+                    }
+                    _ => Some(fold_statement(stmt, self)),
+                }
+            }
+        }
+
+        let module = parse("def f(x):\n    log(x)\n    return x\n\ndef g(x):\n    log(x)\n", "test.py").unwrap();
+        let before = parsed_module_to_rust(module.clone(), PythonOptions::default());
+        let module = module.transform(&mut StripLogging);
+        let bodies: Vec<_> = module
+            .raw
+            .body
+            .iter()
+            .map(|stmt| match &stmt.statement {
+                StatementType::FunctionDef(function) => function.body.clone(),
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        assert!(matches!(bodies[0].as_slice(), [stmt] if matches!(stmt.statement, StatementType::Return(_))));
+        assert!(matches!(bodies[1].as_slice(), [stmt] if matches!(stmt.statement, StatementType::Pass)));
+        let after = parsed_module_to_rust(module, PythonOptions::default());
+        assert!(before.contains("log (x)"), "{}", before);
+        assert!(!after.contains("log"), "{}", after);
+    }
+
+    #[test]
+    fn test_strip_docstrings() {
+        let module = parse(
+            "\"\"\"The module.\"\"\"
+
+class Shape:
+    \"\"\"A shape.\"\"\"
+
+    def area(self) -> int:
+        \"\"\"The area.\"\"\"
+        return 0
+
+def f():
+    \"\"\"Only a docstring.\"\"\"
+",
+            "test.py",
+        )
+        .unwrap();
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        let before = parsed_module_to_rust(module.clone(), options.clone());
+        assert!(before.contains("A shape.") && before.contains("The area.") && before.contains("Only a docstring."), "{}", before);
+
+        let after = parsed_module_to_rust(module.transform(&mut StripDocstrings), options);
+        assert!(!after.contains("doc"), "{}", after);
+        assert!(after.contains("fn area (& self) -> i64 { 0 }"), "{}", after);
+        assert!(after.contains("pub fn f () {"), "{}", after);
+        syn::parse_str::<syn::File>(&after).unwrap_or_else(|e| panic!("{}: {}", e, after));
+    }
+
+    #[test]
+    fn test_transformers_run_before_code_generation() {
+        let mut options = PythonOptions::default();
+        options.with_std_python = false;
+        options.transformers.push(Box::new(PrintInstrumenter));
+        options.transformers.push(Box::new(ConstantFolder));
+        let module = parse("def f():\n    return 2 * 3\n", "test.py").unwrap();
+        let code = parsed_module_to_rust(module, options);
+        assert!(code.contains("{ println ! (\"enter f\") ; 6 }"), "{}", code);
+    }
+}
@@ -0,0 +1,526 @@
+//! Traversal of the syntax tree, for analysis passes that don't generate code.
+//!
+//! Implement [`Visitor`] and override the methods for the nodes you're interested in. The
+//! default implementation of each method walks the node's children by calling the matching
+//! `walk_*` function, so an overriding method calls that function too if it wants the
+//! traversal to continue below the node. The `walk_*` functions all take the node first and
+//! the visitor second, like `walk_module(&module, &mut visitor)`.
+//!
+//! ```no_run
+//! use python_ast::{parse, Call, ExprType, Visitor, walk_call};
+//!
+//! /// Counts the calls to print().
+//! struct Prints(usize);
+//!
+//! impl Visitor for Prints {
+//!     fn visit_call(&mut self, node: &Call) {
+//!         if matches!(node.func.as_ref(), ExprType::Name(name) if name.id == "print") {
+//!             self.0 += 1;
+//!         }
+//!         walk_call(node, self);
+//!     }
+//! }
+//!
+//! let module = parse("print(print('nested'))", "test.py").unwrap();
+//! let mut prints = Prints(0);
+//! prints.visit_module(&module);
+//! assert_eq!(prints.0, 2);
+//! ```
+
+use crate::{
+    Arguments, Call, ClassDef, Comprehension, ExceptHandler, ExprType, FunctionDef, Keyword,
+    MatchCase, Module, Name, Pattern, Statement, StatementType, WithItem,
+};
+
+/// A pass over the syntax tree. Every method walks the children of its node by default.
+pub trait Visitor {
+    fn visit_module(&mut self, node: &Module) {
+        walk_module(node, self);
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement) {
+        walk_statement(stmt, self);
+    }
+
+    /// Visits both regular and async function definitions.
+    fn visit_function_def(&mut self, node: &FunctionDef) {
+        walk_function_def(node, self);
+    }
+
+    fn visit_class_def(&mut self, node: &ClassDef) {
+        walk_class_def(node, self);
+    }
+
+    fn visit_arguments(&mut self, node: &Arguments) {
+        walk_arguments(node, self);
+    }
+
+    fn visit_except_handler(&mut self, node: &ExceptHandler) {
+        walk_except_handler(node, self);
+    }
+
+    fn visit_with_item(&mut self, node: &WithItem) {
+        walk_with_item(node, self);
+    }
+
+    fn visit_match_case(&mut self, node: &MatchCase) {
+        walk_match_case(node, self);
+    }
+
+    fn visit_pattern(&mut self, node: &Pattern) {
+        walk_pattern(node, self);
+    }
+
+    fn visit_expr(&mut self, expr: &ExprType) {
+        walk_expr(expr, self);
+    }
+
+    fn visit_call(&mut self, node: &Call) {
+        walk_call(node, self);
+    }
+
+    fn visit_keyword(&mut self, node: &Keyword) {
+        walk_keyword(node, self);
+    }
+
+    fn visit_comprehension(&mut self, node: &Comprehension) {
+        walk_comprehension(node, self);
+    }
+
+    /// Names have no children, so there's nothing to walk.
+    fn visit_name(&mut self, _node: &Name) {}
+}
+
+/// Visit the statements of a module.
+pub fn walk_module<V: Visitor + ?Sized>(node: &Module, visitor: &mut V) {
+    walk_body(&node.raw.body, visitor);
+}
+
+fn walk_body<V: Visitor + ?Sized>(body: &[Statement], visitor: &mut V) {
+    for stmt in body {
+        visitor.visit_statement(stmt);
+    }
+}
+
+fn walk_exprs<'a, V: Visitor + ?Sized>(exprs: impl IntoIterator<Item = &'a ExprType>, visitor: &mut V) {
+    for expr in exprs {
+        visitor.visit_expr(expr);
+    }
+}
+
+/// Visit the expressions and nested statements of a statement.
+pub fn walk_statement<V: Visitor + ?Sized>(stmt: &Statement, visitor: &mut V) {
+    match &stmt.statement {
+        StatementType::FunctionDef(f) | StatementType::AsyncFunctionDef(f) => visitor.visit_function_def(f),
+        StatementType::ClassDef(c) => visitor.visit_class_def(c),
+        StatementType::Assign(a) => {
+            walk_exprs(&a.targets, visitor);
+            visitor.visit_expr(&a.value);
+        }
+        StatementType::AugAssign(a) => {
+            visitor.visit_expr(&a.target);
+            visitor.visit_expr(&a.value);
+        }
+        StatementType::AnnAssign(a) => {
+            visitor.visit_expr(&a.target);
+            visitor.visit_expr(&a.annotation);
+            if let Some(value) = &a.value {
+                visitor.visit_expr(value);
+            }
+        }
+        StatementType::Call(c) => visitor.visit_call(c),
+        StatementType::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expr(&value.value);
+            }
+        }
+        StatementType::Expr(e) => visitor.visit_expr(&e.value),
+        StatementType::If(i) => {
+            visitor.visit_expr(&i.test);
+            walk_body(&i.body, visitor);
+            walk_body(&i.orelse, visitor);
+        }
+        StatementType::For(f) => {
+            visitor.visit_expr(&f.target);
+            visitor.visit_expr(&f.iter);
+            walk_body(&f.body, visitor);
+            walk_body(&f.orelse, visitor);
+        }
+        StatementType::AsyncFor(f) => {
+            visitor.visit_expr(&f.target);
+            visitor.visit_expr(&f.iter);
+            walk_body(&f.body, visitor);
+            walk_body(&f.orelse, visitor);
+        }
+        StatementType::While(w) => {
+            visitor.visit_expr(&w.test);
+            walk_body(&w.body, visitor);
+            walk_body(&w.orelse, visitor);
+        }
+        StatementType::Try(t) => {
+            walk_body(&t.body, visitor);
+            for handler in &t.handlers {
+                visitor.visit_except_handler(handler);
+            }
+            walk_body(&t.orelse, visitor);
+            walk_body(&t.finalbody, visitor);
+        }
+        StatementType::With(w) => {
+            for item in &w.items {
+                visitor.visit_with_item(item);
+            }
+            walk_body(&w.body, visitor);
+        }
+        StatementType::AsyncWith(w) => {
+            for item in &w.items {
+                visitor.visit_with_item(item);
+            }
+            walk_body(&w.body, visitor);
+        }
+        StatementType::Match(m) => {
+            visitor.visit_expr(&m.subject);
+            for case in &m.cases {
+                visitor.visit_match_case(case);
+            }
+        }
+        StatementType::Raise(r) => walk_exprs(r.exc.iter().chain(&r.cause), visitor),
+        StatementType::Assert(a) => {
+            visitor.visit_expr(&a.test);
+            walk_exprs(&a.msg, visitor);
+        }
+        StatementType::Import(_)
+        | StatementType::ImportFrom(_)
+        | StatementType::Global(_)
+        | StatementType::Nonlocal(_)
+        | StatementType::Pass
+        | StatementType::Break
+        | StatementType::Continue
+        | StatementType::Unimplemented(_) => {}
+    }
+}
+
+/// Visit the decorators, parameters, return annotation and body of a function.
+pub fn walk_function_def<V: Visitor + ?Sized>(node: &FunctionDef, visitor: &mut V) {
+    walk_exprs(&node.decorator_list, visitor);
+    visitor.visit_arguments(&node.args);
+    if let Some(returns) = &node.returns {
+        visitor.visit_expr(returns);
+    }
+    walk_body(&node.body, visitor);
+}
+
+/// Visit the decorators, bases, keywords and body of a class.
+pub fn walk_class_def<V: Visitor + ?Sized>(node: &ClassDef, visitor: &mut V) {
+    walk_exprs(&node.decorator_list, visitor);
+    for base in &node.bases {
+        visitor.visit_expr(base);
+    }
+    for keyword in &node.keywords {
+        visitor.visit_keyword(keyword);
+    }
+    walk_body(&node.body, visitor);
+}
+
+/// Visit the annotations and default values of parameters.
+pub fn walk_arguments<V: Visitor + ?Sized>(node: &Arguments, visitor: &mut V) {
+    let parameters = node
+        .posonlyargs
+        .iter()
+        .chain(&node.args)
+        .chain(&node.vararg)
+        .chain(&node.kwonlyargs)
+        .chain(&node.kwarg);
+    for parameter in parameters {
+        if let Some(annotation) = &parameter.annotation {
+            visitor.visit_expr(annotation);
+        }
+    }
+    walk_exprs(node.defaults.iter().map(Box::as_ref), visitor);
+    walk_exprs(node.kw_defaults.iter().flatten().map(Box::as_ref), visitor);
+}
+
+/// Visit the exception type and body of an except clause.
+pub fn walk_except_handler<V: Visitor + ?Sized>(node: &ExceptHandler, visitor: &mut V) {
+    walk_exprs(&node.exception_type, visitor);
+    walk_body(&node.body, visitor);
+}
+
+/// Visit the context manager and target of a with item.
+pub fn walk_with_item<V: Visitor + ?Sized>(node: &WithItem, visitor: &mut V) {
+    visitor.visit_expr(&node.context_expr);
+    walk_exprs(&node.optional_vars, visitor);
+}
+
+/// Visit the pattern, guard and body of a case.
+pub fn walk_match_case<V: Visitor + ?Sized>(node: &MatchCase, visitor: &mut V) {
+    visitor.visit_pattern(&node.pattern);
+    walk_exprs(&node.guard, visitor);
+    walk_body(&node.body, visitor);
+}
+
+/// Visit the subpatterns and values of a pattern.
+pub fn walk_pattern<V: Visitor + ?Sized>(node: &Pattern, visitor: &mut V) {
+    match node {
+        Pattern::MatchValue(value) => visitor.visit_expr(value),
+        Pattern::MatchSingleton(_) | Pattern::MatchStar(_) => {}
+        Pattern::MatchSequence(patterns) | Pattern::MatchOr(patterns) => {
+            for pattern in patterns {
+                visitor.visit_pattern(pattern);
+            }
+        }
+        Pattern::MatchMapping { keys, patterns, .. } => {
+            walk_exprs(keys, visitor);
+            for pattern in patterns {
+                visitor.visit_pattern(pattern);
+            }
+        }
+        Pattern::MatchClass { cls, patterns, kwd_patterns, .. } => {
+            visitor.visit_expr(cls);
+            for pattern in patterns.iter().chain(kwd_patterns) {
+                visitor.visit_pattern(pattern);
+            }
+        }
+        Pattern::MatchAs { pattern, .. } => {
+            if let Some(pattern) = pattern {
+                visitor.visit_pattern(pattern);
+            }
+        }
+    }
+}
+
+/// Visit the subexpressions of an expression.
+pub fn walk_expr<V: Visitor + ?Sized>(expr: &ExprType, visitor: &mut V) {
+    match expr {
+        ExprType::BoolOp(b) => {
+            visitor.visit_expr(&b.left);
+            visitor.visit_expr(&b.right);
+        }
+        ExprType::NamedExpr(n) => {
+            visitor.visit_expr(&n.target);
+            visitor.visit_expr(&n.value);
+        }
+        ExprType::BinOp(b) => {
+            visitor.visit_expr(&b.left);
+            visitor.visit_expr(&b.right);
+        }
+        ExprType::UnaryOp(u) => visitor.visit_expr(&u.operand),
+        ExprType::Lambda(l) => {
+            visitor.visit_arguments(&l.args);
+            visitor.visit_expr(&l.body);
+        }
+        ExprType::IfExp(i) => {
+            visitor.visit_expr(&i.test);
+            visitor.visit_expr(&i.body);
+            visitor.visit_expr(&i.orelse);
+        }
+        ExprType::Dict(d) => {
+            walk_exprs(d.keys.iter().flatten(), visitor);
+            walk_exprs(&d.values, visitor);
+        }
+        ExprType::Set(s) => walk_exprs(&s.elts, visitor),
+        ExprType::ListComp(c) => {
+            visitor.visit_expr(&c.elt);
+            walk_comprehensions(&c.generators, visitor);
+        }
+        ExprType::SetComp(c) => {
+            visitor.visit_expr(&c.elt);
+            walk_comprehensions(&c.generators, visitor);
+        }
+        ExprType::GeneratorExp(c) => {
+            visitor.visit_expr(&c.elt);
+            walk_comprehensions(&c.generators, visitor);
+        }
+        ExprType::DictComp(c) => {
+            visitor.visit_expr(&c.key);
+            visitor.visit_expr(&c.value);
+            walk_comprehensions(&c.generators, visitor);
+        }
+        ExprType::Await(a) => visitor.visit_expr(&a.value),
+        ExprType::Yield(y) => walk_exprs(y.value.iter().map(Box::as_ref), visitor),
+        ExprType::YieldFrom(y) => visitor.visit_expr(&y.value),
+        ExprType::Compare(c) => {
+            visitor.visit_expr(&c.left);
+            walk_exprs(&c.comparators, visitor);
+        }
+        ExprType::Call(c) => visitor.visit_call(c),
+        ExprType::FormattedValue(f) => {
+            visitor.visit_expr(&f.value);
+            walk_exprs(f.format_spec.iter().map(Box::as_ref), visitor);
+        }
+        ExprType::JoinedStr(j) => walk_exprs(&j.values, visitor),
+        ExprType::Attribute(a) => visitor.visit_expr(&a.value),
+        ExprType::Subscript(s) => {
+            visitor.visit_expr(&s.value);
+            visitor.visit_expr(&s.slice);
+        }
+        ExprType::Starred(s) => visitor.visit_expr(&s.value),
+        ExprType::Name(n) => visitor.visit_name(n),
+        ExprType::List(elts) => walk_exprs(elts, visitor),
+        ExprType::Tuple(t) => walk_exprs(&t.elts, visitor),
+        ExprType::Constant(_) | ExprType::Bytes(_) | ExprType::NoneType(_) | ExprType::Ellipsis | ExprType::Unimplemented(_) | ExprType::Unknown => {}
+    }
+}
+
+fn walk_comprehensions<V: Visitor + ?Sized>(generators: &[Comprehension], visitor: &mut V) {
+    for generator in generators {
+        visitor.visit_comprehension(generator);
+    }
+}
+
+/// Visit the function, arguments and keyword arguments of a call.
+pub fn walk_call<V: Visitor + ?Sized>(node: &Call, visitor: &mut V) {
+    visitor.visit_expr(&node.func);
+    walk_exprs(&node.args, visitor);
+    for keyword in &node.keywords {
+        visitor.visit_keyword(keyword);
+    }
+}
+
+/// Visit the value of a keyword argument.
+pub fn walk_keyword<V: Visitor + ?Sized>(node: &Keyword, visitor: &mut V) {
+    visitor.visit_expr(&node.value);
+}
+
+/// Visit the target, iterable and conditions of a comprehension's `for` clause.
+pub fn walk_comprehension<V: Visitor + ?Sized>(node: &Comprehension, visitor: &mut V) {
+    visitor.visit_expr(&node.target);
+    visitor.visit_expr(&node.iter);
+    walk_exprs(&node.ifs, visitor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[derive(Default)]
+    struct Collector {
+        functions: Vec<String>,
+        classes: Vec<String>,
+        names: Vec<String>,
+    }
+
+    impl Visitor for Collector {
+        fn visit_function_def(&mut self, node: &FunctionDef) {
+            self.functions.push(node.name.clone());
+            walk_function_def(node, self);
+        }
+
+        fn visit_class_def(&mut self, node: &ClassDef) {
+            self.classes.push(node.name.clone());
+            walk_class_def(node, self);
+        }
+
+        fn visit_name(&mut self, node: &Name) {
+            self.names.push(node.id.clone());
+        }
+    }
+
+    #[test]
+    fn test_visits_nested_definitions_and_names() {
+        let module = parse(
+            "class Shape(Base):
+    def area(self):
+        return [x * scale for x in self.sides if x]
+
+async def main(limit=default):
+    def helper():
+        pass
+    while limit > 0:
+        try:
+            helper()
+        except ValueError:
+            pass
+",
+            "test.py",
+        )
+        .unwrap();
+        let mut collector = Collector::default();
+        collector.visit_module(&module);
+
+        assert_eq!(collector.functions, vec!["area", "main", "helper"]);
+        assert_eq!(collector.classes, vec!["Shape"]);
+        for name in ["Base", "x", "scale", "self", "default", "limit", "helper", "ValueError"] {
+            assert!(collector.names.contains(&name.to_string()), "{} in {:?}", name, collector.names);
+        }
+    }
+
+    #[test]
+    fn test_visits_every_name() {
+        let module = parse(
+            "@decorator(flag=decorator_arg)
+class Shape(Base, metaclass=Meta):
+    side: SideType = side_default
+
+    def area(self, scale: ScaleType = scale_default, *rest: RestType, key=key_default, **extra: ExtraType) -> AreaType:
+        return [x * scale for x in self.sides if x > threshold]
+
+async def main(limit):
+    async with manager(resource) as handle:
+        await handle
+    async for item in stream:
+        pass
+    for row in rows:
+        total += row
+    else:
+        summary = {label: f\"{value:{width}}\" for label, value in pairs}
+    while limit > 0:
+        try:
+            raise error from cause
+        except ValueError:
+            pass
+        else:
+            fallback()
+        finally:
+            cleanup()
+    else:
+        done = lambda arg=lambda_default: arg
+    if (chunk := source[start]):
+        assert chunk, message
+    match subject:
+        case Point(x=px) if guard:
+            pass
+        case {keys.name: matched}:
+            pass
+        case literal.VALUE:
+            pass
+
+def produce():
+    yield from ([*spread], {dict_key: dict_value}, {member}, -negative, not flag if cond else alt)
+",
+            "test.py",
+        )
+        .unwrap();
+        let mut collector = Collector::default();
+        collector.visit_module(&module);
+
+        let names: std::collections::BTreeSet<_> = collector.names.iter().map(String::as_str).collect();
+        let expected: std::collections::BTreeSet<_> = [
+            "decorator", "decorator_arg", "Base", "Meta", "side", "SideType", "side_default", "self", "ScaleType",
+            "scale_default", "RestType", "key_default", "ExtraType", "AreaType", "x", "scale", "threshold", "limit",
+            "manager", "resource", "handle", "item", "stream", "row", "rows", "total", "summary", "label", "value",
+            "width", "pairs", "error", "cause", "ValueError", "fallback", "cleanup", "done", "arg", "lambda_default",
+            "chunk", "source", "start", "message", "subject", "Point", "guard", "keys", "literal",
+            "spread", "dict_key", "dict_value", "member", "negative", "flag", "cond", "alt",
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_overriding_without_walking_prunes_the_subtree() {
+        struct TopLevel(Vec<String>);
+
+        impl Visitor for TopLevel {
+            fn visit_function_def(&mut self, node: &FunctionDef) {
+                self.0.push(node.name.clone());
+            }
+        }
+
+        let module = parse("def outer():\n    def inner():\n        pass\n", "test.py").unwrap();
+        let mut top_level = TopLevel(Vec::new());
+        walk_module(&module, &mut top_level);
+        assert_eq!(top_level.0, vec!["outer"]);
+    }
+}
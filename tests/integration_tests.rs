@@ -289,6 +289,77 @@ def broken_function(
     assert!(result.is_err(), "Expected parsing to fail for invalid syntax");
 }
 
+#[test]
+fn test_parse_enhanced_returns_top_level_statements() {
+    let code = r#"
+import os
+
+LIMIT = 10
+
+def count(items):
+    return len(items)
+
+class Box:
+    pass
+
+print(count([1, 2]))
+"#;
+
+    // The interpreter is started by parse_enhanced itself, callers don't touch pyo3.
+    let module = parse_enhanced(code, "script.py").expect("Failed to parse script");
+    assert_eq!(module.raw.body.len(), 5);
+
+    match parse_enhanced("def broken(:\n", "broken.py") {
+        Err(Error::ParseError { location, .. }) => {
+            assert_eq!(location.filename, "broken.py");
+        }
+        other => panic!("Expected a parse error, got {:?}", other.map(|m| m.raw.body.len())),
+    }
+
+    // Python parses the script, but a statement can't be read into the AST.
+    match parse_enhanced("x = 1\ndel x\n", "unreadable.py") {
+        Err(Error::ParseError { location, message, .. }) => {
+            assert_eq!(location.filename, "unreadable.py");
+            assert!(message.contains("Failed to extract AST"), "{}", message);
+        }
+        other => panic!("Expected a parse error, got {:?}", other.map(|m| m.raw.body.len())),
+    }
+}
+
+#[test]
+fn test_end_to_end_union_parameter_is_matched_by_variant() {
+    let code = r#"
+from typing import Optional, Union
+
+def describe(value: Union[int, str], label: Optional[str]) -> str:
+    match value:
+        case int(n) if n > 0:
+            return "positive"
+        case int():
+            return "number"
+        case str() as text:
+            return text
+"#;
+
+    let module = parse(code, "union_match.py").expect("Failed to parse union match");
+    let mut options = PythonOptions::default();
+    options.with_std_python = false;
+    let symbols = module.clone().find_symbols(SymbolTableScopes::new());
+    let rust_code = module
+        .to_rust(CodeGenContext::Module("union_match".to_string()), options, symbols)
+        .expect("Failed to generate union match")
+        .to_string();
+
+    syn::parse_str::<syn::File>(&rust_code).unwrap_or_else(|e| panic!("{}: {}", e, rust_code));
+    assert_eq!(rust_code.matches("pub enum IntOrStr").count(), 1, "{}", rust_code);
+    assert!(rust_code.contains("impl From < i64 > for IntOrStr"), "{}", rust_code);
+    assert!(rust_code.contains("impl From < String > for IntOrStr"), "{}", rust_code);
+    assert!(rust_code.contains("pub fn describe (value : IntOrStr , label : Option < String >) -> String"), "{}", rust_code);
+    assert!(rust_code.contains("IntOrStr :: Int (n) if (n) > (0) =>"), "{}", rust_code);
+    assert!(rust_code.contains("IntOrStr :: Int (_) =>"), "{}", rust_code);
+    assert!(rust_code.contains("IntOrStr :: Str (text) =>"), "{}", rust_code);
+}
+
 #[test]
 fn test_end_to_end_large_module() {
     // Generate a larger module to test performance and memory usage